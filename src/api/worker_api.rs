@@ -1,10 +1,135 @@
 use crate::compute::{
     errors::ReplicateStatusCause,
-    utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error},
+    events::EventRecord,
+    schema,
+    utils::{
+        enclave_utils::mr_enclave,
+        env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error},
+        file_utils::http_client,
+    },
 };
-use log::error;
-use reqwest::{blocking::Client, header::AUTHORIZATION};
-use serde::Serialize;
+#[cfg(feature = "compression")]
+use flate2::{Compression, write::GzEncoder};
+use log::{error, info, warn};
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io;
+#[cfg(feature = "compression")]
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Stage of a task reporting to the worker API.
+///
+/// The stage selects the `/compute/<stage>/...` endpoints. Only the pre-compute stage
+/// exists: the crate has no library target, so the post-compute enclave cannot depend on
+/// this client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComputeStage {
+    Pre,
+}
+
+impl fmt::Display for ComputeStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ComputeStage::Pre => "pre",
+        })
+    }
+}
+
+/// Details about the download which made the pre-compute stage fail.
+///
+/// # Arguments
+///
+/// * `url` - The URL which could not be downloaded
+/// * `input_file_index` - The 1-based index of the input file (`IEXEC_INPUT_FILE_URL_<index>`),
+///   absent when the failing download is the dataset
+/// * `http_status` - The HTTP status returned by the server, absent when no response was received
+/// * `attempts` - The number of download attempts made for this URL
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadFailure {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_file_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    pub attempts: u32,
+}
+
+/// Status of an input file download, as reported to the worker API.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FileStatus {
+    Started,
+    Succeeded,
+    Failed,
+}
+
+/// Progress update sent to the worker API each time an input file download starts or
+/// completes.
+///
+/// The JSON structure expected by the REST endpoint is:
+/// ```json
+/// {
+///   "url": "https://host/input.txt",
+///   "inputFileIndex": 1,
+///   "status": "SUCCEEDED",
+///   "size": 1024,
+///   "durationMs": 42
+/// }
+/// ```
+///
+/// # Arguments
+///
+/// * `url` - The URL of the input file
+/// * `input_file_index` - The 1-based index of the input file (`IEXEC_INPUT_FILE_URL_<index>`)
+/// * `status` - The download status
+/// * `size` - The size in bytes of the downloaded file, only present on success
+/// * `duration_ms` - The download duration in milliseconds, absent when the download starts
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileProgress {
+    pub url: String,
+    pub input_file_index: usize,
+    pub status: FileStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+/// Notification sent to the worker API once the pre-compute stage has read its arguments and
+/// starts working, so that the scheduler can measure the delay between the task being queued
+/// and the enclave starting, and detect enclaves which never started.
+///
+/// The JSON structure expected by the REST endpoint is:
+/// ```json
+/// {
+///   "chainTaskId": "0x123456789abcdef",
+///   "version": "<pre-compute crate version>",
+///   "isDatasetRequired": true,
+///   "inputFilesNumber": 2
+/// }
+/// ```
+///
+/// # Arguments
+///
+/// * `chain_task_id` - The chain task ID being pre-computed
+/// * `version` - The version of the pre-compute crate which started
+/// * `is_dataset_required` - Whether a dataset is configured
+/// * `input_files_number` - The number of configured input files
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StartNotification {
+    pub chain_task_id: String,
+    pub version: &'static str,
+    pub is_dataset_required: bool,
+    pub input_files_number: usize,
+}
 
 /// Represents payload that can be sent to the worker API to report the outcome of the
 /// pre‑compute stage.
@@ -12,13 +137,36 @@ use serde::Serialize;
 /// The JSON structure expected by the REST endpoint is:
 /// ```json
 /// {
-///   "cause": "<ReplicateStatusCause as string>"
+///   "cause": "<ReplicateStatusCause known to the worker>",
+///   "code": "<stable machine code of the cause>",
+///   "hint": "<actionable hint about the cause>",
+///   "version": "<pre-compute crate version>",
+///   "mrEnclave": "0x<hex-encoded MRENCLAVE>",
+///   "downloadFailure": {
+///     "url": "<failing URL>",
+///     "inputFileIndex": 1,
+///     "httpStatus": 404,
+///     "attempts": 1
+///   },
+///   "detail": "panic"
 /// }
 /// ```
+/// `mrEnclave` is only present when running inside an enclave exposing its measurement,
+/// `downloadFailure` is only present when the failure is caused by a download, and `detail`
+/// is only present when the cause alone does not tell what happened, e.g. after a panic.
 ///
 /// # Arguments
 ///
-/// * `cause` - A reference to the ReplicateStatusCause indicating why the pre-compute operation exited
+/// * `cause` - A reference to the ReplicateStatusCause indicating why the pre-compute operation exited,
+///   serialized as [`ReplicateStatusCause::worker_cause`]
+/// * `code` - The stable machine code of the cause, see [`ReplicateStatusCause::code`]
+/// * `hint` - The actionable hint about the cause, see [`ReplicateStatusCause::user_hint`]
+/// * `version` - The version of the pre-compute crate which produced the message
+/// * `mr_enclave` - Optional measurement of the running enclave
+/// * `download_failure` - Optional details about the failing download
+/// * `detail` - Optional short qualifier of the cause
+/// * `config_fingerprint` - Optional digest of the task parameters, see
+///   [`crate::compute::diagnostics::config_fingerprint`]
 ///
 /// # Example
 ///
@@ -29,13 +177,42 @@ use serde::Serialize;
 /// let exit_message = ExitMessage::from(&ReplicateStatusCause::PreComputeInvalidTeeSignature);
 /// ```
 #[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct ExitMessage<'a> {
+    #[serde(serialize_with = "serialize_worker_cause")]
     pub cause: &'a ReplicateStatusCause,
+    pub code: &'static str,
+    pub hint: &'static str,
+    pub version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mr_enclave: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_failure: Option<&'a DownloadFailure>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_fingerprint: Option<String>,
+}
+
+fn serialize_worker_cause<S: Serializer>(
+    cause: &&ReplicateStatusCause,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    cause.worker_cause().serialize(serializer)
 }
 
 impl<'a> From<&'a ReplicateStatusCause> for ExitMessage<'a> {
     fn from(cause: &'a ReplicateStatusCause) -> Self {
-        Self { cause }
+        Self {
+            cause,
+            code: cause.code(),
+            hint: cause.user_hint(),
+            version: env!("CARGO_PKG_VERSION"),
+            mr_enclave: mr_enclave(),
+            download_failure: None,
+            detail: None,
+            config_fingerprint: None,
+        }
     }
 }
 
@@ -44,6 +221,18 @@ impl<'a> From<&'a ReplicateStatusCause> for ExitMessage<'a> {
 /// This client can be created directly with a base URL using [`new()`], or
 /// configured from environment variables using [`from_env()`].
 ///
+/// When a health path is configured, [`is_healthy()`] probes it before any report is sent.
+///
+/// When `IEXEC_WORKER_API_COMPRESSION` is "true", request bodies larger than
+/// [`COMPRESSION_THRESHOLD`] are sent gzip-compressed with a `Content-Encoding: gzip` header.
+///
+/// Several worker hosts can be configured for highly available deployments. Requests are sent
+/// to the first host, then to the next ones in order until one of them accepts the request.
+///
+/// When the worker rate-limits an exit report with `429 Too Many Requests`, the report is
+/// re-sent after the delay advised by its `Retry-After` header, as long as it fits in the
+/// window set by `IEXEC_WORKER_API_RATE_LIMIT_WINDOW_SECS`.
+///
 /// # Example
 ///
 /// ```
@@ -52,28 +241,48 @@ impl<'a> From<&'a ReplicateStatusCause> for ExitMessage<'a> {
 /// let client = WorkerApiClient::new("http://worker:13100");
 /// ```
 pub struct WorkerApiClient {
-    base_url: String,
+    base_urls: Vec<String>,
     client: Client,
+    health_path: Option<String>,
+    compress_requests: bool,
+    rate_limit_window: Duration,
 }
 
 const DEFAULT_WORKER_HOST: &str = "worker:13100";
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Time allowed to the worker to accept an event, so that reporting never stalls a run.
+const EVENT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Size from which request bodies are compressed, smaller bodies are not worth it.
+pub const COMPRESSION_THRESHOLD: usize = 8 * 1024;
+/// Time during which a rate-limited exit report is re-sent, by default.
+const DEFAULT_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(30);
+/// Delay before re-sending a rate-limited request without a usable `Retry-After` header.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
 
 impl WorkerApiClient {
     fn new(base_url: &str) -> Self {
         WorkerApiClient {
-            base_url: base_url.to_string(),
-            client: Client::new(),
+            base_urls: vec![base_url.to_string()],
+            client: http_client().clone(),
+            health_path: None,
+            compress_requests: false,
+            rate_limit_window: DEFAULT_RATE_LIMIT_WINDOW,
         }
     }
 
     /// Creates a new WorkerApiClient instance with configuration from environment variables.
     ///
-    /// This method retrieves the worker host from the [`WORKER_HOST_ENV_VAR`] environment variable.
-    /// If the variable is not set or empty, it defaults to `"worker:13100"`.
+    /// This method retrieves the worker hosts from the [`WORKER_HOST_ENV_VAR`] environment
+    /// variable, as a comma-separated list tried in order. If the variable is not set or empty,
+    /// it defaults to `"worker:13100"`.
+    /// The optional health check path is read from `IEXEC_WORKER_HEALTH_PATH` (e.g. `/health`),
+    /// and request compression is enabled by `IEXEC_WORKER_API_COMPRESSION` (defaults to "false").
+    /// Rate-limited exit reports are re-sent for up to `IEXEC_WORKER_API_RATE_LIMIT_WINDOW_SECS`
+    /// seconds (defaults to 30).
     ///
     /// # Returns
     ///
-    /// * `WorkerApiClient` - A new client configured with the appropriate base URL
+    /// * `WorkerApiClient` - A new client configured with the appropriate base URLs
     ///
     /// # Example
     ///
@@ -83,23 +292,201 @@ impl WorkerApiClient {
     /// let client = WorkerApiClient::from_env();
     /// ```
     pub fn from_env() -> Self {
-        let worker_host = get_env_var_or_error(
+        let worker_hosts = get_env_var_or_error(
             TeeSessionEnvironmentVariable::WorkerHostEnvVar,
             ReplicateStatusCause::PreComputeWorkerAddressMissing,
         )
-        .unwrap_or_else(|_| DEFAULT_WORKER_HOST.to_string());
+        .unwrap_or_default();
+        let mut base_urls: Vec<String> = worker_hosts
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .map(|host| format!("http://{host}"))
+            .collect();
+        if base_urls.is_empty() {
+            base_urls.push(format!("http://{DEFAULT_WORKER_HOST}"));
+        }
+
+        let health_path = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecWorkerHealthPath,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .filter(|path| !path.trim().is_empty());
+
+        let compress_requests = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecWorkerApiCompression,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+
+        let rate_limit_window = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecWorkerApiRateLimitWindowSecs,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .map_or(DEFAULT_RATE_LIMIT_WINDOW, Duration::from_secs);
+
+        let client = Self::new(&base_urls[0]);
+        WorkerApiClient {
+            base_urls,
+            health_path,
+            compress_requests,
+            rate_limit_window,
+            ..client
+        }
+    }
+
+    /// Sets `body` as the JSON body of `request`, tagged with its schema version and
+    /// gzip-compressed when compression is enabled
+    /// and the body is at least [`COMPRESSION_THRESHOLD`] bytes long.
+    fn json_body<T: Serialize + ?Sized>(
+        &self,
+        request: RequestBuilder,
+        body: &T,
+    ) -> RequestBuilder {
+        let Ok(content) = schema::to_vec(body) else {
+            return request.json(body);
+        };
+        let request = request.header(CONTENT_TYPE, "application/json");
+        if self.compress_requests && content.len() >= COMPRESSION_THRESHOLD {
+            match gzip(&content) {
+                Ok(compressed) => {
+                    info!(
+                        "Compressed worker API request [size:{}, compressedSize:{}]",
+                        content.len(),
+                        compressed.len()
+                    );
+                    return request.header(CONTENT_ENCODING, "gzip").body(compressed);
+                }
+                Err(e) => warn!("Failed to compress worker API request, sending it as is: {e}"),
+            }
+        }
+        request.body(content)
+    }
+
+    /// Calls `send` with each worker host base URL in turn, until one of them succeeds.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` as soon as a host accepted the request.
+    /// * `Err(ReplicateStatusCause)` with the error of the last host if every host failed.
+    fn with_failover(
+        &self,
+        mut send: impl FnMut(&str) -> Result<(), ReplicateStatusCause>,
+    ) -> Result<(), ReplicateStatusCause> {
+        let mut result = Err(ReplicateStatusCause::PreComputeFailedUnknownIssue);
+        for (index, base_url) in self.base_urls.iter().enumerate() {
+            if index > 0 {
+                warn!("Failing over to next worker API host [url:{base_url}]");
+            }
+            result = send(base_url);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Sends the request built by `build`, then re-sends it while the worker answers with
+    /// `429 Too Many Requests` and the delay it advises elapses before `deadline`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Response)` with the first response which is not rate-limited, or the last
+    ///   rate-limited one when waiting any longer would exceed `deadline`.
+    /// * `Err(reqwest::Error)` if the request could not be sent.
+    fn send_rate_limited(
+        &self,
+        url: &str,
+        deadline: Instant,
+        build: impl Fn() -> RequestBuilder,
+    ) -> reqwest::Result<Response> {
+        loop {
+            let resp = build().send()?;
+            if resp.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(resp);
+            }
+            let delay = retry_after(&resp).unwrap_or(DEFAULT_RETRY_AFTER);
+            if Instant::now() + delay > deadline {
+                warn!(
+                    "Worker API rate limit outlasts re-send window [url:{url}, retryAfter:{}s]",
+                    delay.as_secs()
+                );
+                return Ok(resp);
+            }
+            warn!(
+                "Worker API rate-limited request, re-sending it later [url:{url}, retryAfter:{}s]",
+                delay.as_secs()
+            );
+            thread::sleep(delay);
+        }
+    }
+
+    /// Probes the worker API health path with a short timeout.
+    ///
+    /// This allows giving up on reporting quickly, with actionable diagnostics, when the
+    /// worker cannot be reached instead of waiting for the report request to time out.
+    ///
+    /// # Returns
+    ///
+    /// * `true` if no health path is configured or any worker host answered with a success
+    ///   status.
+    /// * `false` if every worker host is unreachable or answered with an error status.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crate::api::worker_api::WorkerApiClient;
+    ///
+    /// let client = WorkerApiClient::from_env();
+    /// if !client.is_healthy() {
+    ///     eprintln!("Worker API is not reachable");
+    /// }
+    /// ```
+    pub fn is_healthy(&self) -> bool {
+        let Some(health_path) = &self.health_path else {
+            return true;
+        };
+        self.base_urls
+            .iter()
+            .any(|base_url| self.is_host_healthy(base_url, health_path))
+    }
 
-        let base_url = format!("http://{worker_host}");
-        Self::new(&base_url)
+    fn is_host_healthy(&self, base_url: &str, health_path: &str) -> bool {
+        let url = format!("{base_url}{health_path}");
+        match self.client.get(&url).timeout(HEALTH_CHECK_TIMEOUT).send() {
+            Ok(resp) if resp.status().is_success() => {
+                info!("Worker API is healthy [url:{url}]");
+                true
+            }
+            Ok(resp) => {
+                error!(
+                    "Worker API health check failed, check the worker logs [url:{url}, status:{}]",
+                    resp.status()
+                );
+                false
+            }
+            Err(err) => {
+                error!(
+                    "Worker API is unreachable, check {} and the enclave network configuration [url:{url}, timeout:{}s]: {err}",
+                    TeeSessionEnvironmentVariable::WorkerHostEnvVar.name(),
+                    HEALTH_CHECK_TIMEOUT.as_secs()
+                );
+                false
+            }
+        }
     }
 
-    /// Sends an exit cause for a pre-compute operation to the Worker API.
+    /// Sends the exit cause of a compute stage to the Worker API.
     ///
-    /// This method reports the exit cause of a pre-compute operation to the Worker API,
-    /// which can be used for tracking and debugging purposes.
+    /// This method reports the exit cause of a pre-compute or post-compute operation to the
+    /// Worker API, which can be used for tracking and debugging purposes.
     ///
     /// # Arguments
     ///
+    /// * `stage` - The compute stage reporting its exit cause
     /// * `authorization` - The authorization token to use for the API request
     /// * `chain_task_id` - The chain task ID for which to report the exit cause
     /// * `exit_cause` - The exit cause to report
@@ -117,13 +504,14 @@ impl WorkerApiClient {
     /// # Example
     ///
     /// ```
-    /// use crate::compute::worker_api::{ExitMessage, WorkerApiClient};
+    /// use crate::compute::worker_api::{ComputeStage, ExitMessage, WorkerApiClient};
     /// use crate::compute::errors::ReplicateStatusCause;
     ///
     /// let client = WorkerApiClient::new("http://worker:13100");
     /// let exit_message = ExitMessage::from(&ReplicateStatusCause::PreComputeInvalidTeeSignature);
     ///
-    /// match client.send_exit_cause_for_pre_compute_stage(
+    /// match client.send_exit_cause(
+    ///     ComputeStage::Pre,
     ///     "authorization_token",
     ///     "0x123456789abcdef",
     ///     &exit_message,
@@ -132,42 +520,259 @@ impl WorkerApiClient {
     ///     Err(error) => eprintln!("Failed to report exit cause: {error}"),
     /// }
     /// ```
-    pub fn send_exit_cause_for_pre_compute_stage(
+    pub fn send_exit_cause(
         &self,
+        stage: ComputeStage,
         authorization: &str,
         chain_task_id: &str,
         exit_cause: &ExitMessage,
     ) -> Result<(), ReplicateStatusCause> {
-        let url = format!("{}/compute/pre/{chain_task_id}/exit", self.base_url);
-        match self
-            .client
-            .post(&url)
-            .header(AUTHORIZATION, authorization)
-            .json(exit_cause)
-            .send()
-        {
-            Ok(resp) => {
-                let status = resp.status();
-                if status.is_success() {
-                    Ok(())
-                } else {
-                    let body = resp.text().unwrap_or_default();
-                    error!("Failed to send exit cause: [status:{status}, body:{body}]");
+        let deadline = Instant::now() + self.rate_limit_window;
+        self.with_failover(|base_url| {
+            let url = format!("{base_url}/compute/{stage}/{chain_task_id}/exit");
+            match self.send_rate_limited(&url, deadline, || {
+                self.json_body(
+                    self.client.post(&url).header(AUTHORIZATION, authorization),
+                    exit_cause,
+                )
+            }) {
+                Ok(resp) => {
+                    let status = resp.status();
+                    if status.is_success() {
+                        Ok(())
+                    } else {
+                        let body = resp.text().unwrap_or_default();
+                        error!("Failed to send exit cause: [status:{status}, body:{body}]");
+                        Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                    }
+                }
+                Err(err) => {
+                    error!("HTTP request failed when sending exit cause to {url}: {err:?}");
                     Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
                 }
             }
-            Err(err) => {
-                error!("HTTP request failed when sending exit cause to {url}: {err:?}");
-                Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+        })
+    }
+
+    /// Sends an input file progress update of a compute stage to the Worker API.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The compute stage downloading the input file
+    /// * `authorization` - The authorization token to use for the API request
+    /// * `chain_task_id` - The chain task ID for which to report the progress
+    /// * `progress` - The progress update to report
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the progress update was successfully reported
+    /// * `Err(ReplicateStatusCause)` - If the request could not be sent or the server
+    ///   responded with a non‑success status
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crate::api::worker_api::{ComputeStage, FileProgress, FileStatus, WorkerApiClient};
+    ///
+    /// let client = WorkerApiClient::from_env();
+    /// let progress = FileProgress {
+    ///     url: "https://host/input.txt".to_string(),
+    ///     input_file_index: 1,
+    ///     status: FileStatus::Started,
+    ///     size: None,
+    ///     duration_ms: None,
+    /// };
+    /// client.send_file_progress(ComputeStage::Pre, "authorization_token", "0x123456789abcdef", &progress)?;
+    /// ```
+    pub fn send_file_progress(
+        &self,
+        stage: ComputeStage,
+        authorization: &str,
+        chain_task_id: &str,
+        progress: &FileProgress,
+    ) -> Result<(), ReplicateStatusCause> {
+        self.with_failover(|base_url| {
+            let url = format!("{base_url}/compute/{stage}/{chain_task_id}/progress");
+            match self
+                .json_body(
+                    self.client.post(&url).header(AUTHORIZATION, authorization),
+                    progress,
+                )
+                .send()
+            {
+                Ok(resp) if resp.status().is_success() => Ok(()),
+                Ok(resp) => {
+                    error!(
+                        "Failed to send file progress: [status:{}, url:{}]",
+                        resp.status(),
+                        progress.url
+                    );
+                    Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                }
+                Err(err) => {
+                    error!("HTTP request failed when sending file progress to {url}: {err:?}");
+                    Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                }
             }
-        }
+        })
+    }
+
+    /// Notifies the Worker API that a compute stage started.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The compute stage which started
+    /// * `authorization` - The authorization token to use for the API request
+    /// * `chain_task_id` - The chain task ID of the started stage
+    /// * `notification` - The start notification to send
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the notification was successfully sent
+    /// * `Err(ReplicateStatusCause)` - If the request could not be sent or the server
+    ///   responded with a non‑success status
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crate::api::worker_api::{ComputeStage, StartNotification, WorkerApiClient};
+    ///
+    /// let client = WorkerApiClient::from_env();
+    /// let notification = StartNotification {
+    ///     chain_task_id: "0x123456789abcdef".to_string(),
+    ///     version: env!("CARGO_PKG_VERSION"),
+    ///     is_dataset_required: true,
+    ///     input_files_number: 2,
+    /// };
+    /// client.send_start_notification(ComputeStage::Pre, "authorization_token", "0x123456789abcdef", &notification)?;
+    /// ```
+    pub fn send_start_notification(
+        &self,
+        stage: ComputeStage,
+        authorization: &str,
+        chain_task_id: &str,
+        notification: &StartNotification,
+    ) -> Result<(), ReplicateStatusCause> {
+        self.with_failover(|base_url| {
+            let url = format!("{base_url}/compute/{stage}/{chain_task_id}/started");
+            match self
+                .json_body(
+                    self.client.post(&url).header(AUTHORIZATION, authorization),
+                    notification,
+                )
+                .send()
+            {
+                Ok(resp) if resp.status().is_success() => Ok(()),
+                Ok(resp) => {
+                    error!(
+                        "Failed to send start notification: [status:{}]",
+                        resp.status()
+                    );
+                    Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                }
+                Err(err) => {
+                    error!("HTTP request failed when sending start notification to {url}: {err:?}");
+                    Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                }
+            }
+        })
+    }
+
+    /// Sends a lifecycle event of a compute stage to the Worker API.
+    ///
+    /// # Arguments
+    ///
+    /// * `stage` - The compute stage which emitted the event
+    /// * `authorization` - The authorization token to use for the API request
+    /// * `chain_task_id` - The chain task ID the event belongs to
+    /// * `event` - The event to send
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the event was successfully sent
+    /// * `Err(ReplicateStatusCause)` - If the request could not be sent or the server
+    ///   responded with a non‑success status
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crate::api::worker_api::{ComputeStage, WorkerApiClient};
+    /// use crate::compute::events::{Event, EventRecord};
+    ///
+    /// let client = WorkerApiClient::from_env();
+    /// let event = EventRecord {
+    ///     timestamp: 1718000000000,
+    ///     chain_task_id: "0x123456789abcdef",
+    ///     event: &Event::DecryptDone { size: 42 },
+    /// };
+    /// client.send_event(ComputeStage::Pre, "authorization_token", "0x123456789abcdef", &event)?;
+    /// ```
+    pub fn send_event(
+        &self,
+        stage: ComputeStage,
+        authorization: &str,
+        chain_task_id: &str,
+        event: &EventRecord,
+    ) -> Result<(), ReplicateStatusCause> {
+        self.with_failover(|base_url| {
+            let url = format!("{base_url}/compute/{stage}/{chain_task_id}/events");
+            match self
+                .json_body(
+                    self.client.post(&url).header(AUTHORIZATION, authorization),
+                    event,
+                )
+                .timeout(EVENT_TIMEOUT)
+                .send()
+            {
+                Ok(resp) if resp.status().is_success() => Ok(()),
+                Ok(resp) => {
+                    error!("Failed to send event: [status:{}]", resp.status());
+                    Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                }
+                Err(err) => {
+                    error!("HTTP request failed when sending event to {url}: {err:?}");
+                    Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                }
+            }
+        })
     }
 }
 
+#[cfg(feature = "compression")]
+fn gzip(content: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content)?;
+    encoder.finish()
+}
+
+#[cfg(not(feature = "compression"))]
+fn gzip(_content: &[u8]) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "compression is not supported by this build",
+    ))
+}
+
+/// Reads the delay advised by the `Retry-After` header of `resp`, in seconds.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    resp.headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::compute::utils::env_utils::TeeSessionEnvironmentVariable::WorkerHostEnvVar;
+    use crate::compute::events::Event;
+    use crate::compute::schema::SCHEMA_VERSION;
+    use crate::compute::utils::env_utils::TeeSessionEnvironmentVariable::{
+        IexecWorkerApiRateLimitWindowSecs, IexecWorkerHealthPath, WorkerHostEnvVar,
+    };
     use serde_json::{json, to_string};
     use temp_env::with_vars;
     use wiremock::{
@@ -196,10 +801,115 @@ mod tests {
         for (cause, message) in causes {
             let exit_message = ExitMessage::from(&cause);
             let serialized = to_string(&exit_message).expect("Failed to serialize");
-            let expected = format!("{{\"cause\":\"{message}\"}}");
+            let expected = format!(
+                "{{\"cause\":\"{message}\",\"code\":\"{}\",\"hint\":\"{}\",\"version\":\"{}\"}}",
+                cause.code(),
+                cause.user_hint(),
+                env!("CARGO_PKG_VERSION")
+            );
             assert_eq!(serialized, expected);
         }
     }
+
+    #[test]
+    fn should_serialize_exit_message_with_cause_known_to_worker() {
+        let cause = ReplicateStatusCause::PreComputeDatasetTooLarge;
+
+        let serialized = serde_json::to_value(ExitMessage::from(&cause)).unwrap();
+
+        assert_eq!(serialized["cause"], "PRE_COMPUTE_DATASET_DOWNLOAD_FAILED");
+        assert_eq!(serialized["code"], "PRE-204");
+        assert_eq!(serialized["hint"], cause.user_hint());
+    }
+
+    #[test]
+    fn should_serialize_exit_message_with_download_failure() {
+        let download_failure = DownloadFailure {
+            url: "https://host/input.txt".to_string(),
+            input_file_index: Some(2),
+            http_status: Some(404),
+            attempts: 1,
+        };
+        let exit_message = ExitMessage {
+            cause: &ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+            code: "PRE-301",
+            hint: "hint",
+            version: "1.2.3",
+            mr_enclave: None,
+            download_failure: Some(&download_failure),
+            detail: None,
+            config_fingerprint: None,
+        };
+        let serialized = to_string(&exit_message).expect("Failed to serialize");
+        assert_eq!(
+            serialized,
+            "{\"cause\":\"PRE_COMPUTE_INPUT_FILE_DOWNLOAD_FAILED\",\"code\":\"PRE-301\",\"hint\":\"hint\",\"version\":\"1.2.3\",\"downloadFailure\":{\"url\":\"https://host/input.txt\",\"inputFileIndex\":2,\"httpStatus\":404,\"attempts\":1}}"
+        );
+    }
+
+    #[test]
+    fn should_serialize_exit_message_with_mr_enclave() {
+        let mr_enclave = format!("0x{}", "ab".repeat(32));
+        let exit_message = ExitMessage {
+            cause: &ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            code: "PRE-999",
+            hint: "hint",
+            version: "1.2.3",
+            mr_enclave: Some(mr_enclave.clone()),
+            download_failure: None,
+            detail: None,
+            config_fingerprint: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&exit_message).unwrap(),
+            json!({
+                "cause": "PRE_COMPUTE_FAILED_UNKNOWN_ISSUE",
+                "code": "PRE-999",
+                "hint": "hint",
+                "version": "1.2.3",
+                "mrEnclave": mr_enclave,
+            })
+        );
+    }
+
+    #[test]
+    fn should_serialize_exit_message_with_config_fingerprint() {
+        let config_fingerprint = format!("0x{}", "cd".repeat(32));
+        let exit_message = ExitMessage {
+            mr_enclave: None,
+            config_fingerprint: Some(config_fingerprint.clone()),
+            ..ExitMessage::from(&ReplicateStatusCause::PreComputeFailedUnknownIssue)
+        };
+        assert_eq!(
+            serde_json::to_value(&exit_message).unwrap(),
+            json!({
+                "cause": "PRE_COMPUTE_FAILED_UNKNOWN_ISSUE",
+                "code": "PRE-999",
+                "hint": ReplicateStatusCause::PreComputeFailedUnknownIssue.user_hint(),
+                "version": env!("CARGO_PKG_VERSION"),
+                "configFingerprint": config_fingerprint,
+            })
+        );
+    }
+
+    #[test]
+    fn should_serialize_exit_message_with_detail() {
+        let exit_message = ExitMessage {
+            mr_enclave: None,
+            detail: Some("panic"),
+            ..ExitMessage::from(&ReplicateStatusCause::PreComputeFailedUnknownIssue)
+        };
+        assert_eq!(
+            serde_json::to_value(&exit_message).unwrap(),
+            json!({
+                "cause": "PRE_COMPUTE_FAILED_UNKNOWN_ISSUE",
+                "code": "PRE-999",
+                "hint": ReplicateStatusCause::PreComputeFailedUnknownIssue.user_hint(),
+                "version": env!("CARGO_PKG_VERSION"),
+                "detail": "panic",
+            })
+        );
+    }
     // endregion
 
     // region get_worker_api_client
@@ -209,7 +919,24 @@ mod tests {
             vec![(WorkerHostEnvVar.name(), Some("custom-worker-host:9999"))],
             || {
                 let client = WorkerApiClient::from_env();
-                assert_eq!(client.base_url, "http://custom-worker-host:9999");
+                assert_eq!(client.base_urls, vec!["http://custom-worker-host:9999"]);
+            },
+        );
+    }
+
+    #[test]
+    fn should_get_worker_api_client_with_several_hosts() {
+        with_vars(
+            vec![(
+                WorkerHostEnvVar.name(),
+                Some("worker-1:13100, ,worker-2:13100"),
+            )],
+            || {
+                let client = WorkerApiClient::from_env();
+                assert_eq!(
+                    client.base_urls,
+                    vec!["http://worker-1:13100", "http://worker-2:13100"]
+                );
             },
         );
     }
@@ -218,12 +945,22 @@ mod tests {
     fn should_get_worker_api_client_without_env_var() {
         temp_env::with_vars_unset(vec![WorkerHostEnvVar.name()], || {
             let client = WorkerApiClient::from_env();
-            assert_eq!(client.base_url, format!("http://{DEFAULT_WORKER_HOST}"));
+            assert_eq!(
+                client.base_urls,
+                vec![format!("http://{DEFAULT_WORKER_HOST}")]
+            );
         });
     }
     // endregion
 
-    // region send_exit_cause_for_pre_compute_stage()
+    // region ComputeStage
+    #[test]
+    fn should_display_compute_stage() {
+        assert_eq!(ComputeStage::Pre.to_string(), "pre");
+    }
+    // endregion
+
+    // region send_exit_cause()
     const CHALLENGE: &str = "challenge";
     const CHAIN_TASK_ID: &str = "0x123456789abcdef";
 
@@ -233,7 +970,11 @@ mod tests {
         let server_url = mock_server.uri();
 
         let expected_body = json!({
+            "schemaVersion": SCHEMA_VERSION,
             "cause": ReplicateStatusCause::PreComputeInvalidTeeSignature,
+            "code": "PRE-901",
+            "hint": ReplicateStatusCause::PreComputeInvalidTeeSignature.user_hint(),
+            "version": env!("CARGO_PKG_VERSION"),
         });
 
         Mock::given(method("POST"))
@@ -249,7 +990,8 @@ mod tests {
             let exit_message =
                 ExitMessage::from(&ReplicateStatusCause::PreComputeInvalidTeeSignature);
             let worker_api_client = WorkerApiClient::new(&server_url);
-            worker_api_client.send_exit_cause_for_pre_compute_stage(
+            worker_api_client.send_exit_cause(
+                ComputeStage::Pre,
                 CHALLENGE,
                 CHAIN_TASK_ID,
                 &exit_message,
@@ -261,6 +1003,103 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn should_send_exit_cause_to_next_host_when_first_fails() {
+        let failing_server = MockServer::start().await;
+        let mock_server = MockServer::start().await;
+        let base_urls = vec![
+            "http://127.0.0.1:1".to_string(),
+            failing_server.uri(),
+            mock_server.uri(),
+        ];
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&failing_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = tokio::task::spawn_blocking(move || {
+            let exit_message =
+                ExitMessage::from(&ReplicateStatusCause::PreComputeFailedUnknownIssue);
+            WorkerApiClient {
+                base_urls,
+                ..WorkerApiClient::new("")
+            }
+            .send_exit_cause(
+                ComputeStage::Pre,
+                CHALLENGE,
+                CHAIN_TASK_ID,
+                &exit_message,
+            )
+        })
+        .await
+        .expect("Task panicked");
+
+        assert!(result.is_ok());
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn should_send_large_exit_cause_compressed() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let mock_server = MockServer::start().await;
+        let server_url = mock_server.uri();
+        let download_failure = DownloadFailure {
+            url: format!("https://host/{}", "a".repeat(COMPRESSION_THRESHOLD)),
+            input_file_index: Some(1),
+            http_status: Some(404),
+            attempts: 1,
+        };
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .and(header("Content-Encoding", "gzip"))
+            .and(header("Content-Type", "application/json"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let failure = download_failure.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let cause = ReplicateStatusCause::PreComputeInputFileDownloadFailed;
+            let exit_message = ExitMessage {
+                download_failure: Some(&failure),
+                ..ExitMessage::from(&cause)
+            };
+            WorkerApiClient {
+                compress_requests: true,
+                ..WorkerApiClient::new(&server_url)
+            }
+            .send_exit_cause(
+                ComputeStage::Pre,
+                CHALLENGE,
+                CHAIN_TASK_ID,
+                &exit_message,
+            )
+        })
+        .await
+        .expect("Task panicked");
+        assert!(result.is_ok());
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let mut body = String::new();
+        GzDecoder::new(&requests[0].body[..])
+            .read_to_string(&mut body)
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["downloadFailure"]["url"], download_failure.url);
+    }
+
     #[tokio::test]
     async fn should_not_send_exit_cause() {
         testing_logger::setup();
@@ -278,7 +1117,8 @@ mod tests {
             let exit_message =
                 ExitMessage::from(&ReplicateStatusCause::PreComputeFailedUnknownIssue);
             let worker_api_client = WorkerApiClient::new(&server_url);
-            let response = worker_api_client.send_exit_cause_for_pre_compute_stage(
+            let response = worker_api_client.send_exit_cause(
+                ComputeStage::Pre,
                 CHALLENGE,
                 CHAIN_TASK_ID,
                 &exit_message,
@@ -307,12 +1147,97 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn should_resend_exit_cause_when_rate_limited() {
+        let mock_server = MockServer::start().await;
+        let server_url = mock_server.uri();
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = tokio::task::spawn_blocking(move || {
+            let exit_message =
+                ExitMessage::from(&ReplicateStatusCause::PreComputeInvalidTeeSignature);
+            WorkerApiClient::new(&server_url).send_exit_cause(
+                ComputeStage::Pre,
+                CHALLENGE,
+                CHAIN_TASK_ID,
+                &exit_message,
+            )
+        })
+        .await
+        .expect("Task panicked");
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_not_resend_exit_cause_when_rate_limit_outlasts_window() {
+        let mock_server = MockServer::start().await;
+        let server_url = mock_server.uri();
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "60"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = tokio::task::spawn_blocking(move || {
+            let exit_message =
+                ExitMessage::from(&ReplicateStatusCause::PreComputeInvalidTeeSignature);
+            WorkerApiClient::new(&server_url).send_exit_cause(
+                ComputeStage::Pre,
+                CHALLENGE,
+                CHAIN_TASK_ID,
+                &exit_message,
+            )
+        })
+        .await
+        .expect("Task panicked");
+
+        assert_eq!(
+            result,
+            Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+        );
+    }
+
+    #[test]
+    fn should_read_rate_limit_window_from_env() {
+        with_vars(
+            vec![(IexecWorkerApiRateLimitWindowSecs.name(), Some("5"))],
+            || {
+                let client = WorkerApiClient::from_env();
+                assert_eq!(client.rate_limit_window, Duration::from_secs(5));
+            },
+        );
+        with_vars(
+            vec![(IexecWorkerApiRateLimitWindowSecs.name(), None::<&str>)],
+            || {
+                let client = WorkerApiClient::from_env();
+                assert_eq!(client.rate_limit_window, DEFAULT_RATE_LIMIT_WINDOW);
+            },
+        );
+    }
+
     #[test]
     fn test_send_exit_cause_http_request_failure() {
         testing_logger::setup();
         let exit_message = ExitMessage::from(&ReplicateStatusCause::PreComputeFailedUnknownIssue);
         let worker_api_client = WorkerApiClient::new("wrong_url");
-        let result = worker_api_client.send_exit_cause_for_pre_compute_stage(
+        let result = worker_api_client.send_exit_cause(
+            ComputeStage::Pre,
             CHALLENGE,
             CHAIN_TASK_ID,
             &exit_message,
@@ -336,4 +1261,243 @@ mod tests {
         );
     }
     // endregion
+
+    // region send_file_progress()
+    #[test]
+    fn should_serialize_file_progress() {
+        let progress = FileProgress {
+            url: "https://host/input.txt".to_string(),
+            input_file_index: 1,
+            status: FileStatus::Succeeded,
+            size: Some(1024),
+            duration_ms: Some(42),
+        };
+        assert_eq!(
+            serde_json::to_value(&progress).unwrap(),
+            json!({
+                "url": "https://host/input.txt",
+                "inputFileIndex": 1,
+                "status": "SUCCEEDED",
+                "size": 1024,
+                "durationMs": 42,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_send_file_progress() {
+        let mock_server = MockServer::start().await;
+        let server_url = mock_server.uri();
+
+        let expected_body = json!({
+            "schemaVersion": SCHEMA_VERSION,
+            "url": "https://host/input.txt",
+            "inputFileIndex": 2,
+            "status": "STARTED",
+        });
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/progress")))
+            .and(header("Authorization", CHALLENGE))
+            .and(body_json(&expected_body))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = tokio::task::spawn_blocking(move || {
+            let progress = FileProgress {
+                url: "https://host/input.txt".to_string(),
+                input_file_index: 2,
+                status: FileStatus::Started,
+                size: None,
+                duration_ms: None,
+            };
+            WorkerApiClient::new(&server_url).send_file_progress(
+                ComputeStage::Pre,
+                CHALLENGE,
+                CHAIN_TASK_ID,
+                &progress,
+            )
+        })
+        .await
+        .expect("Task panicked");
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_send_event() {
+        let mock_server = MockServer::start().await;
+        let server_url = mock_server.uri();
+
+        let expected_body = json!({
+            "schemaVersion": SCHEMA_VERSION,
+            "timestamp": 1_718_000_000_000u64,
+            "chainTaskId": CHAIN_TASK_ID,
+            "event": "decrypt_done",
+            "size": 42,
+        });
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/events")))
+            .and(header("Authorization", CHALLENGE))
+            .and(body_json(&expected_body))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = tokio::task::spawn_blocking(move || {
+            let event = EventRecord {
+                timestamp: 1_718_000_000_000,
+                chain_task_id: CHAIN_TASK_ID,
+                event: &Event::DecryptDone { size: 42 },
+            };
+            WorkerApiClient::new(&server_url).send_event(
+                ComputeStage::Pre,
+                CHALLENGE,
+                CHAIN_TASK_ID,
+                &event,
+            )
+        })
+        .await
+        .expect("Task panicked");
+
+        assert!(result.is_ok());
+    }
+    // endregion
+
+    // region send_start_notification()
+    #[tokio::test]
+    async fn should_send_start_notification() {
+        let mock_server = MockServer::start().await;
+        let server_url = mock_server.uri();
+
+        let expected_body = json!({
+            "schemaVersion": SCHEMA_VERSION,
+            "chainTaskId": CHAIN_TASK_ID,
+            "version": env!("CARGO_PKG_VERSION"),
+            "isDatasetRequired": true,
+            "inputFilesNumber": 2,
+        });
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/started")))
+            .and(header("Authorization", CHALLENGE))
+            .and(body_json(&expected_body))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let result = tokio::task::spawn_blocking(move || {
+            let notification = StartNotification {
+                chain_task_id: CHAIN_TASK_ID.to_string(),
+                version: env!("CARGO_PKG_VERSION"),
+                is_dataset_required: true,
+                input_files_number: 2,
+            };
+            WorkerApiClient::new(&server_url).send_start_notification(
+                ComputeStage::Pre,
+                CHALLENGE,
+                CHAIN_TASK_ID,
+                &notification,
+            )
+        })
+        .await
+        .expect("Task panicked");
+
+        assert!(result.is_ok());
+    }
+    // endregion
+
+    // region is_healthy()
+    #[test]
+    fn should_be_healthy_without_health_path() {
+        let worker_api_client = WorkerApiClient::new("wrong_url");
+        assert!(worker_api_client.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn should_check_health_path() {
+        let mock_server = MockServer::start().await;
+        let server_url = mock_server.uri();
+
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/unhealthy"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let (healthy, unhealthy) = tokio::task::spawn_blocking(move || {
+            let check = |health_path: &str| {
+                WorkerApiClient {
+                    health_path: Some(health_path.to_string()),
+                    ..WorkerApiClient::new(&server_url)
+                }
+                .is_healthy()
+            };
+            (check("/health"), check("/unhealthy"))
+        })
+        .await
+        .expect("Task panicked");
+
+        assert!(healthy);
+        assert!(!unhealthy);
+    }
+
+    #[test]
+    fn should_not_be_healthy_when_worker_unreachable() {
+        let worker_api_client = WorkerApiClient {
+            health_path: Some("/health".to_string()),
+            ..WorkerApiClient::new("http://127.0.0.1:1")
+        };
+        assert!(!worker_api_client.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn should_be_healthy_when_any_host_is_healthy() {
+        let mock_server = MockServer::start().await;
+        let base_urls = vec!["http://127.0.0.1:1".to_string(), mock_server.uri()];
+
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let healthy = tokio::task::spawn_blocking(move || {
+            WorkerApiClient {
+                base_urls,
+                health_path: Some("/health".to_string()),
+                ..WorkerApiClient::new("")
+            }
+            .is_healthy()
+        })
+        .await
+        .expect("Task panicked");
+
+        assert!(healthy);
+    }
+
+    #[test]
+    fn should_read_health_path_from_env() {
+        with_vars(
+            vec![(IexecWorkerHealthPath.name(), Some("/health"))],
+            || {
+                let client = WorkerApiClient::from_env();
+                assert_eq!(client.health_path, Some("/health".to_string()));
+            },
+        );
+    }
+    // endregion
 }
@@ -1,6 +1,24 @@
 pub mod app_runner;
+#[cfg(feature = "bench")]
+pub mod benchmark;
+pub mod cancellation;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod diagnostics;
 pub mod errors;
+pub mod events;
 mod pre_compute_app;
 mod pre_compute_args;
+pub mod report;
+pub mod schema;
+pub mod service;
 pub mod signer;
+pub mod status;
+pub mod summary;
+pub mod supervisor;
+pub mod telemetry;
+pub mod types;
 pub mod utils;
+pub mod verifier;
+pub mod warm_start;
+pub mod workspace;
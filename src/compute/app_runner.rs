@@ -1,23 +1,98 @@
-use crate::api::worker_api::{ExitMessage, WorkerApiClient};
+use crate::api::worker_api::{ComputeStage, ExitMessage, WorkerApiClient};
 use crate::compute::pre_compute_app::{PreComputeApp, PreComputeAppTrait};
 use crate::compute::{
+    cancellation::CancellationToken,
+    diagnostics,
     errors::ReplicateStatusCause,
-    signer::get_challenge,
+    events::{self, Event},
+    signer::TaskChallenge,
+    summary::ExitSummary,
+    supervisor::Supervisor,
+    types::TaskId,
     utils::env_utils::{TeeSessionEnvironmentVariable::IexecTaskId, get_env_var_or_error},
+    warm_start::warm_up_duration,
+    workspace,
 };
-use log::{error, info};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::backtrace::Backtrace;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::Instant;
+use std::{panic, process};
 
 /// Represents the different exit modes for a process or application.
 ///
 /// Each variant is explicitly assigned an `i32` value, and the enum
 /// uses `#[repr(i32)]` to ensure its memory representation matches C-style enums.
+///
+/// `Timeout`, `Aborted` and `PartialSuccess` let the worker tell a task ended by its
+/// deadline or by a termination signal, or a task which skipped some input files, apart
+/// from a regular success or failure. `Crashed` is used after an unexpected panic.
 #[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[repr(i32)]
 pub enum ExitMode {
     Success = 0,
     ReportedFailure = 1,
     UnreportedFailure = 2,
     InitializationFailure = 3,
+    Timeout = 4,
+    Aborted = 5,
+    PartialSuccess = 6,
+    Crashed = 7,
+}
+
+/// Detail of the exit message reported after a panic.
+const PANIC_DETAIL: &str = "panic";
+
+/// Installs a panic hook which logs the panic with a backtrace, reports
+/// [`ReplicateStatusCause::PreComputeFailedUnknownIssue`] to the worker, removes the
+/// temporary workspace of the task, writes the [`ExitSummary`] to the output returned by
+/// `summary_output` and exits with [`ExitMode::Crashed`], so that an unexpected panic never
+/// ends the task silently.
+pub fn install_panic_hook(summary_output: fn() -> Box<dyn Write>) {
+    panic::set_hook(Box::new(move |info| {
+        error!(
+            "TEE pre-compute panicked: {info}\n{}",
+            Backtrace::force_capture()
+        );
+        report_panic();
+        workspace::remove_active();
+        let chain_task_id =
+            get_env_var_or_error(IexecTaskId, ReplicateStatusCause::PreComputeTaskIdMissing)
+                .unwrap_or_default();
+        ExitSummary::failure(
+            ExitMode::Crashed,
+            chain_task_id,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .write_to(summary_output().as_mut());
+        log::logger().flush();
+        process::exit(ExitMode::Crashed as i32);
+    }));
+}
+
+/// Reports a panic of the task named by `IEXEC_TASK_ID` to the worker.
+fn report_panic() -> ExitMode {
+    let chain_task_id =
+        match get_env_var_or_error(IexecTaskId, ReplicateStatusCause::PreComputeTaskIdMissing)
+            .and_then(|id| id.parse::<TaskId>())
+        {
+            Ok(id) => id,
+            Err(e) => {
+                error!("Cannot report panic without taskID context: {e:?}");
+                return ExitMode::UnreportedFailure;
+            }
+        };
+    let exit_cause = ReplicateStatusCause::PreComputeFailedUnknownIssue;
+    let exit_message = ExitMessage {
+        detail: Some(PANIC_DETAIL),
+        config_fingerprint: Some(diagnostics::config_fingerprint()),
+        ..ExitMessage::from(&exit_cause)
+    };
+    report_exit_cause(&TaskChallenge::new(&chain_task_id), &exit_message)
 }
 
 /// Executes the pre-compute workflow with a provided PreComputeApp implementation.
@@ -27,34 +102,73 @@ pub enum ExitMode {
 /// It uses the provided app to execute core operations and handles all the
 /// workflow states and transitions.
 ///
+/// When the run fails because `cancellation` was cancelled, the exit mode the task was
+/// cancelled with is returned as is, without reporting an exit cause to the worker.
+///
 /// # Example
 ///
 /// ```
 /// use crate::app_runner::start;
 /// use crate::pre_compute_app::PreComputeApp;
 ///
-/// let chain_task_id = "0x123456789abcdef".to_string();
-/// let mut pre_compute_app = PreComputeApp::new(chain_task_id.clone());
+/// let challenge = Rc::new(TaskChallenge::new("0x123456789abcdef"));
+/// let cancellation = CancellationToken::new();
+/// let mut pre_compute_app =
+///     PreComputeApp::new(challenge.clone()).with_cancellation(cancellation.clone());
 ///
-/// let exit_code = start_with_app(&pre_compute_app, &chain_task_id)
+/// let exit_code = start_with_app(&mut pre_compute_app, &challenge, &cancellation)
 /// ```
 pub fn start_with_app<A: PreComputeAppTrait>(
     pre_compute_app: &mut A,
-    chain_task_id: &str,
+    challenge: &TaskChallenge,
+    cancellation: &CancellationToken,
 ) -> ExitMode {
-    let exit_cause = ReplicateStatusCause::PreComputeFailedUnknownIssue;
-
-    match pre_compute_app.run() {
+    let exit_cause = match pre_compute_app.run() {
+        Ok(_) if pre_compute_app.has_skipped_input_files() => {
+            warn!("TEE pre-compute completed with skipped input files");
+            return ExitMode::PartialSuccess;
+        }
         Ok(_) => {
             info!("TEE pre-compute completed");
             return ExitMode::Success;
         }
         Err(exit_cause) => {
-            error!("TEE pre-compute failed with known exit cause [{exit_cause:?}]");
+            diagnostics::write_on_failure(challenge.chain_task_id(), &exit_cause, || {
+                pre_compute_app.network_summary()
+            });
+            if let Some(exit_mode) = cancellation.exit_mode() {
+                warn!("TEE pre-compute cancelled [exitCode:{}]", exit_mode as i32);
+                return exit_mode;
+            }
+            error!(
+                "TEE pre-compute failed with known exit cause [{exit_cause:?}, code:{}, hint:{}]",
+                exit_cause.code(),
+                exit_cause.user_hint()
+            );
+            exit_cause
         }
-    }
+    };
 
-    let authorization = match get_challenge(chain_task_id) {
+    let download_failure = pre_compute_app.download_failure();
+    let exit_message = ExitMessage {
+        download_failure: download_failure.as_ref(),
+        config_fingerprint: Some(diagnostics::config_fingerprint()),
+        ..ExitMessage::from(&exit_cause)
+    };
+    report_exit_cause(challenge, &exit_message)
+}
+
+/// Sends `exit_message` to the worker API, signed with the task challenge.
+///
+/// # Returns
+///
+/// * `ExitMode::ReportedFailure` if the worker API accepted the message.
+/// * `ExitMode::UnreportedFailure` if the message could not be signed or sent.
+fn report_exit_cause(challenge: &TaskChallenge, exit_message: &ExitMessage) -> ExitMode {
+    let chain_task_id = challenge.chain_task_id();
+    let exit_cause = exit_message.cause;
+
+    let authorization = match challenge.get() {
         Ok(auth) => auth,
         Err(_) => {
             error!("Failed to sign exitCause message [{exit_cause:?}]");
@@ -62,15 +176,25 @@ pub fn start_with_app<A: PreComputeAppTrait>(
         }
     };
 
-    let exit_message = ExitMessage {
-        cause: &exit_cause.clone(),
-    };
+    let worker_api_client = WorkerApiClient::from_env();
+    if !worker_api_client.is_healthy() {
+        warn!("Worker API health probe failed, reporting exitCause anyway [{exit_cause:?}]");
+    }
 
-    match WorkerApiClient::from_env().send_exit_cause_for_pre_compute_stage(
+    let result = worker_api_client.send_exit_cause(
+        ComputeStage::Pre,
         &authorization,
         chain_task_id,
-        &exit_message,
-    ) {
+        exit_message,
+    );
+    events::emit(
+        chain_task_id,
+        &Event::ExitReported {
+            cause: exit_cause.clone(),
+            reported: result.is_ok(),
+        },
+    );
+    match result {
         Ok(_) => ExitMode::ReportedFailure,
         Err(_) => {
             error!("Failed to report exitCause [{exit_cause:?}]");
@@ -82,7 +206,7 @@ pub fn start_with_app<A: PreComputeAppTrait>(
 /// Starts the pre-compute process using the [`PreComputeApp`].
 ///
 /// This is a convenience function that creates a [`PreComputeApp`]
-/// and passes it to [`start_with_app`].
+/// and passes it to [`start_with_app`], then prints the [`ExitSummary`] to stdout.
 ///
 /// # Example
 ///
@@ -93,25 +217,70 @@ pub fn start_with_app<A: PreComputeAppTrait>(
 /// std::process::exit(exit_code);
 /// ```
 pub fn start() -> ExitMode {
-    info!("TEE pre-compute started");
+    start_with_summary_output(&mut io::stdout().lock())
+}
 
-    let chain_task_id =
-        match get_env_var_or_error(IexecTaskId, ReplicateStatusCause::PreComputeTaskIdMissing) {
-            Ok(id) => id,
-            Err(e) => {
-                error!("TEE pre-compute cannot proceed without taskID context: {e:?}");
-                return ExitMode::InitializationFailure;
-            }
-        };
-    let mut pre_compute_app = PreComputeApp::new(chain_task_id.clone());
+/// Starts the pre-compute process like [`start`], writing the [`ExitSummary`] to
+/// `summary_output`, so that the service mode can keep stdout for its job results.
+///
+/// # Example
+///
+/// ```
+/// let exit_code = start_with_summary_output(&mut io::stderr());
+/// ```
+pub fn start_with_summary_output(summary_output: &mut dyn Write) -> ExitMode {
+    info!("TEE pre-compute started");
 
-    start_with_app(&mut pre_compute_app, &chain_task_id)
+    let configured_task_id =
+        get_env_var_or_error(IexecTaskId, ReplicateStatusCause::PreComputeTaskIdMissing);
+    let chain_task_id = match configured_task_id
+        .clone()
+        .and_then(|id| id.parse::<TaskId>())
+    {
+        Ok(id) => id,
+        Err(e) => {
+            error!("TEE pre-compute cannot proceed without taskID context: {e:?}");
+            let exit_mode = ExitMode::InitializationFailure;
+            ExitSummary::failure(exit_mode, configured_task_id.unwrap_or_default(), e)
+                .write_to(summary_output);
+            return exit_mode;
+        }
+    };
+    let started_at = Instant::now();
+    let cancellation = CancellationToken::new();
+    let supervisor = Supervisor::from_env(&chain_task_id, cancellation.clone());
+    let challenge = Rc::new(TaskChallenge::new(&chain_task_id));
+    let mut pre_compute_app =
+        PreComputeApp::new(challenge.clone()).with_cancellation(cancellation.clone());
+
+    let exit_mode = start_with_app(&mut pre_compute_app, &challenge, &cancellation);
+    drop(supervisor);
+    let task_duration = started_at.elapsed();
+    let warm_start_saved = warm_up_duration().unwrap_or_default();
+    let run_status = pre_compute_app.run_status();
+    info!(
+        "Timing summary [chainTaskId:{chain_task_id}, taskMs:{}, warmStartSavedMs:{}]",
+        task_duration.as_millis(),
+        warm_start_saved.as_millis()
+    );
+    for usage in &run_status.stages {
+        info!(
+            "Stage usage [chainTaskId:{chain_task_id}, stage:{:?}, cpuMs:{}, peakRssKb:{}]",
+            usage.stage, usage.cpu_ms, usage.peak_rss_kb
+        );
+    }
+    ExitSummary::new(exit_mode, run_status, task_duration, warm_start_saved)
+        .write_to(summary_output);
+    exit_mode
 }
 
 #[cfg(test)]
 mod pre_compute_start_with_app_tests {
     use super::*;
+    use crate::api::worker_api::DownloadFailure;
+    use crate::compute::diagnostics::NetworkSummary;
     use crate::compute::pre_compute_app::MockPreComputeAppTrait;
+    use crate::compute::schema::SCHEMA_VERSION;
     use serde_json::json;
     use temp_env;
     use wiremock::matchers::{body_json, method, path};
@@ -123,6 +292,7 @@ mod pre_compute_start_with_app_tests {
     const ENV_IEXEC_TASK_ID: &str = "IEXEC_TASK_ID";
     const ENV_SIGN_WORKER_ADDRESS: &str = "SIGN_WORKER_ADDRESS";
     const ENV_SIGN_TEE_CHALLENGE_PRIVATE_KEY: &str = "SIGN_TEE_CHALLENGE_PRIVATE_KEY";
+    const ENV_WORKER_HEALTH_PATH: &str = "IEXEC_WORKER_HEALTH_PATH";
     const ENV_WORKER_HOST: &str = "WORKER_HOST_ENV_VAR";
     const IEXEC_INPUT_FILES_NUMBER: &str = "IEXEC_INPUT_FILES_NUMBER";
     const IEXEC_PRE_COMPUTE_OUT: &str = "IEXEC_PRE_COMPUTE_OUT";
@@ -134,13 +304,24 @@ mod pre_compute_start_with_app_tests {
     fn start_fails_when_task_id_missing() {
         temp_env::with_vars_unset(vec![ENV_IEXEC_TASK_ID], || {
             assert_eq!(
-                start(),
+                start_with_summary_output(&mut Vec::new()),
                 ExitMode::InitializationFailure,
                 "Should return 3 if IEXEC_TASK_ID is missing"
             );
         });
     }
 
+    #[test]
+    fn start_fails_when_task_id_malformed() {
+        temp_env::with_vars(vec![(ENV_IEXEC_TASK_ID, Some("not-a-task-id"))], || {
+            assert_eq!(
+                start_with_summary_output(&mut Vec::new()),
+                ExitMode::InitializationFailure,
+                "Should return 3 if IEXEC_TASK_ID is malformed"
+            );
+        });
+    }
+
     #[test]
     fn start_fails_when_signer_address_missing() {
         let env_vars_to_set = vec![
@@ -155,13 +336,18 @@ mod pre_compute_start_with_app_tests {
         let mut mock = MockPreComputeAppTrait::new();
         mock.expect_run()
             .returning(|| Err(ReplicateStatusCause::PreComputeWorkerAddressMissing));
+        mock.expect_download_failure().returning(|| None);
 
         temp_env::with_vars(env_vars_to_set, || {
             temp_env::with_vars_unset(env_vars_to_unset, || {
                 assert_eq!(
-                    start_with_app(&mut mock, CHAIN_TASK_ID),
+                    start_with_app(
+                        &mut mock,
+                        &TaskChallenge::new(CHAIN_TASK_ID),
+                        &CancellationToken::new(),
+                    ),
                     ExitMode::UnreportedFailure,
-                    "Should return 2 if get_challenge fails due to missing signer address"
+                    "Should return 2 if the challenge fails due to missing signer address"
                 );
             });
         });
@@ -178,18 +364,138 @@ mod pre_compute_start_with_app_tests {
         let mut mock = MockPreComputeAppTrait::new();
         mock.expect_run()
             .returning(|| Err(ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing));
+        mock.expect_download_failure().returning(|| None);
 
         temp_env::with_vars(env_vars_to_set, || {
             temp_env::with_vars_unset(env_vars_to_unset, || {
                 assert_eq!(
-                    start_with_app(&mut mock, CHAIN_TASK_ID),
+                    start_with_app(
+                        &mut mock,
+                        &TaskChallenge::new(CHAIN_TASK_ID),
+                        &CancellationToken::new(),
+                    ),
                     ExitMode::UnreportedFailure,
-                    "Should return 2 if get_challenge fails due to missing private key"
+                    "Should return 2 if the challenge fails due to missing private key"
                 );
             });
         });
     }
 
+    #[test]
+    fn start_returns_partial_success_when_input_files_skipped() {
+        let mut mock = MockPreComputeAppTrait::new();
+        mock.expect_run().returning(|| Ok(()));
+        mock.expect_has_skipped_input_files().returning(|| true);
+
+        assert_eq!(
+            start_with_app(
+                &mut mock,
+                &TaskChallenge::new(CHAIN_TASK_ID),
+                &CancellationToken::new(),
+            ),
+            ExitMode::PartialSuccess
+        );
+        assert_eq!(ExitMode::PartialSuccess as i32, 6);
+    }
+
+    #[test]
+    fn start_returns_cancellation_exit_mode_without_reporting() {
+        let cancellation = CancellationToken::new();
+        cancellation.cancel(ExitMode::Timeout);
+        let mut mock = MockPreComputeAppTrait::new();
+        mock.expect_run()
+            .returning(|| Err(ReplicateStatusCause::PreComputeCancelled));
+        mock.expect_download_failure().never();
+
+        assert_eq!(
+            start_with_app(&mut mock, &TaskChallenge::new(CHAIN_TASK_ID), &cancellation),
+            ExitMode::Timeout
+        );
+    }
+
+    #[test]
+    fn start_writes_diagnostic_bundle_when_run_fails() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let cancellation = CancellationToken::new();
+        cancellation.cancel(ExitMode::Timeout);
+        let mut mock = MockPreComputeAppTrait::new();
+        mock.expect_run()
+            .returning(|| Err(ReplicateStatusCause::PreComputeCancelled));
+        mock.expect_network_summary()
+            .times(1)
+            .returning(NetworkSummary::default);
+
+        temp_env::with_vars(
+            [
+                (
+                    "IEXEC_PRE_COMPUTE_DIAGNOSTICS_DIR",
+                    Some(temp_dir.path().to_str().unwrap()),
+                ),
+                ("IEXEC_PRE_COMPUTE_ARTIFACTS_RECIPIENT", None),
+            ],
+            || {
+                start_with_app(&mut mock, &TaskChallenge::new(CHAIN_TASK_ID), &cancellation);
+            },
+        );
+
+        assert!(
+            temp_dir
+                .path()
+                .join(format!("diagnostics-{CHAIN_TASK_ID}.tar"))
+                .is_file()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn start_reports_exit_cause_when_worker_health_probe_fails() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mock_server_addr_string = mock_server.address().to_string();
+
+        let mut mock = MockPreComputeAppTrait::new();
+        mock.expect_run()
+            .returning(|| Err(ReplicateStatusCause::PreComputeOutputFolderNotFound));
+        mock.expect_download_failure().returning(|| None);
+
+        let result_code = tokio::task::spawn_blocking(move || {
+            let env_vars = vec![
+                (ENV_IEXEC_TASK_ID, Some(CHAIN_TASK_ID)),
+                (ENV_SIGN_WORKER_ADDRESS, Some(WORKER_ADDRESS)),
+                (
+                    ENV_SIGN_TEE_CHALLENGE_PRIVATE_KEY,
+                    Some(ENCLAVE_CHALLENGE_PRIVATE_KEY),
+                ),
+                (ENV_WORKER_HOST, Some(mock_server_addr_string.as_str())),
+                (ENV_WORKER_HEALTH_PATH, Some("/health")),
+            ];
+
+            temp_env::with_vars(env_vars, || {
+                start_with_app(
+                    &mut mock,
+                    &TaskChallenge::new(CHAIN_TASK_ID),
+                    &CancellationToken::new(),
+                )
+            })
+        })
+        .await
+        .expect("Blocking task panicked");
+
+        assert_eq!(result_code, ExitMode::ReportedFailure);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn start_fails_when_send_exit_cause_api_error() {
         let mock_server = MockServer::start().await;
@@ -205,6 +511,7 @@ mod pre_compute_start_with_app_tests {
         let mut mock = MockPreComputeAppTrait::new();
         mock.expect_run()
             .returning(|| Err(ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing));
+        mock.expect_download_failure().returning(|| None);
 
         let result_code = tokio::task::spawn_blocking(move || {
             let env_vars = vec![
@@ -217,7 +524,13 @@ mod pre_compute_start_with_app_tests {
                 (ENV_WORKER_HOST, Some(mock_server_addr_string.as_str())),
             ];
 
-            temp_env::with_vars(env_vars, || start_with_app(&mut mock, CHAIN_TASK_ID))
+            temp_env::with_vars(env_vars, || {
+                start_with_app(
+                    &mut mock,
+                    &TaskChallenge::new(CHAIN_TASK_ID),
+                    &CancellationToken::new(),
+                )
+            })
         })
         .await
         .expect("Blocking task panicked");
@@ -229,13 +542,167 @@ mod pre_compute_start_with_app_tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn start_reports_download_failure_details() {
+        let mock_server = MockServer::start().await;
+        let config_fingerprint =
+            temp_env::with_vars_unset(Vec::<&str>::new(), diagnostics::config_fingerprint);
+
+        let expected_exit_message_payload = json!({
+            "schemaVersion": SCHEMA_VERSION,
+            "cause": ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+            "code": ReplicateStatusCause::PreComputeInputFileDownloadFailed.code(),
+            "hint": ReplicateStatusCause::PreComputeInputFileDownloadFailed.user_hint(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "downloadFailure": {
+                "url": "https://host/input.txt",
+                "inputFileIndex": 1,
+                "httpStatus": 404,
+                "attempts": 3
+            },
+            "configFingerprint": config_fingerprint
+        });
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .and(body_json(expected_exit_message_payload))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mock_server_addr_string = mock_server.address().to_string();
+
+        let mut mock = MockPreComputeAppTrait::new();
+        mock.expect_run()
+            .returning(|| Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed));
+        mock.expect_download_failure().returning(|| {
+            Some(DownloadFailure {
+                url: "https://host/input.txt".to_string(),
+                input_file_index: Some(1),
+                http_status: Some(404),
+                attempts: 3,
+            })
+        });
+
+        let result_code = tokio::task::spawn_blocking(move || {
+            let env_vars = vec![
+                (ENV_SIGN_WORKER_ADDRESS, Some(WORKER_ADDRESS)),
+                (
+                    ENV_SIGN_TEE_CHALLENGE_PRIVATE_KEY,
+                    Some(ENCLAVE_CHALLENGE_PRIVATE_KEY),
+                ),
+                (ENV_WORKER_HOST, Some(mock_server_addr_string.as_str())),
+            ];
+
+            temp_env::with_vars(env_vars, || {
+                start_with_app(
+                    &mut mock,
+                    &TaskChallenge::new(CHAIN_TASK_ID),
+                    &CancellationToken::new(),
+                )
+            })
+        })
+        .await
+        .expect("Blocking task panicked");
+
+        assert_eq!(result_code, ExitMode::ReportedFailure);
+    }
+
+    #[test]
+    fn start_writes_exit_summary_when_task_id_missing() {
+        let mut summary_output = Vec::new();
+
+        let exit_mode = temp_env::with_var(ENV_IEXEC_TASK_ID, None::<&str>, || {
+            start_with_summary_output(&mut summary_output)
+        });
+
+        assert_eq!(exit_mode, ExitMode::InitializationFailure);
+        let expected = ExitSummary::failure(
+            ExitMode::InitializationFailure,
+            String::new(),
+            ReplicateStatusCause::PreComputeTaskIdMissing,
+        );
+        assert_eq!(
+            String::from_utf8(summary_output).unwrap(),
+            format!("{}\n", expected.line().unwrap())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn report_panic_sends_unknown_issue_with_panic_detail() {
+        let mock_server = MockServer::start().await;
+        let config_fingerprint = temp_env::with_vars(
+            [(ENV_IEXEC_TASK_ID, Some(CHAIN_TASK_ID))],
+            diagnostics::config_fingerprint,
+        );
+
+        let expected_exit_message_payload = json!({
+            "schemaVersion": SCHEMA_VERSION,
+            "cause": ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            "code": "PRE-999",
+            "hint": ReplicateStatusCause::PreComputeFailedUnknownIssue.user_hint(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "detail": "panic",
+            "configFingerprint": config_fingerprint
+        });
+
+        Mock::given(method("POST"))
+            .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/exit")))
+            .and(body_json(expected_exit_message_payload))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mock_server_addr_string = mock_server.address().to_string();
+
+        let result_code = tokio::task::spawn_blocking(move || {
+            let env_vars = vec![
+                (ENV_IEXEC_TASK_ID, Some(CHAIN_TASK_ID)),
+                (ENV_SIGN_WORKER_ADDRESS, Some(WORKER_ADDRESS)),
+                (
+                    ENV_SIGN_TEE_CHALLENGE_PRIVATE_KEY,
+                    Some(ENCLAVE_CHALLENGE_PRIVATE_KEY),
+                ),
+                (ENV_WORKER_HOST, Some(mock_server_addr_string.as_str())),
+            ];
+
+            temp_env::with_vars(env_vars, report_panic)
+        })
+        .await
+        .expect("Blocking task panicked");
+
+        assert_eq!(result_code, ExitMode::ReportedFailure);
+    }
+
+    #[test]
+    fn report_panic_fails_without_task_id() {
+        temp_env::with_vars_unset(vec![ENV_IEXEC_TASK_ID], || {
+            assert_eq!(report_panic(), ExitMode::UnreportedFailure);
+        });
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn start_succeeds_when_send_exit_cause_api_success() {
         let mock_server = MockServer::start().await;
 
-        let expected_cause_enum = ReplicateStatusCause::PreComputeFailedUnknownIssue;
+        let config_fingerprint = temp_env::with_vars(
+            [
+                (ENV_IEXEC_TASK_ID, Some(CHAIN_TASK_ID)),
+                (IEXEC_INPUT_FILES_NUMBER, Some("0")),
+                (IEXEC_PRE_COMPUTE_OUT, Some(OUTPUT_DIR)),
+            ],
+            diagnostics::config_fingerprint,
+        );
+        let expected_cause_enum = ReplicateStatusCause::PreComputeOutputFolderNotFound;
         let expected_exit_message_payload = json!({
-            "cause": expected_cause_enum // Relies on ReplicateStatusCause's Serialize impl
+            "schemaVersion": SCHEMA_VERSION,
+            "cause": expected_cause_enum, // Relies on ReplicateStatusCause's Serialize impl
+            "code": expected_cause_enum.code(),
+            "hint": expected_cause_enum.user_hint(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "configFingerprint": config_fingerprint,
         });
 
         // Mock the worker API to return success
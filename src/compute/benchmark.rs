@@ -0,0 +1,200 @@
+use crate::compute::app_runner::ExitMode;
+use crate::compute::utils::crypto_utils::{
+    AES_IV_LENGTH, AES_KEY_LENGTH, decrypt_aes_cbc, encrypt_aes256_cbc,
+};
+use crate::compute::utils::file_utils::write_file;
+use crate::compute::utils::hash_utils::sha256_from_bytes;
+use bytes::Bytes;
+use log::{error, info};
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+const DEFAULT_BENCH_SIZES: &[usize] = &[1 << 20, 16 << 20, 128 << 20];
+const BENCH_KEY: [u8; AES_KEY_LENGTH] = [0x42; AES_KEY_LENGTH];
+const BENCH_IV: [u8; AES_IV_LENGTH] = [0x24; AES_IV_LENGTH];
+
+/// Throughput measured for one synthetic dataset size.
+#[cfg_attr(test, derive(Debug))]
+pub struct BenchmarkResult {
+    pub size: usize,
+    pub decryption: Duration,
+    pub hashing: Duration,
+    pub write: Duration,
+}
+
+impl BenchmarkResult {
+    fn throughput(&self, duration: Duration) -> f64 {
+        let seconds = duration.as_secs_f64().max(f64::EPSILON);
+        self.size as f64 / (1024.0 * 1024.0) / seconds
+    }
+}
+
+/// Parses a comma-separated list of sizes such as `1M,64M,1G` into byte counts.
+///
+/// Sizes are plain byte counts, optionally suffixed with `K`, `M` or `G` (powers of 1024).
+///
+/// # Returns
+///
+/// * `Some(Vec<usize>)` with one entry per size if every value is valid and non-zero.
+/// * `None` if the list is empty or any value cannot be parsed.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(parse_sizes("1K,2M"), Some(vec![1024, 2 * 1024 * 1024]));
+/// ```
+pub fn parse_sizes(sizes: &str) -> Option<Vec<usize>> {
+    let parsed: Option<Vec<usize>> = sizes
+        .split(',')
+        .map(|size| {
+            let size = size.trim().to_uppercase();
+            let (digits, multiplier) = match size.chars().last()? {
+                'K' => (&size[..size.len() - 1], 1 << 10),
+                'M' => (&size[..size.len() - 1], 1 << 20),
+                'G' => (&size[..size.len() - 1], 1 << 30),
+                _ => (size.as_str(), 1),
+            };
+            let value = digits.parse::<usize>().ok()?.checked_mul(multiplier)?;
+            (value > 0).then_some(value)
+        })
+        .collect();
+    parsed.filter(|sizes| !sizes.is_empty())
+}
+
+/// Runs decryption, hashing and write measurements on a synthetic dataset of `size` bytes.
+///
+/// The dataset is filled with pseudo-random bytes, encrypted with AES-256-CBC and then
+/// processed with the same helpers as the real pre-compute pipeline.
+///
+/// # Returns
+///
+/// * `Some(BenchmarkResult)` with the measured durations.
+/// * `None` if decryption or the write to the temporary directory fails.
+pub fn run_benchmark(size: usize) -> Option<BenchmarkResult> {
+    let plain_content = generate_synthetic_content(size);
+    let encrypted_content = Bytes::from(encrypt_aes256_cbc(&BENCH_KEY, &BENCH_IV, &plain_content));
+
+    let start = Instant::now();
+    sha256_from_bytes(&encrypted_content);
+    let hashing = start.elapsed();
+
+    let start = Instant::now();
+    let decrypted_content = decrypt_aes_cbc(&BENCH_KEY, encrypted_content).ok()?;
+    let decryption = start.elapsed();
+
+    let path = env::temp_dir().join(format!("iexec-pre-compute-bench-{size}.bin"));
+    let start = Instant::now();
+    let written = write_file(&decrypted_content, &path, &format!("bench:{size}"));
+    let write = start.elapsed();
+    let _ = fs::remove_file(&path);
+    written.ok()?;
+
+    Some(BenchmarkResult {
+        size,
+        decryption,
+        hashing,
+        write,
+    })
+}
+
+/// Runs the benchmark mode for every requested size and logs the measured throughputs.
+///
+/// # Arguments
+///
+/// * `sizes` - Optional comma-separated list of sizes (see [`parse_sizes`]). Defaults to
+///   1 MiB, 16 MiB and 128 MiB when `None`.
+///
+/// # Returns
+///
+/// * `ExitMode::Success` if every benchmark completed.
+/// * `ExitMode::InitializationFailure` if the sizes are invalid or a benchmark failed.
+///
+/// # Example
+///
+/// ```
+/// let exit_mode = run(Some("1M,64M"));
+/// ```
+pub fn run(sizes: Option<&str>) -> ExitMode {
+    let sizes = match sizes {
+        Some(sizes) => match parse_sizes(sizes) {
+            Some(parsed) => parsed,
+            None => {
+                error!("Invalid benchmark sizes [sizes:{sizes}]");
+                return ExitMode::InitializationFailure;
+            }
+        },
+        None => DEFAULT_BENCH_SIZES.to_vec(),
+    };
+
+    info!("TEE pre-compute benchmark started [sizes:{sizes:?}]");
+    for size in sizes {
+        match run_benchmark(size) {
+            Some(result) => info!(
+                "Benchmark result [size:{size}, decryption:{:.2}MiB/s, hashing:{:.2}MiB/s, write:{:.2}MiB/s]",
+                result.throughput(result.decryption),
+                result.throughput(result.hashing),
+                result.throughput(result.write)
+            ),
+            None => {
+                error!("Benchmark failed [size:{size}]");
+                return ExitMode::InitializationFailure;
+            }
+        }
+    }
+    info!("TEE pre-compute benchmark completed");
+    ExitMode::Success
+}
+
+/// Generates `size` pseudo-random bytes, the same on every run.
+pub fn generate_synthetic_content(size: usize) -> Vec<u8> {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    (0..size)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // region parse_sizes
+    #[test]
+    fn parse_sizes_accepts_suffixes() {
+        assert_eq!(
+            parse_sizes("512,1K,2m,1G"),
+            Some(vec![512, 1024, 2 << 20, 1 << 30])
+        );
+    }
+
+    #[test]
+    fn parse_sizes_rejects_invalid_values() {
+        assert_eq!(parse_sizes(""), None);
+        assert_eq!(parse_sizes("1M,abc"), None);
+        assert_eq!(parse_sizes("0"), None);
+    }
+    // endregion
+
+    // region run
+    #[test]
+    fn run_benchmark_measures_small_dataset() {
+        let result = run_benchmark(4096).expect("Benchmark should succeed");
+        assert_eq!(result.size, 4096);
+    }
+
+    #[test]
+    fn run_fails_with_invalid_sizes() {
+        assert_eq!(run(Some("not-a-size")), ExitMode::InitializationFailure);
+    }
+
+    #[test]
+    fn run_succeeds_with_valid_sizes() {
+        assert_eq!(run(Some("1K")), ExitMode::Success);
+    }
+    // endregion
+}
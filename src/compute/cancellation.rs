@@ -0,0 +1,80 @@
+use crate::compute::app_runner::ExitMode;
+use crate::compute::errors::ReplicateStatusCause;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+/// Shared flag asking the pipeline to stop at its next safe point.
+///
+/// The token is cancelled by the [`Supervisor`](crate::compute::supervisor::Supervisor) when
+/// the task times out or receives a termination signal. Downloads check it between chunks and
+/// the pipeline between steps, so that the task stops cleanly instead of being killed while a
+/// file is half written. Clones share the same state.
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    exit_mode: Arc<OnceLock<ExitMode>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token, the process being expected to exit with `exit_mode`.
+    ///
+    /// Only the first cancellation is kept, later calls have no effect.
+    pub fn cancel(&self, exit_mode: ExitMode) {
+        let _ = self.exit_mode.set(exit_mode);
+    }
+
+    /// Returns the exit mode of the process if the token has been cancelled.
+    pub fn exit_mode(&self) -> Option<ExitMode> {
+        self.exit_mode.get().copied()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.exit_mode.get().is_some()
+    }
+
+    /// Checks the token at a safe point of the pipeline.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the pipeline can go on.
+    /// * `Err(ReplicateStatusCause::PreComputeCancelled)` if the token has been cancelled.
+    pub fn check(&self) -> Result<(), ReplicateStatusCause> {
+        if self.is_cancelled() {
+            return Err(ReplicateStatusCause::PreComputeCancelled);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("is_cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_is_shared_by_clones_and_keeps_first_exit_mode() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert_eq!(clone.check(), Ok(()));
+
+        token.cancel(ExitMode::Timeout);
+        token.cancel(ExitMode::Aborted);
+
+        assert!(clone.is_cancelled());
+        assert_eq!(clone.exit_mode(), Some(ExitMode::Timeout));
+        assert_eq!(
+            clone.check(),
+            Err(ReplicateStatusCause::PreComputeCancelled)
+        );
+    }
+}
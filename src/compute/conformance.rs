@@ -0,0 +1,592 @@
+use crate::compute::app_runner::{self, ExitMode};
+use crate::compute::benchmark::{generate_synthetic_content, parse_sizes};
+use crate::compute::schema;
+use crate::compute::service::{self, JobRequest};
+use crate::compute::utils::crypto_utils::{encrypt_aes256_cbc, generate_aes256_key_and_iv};
+use crate::compute::utils::env_utils::TeeSessionEnvironmentVariable;
+use crate::compute::utils::hash_utils::{sha256, sha256_from_bytes};
+use base64::{Engine as _, engine::general_purpose};
+use log::{error, info};
+use rand::rngs::OsRng;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const DEFAULT_CONFORMANCE_SIZES: &[usize] = &[1 << 10, 1 << 20, 16 << 20];
+const DATASET_FILENAME: &str = "conformance-dataset.bin";
+const INPUT_FILE_PATH: &str = "/inputs/input.txt";
+const INPUT_FILE_CONTENT: &[u8] = b"iExec pre-compute conformance input file";
+/// Throwaway key signing the exit reports sent to the mock worker, which does not check them.
+const HARNESS_CHALLENGE_PRIVATE_KEY: &str =
+    "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+const HARNESS_WORKER_ADDRESS: &str = "0x0000000000000000000000000000000000000001";
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Request received by a [`LocalServer`].
+#[derive(Clone, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Vec<u8>,
+}
+
+/// Minimal HTTP server bound to the loopback interface, standing for the file servers and the
+/// worker API during a conformance run.
+///
+/// `GET` and `HEAD` requests are answered with the file registered for their path, or `404`,
+/// and `POST` requests are accepted with an empty `200`. Every request is recorded, see
+/// [`requests`](Self::requests). The server is stopped when dropped.
+pub struct LocalServer {
+    address: SocketAddr,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LocalServer {
+    /// Starts a server serving `files`, indexed by their path, on a free loopback port.
+    pub fn start(files: HashMap<String, Vec<u8>>) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let files = Arc::new(files);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let requests = requests.clone();
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let Ok(stream) = stream else { continue };
+                    let files = files.clone();
+                    let requests = requests.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, &files, &requests) {
+                            error!("Conformance server failed to answer a request: {e}");
+                        }
+                    });
+                }
+            })
+        };
+        Ok(LocalServer {
+            address,
+            requests,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the `host:port` the server listens on.
+    pub fn host(&self) -> String {
+        self.address.to_string()
+    }
+
+    /// Returns the URL of `path` on this server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.address)
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes the accepting thread up so that it sees the stop flag.
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    files: &HashMap<String, Vec<u8>>,
+    requests: &Mutex<Vec<RecordedRequest>>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let (status, content) = match (method.as_str(), files.get(&path)) {
+        ("GET" | "HEAD", Some(content)) => ("200 OK", content.as_slice()),
+        ("POST", _) => ("200 OK", &[][..]),
+        _ => ("404 Not Found", &[][..]),
+    };
+    requests
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(RecordedRequest { method, path, body });
+
+    let mut writer = &stream;
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        content.len()
+    )?;
+    if !request_line.starts_with("HEAD") {
+        writer.write_all(content)?;
+    }
+    writer.flush()
+}
+
+/// Outcome of one conformance case.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CaseResult {
+    pub name: String,
+    pub expected_exit_code: i32,
+    pub exit_code: i32,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub duration_ms: u128,
+}
+
+/// Conformance report printed once every case has run.
+///
+/// The JSON structure of the report is:
+/// ```json
+/// {"schemaVersion":"1.0","passed":1,"failed":0,"cases":[{"name":"dataset-1024","expectedExitCode":0,"exitCode":0,"passed":true,"durationMs":12}]}
+/// ```
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ConformanceReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub cases: Vec<CaseResult>,
+}
+
+/// What a case checks once the pipeline is done.
+enum Expectation {
+    /// The pipeline writes `content` to the `file_name` file of the output folder.
+    Output { file_name: String, content: Vec<u8> },
+    /// The pipeline reports its failure to the worker API.
+    ExitReported,
+}
+
+struct ConformanceCase {
+    name: String,
+    env: HashMap<String, String>,
+    expected: ExitMode,
+    expectation: Expectation,
+}
+
+/// Runs the full pre-compute pipeline against a local mock worker and file server, for
+/// synthetic encrypted datasets of every size of `sizes` and a few failure scenarios.
+///
+/// Each case runs [`app_runner::start`] with its own session environment, applied like a
+/// service mode job, and in its own output folder under the temporary directory.
+///
+/// # Returns
+///
+/// * `Ok(ConformanceReport)` with the outcome of every case.
+/// * `Err(io::Error)` if the local servers or the output folders could not be set up.
+pub fn run_conformance(sizes: &[usize]) -> io::Result<ConformanceReport> {
+    let mut files = HashMap::new();
+    let mut datasets = Vec::new();
+    for &size in sizes {
+        let plain_content = generate_synthetic_content(size);
+        let (key, iv) = generate_aes256_key_and_iv(&mut OsRng);
+        let encrypted_content = encrypt_aes256_cbc(&key, &iv, &plain_content);
+        let path = format!("/datasets/{size}.bin");
+        datasets.push(SyntheticDataset {
+            size,
+            path: path.clone(),
+            key: general_purpose::STANDARD.encode(key),
+            checksum: sha256_from_bytes(&encrypted_content),
+            plain_content,
+        });
+        files.insert(path, encrypted_content);
+    }
+    files.insert(INPUT_FILE_PATH.to_string(), INPUT_FILE_CONTENT.to_vec());
+    let file_server = LocalServer::start(files)?;
+    let worker = LocalServer::start(HashMap::new())?;
+
+    let mut cases: Vec<ConformanceCase> = datasets
+        .iter()
+        .map(|dataset| ConformanceCase {
+            name: format!("dataset-{}", dataset.size),
+            env: dataset_env(
+                &file_server.url(&dataset.path),
+                &dataset.key,
+                &dataset.checksum,
+            ),
+            expected: ExitMode::Success,
+            expectation: Expectation::Output {
+                file_name: DATASET_FILENAME.to_string(),
+                content: dataset.plain_content.clone(),
+            },
+        })
+        .collect();
+    let input_file_url = file_server.url(INPUT_FILE_PATH);
+    cases.push(ConformanceCase {
+        name: "input-file".to_string(),
+        env: HashMap::from([
+            (
+                TeeSessionEnvironmentVariable::IsDatasetRequired.name(),
+                "false".to_string(),
+            ),
+            (
+                TeeSessionEnvironmentVariable::IexecInputFilesNumber.name(),
+                "1".to_string(),
+            ),
+            (
+                TeeSessionEnvironmentVariable::IexecInputFileUrlPrefix(1).name(),
+                input_file_url.clone(),
+            ),
+        ]),
+        expected: ExitMode::Success,
+        expectation: Expectation::Output {
+            file_name: sha256(input_file_url),
+            content: INPUT_FILE_CONTENT.to_vec(),
+        },
+    });
+    if let Some(dataset) = datasets.first() {
+        cases.push(ConformanceCase {
+            name: "dataset-checksum-mismatch".to_string(),
+            env: dataset_env(
+                &file_server.url(&dataset.path),
+                &dataset.key,
+                &format!("0x{}", "00".repeat(32)),
+            ),
+            expected: ExitMode::ReportedFailure,
+            expectation: Expectation::ExitReported,
+        });
+        cases.push(ConformanceCase {
+            name: "dataset-not-found".to_string(),
+            env: dataset_env(
+                &file_server.url("/datasets/missing.bin"),
+                &dataset.key,
+                &dataset.checksum,
+            ),
+            expected: ExitMode::ReportedFailure,
+            expectation: Expectation::ExitReported,
+        });
+    }
+
+    let work_dir = env::temp_dir().join(format!("iexec-pre-compute-conformance-{}", process::id()));
+    let mut results = Vec::new();
+    for (index, case) in cases.into_iter().enumerate() {
+        let output_dir = work_dir.join(&case.name);
+        fs::create_dir_all(&output_dir)?;
+        results.push(run_case(case, index + 1, &output_dir, &worker));
+    }
+    if let Err(e) = fs::remove_dir_all(&work_dir) {
+        error!(
+            "Failed to remove conformance output folders [path:{}]: {e}",
+            work_dir.display()
+        );
+    }
+
+    let passed = results.iter().filter(|result| result.passed).count();
+    Ok(ConformanceReport {
+        passed,
+        failed: results.len() - passed,
+        cases: results,
+    })
+}
+
+/// Runs the pipeline for `case` as the `index`-th task, then checks its outcome.
+fn run_case(
+    case: ConformanceCase,
+    index: usize,
+    output_dir: &Path,
+    worker: &LocalServer,
+) -> CaseResult {
+    let chain_task_id = format!("0x{index:064x}");
+    let mut env = HashMap::from([
+        (
+            TeeSessionEnvironmentVariable::IexecTaskId.name(),
+            chain_task_id.clone(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::IexecPreComputeOut.name(),
+            output_dir.to_string_lossy().into_owned(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::IexecInputFilesNumber.name(),
+            "0".to_string(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::SignTeeChallengePrivateKey.name(),
+            HARNESS_CHALLENGE_PRIVATE_KEY.to_string(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::SignWorkerAddress.name(),
+            HARNESS_WORKER_ADDRESS.to_string(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::WorkerHostEnvVar.name(),
+            worker.host(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::IexecWorkerApiCompression.name(),
+            "false".to_string(),
+        ),
+    ]);
+    // Variables of the case take precedence over the common ones.
+    env.extend(case.env);
+    let job = JobRequest {
+        schema_version: None,
+        env,
+    };
+
+    info!("Conformance case started [case:{}]", case.name);
+    let start = Instant::now();
+    let exit_code = service::run_with_env(&job, &mut || {
+        app_runner::start_with_summary_output(&mut io::stderr())
+    })
+    .exit_code;
+    let duration_ms = start.elapsed().as_millis();
+    let expected_exit_code = case.expected as i32;
+    let detail =
+        check_exit_code(expected_exit_code, exit_code).or_else(|| match &case.expectation {
+            Expectation::Output { file_name, content } => {
+                check_output(&output_dir.join(file_name), content)
+            }
+            Expectation::ExitReported => check_exit_reported(worker, &chain_task_id),
+        });
+    let passed = detail.is_none();
+    match &detail {
+        None => info!(
+            "Conformance case passed [case:{}, durationMs:{duration_ms}]",
+            case.name
+        ),
+        Some(detail) => error!(
+            "Conformance case failed [case:{}, durationMs:{duration_ms}]: {detail}",
+            case.name
+        ),
+    }
+    CaseResult {
+        name: case.name,
+        expected_exit_code,
+        exit_code,
+        passed,
+        detail,
+        duration_ms,
+    }
+}
+
+fn check_exit_code(expected: i32, actual: i32) -> Option<String> {
+    (expected != actual).then(|| format!("expected exit code {expected}, got {actual}"))
+}
+
+fn check_output(path: &Path, expected: &[u8]) -> Option<String> {
+    match fs::read(path) {
+        Ok(content) if content == expected => None,
+        Ok(content) => Some(format!(
+            "unexpected content in {} ({} bytes instead of {})",
+            path.display(),
+            content.len(),
+            expected.len()
+        )),
+        Err(e) => Some(format!("missing output file {}: {e}", path.display())),
+    }
+}
+
+fn check_exit_reported(worker: &LocalServer, chain_task_id: &str) -> Option<String> {
+    let exit_path = format!("/compute/pre/{chain_task_id}/exit");
+    let reported = worker.requests().into_iter().any(|request| {
+        request.method == "POST"
+            && request.path == exit_path
+            && serde_json::from_slice::<Value>(&request.body)
+                .is_ok_and(|body| body["cause"].is_string())
+    });
+    (!reported).then(|| "no exit cause was reported to the worker API".to_string())
+}
+
+/// Runs the conformance mode and prints the [`ConformanceReport`] as JSON to stdout.
+///
+/// The exit summary of every case is written to stderr, with the logs, so that stdout only
+/// carries the report.
+///
+/// # Arguments
+///
+/// * `sizes` - Optional comma-separated list of dataset sizes (see [`parse_sizes`]).
+///   Defaults to 1 KiB, 1 MiB and 16 MiB when `None`.
+///
+/// # Returns
+///
+/// * `ExitMode::Success` if every case passed.
+/// * `ExitMode::InitializationFailure` if the sizes are invalid, the harness could not be
+///   set up or a case failed.
+///
+/// # Example
+///
+/// ```
+/// let exit_mode = run(Some("1K,64M"));
+/// ```
+pub fn run(sizes: Option<&str>) -> ExitMode {
+    let sizes = match sizes {
+        Some(sizes) => match parse_sizes(sizes) {
+            Some(parsed) => parsed,
+            None => {
+                error!("Invalid conformance dataset sizes [sizes:{sizes}]");
+                return ExitMode::InitializationFailure;
+            }
+        },
+        None => DEFAULT_CONFORMANCE_SIZES.to_vec(),
+    };
+
+    info!("TEE pre-compute conformance run started [sizes:{sizes:?}]");
+    let report = match run_conformance(&sizes) {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Failed to set up the conformance harness: {e}");
+            return ExitMode::InitializationFailure;
+        }
+    };
+    match schema::to_string(&report) {
+        Ok(json) => println!("{json}"),
+        Err(e) => error!("Failed to serialize conformance report: {e}"),
+    }
+    info!(
+        "TEE pre-compute conformance run completed [passed:{}, failed:{}]",
+        report.passed, report.failed
+    );
+    if report.failed == 0 {
+        ExitMode::Success
+    } else {
+        ExitMode::InitializationFailure
+    }
+}
+
+struct SyntheticDataset {
+    size: usize,
+    path: String,
+    key: String,
+    checksum: String,
+    plain_content: Vec<u8>,
+}
+
+fn dataset_env(url: &str, key: &str, checksum: &str) -> HashMap<String, String> {
+    HashMap::from([
+        (
+            TeeSessionEnvironmentVariable::IsDatasetRequired.name(),
+            "true".to_string(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::IexecDatasetUrl.name(),
+            url.to_string(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::IexecDatasetKey.name(),
+            key.to_string(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::IexecDatasetChecksum.name(),
+            checksum.to_string(),
+        ),
+        (
+            TeeSessionEnvironmentVariable::IexecDatasetFilename.name(),
+            DATASET_FILENAME.to_string(),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // region LocalServer
+    #[test]
+    fn local_server_serves_files_and_records_requests() {
+        let server = LocalServer::start(HashMap::from([(
+            "/file.bin".to_string(),
+            b"content".to_vec(),
+        )]))
+        .unwrap();
+        let client = reqwest::blocking::Client::new();
+
+        let served = client.get(server.url("/file.bin")).send().unwrap();
+        assert_eq!(served.status(), 200);
+        assert_eq!(served.bytes().unwrap().as_ref(), b"content");
+        let missing = client.get(server.url("/missing.bin")).send().unwrap();
+        assert_eq!(missing.status(), 404);
+        let posted = client
+            .post(server.url("/compute/pre/0x1/exit"))
+            .body("{}")
+            .send()
+            .unwrap();
+        assert_eq!(posted.status(), 200);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2].method, "POST");
+        assert_eq!(requests[2].path, "/compute/pre/0x1/exit");
+        assert_eq!(requests[2].body, b"{}");
+    }
+    // endregion
+
+    // region run
+    #[test]
+    fn run_conformance_passes_every_case() {
+        let report = temp_env::with_vars_unset(
+            vec![
+                TeeSessionEnvironmentVariable::IexecTaskId.name(),
+                TeeSessionEnvironmentVariable::IexecPreComputeOut.name(),
+                TeeSessionEnvironmentVariable::WorkerHostEnvVar.name(),
+            ],
+            || run_conformance(&[1024]).unwrap(),
+        );
+
+        let names: Vec<&str> = report.cases.iter().map(|case| case.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "dataset-1024",
+                "input-file",
+                "dataset-checksum-mismatch",
+                "dataset-not-found"
+            ]
+        );
+        assert_eq!(report.failed, 0, "{report:?}");
+        assert_eq!(report.passed, 4);
+    }
+
+    #[test]
+    fn run_fails_with_invalid_sizes() {
+        assert_eq!(run(Some("not-a-size")), ExitMode::InitializationFailure);
+    }
+    // endregion
+}
@@ -7,6 +7,8 @@ use thiserror::Error;
 pub enum ReplicateStatusCause {
     #[error("At least one input file URL is missing")]
     PreComputeAtLeastOneInputFileUrlMissing,
+    #[error("Pre-compute was cancelled before completion")]
+    PreComputeCancelled,
     #[error("Dataset checksum related environment variable is missing")]
     PreComputeDatasetChecksumMissing,
     #[error("Failed to decrypt dataset")]
@@ -17,30 +19,386 @@ pub enum ReplicateStatusCause {
     PreComputeDatasetFilenameMissing,
     #[error("Dataset key related environment variable is missing")]
     PreComputeDatasetKeyMissing,
+    #[error("Dataset exceeds the maximum allowed size")]
+    PreComputeDatasetTooLarge,
     #[error("Dataset URL related environment variable is missing")]
     PreComputeDatasetUrlMissing,
     #[error("Unexpected error occurred")]
     PreComputeFailedUnknownIssue,
+    #[error("At least one host of the task could not be resolved")]
+    PreComputeHostResolutionFailed,
     #[error("Invalid TEE signature")]
     PreComputeInvalidTeeSignature,
     #[error("IS_DATASET_REQUIRED environment variable is missing")]
     PreComputeIsDatasetRequiredMissing,
     #[error("Input files download failed")]
     PreComputeInputFileDownloadFailed,
+    #[error("Failed to download or parse input files checksums file")]
+    PreComputeInputFilesChecksumDownloadFailed,
     #[error("Input files number related environment variable is missing")]
     PreComputeInputFilesNumberMissing,
+    #[error("Unsupported checksum algorithm")]
+    PreComputeInvalidChecksumAlgorithm,
     #[error("Invalid dataset checksum")]
     PreComputeInvalidDatasetChecksum,
+    #[error("Invalid input file checksum")]
+    PreComputeInvalidInputFileChecksum,
+    #[error("Invalid request headers of the input files")]
+    PreComputeInvalidInputFileHeaders,
+    #[error("At least one input file URL is malformed or has an unsupported scheme")]
+    PreComputeInvalidInputFileUrl,
+    #[error("Invalid age recipient of the pre-compute artifacts")]
+    PreComputeInvalidArtifactsRecipient,
+    #[error("Configuration signature is missing or invalid")]
+    PreComputeInvalidConfigSignature,
+    #[error("Task ID is not a 0x-prefixed hexadecimal value")]
+    PreComputeInvalidTaskId,
+    #[error("Not enough disk space to write the output files")]
+    PreComputeNotEnoughDiskSpace,
     #[error("Input files number related environment variable is missing")]
     PreComputeOutputFolderNotFound,
     #[error("Output path related environment variable is missing")]
     PreComputeOutputPathMissing,
+    #[error("At least one URL failed the pre-flight check")]
+    PreComputePreflightCheckFailed,
     #[error("Failed to write plain dataset file")]
     PreComputeSavingPlainDatasetFailed,
     #[error("Task ID related environment variable is missing")]
     PreComputeTaskIdMissing,
     #[error("TEE challenge private key related environment variable is missing")]
     PreComputeTeeChallengePrivateKeyMissing,
+    #[error("Number of input files exceeds the maximum accepted by the worker")]
+    PreComputeTooManyInputFiles,
+    #[error("Dataset URL is neither a supported URL nor a supported multiaddr")]
+    PreComputeUnsupportedDatasetAddress,
     #[error("Worker address related environment variable is missing")]
     PreComputeWorkerAddressMissing,
+    #[error("Failed to create the temporary workspace of the task")]
+    PreComputeWorkspaceCreationFailed,
+}
+
+impl ReplicateStatusCause {
+    /// Returns the stable machine code of the cause, written in logs and exit messages.
+    ///
+    /// Codes never change nor get reused once released, unlike variant names and messages,
+    /// so that users and tooling can rely on them. They are grouped by range:
+    /// - `PRE-1xx`: missing or invalid configuration
+    /// - `PRE-2xx`: dataset download, verification and decryption
+    /// - `PRE-3xx`: input files download and verification
+    /// - `PRE-4xx`: output folder
+    /// - `PRE-9xx`: runtime failures
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// assert_eq!(ReplicateStatusCause::PreComputeDatasetUrlMissing.code(), "PRE-109");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            ReplicateStatusCause::PreComputeTaskIdMissing => "PRE-101",
+            ReplicateStatusCause::PreComputeWorkerAddressMissing => "PRE-102",
+            ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing => "PRE-103",
+            ReplicateStatusCause::PreComputeIsDatasetRequiredMissing => "PRE-104",
+            ReplicateStatusCause::PreComputeOutputPathMissing => "PRE-105",
+            ReplicateStatusCause::PreComputeInputFilesNumberMissing => "PRE-106",
+            ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing => "PRE-107",
+            ReplicateStatusCause::PreComputeInvalidChecksumAlgorithm => "PRE-108",
+            ReplicateStatusCause::PreComputeDatasetUrlMissing => "PRE-109",
+            ReplicateStatusCause::PreComputeDatasetKeyMissing => "PRE-110",
+            ReplicateStatusCause::PreComputeDatasetChecksumMissing => "PRE-111",
+            ReplicateStatusCause::PreComputeDatasetFilenameMissing => "PRE-112",
+            ReplicateStatusCause::PreComputeInvalidArtifactsRecipient => "PRE-113",
+            ReplicateStatusCause::PreComputeInvalidConfigSignature => "PRE-114",
+            ReplicateStatusCause::PreComputeUnsupportedDatasetAddress => "PRE-115",
+            ReplicateStatusCause::PreComputeTooManyInputFiles => "PRE-116",
+            ReplicateStatusCause::PreComputeInvalidInputFileHeaders => "PRE-117",
+            ReplicateStatusCause::PreComputeInvalidTaskId => "PRE-118",
+            ReplicateStatusCause::PreComputeInvalidInputFileUrl => "PRE-119",
+            ReplicateStatusCause::PreComputeDatasetDownloadFailed => "PRE-201",
+            ReplicateStatusCause::PreComputeInvalidDatasetChecksum => "PRE-202",
+            ReplicateStatusCause::PreComputeDatasetDecryptionFailed => "PRE-203",
+            ReplicateStatusCause::PreComputeDatasetTooLarge => "PRE-204",
+            ReplicateStatusCause::PreComputeSavingPlainDatasetFailed => "PRE-205",
+            ReplicateStatusCause::PreComputeInputFileDownloadFailed => "PRE-301",
+            ReplicateStatusCause::PreComputeInvalidInputFileChecksum => "PRE-302",
+            ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed => "PRE-303",
+            ReplicateStatusCause::PreComputePreflightCheckFailed => "PRE-304",
+            ReplicateStatusCause::PreComputeHostResolutionFailed => "PRE-305",
+            ReplicateStatusCause::PreComputeOutputFolderNotFound => "PRE-401",
+            ReplicateStatusCause::PreComputeNotEnoughDiskSpace => "PRE-402",
+            ReplicateStatusCause::PreComputeWorkspaceCreationFailed => "PRE-403",
+            ReplicateStatusCause::PreComputeInvalidTeeSignature => "PRE-901",
+            ReplicateStatusCause::PreComputeCancelled => "PRE-902",
+            ReplicateStatusCause::PreComputeFailedUnknownIssue => "PRE-999",
+        }
+    }
+
+    /// Returns the cause reported to the worker in exit messages.
+    ///
+    /// The worker deserializes causes into its own enum and rejects the values it does not
+    /// know, which are all the causes added after its `PRE_COMPUTE_*` causes. These are
+    /// reported as the closest cause it knows, [`code`](Self::code) still telling them apart.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// assert_eq!(
+    ///     ReplicateStatusCause::PreComputeDatasetTooLarge.worker_cause(),
+    ///     ReplicateStatusCause::PreComputeDatasetDownloadFailed
+    /// );
+    /// ```
+    pub fn worker_cause(&self) -> ReplicateStatusCause {
+        match self {
+            ReplicateStatusCause::PreComputeInvalidTaskId => {
+                ReplicateStatusCause::PreComputeTaskIdMissing
+            }
+            ReplicateStatusCause::PreComputeTooManyInputFiles => {
+                ReplicateStatusCause::PreComputeInputFilesNumberMissing
+            }
+            ReplicateStatusCause::PreComputeInvalidInputFileUrl => {
+                ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing
+            }
+            ReplicateStatusCause::PreComputeUnsupportedDatasetAddress => {
+                ReplicateStatusCause::PreComputeDatasetUrlMissing
+            }
+            ReplicateStatusCause::PreComputeDatasetTooLarge => {
+                ReplicateStatusCause::PreComputeDatasetDownloadFailed
+            }
+            ReplicateStatusCause::PreComputeInvalidInputFileHeaders
+            | ReplicateStatusCause::PreComputeInvalidInputFileChecksum
+            | ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed
+            | ReplicateStatusCause::PreComputePreflightCheckFailed
+            | ReplicateStatusCause::PreComputeHostResolutionFailed => {
+                ReplicateStatusCause::PreComputeInputFileDownloadFailed
+            }
+            ReplicateStatusCause::PreComputeWorkspaceCreationFailed => {
+                ReplicateStatusCause::PreComputeOutputFolderNotFound
+            }
+            ReplicateStatusCause::PreComputeInvalidChecksumAlgorithm
+            | ReplicateStatusCause::PreComputeInvalidArtifactsRecipient
+            | ReplicateStatusCause::PreComputeInvalidConfigSignature
+            | ReplicateStatusCause::PreComputeNotEnoughDiskSpace
+            | ReplicateStatusCause::PreComputeCancelled => {
+                ReplicateStatusCause::PreComputeFailedUnknownIssue
+            }
+            cause => cause.clone(),
+        }
+    }
+
+    /// Returns an actionable hint telling users how to fix the most common causes of the
+    /// failure.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let cause = ReplicateStatusCause::PreComputeDatasetDownloadFailed;
+    /// error!("{} [code:{}, hint:{}]", cause, cause.code(), cause.user_hint());
+    /// ```
+    pub fn user_hint(&self) -> &'static str {
+        match self {
+            ReplicateStatusCause::PreComputeTaskIdMissing => "Set IEXEC_TASK_ID in the TEE session",
+            ReplicateStatusCause::PreComputeWorkerAddressMissing => {
+                "Set SIGN_WORKER_ADDRESS in the TEE session"
+            }
+            ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing => {
+                "Set SIGN_TEE_CHALLENGE_PRIVATE_KEY in the TEE session"
+            }
+            ReplicateStatusCause::PreComputeIsDatasetRequiredMissing => {
+                "Set IS_DATASET_REQUIRED to \"true\" or \"false\""
+            }
+            ReplicateStatusCause::PreComputeOutputPathMissing => {
+                "Set IEXEC_PRE_COMPUTE_OUT to the output directory"
+            }
+            ReplicateStatusCause::PreComputeInputFilesNumberMissing => {
+                "Set IEXEC_INPUT_FILES_NUMBER to the number of input files, 0 if none"
+            }
+            ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing => {
+                "Set IEXEC_INPUT_FILE_URL_1 to IEXEC_INPUT_FILE_URL_N, N being IEXEC_INPUT_FILES_NUMBER"
+            }
+            ReplicateStatusCause::PreComputeInvalidChecksumAlgorithm => {
+                "Set IEXEC_CHECKSUM_ALGORITHM to sha256, keccak256, blake3 or cid"
+            }
+            ReplicateStatusCause::PreComputeDatasetUrlMissing => {
+                "Set IEXEC_DATASET_URL when IS_DATASET_REQUIRED is \"true\""
+            }
+            ReplicateStatusCause::PreComputeDatasetKeyMissing => {
+                "Set IEXEC_DATASET_KEY, an age identity or key shares, and check the key encoding"
+            }
+            ReplicateStatusCause::PreComputeDatasetChecksumMissing => {
+                "Set IEXEC_DATASET_CHECKSUM to the checksum of the encrypted dataset"
+            }
+            ReplicateStatusCause::PreComputeDatasetFilenameMissing => {
+                "Set IEXEC_DATASET_FILENAME to the name of the decrypted dataset file"
+            }
+            ReplicateStatusCause::PreComputeInvalidArtifactsRecipient => {
+                "Set IEXEC_PRE_COMPUTE_ARTIFACTS_RECIPIENT to an age1... X25519 public key"
+            }
+            ReplicateStatusCause::PreComputeInvalidConfigSignature => {
+                "Check that IEXEC_PRE_COMPUTE_CONFIG_SIGNATURE signs the session variables with the key of the configuration signer pinned in this build"
+            }
+            ReplicateStatusCause::PreComputeUnsupportedDatasetAddress => {
+                "Set IEXEC_DATASET_URL to an http(s) URL, an /ipfs/<cid> path or an HTTP multiaddr such as /dns4/<host>/tcp/443/https"
+            }
+            ReplicateStatusCause::PreComputeTooManyInputFiles => {
+                "Reduce the number of input files of the task to at most IEXEC_MAX_INPUT_FILES, or run it on another worker"
+            }
+            ReplicateStatusCause::PreComputeInvalidInputFileHeaders => {
+                "Set IEXEC_INPUT_FILE_HEADERS and IEXEC_INPUT_FILE_HEADERS_<N> to JSON objects of valid header names and values, such as {\"Authorization\":\"Bearer <token>\"}"
+            }
+            ReplicateStatusCause::PreComputeInvalidTaskId => {
+                "Set IEXEC_TASK_ID to the 0x-prefixed hexadecimal ID of the task"
+            }
+            ReplicateStatusCause::PreComputeInvalidInputFileUrl => {
+                "Set IEXEC_INPUT_FILE_URL_<N> to absolute http(s), s3, ftp, sftp or data: URLs"
+            }
+            ReplicateStatusCause::PreComputeDatasetDownloadFailed => {
+                "Check that the dataset URL is reachable from the worker, or configure other gateways"
+            }
+            ReplicateStatusCause::PreComputeInvalidDatasetChecksum => {
+                "Check that IEXEC_DATASET_CHECKSUM is well-formed and matches the encrypted dataset"
+            }
+            ReplicateStatusCause::PreComputeDatasetDecryptionFailed => {
+                "Check that the dataset key matches the key used to encrypt the dataset"
+            }
+            ReplicateStatusCause::PreComputeDatasetTooLarge => {
+                "Raise IEXEC_DATASET_MAX_SIZE or IEXEC_DATASET_MAX_EXPANSION_RATIO, or use a smaller dataset"
+            }
+            ReplicateStatusCause::PreComputeSavingPlainDatasetFailed => {
+                "Check that the output directory is writable and has enough free space"
+            }
+            ReplicateStatusCause::PreComputeInputFileDownloadFailed => {
+                "Check that every input file URL is reachable from the worker"
+            }
+            ReplicateStatusCause::PreComputeInvalidInputFileChecksum => {
+                "Check that the input file checksums match the files served at their URLs"
+            }
+            ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed => {
+                "Check that IEXEC_INPUT_FILES_CHECKSUM_URL is reachable and in SHA256SUMS format"
+            }
+            ReplicateStatusCause::PreComputePreflightCheckFailed => {
+                "Check the URLs reported as unreachable, or disable IEXEC_PRE_COMPUTE_PREFLIGHT_CHECK"
+            }
+            ReplicateStatusCause::PreComputeHostResolutionFailed => {
+                "Check the hosts reported as unresolvable and the DNS configuration of the worker"
+            }
+            ReplicateStatusCause::PreComputeOutputFolderNotFound => {
+                "Check that IEXEC_PRE_COMPUTE_OUT points to an existing directory"
+            }
+            ReplicateStatusCause::PreComputeNotEnoughDiskSpace => {
+                "Free disk space on the worker or reduce the size of the dataset and input files"
+            }
+            ReplicateStatusCause::PreComputeWorkspaceCreationFailed => {
+                "Check that the IEXEC_PRE_COMPUTE_OUT directory is writable by the enclave"
+            }
+            ReplicateStatusCause::PreComputeInvalidTeeSignature => {
+                "Check that the TEE challenge private key matches the worker enclave challenge"
+            }
+            ReplicateStatusCause::PreComputeCancelled => {
+                "Raise IEXEC_PRE_COMPUTE_TIMEOUT_SECS or check why the task was terminated"
+            }
+            ReplicateStatusCause::PreComputeFailedUnknownIssue => {
+                "Check the pre-compute logs for details and report the issue if it persists"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const ALL_CAUSES: [ReplicateStatusCause; 35] = [
+        ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing,
+        ReplicateStatusCause::PreComputeCancelled,
+        ReplicateStatusCause::PreComputeDatasetChecksumMissing,
+        ReplicateStatusCause::PreComputeDatasetDecryptionFailed,
+        ReplicateStatusCause::PreComputeDatasetDownloadFailed,
+        ReplicateStatusCause::PreComputeDatasetFilenameMissing,
+        ReplicateStatusCause::PreComputeDatasetKeyMissing,
+        ReplicateStatusCause::PreComputeDatasetTooLarge,
+        ReplicateStatusCause::PreComputeDatasetUrlMissing,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        ReplicateStatusCause::PreComputeHostResolutionFailed,
+        ReplicateStatusCause::PreComputeInvalidTeeSignature,
+        ReplicateStatusCause::PreComputeIsDatasetRequiredMissing,
+        ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+        ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed,
+        ReplicateStatusCause::PreComputeInputFilesNumberMissing,
+        ReplicateStatusCause::PreComputeInvalidChecksumAlgorithm,
+        ReplicateStatusCause::PreComputeInvalidDatasetChecksum,
+        ReplicateStatusCause::PreComputeInvalidInputFileChecksum,
+        ReplicateStatusCause::PreComputeInvalidInputFileHeaders,
+        ReplicateStatusCause::PreComputeInvalidInputFileUrl,
+        ReplicateStatusCause::PreComputeInvalidArtifactsRecipient,
+        ReplicateStatusCause::PreComputeInvalidConfigSignature,
+        ReplicateStatusCause::PreComputeInvalidTaskId,
+        ReplicateStatusCause::PreComputeNotEnoughDiskSpace,
+        ReplicateStatusCause::PreComputeOutputFolderNotFound,
+        ReplicateStatusCause::PreComputeOutputPathMissing,
+        ReplicateStatusCause::PreComputePreflightCheckFailed,
+        ReplicateStatusCause::PreComputeSavingPlainDatasetFailed,
+        ReplicateStatusCause::PreComputeTaskIdMissing,
+        ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing,
+        ReplicateStatusCause::PreComputeTooManyInputFiles,
+        ReplicateStatusCause::PreComputeUnsupportedDatasetAddress,
+        ReplicateStatusCause::PreComputeWorkerAddressMissing,
+        ReplicateStatusCause::PreComputeWorkspaceCreationFailed,
+    ];
+
+    #[test]
+    fn code_is_unique_and_well_formed() {
+        let codes: HashSet<&str> = ALL_CAUSES.iter().map(|cause| cause.code()).collect();
+
+        assert_eq!(codes.len(), ALL_CAUSES.len());
+        for code in codes {
+            let number = code.strip_prefix("PRE-").unwrap();
+            assert_eq!(number.len(), 3);
+            assert!(number.chars().all(|c| c.is_ascii_digit()));
+        }
+        assert_eq!(
+            ReplicateStatusCause::PreComputeFailedUnknownIssue.code(),
+            "PRE-999"
+        );
+    }
+
+    /// Causes known to the worker, which it can deserialize.
+    const WORKER_CAUSES: [ReplicateStatusCause; 19] = [
+        ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing,
+        ReplicateStatusCause::PreComputeDatasetChecksumMissing,
+        ReplicateStatusCause::PreComputeDatasetDecryptionFailed,
+        ReplicateStatusCause::PreComputeDatasetDownloadFailed,
+        ReplicateStatusCause::PreComputeDatasetFilenameMissing,
+        ReplicateStatusCause::PreComputeDatasetKeyMissing,
+        ReplicateStatusCause::PreComputeDatasetUrlMissing,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        ReplicateStatusCause::PreComputeInvalidTeeSignature,
+        ReplicateStatusCause::PreComputeIsDatasetRequiredMissing,
+        ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+        ReplicateStatusCause::PreComputeInputFilesNumberMissing,
+        ReplicateStatusCause::PreComputeInvalidDatasetChecksum,
+        ReplicateStatusCause::PreComputeOutputFolderNotFound,
+        ReplicateStatusCause::PreComputeOutputPathMissing,
+        ReplicateStatusCause::PreComputeSavingPlainDatasetFailed,
+        ReplicateStatusCause::PreComputeTaskIdMissing,
+        ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing,
+        ReplicateStatusCause::PreComputeWorkerAddressMissing,
+    ];
+
+    #[test]
+    fn worker_cause_is_known_to_worker() {
+        for cause in ALL_CAUSES {
+            assert!(WORKER_CAUSES.contains(&cause.worker_cause()), "{cause:?}");
+        }
+        for cause in WORKER_CAUSES {
+            assert_eq!(cause.worker_cause(), cause);
+        }
+    }
+
+    #[test]
+    fn user_hint_is_provided_for_every_cause() {
+        for cause in ALL_CAUSES {
+            assert!(!cause.user_hint().is_empty(), "{cause:?}");
+        }
+    }
 }
@@ -1,248 +1,922 @@
+use crate::api::worker_api::{
+    ComputeStage, DownloadFailure, FileProgress, FileStatus, StartNotification, WorkerApiClient,
+};
+use crate::compute::cancellation::CancellationToken;
+use crate::compute::diagnostics::{self, NetworkSummary};
 use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::events::{self, Event};
 use crate::compute::pre_compute_args::PreComputeArgs;
-use crate::compute::utils::file_utils::{download_file, download_from_url, write_file};
-use crate::compute::utils::hash_utils::{sha256, sha256_from_bytes};
-use aes::Aes256;
-use base64::{Engine as _, engine::general_purpose};
-use cbc::{
-    Decryptor,
-    cipher::{BlockDecryptMut, KeyIvInit, block_padding::Pkcs7},
+use crate::compute::report::{
+    self, DatasetReport, GatewayAttempt, PreComputeReport, ReportedFile, SkippedInputFile,
+};
+use crate::compute::signer::{self, TaskChallenge};
+use crate::compute::status::{RunStatus, Stage, StatusFile};
+use crate::compute::utils::age_utils::{self, Identity};
+use crate::compute::utils::crypto_utils::{
+    CbcStreamDecryptor, SecureRng, decode_key, decrypt_aes_cbc, encrypt_aes256_cbc,
+    generate_aes256_key_and_iv, verify_hmac_sha256_trailer,
+};
+use crate::compute::utils::dns_utils::{DnsCache, dns_cache};
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::utils::file_utils::{
+    Download, DownloadError, DownloadOptions, check_url, check_url_with_headers, download_and_hash,
+    download_file, download_file_and_hash, download_from_url, hash_file, partial_path, probe_url,
+    write_file_in,
 };
-use log::{error, info};
+use crate::compute::utils::fs_utils::{Filesystem, StdFilesystem};
+use crate::compute::utils::hash_utils::{Checksum, parse_sha256sums, sha256};
+use crate::compute::utils::log_utils::redact_url;
+use crate::compute::utils::retry_utils::{RetryBudget, RetryUsage};
+use crate::compute::utils::s3_utils::{self, S3Config};
+use crate::compute::verifier::{
+    ChecksumVerifier, ContentHasher, checksum_verifier, verify_checksum,
+};
+use crate::compute::workspace::Workspace;
+use base64::{Engine as _, engine::general_purpose};
+use bytes::Bytes;
+use log::{error, info, warn};
 #[cfg(test)]
 use mockall::automock;
-use multiaddr::Multiaddr;
+use rand::rngs::OsRng;
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
 
-type Aes256CbcDec = Decryptor<Aes256>;
-const IPFS_GATEWAYS: &[&str] = &[
+pub const IPFS_GATEWAYS: &[&str] = &[
     "https://ipfs-gateway.v8-bellecour.iex.ec",
     "https://gateway.ipfs.io",
     "https://gateway.pinata.cloud",
 ];
-const AES_KEY_LENGTH: usize = 32;
-const AES_IV_LENGTH: usize = 16;
+/// Public gateways serving the content of `bzz://` Swarm references.
+pub const SWARM_GATEWAYS: &[&str] = &["https://api.gateway.ethswarm.org"];
+const GATEWAY_PLACEHOLDER: &str = "{gateway}";
+const SWARM_SCHEME: &str = "bzz://";
+/// Expected dataset size from which gateways are probed, when probing is enabled. Smaller
+/// datasets download faster than gateways can be probed.
+const MIN_PROBED_DATASET_SIZE: u64 = 1024 * 1024;
+
+/// Downloaded input file, with its checksum when it was computed while downloading.
+type FetchedInputFile = Result<(PathBuf, Option<Checksum>, Option<String>), DownloadError>;
 
 #[cfg_attr(test, automock)]
 pub trait PreComputeAppTrait {
     fn run(&mut self) -> Result<(), ReplicateStatusCause>;
-    fn check_output_folder(&self) -> Result<(), ReplicateStatusCause>;
-    fn download_input_files(&self) -> Result<(), ReplicateStatusCause>;
-    fn download_encrypted_dataset(&self) -> Result<Vec<u8>, ReplicateStatusCause>;
-    fn decrypt_dataset(&self, encrypted_content: &[u8]) -> Result<Vec<u8>, ReplicateStatusCause>;
-    fn save_plain_dataset_file(&self, plain_content: &[u8]) -> Result<(), ReplicateStatusCause>;
+    fn download_failure(&self) -> Option<DownloadFailure>;
+    fn has_skipped_input_files(&self) -> bool;
+    fn network_summary(&self) -> NetworkSummary;
 }
 
-pub struct PreComputeApp {
-    chain_task_id: String,
-    pre_compute_args: PreComputeArgs,
+/// Fetches the encrypted dataset and turns it into its plain content.
+#[cfg_attr(test, automock)]
+pub trait DatasetFetcher {
+    fn download_encrypted_dataset(
+        &self,
+        context: &PreComputeContext,
+    ) -> Result<Bytes, ReplicateStatusCause>;
+    fn decrypt_dataset(
+        &self,
+        context: &PreComputeContext,
+        encrypted_content: Bytes,
+    ) -> Result<Bytes, ReplicateStatusCause>;
 }
 
-impl PreComputeApp {
-    pub fn new(chain_task_id: String) -> Self {
-        PreComputeApp {
-            chain_task_id,
-            pre_compute_args: PreComputeArgs::default(),
+/// Checks and fetches the input files of the task.
+#[cfg_attr(test, automock)]
+pub trait InputFetcher {
+    fn check_urls(&self, context: &PreComputeContext) -> Result<(), ReplicateStatusCause>;
+    fn download_input_files(&self, context: &PreComputeContext)
+    -> Result<(), ReplicateStatusCause>;
+}
+
+/// Writes the files handed over to the application in the output folder.
+#[cfg_attr(test, automock)]
+pub trait OutputWriter {
+    fn check_output_folder(&self, context: &PreComputeContext) -> Result<(), ReplicateStatusCause>;
+    fn save_plain_dataset_file(
+        &self,
+        context: &PreComputeContext,
+        plain_content: &[u8],
+    ) -> Result<(), ReplicateStatusCause>;
+}
+
+/// Validated state of a run, built by [`PreComputeAppTrait::run`] once the arguments have
+/// been read, and passed explicitly to every step so that no step can run without them.
+pub struct PreComputeContext {
+    pub chain_task_id: String,
+    pub args: PreComputeArgs,
+    pub checksum_verifier: Box<dyn ChecksumVerifier>,
+    pub cancellation: CancellationToken,
+    pub retry_budget: RetryBudget,
+    /// Workspace of the run partial content is written to, next to its destination when
+    /// unset.
+    pub staging_dir: Option<PathBuf>,
+}
+
+impl PreComputeContext {
+    /// Creates the context of the task `chain_task_id`, verifying checksums with the
+    /// algorithm selected by `args` and bounding retries with the budget they configure.
+    ///
+    /// When `args.is_task_subdir_enabled` is set, `args.output_dir` is replaced by its
+    /// `<chain_task_id>` subdirectory, so that every step writes there.
+    pub fn new(chain_task_id: &str, mut args: PreComputeArgs) -> Self {
+        if args.is_task_subdir_enabled {
+            args.output_dir = Path::new(&args.output_dir)
+                .join(chain_task_id)
+                .to_string_lossy()
+                .into_owned();
+        }
+        PreComputeContext {
+            chain_task_id: chain_task_id.to_string(),
+            checksum_verifier: checksum_verifier(args.checksum_algorithm),
+            retry_budget: RetryBudget::new(
+                args.retry_budget_attempts,
+                args.retry_budget_secs.map(Duration::from_secs),
+            ),
+            args,
+            cancellation: CancellationToken::new(),
+            staging_dir: None,
         }
     }
-}
 
-impl PreComputeAppTrait for PreComputeApp {
-    fn run(&mut self) -> Result<(), ReplicateStatusCause> {
-        self.pre_compute_args = PreComputeArgs::read_args()?;
-        self.check_output_folder()?;
-        if self.pre_compute_args.is_dataset_required {
-            let encrypted_content = self.download_encrypted_dataset()?;
-            let plain_content = self.decrypt_dataset(&encrypted_content)?;
-            self.save_plain_dataset_file(&plain_content)?;
-        }
-        self.download_input_files()?;
-        Ok(())
+    /// Stops the steps of the run at their next safe point once `cancellation` is cancelled.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
     }
 
-    /// Checks whether the output folder specified in `pre_compute_args` exists.
-    ///
-    /// # Returns
-    ///
-    /// - `Ok(())` if the output directory (`output_dir`) exists.
-    /// - `Err(ReplicateStatusCause::PreComputeOutputFolderNotFound)` if the directory does not exist,
-    ///   or if `pre_compute_args` is missing.
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use crate::pre_compute_app::PreComputeApp;
+    /// Returns the options shared by every download of the run, which can be cancelled and
+    /// draw from the same retry budget.
+    fn download_options(&self) -> DownloadOptions {
+        DownloadOptions {
+            cancellation: self.cancellation.clone(),
+            retry_budget: self.retry_budget.clone(),
+            staging_dir: self.staging_dir.clone(),
+            chain_task_id: Some(self.chain_task_id.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the decryptor of the dataset when it can be decrypted while it downloads, see
+    /// `IEXEC_DATASET_SPECULATIVE_DECRYPTION`.
     ///
-    /// let pre_compute_app = PreComputeApp::new();
-    /// pre_compute_app.chain_task_id = Some("0x123456789abcdef");
-    /// pre_compute_app.pre_compute_args = Some(PreComputeArgs::read_args()?);
+    /// Datasets encrypted with age or authenticated with an HMAC trailer are never decrypted
+    /// speculatively, the latter having to be authenticated before any decryption.
+    fn speculative_decryptor(&self) -> Option<CbcStreamDecryptor> {
+        let args = &self.args;
+        if !args.is_speculative_decryption_enabled
+            || args.dataset_age_identity.is_some()
+            || args.is_dataset_hmac_enabled
+        {
+            return None;
+        }
+        let key = decode_key(
+            &args.encrypted_dataset_base64_key,
+            args.dataset_key_encoding,
+        )
+        .ok()?;
+        CbcStreamDecryptor::new(&key).ok()
+    }
+
+    /// Returns the gateways serving `url`, with the number of leading gateways located in the
+    /// worker region.
     ///
-    /// pre_compute_app.check_output_folder()?;
-    /// ```
-    fn check_output_folder(&self) -> Result<(), ReplicateStatusCause> {
-        let output_dir: &str = &self.pre_compute_args.output_dir;
-        let chain_task_id: &str = &self.chain_task_id;
+    /// `bzz://` references are served by the gateways configured through
+    /// `IEXEC_DATASET_SWARM_GATEWAYS`, or the public Swarm gateway. Other gateway URLs are
+    /// served by those configured through `IEXEC_DATASET_GATEWAYS`, those of the worker
+    /// region first, or the default IPFS gateways.
+    fn gateways(&self, url: &str) -> (Vec<&str>, usize) {
+        let args = &self.args;
+        let (configured, defaults, local) = if is_swarm_url(url) {
+            (&args.dataset_swarm_gateways, SWARM_GATEWAYS, 0)
+        } else {
+            (
+                &args.dataset_gateways,
+                IPFS_GATEWAYS,
+                args.dataset_local_gateways,
+            )
+        };
+        if configured.is_empty() {
+            (defaults.to_vec(), 0)
+        } else {
+            (configured.iter().map(String::as_str).collect(), local)
+        }
+    }
+}
 
-        info!("Checking output folder [chainTaskId:{chain_task_id}, path:{output_dir}]");
+/// Receives the chunks of the downloaded dataset, feeding them to the checksum hasher and,
+/// when the dataset is decrypted speculatively, to its decryptor.
+struct DatasetSink {
+    hasher: Box<dyn ContentHasher>,
+    decryptor: Option<CbcStreamDecryptor>,
+}
 
-        if Path::new(&output_dir).is_dir() {
-            return Ok(());
+impl ContentHasher for DatasetSink {
+    fn update(&mut self, chunk: &[u8]) {
+        self.hasher.update(chunk);
+        if let Some(decryptor) = &mut self.decryptor {
+            decryptor.update(chunk);
         }
+    }
 
-        error!("Output folder not found [chainTaskId:{chain_task_id}, path:{output_dir}]");
+    fn finalize(self: Box<Self>) -> Checksum {
+        self.hasher.finalize()
+    }
+}
+
+pub struct PreComputeApp {
+    challenge: Rc<TaskChallenge>,
+    download_failure: RefCell<Option<DownloadFailure>>,
+    report: RefCell<PreComputeReport>,
+    /// Dataset decrypted while downloading, kept until its checksum is verified.
+    speculative_decryption: RefCell<Option<CbcStreamDecryptor>>,
+    rng: RefCell<Box<dyn SecureRng>>,
+    filesystem: Rc<dyn Filesystem>,
+    status: StatusFile,
+    cancellation: CancellationToken,
+}
 
-        Err(ReplicateStatusCause::PreComputeOutputFolderNotFound)
+impl PreComputeApp {
+    pub fn new(challenge: Rc<TaskChallenge>) -> Self {
+        let chain_task_id = challenge.chain_task_id();
+        PreComputeApp {
+            report: RefCell::new(PreComputeReport::new(chain_task_id)),
+            status: StatusFile::from_env(chain_task_id),
+            challenge,
+            download_failure: RefCell::new(None),
+            speculative_decryption: RefCell::new(None),
+            rng: RefCell::new(Box::new(OsRng)),
+            filesystem: Rc::new(StdFilesystem),
+            cancellation: CancellationToken::new(),
+        }
     }
 
-    /// Downloads the input files listed in `pre_compute_args.input_files` to the specified `output_dir`.
-    ///
-    /// Each URL is hashed (SHA-256) to generate a unique local filename.
-    /// If any download fails, the function returns an error.
-    ///
-    /// # Returns
-    ///
-    /// - `Ok(())` if all files are downloaded successfully.
-    /// - `Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed)` if any file fails to download.
-    ///
-    /// # Panics
+    /// Stops the run at its next safe point once `cancellation` is cancelled.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Returns the status of the run, including the failure cause once it has failed.
+    pub fn run_status(&self) -> RunStatus {
+        RunStatus {
+            output_tree_sha256: self.report.borrow().output_tree_sha256.clone(),
+            ..self.status.status()
+        }
+    }
+
+    fn download_input_files_checksums(
+        &self,
+        context: &PreComputeContext,
+        url: &str,
+    ) -> Result<HashMap<String, Checksum>, ReplicateStatusCause> {
+        let chain_task_id: &str = &context.chain_task_id;
+        info!("Downloading input files checksums [chainTaskId:{chain_task_id}, url:{url}]");
+
+        let options = context.download_options();
+        let content = download_from_url(url, &options).map_err(|e| {
+            self.record_download_failure(url, None, &e, options.attempts());
+            match e {
+                DownloadError::Cancelled => ReplicateStatusCause::PreComputeCancelled,
+                _ => ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed,
+            }
+        })?;
+        let content = String::from_utf8_lossy(&content);
+        parse_sha256sums(&content).map_err(|line| {
+            error!("Malformed input files checksums file [chainTaskId:{chain_task_id}, url:{url}, line:{line}]");
+            ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed
+        })
+    }
+
+    /// Notifies the worker API that the run started, when progress reporting is enabled.
+    fn notify_started(&self, context: &PreComputeContext) {
+        if !context.args.is_progress_reporting_enabled {
+            return;
+        }
+        if let Some(reporter) = ProgressReporter::new(&self.challenge) {
+            reporter.report_started(&StartNotification {
+                chain_task_id: context.chain_task_id.clone(),
+                version: env!("CARGO_PKG_VERSION"),
+                is_dataset_required: context.args.is_dataset_required,
+                input_files_number: context.args.input_files.len(),
+            });
+        }
+    }
+
+    /// Writes the run report to the directory configured by `IEXEC_PRE_COMPUTE_REPORT_DIR`,
+    /// signed with the enclave challenge key and encrypted to `context.args.artifacts_recipient`
+    /// if any. No report is written when the variable is unset, so that nothing but the inputs
+    /// is left in the output folder read by the application.
     ///
-    /// This function panics if:
-    /// - `pre_compute_args` is `None`.
-    /// - `chain_task_id` is `None`.
+    /// Failing to sign or write the report is logged but does not fail the pre-compute stage.
+    fn write_report(&self, context: &PreComputeContext) {
+        let chain_task_id: &str = &context.chain_task_id;
+        let Some(report_dir) = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeReportDir,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .filter(|dir| !dir.trim().is_empty()) else {
+            return;
+        };
+        if let Err(e) = fs::create_dir_all(&report_dir) {
+            error!(
+                "Failed to create pre-compute report directory [chainTaskId:{chain_task_id}, dir:{report_dir}]: {e}"
+            );
+            return;
+        }
+        let retries = context.retry_budget.usage();
+        if retries != RetryUsage::default() {
+            self.report.borrow_mut().retries = Some(retries);
+        }
+        if let Err(cause) = self
+            .report
+            .borrow_mut()
+            .sign(signer::sign_with_challenge_key)
+        {
+            warn!(
+                "Writing unsigned pre-compute report [chainTaskId:{chain_task_id}, cause:{cause:?}]"
+            );
+        }
+        let report = self.report.borrow();
+        let written = match &context.args.artifacts_recipient {
+            Some(recipient) => report.write_encrypted(&report_dir, recipient),
+            None => report.write(&report_dir),
+        };
+        match written {
+            Ok(path) => info!(
+                "Pre-compute report written [chainTaskId:{chain_task_id}, path:{}]",
+                path.display()
+            ),
+            Err(_) => error!("Failed to write pre-compute report [chainTaskId:{chain_task_id}]"),
+        }
+    }
+
+    /// Resolves every host the task downloads from once, before any download starts, so that
+    /// retries reuse the addresses cached in `cache` and resolution failures are all reported
+    /// at once instead of surfacing download after download.
     ///
-    /// # Example
+    /// The hosts of the dataset URL, the input files checksums URL and the input file URLs
+    /// must all resolve. Gateways only serve as fallbacks for each other, so at least one of
+    /// them must resolve when the dataset is fetched through gateways.
     ///
-    /// ```
-    /// use crate::pre_compute_app::PreComputeApp;
+    /// # Returns
     ///
-    /// let pre_compute_app = PreComputeApp::new();
-    /// pre_compute_app.chain_task_id = Some("0x123456789abcdef");
-    /// pre_compute_app.pre_compute_args = Some(PreComputeArgs::read_args()?);
+    /// - `Ok(())` if every required host is resolved.
+    /// - `Err(ReplicateStatusCause::PreComputeHostResolutionFailed)` otherwise.
+    fn resolve_hosts(
+        &self,
+        context: &PreComputeContext,
+        cache: &DnsCache,
+    ) -> Result<(), ReplicateStatusCause> {
+        let args = &context.args;
+        let chain_task_id: &str = &context.chain_task_id;
+        let host = |url: &str| {
+            let url = if s3_utils::is_s3_url(url) {
+                S3Config::from_env().http_url(url).ok()?
+            } else {
+                Url::parse(url).ok()?
+            };
+            url.host_str().map(str::to_string)
+        };
+
+        let gateway_hosts: Vec<String> =
+            if args.is_dataset_required && is_gateway_url(&args.encrypted_dataset_url) {
+                context
+                    .gateways(&args.encrypted_dataset_url)
+                    .0
+                    .into_iter()
+                    .filter_map(host)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+        let mut required_urls: Vec<&str> = Vec::new();
+        if args.is_dataset_required && !is_gateway_url(&args.encrypted_dataset_url) {
+            required_urls.push(&args.encrypted_dataset_url);
+        }
+        required_urls.extend(args.input_files_checksum_url.as_deref());
+        required_urls.extend(args.input_files.iter().map(|url| &**url));
+        let required_hosts: Vec<String> = required_urls.into_iter().filter_map(host).collect();
+
+        let hosts: Vec<&str> = required_hosts
+            .iter()
+            .chain(&gateway_hosts)
+            .map(String::as_str)
+            .collect();
+        info!(
+            "Resolving hosts [chainTaskId:{chain_task_id}, count:{}]",
+            hosts.len()
+        );
+        let failures = cache.pre_resolve(&hosts);
+        for (host, e) in &failures {
+            warn!("Failed to resolve host [chainTaskId:{chain_task_id}, host:{host}]: {e}");
+        }
+        let is_failed = |host: &String| failures.iter().any(|(failed, _)| failed == host);
+        let all_gateways_failed = gateway_hosts.iter().all(is_failed);
+        let failed_hosts: Vec<&str> = failures
+            .iter()
+            .map(|(host, _)| host)
+            .filter(|host| all_gateways_failed || required_hosts.contains(host))
+            .map(String::as_str)
+            .collect();
+        if !failed_hosts.is_empty() {
+            error!(
+                "Host resolution failed [chainTaskId:{chain_task_id}, hosts:{}]",
+                failed_hosts.join(",")
+            );
+            return Err(ReplicateStatusCause::PreComputeHostResolutionFailed);
+        }
+        Ok(())
+    }
+
+    /// Records the digest of the whole output folder in the run report, see
+    /// [`report::output_tree_sha256`].
     ///
-    /// pre_compute_app.download_input_files()?;
-    /// ```
-    fn download_input_files(&self) -> Result<(), ReplicateStatusCause> {
-        let args = &self.pre_compute_args;
-        let chain_task_id: &str = &self.chain_task_id;
+    /// Failing to hash the output folder is logged but does not fail the pre-compute stage.
+    fn record_output_tree(&self, context: &PreComputeContext) {
+        let chain_task_id: &str = &context.chain_task_id;
+        match report::output_tree_sha256(Path::new(&context.args.output_dir)) {
+            Ok(digest) => {
+                info!("Output tree hashed [chainTaskId:{chain_task_id}, sha256:{digest}]");
+                self.report.borrow_mut().output_tree_sha256 = Some(digest);
+            }
+            Err(e) => error!("Failed to hash output tree [chainTaskId:{chain_task_id}]: {e}"),
+        }
+    }
 
-        for url in &args.input_files {
-            info!("Downloading input file [chainTaskId:{chain_task_id}, url:{url}]");
+    /// Records the digest of a file written to the output folder in the run report.
+    fn record_output_file(&self, context: &PreComputeContext, path: &Path, content: &[u8]) {
+        let name = output_file_name(context, path);
+        self.report
+            .borrow_mut()
+            .files
+            .push(ReportedFile::new(&name, content));
+    }
 
-            let filename = sha256(url.to_string());
-            if download_file(url, &args.output_dir, &filename).is_none() {
-                return Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed);
-            }
+    /// Records the digest of a file of the output folder in the run report, with the URL
+    /// which served it, hashing it in chunks so that large input files never have to fit in
+    /// memory.
+    fn record_streamed_output_file(
+        &self,
+        context: &PreComputeContext,
+        path: &Path,
+        final_url: Option<&str>,
+    ) {
+        let mut hasher: Box<dyn ContentHasher> = Box::new(Sha256::new());
+        if hash_file(self.filesystem.as_ref(), path, hasher.as_mut()).is_ok() {
+            let name = output_file_name(context, path);
+            self.report.borrow_mut().files.push(ReportedFile {
+                final_url: final_url.map(redact_url),
+                ..ReportedFile::with_checksum(&name, &hasher.finalize())
+            });
         }
+    }
+
+    /// Creates the workspace of the run in the output folder, checking that its volume can
+    /// hold the dataset when its size is known.
+    fn create_workspace(
+        &self,
+        context: &PreComputeContext,
+    ) -> Result<Workspace, ReplicateStatusCause> {
+        let args = &context.args;
+        let required_space = args.dataset_size.filter(|_| args.is_dataset_required);
+        Workspace::create(
+            self.filesystem.clone(),
+            Path::new(&args.output_dir),
+            self.rng.borrow_mut().as_mut(),
+            required_space,
+        )
+    }
+
+    /// Writes the dataset to the workspace of the run then renames it into place, so that
+    /// the application never finds a partially written dataset.
+    fn write_dataset_file(
+        &self,
+        context: &PreComputeContext,
+        content: &[u8],
+        path: &Path,
+    ) -> Result<(), ReplicateStatusCause> {
+        let chain_task_id: &str = &context.chain_task_id;
+        let partial_path = partial_path(path, context.staging_dir.as_deref());
+        write_file_in(
+            self.filesystem.as_ref(),
+            content,
+            &partial_path,
+            &format!("chainTaskId:{chain_task_id}"),
+        )
+        .map_err(|e| saving_failure_cause(&e))?;
+        self.filesystem
+            .rename(&partial_path, path)
+            .map_err(|e| {
+                error!(
+                    "Failed to move dataset file into place [chainTaskId:{chain_task_id}, path:{}]: {e}",
+                    path.display()
+                );
+                let _ = self.filesystem.remove_file(&partial_path);
+                ReplicateStatusCause::PreComputeSavingPlainDatasetFailed
+            })?;
+        self.record_output_file(context, path, content);
         Ok(())
     }
 
-    /// Downloads the encrypted dataset file from a URL or IPFS multi-address, and verifies its checksum.
+    /// Downloads `url` while computing its checksum with the configured verifier, so that
+    /// hashing overlaps with the transfer. When the dataset is decrypted speculatively, the
+    /// decryptor is kept until [`DatasetFetcher::decrypt_dataset`] once the download succeeds.
     ///
-    /// # Returns
+    /// When `IEXEC_DATASET_SIZE` is set, the transfer is aborted as soon as more bytes are
+    /// received, and shorter content is rejected, before its checksum is even compared.
+    fn download_and_checksum(
+        &self,
+        context: &PreComputeContext,
+        url: &str,
+    ) -> Result<(Download, Checksum), DownloadError> {
+        let args = &context.args;
+        let max_size = [args.dataset_size, args.dataset_max_size]
+            .into_iter()
+            .flatten()
+            .min();
+        let mut sink = DatasetSink {
+            hasher: context.checksum_verifier.hasher(),
+            decryptor: context.speculative_decryptor(),
+        };
+        let options = DownloadOptions {
+            max_size,
+            spki_pins: args.dataset_tls_pins.clone(),
+            ..context.download_options()
+        };
+        let download = download_and_hash(url, &mut sink, &options)?;
+        check_size(url, download.content.len() as u64, args.dataset_size)?;
+        *self.speculative_decryption.borrow_mut() = sink.decryptor;
+        Ok((download, sink.hasher.finalize()))
+    }
+
+    /// Returns the path of the file staged for `url` in the `IEXEC_PRE_COMPUTE_IN` directory.
     ///
-    /// * `Ok(Vec<u8>)` containing the dataset's encrypted content if download and verification succeed.
-    /// * `Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed)` if the download fails or inputs are missing.
-    /// * `Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum)` if checksum validation fails.
+    /// A staged file is named after the SHA-256 of its URL, or after the last segment of the
+    /// URL path.
+    fn staged_file(&self, context: &PreComputeContext, url: &str) -> Option<PathBuf> {
+        let input_dir = Path::new(context.args.input_dir.as_deref()?);
+        [sha256(url.to_string()), url_file_name(url).to_string()]
+            .into_iter()
+            .filter(|name| !matches!(name.as_str(), "" | "." | ".."))
+            .map(|name| input_dir.join(name))
+            .find(|path| self.filesystem.exists(path))
+    }
+
+    /// Reads the dataset staged for `url` if its checksum matches the expected one.
+    fn read_staged_dataset(
+        &self,
+        context: &PreComputeContext,
+        url: &str,
+        expected_checksum: &Checksum,
+    ) -> Option<Bytes> {
+        let chain_task_id: &str = &context.chain_task_id;
+        let path = self.staged_file(context, url)?;
+        let content = self.filesystem.read(&path).ok()?;
+        match context
+            .checksum_verifier
+            .verify(&content, expected_checksum)
+        {
+            Ok(checksum) => {
+                events::emit(
+                    chain_task_id,
+                    &Event::ChecksumVerified {
+                        checksum: checksum.to_string(),
+                    },
+                );
+                info!(
+                    "Using staged dataset file [chainTaskId:{chain_task_id}, path:{}]",
+                    path.display()
+                );
+                self.report.borrow_mut().dataset = Some(DatasetReport {
+                    url: redact_url(url),
+                    final_url: Some(format!("file://{}", path.display())),
+                    ..Default::default()
+                });
+                Some(Bytes::from(content))
+            }
+            Err(actual_checksum) => {
+                warn!(
+                    "Ignoring staged dataset file with invalid checksum [chainTaskId:{chain_task_id}, path:{}, expected:{expected_checksum}, actual:{actual_checksum}]",
+                    path.display()
+                );
+                None
+            }
+        }
+    }
+
+    /// Copies the input file staged for `url` to `file_path` if it matches its entry in
+    /// `checksums`.
     ///
-    /// # Example
+    /// The staged file is read once, and the verified content is the one written, so that
+    /// the host cannot swap the file between its verification and its copy.
     ///
-    /// ```
-    /// let app = PreComputeApp::new();
-    /// pre_compute_app.chain_task_id = Some("0x123456789abcdef");
-    /// pre_compute_app.pre_compute_args = Some(PreComputeArgs::read_args()?);
+    /// # Returns
     ///
-    /// app.download_encrypted_dataset()?;
-    /// ```
-    fn download_encrypted_dataset(&self) -> Result<Vec<u8>, ReplicateStatusCause> {
-        let args = &self.pre_compute_args;
-        let chain_task_id = &self.chain_task_id;
-        let encrypted_dataset_url: &str = &args.encrypted_dataset_url;
-
+    /// * `true` if the staged file has been copied, `false` if it must be downloaded.
+    fn copy_staged_input_file(
+        &self,
+        context: &PreComputeContext,
+        checksums: &HashMap<String, Checksum>,
+        url: &str,
+        file_path: &Path,
+    ) -> bool {
+        let chain_task_id: &str = &context.chain_task_id;
+        let Some(staged_path) = self.staged_file(context, url) else {
+            return false;
+        };
+        let filesystem = self.filesystem.as_ref();
+        let Ok(content) = filesystem.read(&staged_path) else {
+            return false;
+        };
+        let mut hasher = context.checksum_verifier.hasher();
+        hasher.update(&content);
+        if verify_input_file_checksum(
+            filesystem,
+            context.checksum_verifier.as_ref(),
+            checksums,
+            url,
+            &staged_path,
+            Some(hasher.finalize()),
+        )
+        .is_err()
+        {
+            warn!(
+                "Ignoring staged input file with invalid checksum [chainTaskId:{chain_task_id}, path:{}]",
+                staged_path.display()
+            );
+            return false;
+        }
         info!(
-            "Downloading encrypted dataset file [chainTaskId:{chain_task_id}, url:{encrypted_dataset_url}]",
+            "Copying staged input file [chainTaskId:{chain_task_id}, url:{url}, path:{}]",
+            staged_path.display()
         );
+        write_file_in(
+            filesystem,
+            &content,
+            file_path,
+            &format!("chainTaskId:{chain_task_id}"),
+        )
+        .is_ok()
+    }
 
-        let encrypted_content = if is_multi_address(encrypted_dataset_url) {
-            IPFS_GATEWAYS.iter().find_map(|gateway| {
-                let full_url = format!("{gateway}{encrypted_dataset_url}");
-                info!("Attempting to download dataset from {full_url}");
-
-                if let Some(content) = download_from_url(&full_url) {
-                    info!("Successfully downloaded from {full_url}");
-                    Some(content)
-                } else {
-                    info!("Failed to download from {full_url}");
-                    None
-                }
-            })
-        } else {
-            download_from_url(encrypted_dataset_url)
+    /// Decrypts a dataset encrypted with AES-CBC, verifying its HMAC-SHA256 trailer first when
+    /// `IEXEC_DATASET_HMAC` is enabled.
+    fn decrypt_aes_dataset(
+        &self,
+        context: &PreComputeContext,
+        encrypted_content: Bytes,
+    ) -> Result<Bytes, ReplicateStatusCause> {
+        let args = &context.args;
+        if let Some(decryptor) = self
+            .speculative_decryption
+            .take()
+            .filter(|decryptor| decryptor.received() == encrypted_content.len())
+        {
+            info!(
+                "Using dataset decrypted while downloading [chainTaskId:{}]",
+                context.chain_task_id
+            );
+            return decryptor.finalize();
         }
-        .ok_or(ReplicateStatusCause::PreComputeDatasetDownloadFailed)?;
+        let key = decode_key(
+            &args.encrypted_dataset_base64_key,
+            args.dataset_key_encoding,
+        )
+        .map_err(|e| {
+            error!(
+                "Invalid dataset key [chainTaskId:{}]: {e}",
+                context.chain_task_id
+            );
+            ReplicateStatusCause::PreComputeDatasetDecryptionFailed
+        })?;
+
+        let encrypted_content = if args.is_dataset_hmac_enabled {
+            verify_hmac_sha256_trailer(&key, encrypted_content).inspect_err(|_| {
+                error!(
+                    "Dataset authentication failed [chainTaskId:{}]",
+                    context.chain_task_id
+                );
+            })?
+        } else {
+            encrypted_content
+        };
+        decrypt_aes_cbc(&key, encrypted_content)
+    }
 
-        info!("Checking encrypted dataset checksum [chainTaskId:{chain_task_id}]");
-        let expected_checksum: &str = &args.encrypted_dataset_checksum;
-        let actual_checksum = sha256_from_bytes(&encrypted_content);
+    /// Decrypts a dataset encrypted with age to the X25519 `identity`.
+    fn decrypt_age_dataset(
+        &self,
+        context: &PreComputeContext,
+        identity: &str,
+        encrypted_content: &[u8],
+    ) -> Result<Bytes, ReplicateStatusCause> {
+        Identity::parse(identity)
+            .and_then(|identity| age_utils::decrypt(&identity, encrypted_content))
+            .map(Bytes::from)
+            .map_err(|e| {
+                error!(
+                    "Failed to decrypt age dataset [chainTaskId:{}]: {e}",
+                    context.chain_task_id
+                );
+                ReplicateStatusCause::PreComputeDatasetDecryptionFailed
+            })
+    }
 
-        if actual_checksum != expected_checksum {
+    /// Checks the decrypted dataset size against `IEXEC_DATASET_MAX_SIZE` and
+    /// `IEXEC_DATASET_MAX_EXPANSION_RATIO`.
+    fn check_decrypted_size(
+        &self,
+        context: &PreComputeContext,
+        encrypted_size: usize,
+        plain_size: usize,
+    ) -> Result<(), ReplicateStatusCause> {
+        let chain_task_id: &str = &context.chain_task_id;
+        let args = &context.args;
+        if let Some(max_size) = args.dataset_max_size
+            && plain_size as u64 > max_size
+        {
+            error!(
+                "Decrypted dataset exceeds maximum size [chainTaskId:{chain_task_id}, size:{plain_size}, maxSize:{max_size}]"
+            );
+            return Err(ReplicateStatusCause::PreComputeDatasetTooLarge);
+        }
+        if let Some(max_ratio) = args.dataset_max_expansion_ratio
+            && plain_size as f64 > encrypted_size.max(1) as f64 * max_ratio
+        {
             error!(
-                "Invalid dataset checksum [chainTaskId:{chain_task_id}, expected:{expected_checksum}, actual:{actual_checksum}]"
+                "Decrypted dataset exceeds maximum expansion ratio [chainTaskId:{chain_task_id}, encryptedSize:{encrypted_size}, size:{plain_size}, maxRatio:{max_ratio}]"
             );
-            return Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum);
+            return Err(ReplicateStatusCause::PreComputeDatasetTooLarge);
         }
+        Ok(())
+    }
 
-        info!("Dataset downloaded and verified successfully.");
-        Ok(encrypted_content)
+    fn record_download_failure(
+        &self,
+        url: &str,
+        input_file_index: Option<usize>,
+        error: &DownloadError,
+        attempts: u32,
+    ) {
+        *self.download_failure.borrow_mut() = Some(DownloadFailure {
+            url: redact_url(url),
+            input_file_index,
+            http_status: error.status(),
+            attempts,
+        });
     }
+}
 
-    /// Decrypts the provided encrypted dataset bytes using AES-CBC.
+impl PreComputeAppTrait for PreComputeApp {
+    fn run(&mut self) -> Result<(), ReplicateStatusCause> {
+        let args = PreComputeArgs::read_args().inspect_err(|cause| self.status.fail(cause))?;
+        let mut context = PreComputeContext::new(self.challenge.chain_task_id(), args)
+            .with_cancellation(self.cancellation.clone());
+        let context = &mut context;
+        events::emit(
+            &context.chain_task_id,
+            &Event::ArgsLoaded {
+                is_dataset_required: context.args.is_dataset_required,
+                input_files_number: context.args.input_files.len(),
+            },
+        );
+        self.notify_started(context);
+        self.report.borrow_mut().config_fingerprint = Some(diagnostics::config_fingerprint());
+        self.status.set_total_steps(
+            usize::from(context.args.is_dataset_required) + context.args.input_files.len(),
+        );
+        self.check_output_folder(context)
+            .inspect_err(|cause| self.status.fail(cause))?;
+        // Dropped when the run returns, removing the partial content left by failures.
+        let workspace = self
+            .create_workspace(context)
+            .inspect_err(|cause| self.status.fail(cause))?;
+        context.staging_dir = Some(workspace.path().to_path_buf());
+        let context = &*context;
+        if let Some(cache) = dns_cache() {
+            self.resolve_hosts(context, cache)
+                .inspect_err(|cause| self.status.fail(cause))?;
+        }
+        let result = prepare_files(context, &self.status, self, self, self);
+        match &result {
+            Ok(()) => self.status.stage(Stage::Completed),
+            Err(cause) => self.status.fail(cause),
+        }
+        if result.is_ok() && context.args.is_output_tree_hash_enabled {
+            self.record_output_tree(context);
+        }
+        self.write_report(context);
+        result
+    }
+
+    /// Returns details about the last failed download, if any.
     ///
-    /// The first 16 bytes of `encrypted_content` are treated as the IV.
-    /// The rest is the ciphertext. The decryption key is decoded from a Base64 string.
+    /// This is used to enrich the exit message sent to the worker, so that requesters know
+    /// which URL was broken without needing access to the worker logs.
+    fn download_failure(&self) -> Option<DownloadFailure> {
+        self.download_failure.borrow().clone()
+    }
+
+    /// Returns whether input files were skipped because `IEXEC_PRE_COMPUTE_CONTINUE_ON_ERROR`
+    /// is enabled, in which case the run only partially succeeded.
     ///
-    /// # Arguments
+    /// Skipped optional input files are not counted, as the run does not need them.
+    fn has_skipped_input_files(&self) -> bool {
+        self.report
+            .borrow()
+            .skipped_input_files
+            .iter()
+            .any(|skipped| !skipped.optional)
+    }
+
+    /// Returns the downloads attempted during the run, for the diagnostic bundle.
+    fn network_summary(&self) -> NetworkSummary {
+        let report = self.report.borrow();
+        NetworkSummary {
+            dataset: report.dataset.clone(),
+            skipped_input_files: report.skipped_input_files.clone(),
+            retries: report.retries.clone(),
+            download_failure: self.download_failure(),
+        }
+    }
+}
+
+impl OutputWriter for PreComputeApp {
+    /// Checks whether the output folder specified in `context.args` exists.
     ///
-    /// * `encrypted_content` - Full encrypted dataset, including the IV prefix.
+    /// When `context.args.is_task_subdir_enabled` is set, the output folder is the task
+    /// subdirectory of the configured one: the configured folder must exist, and the task
+    /// subdirectory is created if needed, so that retries reusing the same volume write to
+    /// the same place without colliding with other tasks.
     ///
     /// # Returns
     ///
-    /// * `Ok(Vec<u8>)` containing the plaintext dataset if decryption succeeds.
-    /// * `Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)` if the key is missing, decoding fails, or decryption fails.
+    /// - `Ok(())` if the output directory (`output_dir`) exists.
+    /// - `Err(ReplicateStatusCause::PreComputeOutputFolderNotFound)` if the directory does not
+    ///   exist or the task subdirectory cannot be created.
     ///
     /// # Example
     ///
     /// ```
-    /// let app = PreComputeApp::new();
-    /// pre_compute_app.chain_task_id = Some("0x123456789abcdef");
-    /// pre_compute_app.pre_compute_args = Some(PreComputeArgs::read_args()?);
+    /// use crate::pre_compute_app::PreComputeApp;
+    ///
+    /// let pre_compute_app = PreComputeApp::new(challenge);
+    /// let context = PreComputeContext::new("0x123456789abcdef", PreComputeArgs::read_args()?);
     ///
-    /// let encrypted = vec![/* ... */];
-    /// let decrypted = app.decrypt_dataset(&encrypted)?;
+    /// pre_compute_app.check_output_folder(&context)?;
     /// ```
-    fn decrypt_dataset(&self, encrypted_content: &[u8]) -> Result<Vec<u8>, ReplicateStatusCause> {
-        let base64_key: &str = &self.pre_compute_args.encrypted_dataset_base64_key;
+    fn check_output_folder(&self, context: &PreComputeContext) -> Result<(), ReplicateStatusCause> {
+        let output_dir: &str = &context.args.output_dir;
+        let chain_task_id: &str = &context.chain_task_id;
 
-        let key = general_purpose::STANDARD
-            .decode(base64_key)
-            .map_err(|_| ReplicateStatusCause::PreComputeDatasetDecryptionFailed)?;
+        info!("Checking output folder [chainTaskId:{chain_task_id}, path:{output_dir}]");
 
-        if encrypted_content.len() < AES_IV_LENGTH || key.len() != AES_KEY_LENGTH {
-            return Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed);
+        let output_path = Path::new(output_dir);
+        let configured_path = if context.args.is_task_subdir_enabled {
+            output_path.parent().unwrap_or(output_path)
+        } else {
+            output_path
+        };
+        if !self
+            .filesystem
+            .stat(configured_path)
+            .is_ok_and(|stat| stat.is_dir)
+        {
+            error!(
+                "Output folder not found [chainTaskId:{chain_task_id}, path:{}]",
+                configured_path.display()
+            );
+            return Err(ReplicateStatusCause::PreComputeOutputFolderNotFound);
         }
-
-        let key_slice = &key[..AES_KEY_LENGTH];
-        let iv_slice = &encrypted_content[..AES_IV_LENGTH];
-        let ciphertext = &encrypted_content[AES_IV_LENGTH..];
-
-        Aes256CbcDec::new(key_slice.into(), iv_slice.into())
-            .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
-            .map_err(|_| ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        if context.args.is_task_subdir_enabled {
+            self.filesystem.create_dir(output_path).map_err(|e| {
+                error!(
+                    "Failed to create task output folder [chainTaskId:{chain_task_id}, path:{output_dir}]: {e}"
+                );
+                ReplicateStatusCause::PreComputeOutputFolderNotFound
+            })?;
+        }
+        Ok(())
     }
 
     /// Saves the decrypted (plain) dataset to disk in the configured output directory.
     ///
-    /// The output filename is taken from `pre_compute_args.plain_dataset_filename`.
+    /// The output filename is taken from `context.args.plain_dataset_filename`.
+    ///
+    /// When `context.args.dataset_reencryption_key_path` is set, the dataset is instead
+    /// re-encrypted with an ephemeral AES-256-CBC key (`IV || ciphertext` layout) and the
+    /// base64-encoded key is written to that path, so that cleartext never rests on the
+    /// shared output volume.
     ///
     /// # Arguments
     ///
@@ -256,277 +930,2696 @@ impl PreComputeAppTrait for PreComputeApp {
     /// # Example
     ///
     /// ```
-    /// let app = PreComputeApp::new();
-    /// pre_compute_app.chain_task_id = Some("0x123456789abcdef");
-    /// pre_compute_app.pre_compute_args = Some(PreComputeArgs::read_args()?);
+    /// let app = PreComputeApp::new(challenge);
+    /// let context = PreComputeContext::new("0x123456789abcdef", PreComputeArgs::read_args()?);
     ///
     /// let plain_data = vec![/* ... */];
-    /// app.save_plain_dataset_file(&plain_data)?;
+    /// app.save_plain_dataset_file(&context, &plain_data)?;
     /// ```
-    fn save_plain_dataset_file(&self, plain_dataset: &[u8]) -> Result<(), ReplicateStatusCause> {
-        let chain_task_id: &str = &self.chain_task_id;
-        let args = &self.pre_compute_args;
+    fn save_plain_dataset_file(
+        &self,
+        context: &PreComputeContext,
+        plain_dataset: &[u8],
+    ) -> Result<(), ReplicateStatusCause> {
+        let chain_task_id: &str = &context.chain_task_id;
+        let args = &context.args;
         let output_dir: &str = &args.output_dir;
         let plain_dataset_filename: &str = &args.plain_dataset_filename;
 
         let mut path = PathBuf::from(output_dir);
         path.push(plain_dataset_filename);
 
+        if let Some(key_path) = &args.dataset_reencryption_key_path {
+            info!(
+                "Saving re-encrypted dataset file [chain_task_id:{chain_task_id}, path:{}, keyPath:{key_path}]",
+                path.display()
+            );
+            let (key, iv) = generate_aes256_key_and_iv(self.rng.borrow_mut().as_mut());
+            let encrypted_dataset = encrypt_aes256_cbc(&key, &iv, plain_dataset);
+            self.filesystem
+                .write_private(
+                    Path::new(key_path),
+                    general_purpose::STANDARD.encode(key).as_bytes(),
+                )
+                .map_err(|e| {
+                    error!(
+                        "Failed to write re-encryption key [chain_task_id:{chain_task_id}, keyPath:{key_path}]: {e}"
+                    );
+                    saving_failure_cause(&e)
+                })?;
+            return self.write_dataset_file(context, &encrypted_dataset, &path);
+        }
+
         info!(
             "Saving plain dataset file [chain_task_id:{chain_task_id}, path:{}]",
             path.display()
         );
 
-        write_file(
-            plain_dataset,
-            &path,
-            &format!("chainTaskId:{chain_task_id}"),
-        )
-        .map_err(|_| ReplicateStatusCause::PreComputeSavingPlainDatasetFailed)
+        self.write_dataset_file(context, plain_dataset, &path)
     }
 }
 
-fn is_multi_address(uri: &str) -> bool {
-    !uri.trim().is_empty() && Multiaddr::from_str(uri).is_ok()
+impl InputFetcher for PreComputeApp {
+    /// Checks every URL of the task with a HEAD request before any download starts.
+    ///
+    /// The encrypted dataset URL (unless it is an IPFS multi-address, which relies on gateway
+    /// fallback) and every input file URL are checked. All failures are logged before
+    /// returning, so that users learn about every broken URL at once.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if every URL is reachable.
+    /// - `Err(ReplicateStatusCause::PreComputePreflightCheckFailed)` if at least one URL is not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crate::pre_compute_app::PreComputeApp;
+    ///
+    /// let pre_compute_app = PreComputeApp::new(challenge);
+    /// let context = PreComputeContext::new("0x123456789abcdef", PreComputeArgs::read_args()?);
+    ///
+    /// pre_compute_app.check_urls(&context)?;
+    /// ```
+    fn check_urls(&self, context: &PreComputeContext) -> Result<(), ReplicateStatusCause> {
+        let args = &context.args;
+        let chain_task_id: &str = &context.chain_task_id;
+
+        let no_headers: &[(String, String)] = &[];
+        let mut urls: Vec<(&str, &[(String, String)])> = Vec::new();
+        if args.is_dataset_required && !is_gateway_url(&args.encrypted_dataset_url) {
+            urls.push((&args.encrypted_dataset_url, no_headers));
+        }
+        urls.extend(
+            args.input_files_checksum_url
+                .as_deref()
+                .map(|url| (url, no_headers)),
+        );
+        urls.extend(args.input_files.iter().enumerate().map(|(index, url)| {
+            let headers = args
+                .input_file_headers
+                .get(&(index + 1))
+                .map_or(no_headers, Vec::as_slice);
+            (&**url, headers)
+        }));
+
+        info!(
+            "Checking URLs [chainTaskId:{chain_task_id}, count:{}]",
+            urls.len()
+        );
+
+        let failures = urls
+            .into_iter()
+            .filter(
+                |(url, headers)| match check_url_with_headers(url, headers) {
+                    Ok(size) => {
+                        info!(
+                            "URL is valid [chainTaskId:{chain_task_id}, url:{url}, size:{size:?}]"
+                        );
+                        false
+                    }
+                    Err(e) => {
+                        error!(
+                            "URL is invalid [chainTaskId:{chain_task_id}, url:{url}, reason:{e:?}]"
+                        );
+                        true
+                    }
+                },
+            )
+            .count();
+
+        if failures > 0 {
+            error!("Pre-flight check failed [chainTaskId:{chain_task_id}, failures:{failures}]");
+            return Err(ReplicateStatusCause::PreComputePreflightCheckFailed);
+        }
+        Ok(())
+    }
+
+    /// Downloads the input files listed in `context.args.input_files` to the specified `output_dir`.
+    ///
+    /// Each URL is hashed (SHA-256) to generate a unique local filename.
+    /// If any download fails, the function returns an error, unless the file is marked optional
+    /// with `IEXEC_INPUT_FILE_OPTIONAL_<i>` or `IEXEC_PRE_COMPUTE_CONTINUE_ON_ERROR` is
+    /// enabled: the file is then skipped and recorded in the report, see
+    /// [`PreComputeApp::has_skipped_input_files`].
+    ///
+    /// When `input_files_checksum_url` is set, the checksums file is downloaded first and
+    /// every input file must match the entry named after its URL or the last segment of its
+    /// URL path.
+    ///
+    /// Files are downloaded in a pipeline: up to `IEXEC_INPUT_FILES_CONCURRENCY` files
+    /// (1 by default) are downloaded at the same time, on their own threads, while the files
+    /// already downloaded are verified and recorded in the order they are listed. Downloads
+    /// never run more than one file ahead of the concurrency, so that a slow verification
+    /// holds them back. Once a file fails, no other download is started.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if all files are downloaded (and verified) successfully.
+    /// - `Err(ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed)` if the checksums
+    ///   file cannot be downloaded or parsed.
+    /// - `Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed)` if any file fails to download.
+    /// - `Err(ReplicateStatusCause::PreComputeInvalidInputFileChecksum)` if a file has no checksum
+    ///   entry or does not match it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use crate::pre_compute_app::PreComputeApp;
+    ///
+    /// let pre_compute_app = PreComputeApp::new(challenge);
+    /// let context = PreComputeContext::new("0x123456789abcdef", PreComputeArgs::read_args()?);
+    ///
+    /// pre_compute_app.download_input_files(&context)?;
+    /// ```
+    fn download_input_files(
+        &self,
+        context: &PreComputeContext,
+    ) -> Result<(), ReplicateStatusCause> {
+        let args = &context.args;
+        let chain_task_id: &str = &context.chain_task_id;
+
+        let checksums = match &args.input_files_checksum_url {
+            Some(url) => Some(self.download_input_files_checksums(context, url)?),
+            None => None,
+        };
+
+        let progress_reporter = if args.is_progress_reporting_enabled {
+            ProgressReporter::new(&self.challenge)
+        } else {
+            None
+        };
+
+        // Staged files are only trusted when they can be verified against a checksum.
+        let staged: Vec<bool> = args
+            .input_files
+            .iter()
+            .map(|url| {
+                let staged_path = Path::new(&args.output_dir).join(sha256(url.to_string()));
+                checksums.as_ref().is_some_and(|checksums| {
+                    self.copy_staged_input_file(context, checksums, url, &staged_path)
+                })
+            })
+            .collect();
+        let to_download: Vec<usize> = (0..args.input_files.len())
+            .filter(|index| !staged[*index])
+            .collect();
+        let workers = args.input_files_concurrency.min(to_download.len());
+        let filesystem = self.filesystem.as_ref();
+        let verifier = context.checksum_verifier.as_ref();
+        let options = context.download_options();
+        // Files with a checksum are hashed while they stream to disk, instead of being read
+        // back once downloaded.
+        let download = |index: usize| {
+            let url: &str = &args.input_files[index];
+            let options = DownloadOptions {
+                headers: args
+                    .input_file_headers
+                    .get(&(index + 1))
+                    .cloned()
+                    .unwrap_or_default(),
+                attempts: Arc::default(),
+                final_url: Arc::default(),
+                ..options.clone()
+            };
+            let filename = sha256(url.to_string());
+            let started_at = Instant::now();
+            let result = match checksums {
+                Some(_) => {
+                    let mut hasher = verifier.hasher();
+                    download_file_and_hash(
+                        filesystem,
+                        url,
+                        &args.output_dir,
+                        &filename,
+                        hasher.as_mut(),
+                        &options,
+                    )
+                    .map(|path| (path, Some(hasher.finalize()), options.final_url()))
+                }
+                None => download_file(filesystem, url, &args.output_dir, &filename, &options)
+                    .map(|path| (path, None, options.final_url())),
+            };
+            (started_at, options.attempts(), result)
+        };
+        // A failed download which cannot be skipped fails the run: no other download starts,
+        // even while earlier files are still being verified.
+        let fails_run = |index: usize, error: &DownloadError| {
+            let skippable = args.is_continue_on_error_enabled
+                || args.optional_input_files.contains(&(index + 1));
+            !skippable
+                || matches!(
+                    error,
+                    DownloadError::NotEnoughDiskSpace | DownloadError::Cancelled
+                )
+        };
+
+        // Downloads run ahead of the verification of the previous files, but never by more
+        // than `window` files: a permit is taken before each download and given back once the
+        // consumer moves past the file, so that a slow verification holds the downloads back.
+        let window = workers + 1;
+        let next = AtomicUsize::new(0);
+        let stopped = AtomicBool::new(false);
+        let (permit_sender, permit_receiver) = mpsc::sync_channel(window);
+        for _ in 0..window {
+            let _ = permit_sender.send(());
+        }
+        let permit_receiver = Mutex::new(permit_receiver);
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::sync_channel(window);
+            if workers > 1 {
+                info!(
+                    "Downloading input files concurrently [chainTaskId:{chain_task_id}, concurrency:{workers}]"
+                );
+            }
+            for _ in 0..workers {
+                let sender = sender.clone();
+                let (next, stopped, download, fails_run, to_download, permit_receiver) = (
+                    &next,
+                    &stopped,
+                    &download,
+                    &fails_run,
+                    &to_download,
+                    &permit_receiver,
+                );
+                scope.spawn(move || {
+                    while !stopped.load(Ordering::Relaxed) {
+                        let permit = permit_receiver
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .recv();
+                        if permit.is_err() || stopped.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        let Some(&index) = to_download.get(next.fetch_add(1, Ordering::Relaxed))
+                        else {
+                            break;
+                        };
+                        let fetched = download(index);
+                        if let (_, _, Err(e)) = &fetched
+                            && fails_run(index, e)
+                        {
+                            stopped.store(true, Ordering::Relaxed);
+                        }
+                        if sender.send((index, fetched)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+            // Files are verified in order, whatever the order their downloads complete in.
+            let mut downloaded = HashMap::new();
+            let mut fetched_before = false;
+            let mut fetch = |index: usize| {
+                // Asking for the next file means the previous one has been processed.
+                if fetched_before {
+                    let _ = permit_sender.try_send(());
+                }
+                fetched_before = true;
+                loop {
+                    if let Some(result) = downloaded.remove(&index) {
+                        return result;
+                    }
+                    match receiver.recv() {
+                        Ok((received, result)) => {
+                            downloaded.insert(received, result);
+                        }
+                        Err(_) => {
+                            return (
+                                Instant::now(),
+                                1,
+                                Err(DownloadError::Unreachable(
+                                    "input file download stopped".to_string(),
+                                )),
+                            );
+                        }
+                    }
+                }
+            };
+            let result = self.process_input_files(
+                context,
+                checksums.as_ref(),
+                &staged,
+                progress_reporter.as_ref(),
+                &mut fetch,
+            );
+            // Downloads in progress complete, but no other download starts.
+            stopped.store(true, Ordering::Relaxed);
+            drop(permit_sender);
+            drop(receiver);
+            result
+        })
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::compute::pre_compute_args::PreComputeArgs;
+impl PreComputeApp {
+    /// Verifies and records the input files in order, taking each of them from `fetch` unless
+    /// it is `staged`, see [`InputFetcher::download_input_files`].
+    fn process_input_files(
+        &self,
+        context: &PreComputeContext,
+        checksums: Option<&HashMap<String, Checksum>>,
+        staged: &[bool],
+        progress_reporter: Option<&ProgressReporter>,
+        fetch: &mut dyn FnMut(usize) -> (Instant, u32, FetchedInputFile),
+    ) -> Result<(), ReplicateStatusCause> {
+        let args = &context.args;
+        let chain_task_id: &str = &context.chain_task_id;
+        let report_progress =
+            |url: &str, index: usize, status, size, started_at: Option<Instant>| {
+                if let Some(reporter) = progress_reporter {
+                    reporter.report(&FileProgress {
+                        url: redact_url(url),
+                        input_file_index: index + 1,
+                        status,
+                        size,
+                        duration_ms: started_at.map(|start| start.elapsed().as_millis() as u64),
+                    });
+                }
+            };
+        for (index, url) in args.input_files.iter().enumerate() {
+            context.cancellation.check()?;
+            info!("Downloading input file [chainTaskId:{chain_task_id}, url:{url}]");
+            report_progress(url, index, FileStatus::Started, None, None);
+            let mut started_at = Some(Instant::now());
+
+            let filename = sha256(url.to_string());
+            let input_file_done = |success| {
+                events::emit(
+                    chain_task_id,
+                    &Event::InputFileDone {
+                        url: url.to_string(),
+                        input_file_index: index + 1,
+                        success,
+                    },
+                )
+            };
+            let mut final_url = None;
+            let result = if staged[index] {
+                Ok((Path::new(&args.output_dir).join(&filename), None))
+            } else {
+                let (download_started_at, attempts, downloaded) = fetch(index);
+                started_at = Some(download_started_at);
+                downloaded
+                    .map(|(file_path, checksum, served_by)| {
+                        final_url = served_by;
+                        (file_path, checksum)
+                    })
+                    .map_err(|e| {
+                        input_file_done(false);
+                        report_progress(url, index, FileStatus::Failed, None, started_at);
+                        self.record_download_failure(url, Some(index + 1), &e, attempts);
+                        match e {
+                            DownloadError::NotEnoughDiskSpace => {
+                                ReplicateStatusCause::PreComputeNotEnoughDiskSpace
+                            }
+                            DownloadError::Cancelled => ReplicateStatusCause::PreComputeCancelled,
+                            _ => ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+                        }
+                    })
+            };
+            let result = result.and_then(|(file_path, checksum)| match checksums {
+                Some(checksums) => verify_input_file_checksum(
+                    self.filesystem.as_ref(),
+                    context.checksum_verifier.as_ref(),
+                    checksums,
+                    url,
+                    &file_path,
+                    checksum,
+                )
+                .map(|_| file_path.clone())
+                .inspect_err(|_| {
+                    error!("Invalid input file checksum [chainTaskId:{chain_task_id}, url:{url}]");
+                    input_file_done(false);
+                    report_progress(url, index, FileStatus::Failed, None, started_at);
+                    let _ = self.filesystem.remove_file(&file_path);
+                }),
+                None => Ok(file_path),
+            });
+            let is_optional = args.optional_input_files.contains(&(index + 1));
+            let file_path = match result {
+                Ok(file_path) => file_path,
+                // A full disk would fail every remaining file, and a cancelled run must stop,
+                // so these failures are never skipped.
+                Err(cause)
+                    if (is_optional || args.is_continue_on_error_enabled)
+                        && cause != ReplicateStatusCause::PreComputeNotEnoughDiskSpace
+                        && cause != ReplicateStatusCause::PreComputeCancelled =>
+                {
+                    warn!(
+                        "Skipping failed input file [chainTaskId:{chain_task_id}, url:{url}, optional:{is_optional}, cause:{cause:?}]"
+                    );
+                    self.report
+                        .borrow_mut()
+                        .skipped_input_files
+                        .push(SkippedInputFile {
+                            url: redact_url(url),
+                            input_file_index: index + 1,
+                            cause,
+                            optional: is_optional,
+                            final_url: final_url.as_deref().map(redact_url),
+                        });
+                    self.status.step_done(0);
+                    continue;
+                }
+                Err(cause) => return Err(cause),
+            };
+
+            let size = self.filesystem.stat(&file_path).ok().map(|stat| stat.size);
+            self.record_streamed_output_file(context, &file_path, final_url.as_deref());
+            input_file_done(true);
+            self.status.step_done(size.unwrap_or_default());
+            report_progress(url, index, FileStatus::Succeeded, size, started_at);
+        }
+        Ok(())
+    }
+}
+
+impl DatasetFetcher for PreComputeApp {
+    /// Downloads the encrypted dataset file from a URL, IPFS multi-address or Swarm reference,
+    /// and verifies its checksum.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Bytes)` containing the dataset's encrypted content if download and verification succeed.
+    /// * `Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed)` if the download fails or inputs are missing.
+    /// * `Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum)` if checksum validation fails,
+    ///   on every gateway tried when the dataset is downloaded through gateways.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let app = PreComputeApp::new(challenge);
+    /// let context = PreComputeContext::new("0x123456789abcdef", PreComputeArgs::read_args()?);
+    ///
+    /// app.download_encrypted_dataset(&context)?;
+    /// ```
+    fn download_encrypted_dataset(
+        &self,
+        context: &PreComputeContext,
+    ) -> Result<Bytes, ReplicateStatusCause> {
+        let args = &context.args;
+        let chain_task_id = &context.chain_task_id;
+        let encrypted_dataset_url: &str = &args.encrypted_dataset_url;
+        self.speculative_decryption.take();
+
+        if let Some(content) = args
+            .encrypted_dataset_checksum
+            .as_ref()
+            .and_then(|checksum| self.read_staged_dataset(context, encrypted_dataset_url, checksum))
+        {
+            return Ok(content);
+        }
+
+        info!(
+            "Downloading encrypted dataset file [chainTaskId:{chain_task_id}, url:{encrypted_dataset_url}]",
+        );
+
+        events::emit(
+            chain_task_id,
+            &Event::DatasetDownloadStarted {
+                url: encrypted_dataset_url.to_string(),
+            },
+        );
+
+        let mut dataset_report = DatasetReport {
+            url: redact_url(encrypted_dataset_url),
+            expected_size: args.dataset_size,
+            ..Default::default()
+        };
+        let download_result = if is_gateway_url(encrypted_dataset_url) {
+            let url_template = gateway_url_template(encrypted_dataset_url);
+            let (gateways, local_gateways) = context.gateways(encrypted_dataset_url);
+            let expected_size = args
+                .dataset_size
+                .or_else(|| consensus_size(&gateways, &url_template));
+            let gateways = if args.is_gateway_probing_enabled
+                && expected_size.is_none_or(|size| size >= MIN_PROBED_DATASET_SIZE)
+            {
+                // Latency only ranks gateways within a region group, so that a fast remote
+                // mirror never overtakes one located in the worker region.
+                let (local, remote) = gateways.split_at(local_gateways.min(gateways.len()));
+                [
+                    rank_gateways(local, &url_template),
+                    rank_gateways(remote, &url_template),
+                ]
+                .concat()
+            } else {
+                gateways
+            };
+            let (result, attempts) = download_from_gateways(
+                &gateways,
+                &url_template,
+                &context.retry_budget,
+                |url| {
+                    let (download, checksum) = self.download_and_checksum(context, url)?;
+                    check_size(url, download.content.len() as u64, expected_size)?;
+                    // A gateway serving corrupted content is skipped like an unreachable one,
+                    // the content being the same on every gateway.
+                    match &args.encrypted_dataset_checksum {
+                        Some(expected) if checksum != *expected => {
+                            self.speculative_decryption.take();
+                            warn!(
+                                "Gateway served content not matching dataset checksum [chainTaskId:{chain_task_id}, url:{url}, expected:{expected}, actual:{checksum}]"
+                            );
+                            Err(DownloadError::ChecksumMismatch)
+                        }
+                        _ => Ok((download, checksum)),
+                    }
+                },
+            );
+            dataset_report.gateway = attempts
+                .iter()
+                .find(|attempt| attempt.success)
+                .map(|attempt| attempt.gateway.clone());
+            dataset_report.gateway_attempts = attempts;
+            result
+        } else {
+            self.download_and_checksum(context, encrypted_dataset_url)
+        };
+        let attempts = dataset_report.gateway_attempts.len().max(1) as u32;
+        dataset_report.final_url = download_result
+            .as_ref()
+            .ok()
+            .map(|(download, _)| redact_url(&download.final_url));
+        self.report.borrow_mut().dataset = Some(dataset_report);
+
+        let (download, actual_checksum) = download_result.map_err(|e| {
+            self.record_download_failure(encrypted_dataset_url, None, &e, attempts);
+            match e {
+                DownloadError::TooLarge { limit } if Some(limit) == args.dataset_max_size => {
+                    ReplicateStatusCause::PreComputeDatasetTooLarge
+                }
+                DownloadError::Cancelled => ReplicateStatusCause::PreComputeCancelled,
+                DownloadError::ChecksumMismatch => {
+                    ReplicateStatusCause::PreComputeInvalidDatasetChecksum
+                }
+                _ => ReplicateStatusCause::PreComputeDatasetDownloadFailed,
+            }
+        })?;
+
+        info!("Checking encrypted dataset checksum [chainTaskId:{chain_task_id}]");
+        let expected_checksum = args
+            .encrypted_dataset_checksum
+            .as_ref()
+            .ok_or(ReplicateStatusCause::PreComputeDatasetChecksumMissing)?;
+        let actual_checksum =
+            verify_checksum(actual_checksum, expected_checksum).map_err(|actual_checksum| {
+                // Content failing its checksum is never decrypted for the application.
+                self.speculative_decryption.take();
+                error!(
+                    "Invalid dataset checksum [chainTaskId:{chain_task_id}, expected:{expected_checksum}, actual:{actual_checksum}]"
+                );
+                ReplicateStatusCause::PreComputeInvalidDatasetChecksum
+            })?;
+
+        events::emit(
+            chain_task_id,
+            &Event::ChecksumVerified {
+                checksum: actual_checksum.to_string(),
+            },
+        );
+
+        info!("Dataset downloaded and verified successfully.");
+        Ok(download.content)
+    }
+
+    /// Decrypts the provided encrypted dataset bytes using AES-CBC.
+    ///
+    /// The first 16 bytes of `encrypted_content` are treated as the IV.
+    /// The rest is the ciphertext. The decryption key is decoded from a base64 or hex string
+    /// with [`decode_key`], which tolerates surrounding whitespace and URL-safe encoding.
+    /// When `context.args.is_dataset_hmac_enabled` is set, the HMAC-SHA256 trailer of the
+    /// content is verified and removed before decryption. When
+    /// `context.args.dataset_age_identity` is set, the content is instead an age file
+    /// encrypted to that X25519 identity.
+    /// The content is taken by value so that decryption can reuse its buffer in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `encrypted_content` - Full encrypted dataset, including the IV prefix.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Bytes)` containing the plaintext dataset if decryption succeeds.
+    /// * `Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)` if the key is missing, decoding fails, or decryption fails.
+    /// * `Err(ReplicateStatusCause::PreComputeDatasetTooLarge)` if the decrypted dataset exceeds
+    ///   the configured maximum size or expansion ratio.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let app = PreComputeApp::new(challenge);
+    /// let context = PreComputeContext::new("0x123456789abcdef", PreComputeArgs::read_args()?);
+    ///
+    /// let encrypted = Bytes::from(vec![/* ... */]);
+    /// let decrypted = app.decrypt_dataset(&context, encrypted)?;
+    /// ```
+    fn decrypt_dataset(
+        &self,
+        context: &PreComputeContext,
+        encrypted_content: Bytes,
+    ) -> Result<Bytes, ReplicateStatusCause> {
+        let encrypted_size = encrypted_content.len();
+        let plain_content = match &context.args.dataset_age_identity {
+            Some(identity) => self.decrypt_age_dataset(context, identity, &encrypted_content)?,
+            None => self.decrypt_aes_dataset(context, encrypted_content)?,
+        };
+        self.check_decrypted_size(context, encrypted_size, plain_content.len())?;
+        events::emit(
+            &context.chain_task_id,
+            &Event::DecryptDone {
+                size: plain_content.len(),
+            },
+        );
+        Ok(plain_content)
+    }
+}
+
+/// Runs the steps preparing the files of the application, once the output folder has been
+/// checked.
+///
+/// Each concern is handled by its own component, so that any of them can be replaced
+/// independently, for instance by a fetcher reading from a cache.
+///
+/// The cancellation token of `context` is checked before each step, so that a cancelled run
+/// never starts decrypting or writing a dataset.
+///
+/// # Arguments
+///
+/// * `context` - The validated state of the run.
+/// * `status` - The status file tracking the current stage.
+/// * `dataset_fetcher` - Downloads and decrypts the dataset, if the task requires one.
+/// * `input_fetcher` - Checks URLs and downloads the input files.
+/// * `output_writer` - Saves the plain dataset in the output folder.
+fn prepare_files(
+    context: &PreComputeContext,
+    status: &StatusFile,
+    dataset_fetcher: &impl DatasetFetcher,
+    input_fetcher: &impl InputFetcher,
+    output_writer: &impl OutputWriter,
+) -> Result<(), ReplicateStatusCause> {
+    let cancellation = &context.cancellation;
+    if context.args.is_preflight_check_enabled {
+        cancellation.check()?;
+        status.stage(Stage::CheckingUrls);
+        input_fetcher.check_urls(context)?;
+    }
+    if context.args.is_dataset_required {
+        cancellation.check()?;
+        status.stage(Stage::DownloadingDataset);
+        let encrypted_content = dataset_fetcher.download_encrypted_dataset(context)?;
+        cancellation.check()?;
+        status.stage(Stage::DecryptingDataset);
+        let plain_content = dataset_fetcher.decrypt_dataset(context, encrypted_content)?;
+        cancellation.check()?;
+        status.stage(Stage::SavingDataset);
+        output_writer.save_plain_dataset_file(context, &plain_content)?;
+        status.step_done(plain_content.len() as u64);
+    }
+    cancellation.check()?;
+    status.stage(Stage::DownloadingInputFiles);
+    input_fetcher.download_input_files(context)
+}
+
+/// Downloads content by expanding the `{gateway}` placeholder of `url_template` with
+/// each gateway in turn, using `download` to fetch each URL.
+///
+/// Falling back to the next gateway is an extra attempt drawn from `retry_budget`, no other
+/// gateway being tried once it is exhausted.
+///
+/// # Returns
+///
+/// A tuple made of:
+/// * `Ok(T)` with the result of `download` for the first gateway which succeeded, or
+///   `Err(DownloadError)` with the error returned by the last gateway.
+/// * The outcome of every attempted gateway, in order.
+fn download_from_gateways<T>(
+    gateways: &[&str],
+    url_template: &str,
+    retry_budget: &RetryBudget,
+    download: impl Fn(&str) -> Result<T, DownloadError>,
+) -> (Result<T, DownloadError>, Vec<GatewayAttempt>) {
+    let mut attempts = Vec::with_capacity(gateways.len());
+    let mut last_error = DownloadError::InvalidUrl;
+    for (index, gateway) in gateways.iter().enumerate() {
+        let full_url = url_template.replace(GATEWAY_PLACEHOLDER, gateway);
+        let _retry_permit = match index {
+            0 => None,
+            _ => match retry_budget.acquire(&full_url) {
+                Some(permit) => Some(permit),
+                None => break,
+            },
+        };
+        info!("Attempting to download dataset from {full_url}");
+
+        match download(&full_url) {
+            Ok(content) => {
+                info!("Successfully downloaded from {full_url}");
+                attempts.push(GatewayAttempt {
+                    gateway: gateway.to_string(),
+                    success: true,
+                    http_status: None,
+                });
+                return (Ok(content), attempts);
+            }
+            // Other gateways would not be tried anyway.
+            Err(DownloadError::Cancelled) => return (Err(DownloadError::Cancelled), attempts),
+            Err(e) => {
+                info!("Failed to download from {full_url}");
+                attempts.push(GatewayAttempt {
+                    gateway: gateway.to_string(),
+                    success: false,
+                    http_status: e.status(),
+                });
+                last_error = e;
+            }
+        }
+    }
+    (Err(last_error), attempts)
+}
+
+/// Returns the content size advertised by at least two gateways in answer to HEAD requests.
+///
+/// Gateways serving the same IPFS content must serve the same number of bytes, so a size
+/// agreed on by several gateways allows rejecting corrupted answers from another gateway
+/// before they are decrypted. Gateways are probed concurrently. When sizes disagree, the
+/// most reported size wins.
+///
+/// # Returns
+///
+/// * `Some(u64)` with the agreed size.
+/// * `None` if fewer than two gateways are configured or no two gateways agree.
+fn consensus_size(gateways: &[&str], url_template: &str) -> Option<u64> {
+    if gateways.len() < 2 {
+        return None;
+    }
+    let sizes: Vec<u64> = thread::scope(|scope| {
+        let handles: Vec<_> = gateways
+            .iter()
+            .map(|gateway| {
+                let url = url_template.replace(GATEWAY_PLACEHOLDER, gateway);
+                scope.spawn(move || check_url(&url).ok().flatten())
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok().flatten())
+            .collect()
+    });
+
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for size in sizes {
+        *counts.entry(size).or_default() += 1;
+    }
+    let (size, count) = counts
+        .into_iter()
+        .max_by_key(|(size, count)| (*count, *size))?;
+    if count < 2 {
+        return None;
+    }
+    info!("Gateways agree on dataset size [size:{size}, gateways:{count}]");
+    Some(size)
+}
+
+/// Orders gateways from the fastest to the slowest to serve the first bytes of the content.
+///
+/// Gateways are probed concurrently with [`probe_url`]. Gateways whose probe fails are kept
+/// last, in their configured order, since they may still serve the content once the faster
+/// ones have failed.
+///
+/// # Example
+///
+/// ```
+/// let gateways = rank_gateways(&["https://slow.net", "https://fast.net"], "{gateway}/ipfs/Qm...");
+/// assert_eq!(gateways, vec!["https://fast.net", "https://slow.net"]);
+/// ```
+fn rank_gateways<'a>(gateways: &[&'a str], url_template: &str) -> Vec<&'a str> {
+    if gateways.len() < 2 {
+        return gateways.to_vec();
+    }
+    let latencies: Vec<Option<Duration>> = thread::scope(|scope| {
+        let handles: Vec<_> = gateways
+            .iter()
+            .map(|gateway| {
+                let url = url_template.replace(GATEWAY_PLACEHOLDER, gateway);
+                scope.spawn(move || match probe_url(&url) {
+                    Ok(latency) => {
+                        info!(
+                            "Probed gateway [gateway:{gateway}, latencyMs:{}]",
+                            latency.as_millis()
+                        );
+                        Some(latency)
+                    }
+                    Err(e) => {
+                        warn!("Failed to probe gateway [gateway:{gateway}, error:{e:?}]");
+                        None
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().ok().flatten())
+            .collect()
+    });
+
+    let mut ranked: Vec<(&str, Option<Duration>)> =
+        gateways.iter().copied().zip(latencies).collect();
+    // Stable sort: failed probes (`None`) go last and keep their configured order.
+    ranked.sort_by_key(|(_, latency)| (latency.is_none(), *latency));
+    ranked.into_iter().map(|(gateway, _)| gateway).collect()
+}
+
+/// Rejects content downloaded from `url` whose size differs from the `expected` one.
+fn check_size(url: &str, actual: u64, expected: Option<u64>) -> Result<(), DownloadError> {
+    match expected {
+        Some(expected) if expected != actual => {
+            warn!(
+                "Rejecting content with unexpected size [url:{url}, expected:{expected}, actual:{actual}]"
+            );
+            Err(DownloadError::SizeMismatch { expected, actual })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the cause reported when the dataset or its key cannot be written.
+fn saving_failure_cause(error: &io::Error) -> ReplicateStatusCause {
+    match error.kind() {
+        io::ErrorKind::StorageFull => ReplicateStatusCause::PreComputeNotEnoughDiskSpace,
+        _ => ReplicateStatusCause::PreComputeSavingPlainDatasetFailed,
+    }
+}
+
+/// Returns whether `uri` is an `/ipfs` or `/ipns` content path, the only multiaddrs left in
+/// the arguments once HTTP ones are converted to URLs, see
+/// [`MultiAddress`](crate::compute::types::MultiAddress).
+fn is_content_path(uri: &str) -> bool {
+    uri.starts_with("/ipfs/") || uri.starts_with("/ipns/")
+}
+
+/// Best-effort sender of start notifications and input file progress updates to the worker
+/// API.
+///
+/// Failing to send an update is logged by the [`WorkerApiClient`] and never fails the
+/// pre-compute stage.
+struct ProgressReporter<'a> {
+    chain_task_id: &'a str,
+    authorization: String,
+    client: WorkerApiClient,
+}
+
+impl<'a> ProgressReporter<'a> {
+    /// Gets the challenge used to authenticate progress updates.
+    ///
+    /// Returns `None`, disabling progress reporting, if the challenge cannot be signed.
+    fn new(challenge: &'a TaskChallenge) -> Option<Self> {
+        let chain_task_id = challenge.chain_task_id();
+        match challenge.get() {
+            Ok(authorization) => Some(ProgressReporter {
+                chain_task_id,
+                authorization,
+                client: WorkerApiClient::from_env(),
+            }),
+            Err(e) => {
+                warn!(
+                    "Progress reporting disabled, failed to sign challenge [chainTaskId:{chain_task_id}, cause:{e:?}]"
+                );
+                None
+            }
+        }
+    }
+
+    fn report_started(&self, notification: &StartNotification) {
+        let _ = self.client.send_start_notification(
+            ComputeStage::Pre,
+            &self.authorization,
+            self.chain_task_id,
+            notification,
+        );
+    }
+
+    fn report(&self, progress: &FileProgress) {
+        let _ = self.client.send_file_progress(
+            ComputeStage::Pre,
+            &self.authorization,
+            self.chain_task_id,
+            progress,
+        );
+    }
+}
+
+/// Verifies a downloaded input file against its entry in a checksums file.
+///
+/// The entry is looked up by the full URL first, then by the last segment of the URL path.
+/// The file is hashed in chunks, unless its `checksum` was already computed while it was
+/// downloaded.
+fn verify_input_file_checksum(
+    filesystem: &dyn Filesystem,
+    verifier: &dyn ChecksumVerifier,
+    checksums: &HashMap<String, Checksum>,
+    url: &str,
+    file_path: &Path,
+    checksum: Option<Checksum>,
+) -> Result<(), ReplicateStatusCause> {
+    let expected_checksum = checksums
+        .get(url)
+        .or_else(|| checksums.get(url_file_name(url)))
+        .ok_or(ReplicateStatusCause::PreComputeInvalidInputFileChecksum)?;
+    let checksum = match checksum {
+        Some(checksum) => checksum,
+        None => {
+            let mut hasher = verifier.hasher();
+            hash_file(filesystem, file_path, hasher.as_mut())
+                .map_err(|_| ReplicateStatusCause::PreComputeInvalidInputFileChecksum)?;
+            hasher.finalize()
+        }
+    };
+    verify_checksum(checksum, expected_checksum)
+        .map(|_| ())
+        .map_err(|_| ReplicateStatusCause::PreComputeInvalidInputFileChecksum)
+}
+
+/// Returns the path of `path` relative to the output folder, as named in the run report.
+fn output_file_name(context: &PreComputeContext, path: &Path) -> String {
+    path.strip_prefix(&context.args.output_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Returns the last segment of the path of `url`, without query string nor fragment.
+fn url_file_name(url: &str) -> &str {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .unwrap_or_default()
+}
+
+/// Returns whether `uri` is a Swarm reference, as in `bzz://<reference>/<path>`.
+fn is_swarm_url(uri: &str) -> bool {
+    uri.get(..SWARM_SCHEME.len())
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case(SWARM_SCHEME))
+}
+
+/// Returns whether `uri` must be downloaded through gateways, either because it is an
+/// IPFS content path or a Swarm reference, or because it contains a `{gateway}` placeholder.
+fn is_gateway_url(uri: &str) -> bool {
+    uri.contains(GATEWAY_PLACEHOLDER) || is_content_path(uri) || is_swarm_url(uri)
+}
+
+/// Returns the URL of `uri` on any gateway, the gateway being left as a `{gateway}`
+/// placeholder.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(gateway_url_template("/ipfs/Qm..."), "{gateway}/ipfs/Qm...");
+/// assert_eq!(gateway_url_template("bzz://abc/dataset.zip"), "{gateway}/bzz/abc/dataset.zip");
+/// ```
+fn gateway_url_template(uri: &str) -> String {
+    if uri.contains(GATEWAY_PLACEHOLDER) {
+        uri.to_string()
+    } else if is_swarm_url(uri) {
+        format!("{GATEWAY_PLACEHOLDER}/bzz/{}", &uri[SWARM_SCHEME.len()..])
+    } else {
+        format!("{GATEWAY_PLACEHOLDER}{uri}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::app_runner::ExitMode;
+    use crate::compute::pre_compute_args::PreComputeArgs;
+    use crate::compute::report::{GatewayAttempt, PreComputeReport};
+    use crate::compute::utils::crypto_utils::KeyEncoding;
+    use crate::compute::utils::env_utils::TeeSessionEnvironmentVariable::{
+        SignTeeChallengePrivateKey, SignWorkerAddress, WorkerHostEnvVar,
+    };
+    use crate::compute::utils::fs_utils::MemoryFilesystem;
+    use crate::compute::utils::hash_utils::{clean_hex_prefix, sha256_from_bytes};
+    use crate::compute::verifier::Blake3Verifier;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use serde_json::json;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::fs;
     use tempfile::TempDir;
     use testcontainers::core::WaitFor;
     use testcontainers::runners::SyncRunner;
     use testcontainers::{Container, GenericImage};
+    use wiremock::matchers::{body_partial_json, header, method, path, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const CHAIN_TASK_ID: &str = "0x123456789abcdef";
+    const DATASET_CHECKSUM: &str =
+        "0x02a12ef127dcfbdb294a090c8f0b69a0ca30b7940fc36cabf971f488efd374d7";
+    const ENCRYPTED_DATASET_KEY: &str = "ubA6H9emVPJT91/flYAmnKHC0phSV3cfuqsLxQfgow0=";
+    const HTTP_DATASET_URL: &str = "https://raw.githubusercontent.com/iExecBlockchainComputing/tee-worker-pre-compute-rust/main/src/tests_resources/encrypted-data.bin";
+    const IPFS_DATASET_URL: &str = "/ipfs/QmUVhChbLFiuzNK1g2GsWyWEiad7SXPqARnWzGumgziwEp";
+    const PLAIN_DATA_FILE: &str = "plain-data.txt";
+
+    fn get_pre_compute_app(
+        chain_task_id: &str,
+        urls: Vec<&str>,
+        output_dir: &str,
+    ) -> (PreComputeApp, PreComputeContext) {
+        let args = PreComputeArgs {
+            input_files: urls.into_iter().map(|url| url.parse().unwrap()).collect(),
+            optional_input_files: BTreeSet::new(),
+            input_file_headers: BTreeMap::new(),
+            input_files_concurrency: 1,
+            input_files_checksum_url: None,
+            input_dir: None,
+            output_dir: output_dir.to_string(),
+            is_task_subdir_enabled: false,
+            is_dataset_required: true,
+            encrypted_dataset_url: HTTP_DATASET_URL.to_string(),
+            encrypted_dataset_base64_key: ENCRYPTED_DATASET_KEY.to_string(),
+            encrypted_dataset_checksum: DATASET_CHECKSUM.parse().ok(),
+            plain_dataset_filename: PLAIN_DATA_FILE.to_string(),
+            dataset_gateways: vec![],
+            dataset_local_gateways: 0,
+            dataset_swarm_gateways: vec![],
+            is_gateway_probing_enabled: false,
+            is_speculative_decryption_enabled: false,
+            dataset_reencryption_key_path: None,
+            dataset_size: None,
+            dataset_max_size: None,
+            dataset_max_expansion_ratio: None,
+            dataset_key_encoding: KeyEncoding::Auto,
+            is_dataset_hmac_enabled: false,
+            dataset_age_identity: None,
+            dataset_tls_pins: vec![],
+            is_preflight_check_enabled: false,
+            is_progress_reporting_enabled: false,
+            is_continue_on_error_enabled: false,
+            is_output_tree_hash_enabled: false,
+            checksum_algorithm: Default::default(),
+            retry_budget_attempts: None,
+            retry_budget_secs: None,
+            artifacts_recipient: None,
+        };
+        let app = PreComputeApp {
+            download_failure: RefCell::new(None),
+            speculative_decryption: RefCell::new(None),
+            rng: RefCell::new(Box::new(OsRng)),
+            filesystem: Rc::new(StdFilesystem),
+            status: StatusFile::new(chain_task_id, None),
+            cancellation: CancellationToken::new(),
+            report: RefCell::new(PreComputeReport::new(chain_task_id)),
+            challenge: Rc::new(TaskChallenge::new(chain_task_id)),
+        };
+        (app, PreComputeContext::new(chain_task_id, args))
+    }
+
+    fn start_container() -> (Container<GenericImage>, String, String) {
+        let container = GenericImage::new("kennethreitz/httpbin", "latest")
+            .with_wait_for(WaitFor::message_on_stderr("Listening at"))
+            .start()
+            .expect("Failed to start Httpbin");
+        let port = container
+            .get_host_port_ipv4(80)
+            .expect("Could not get host port");
+
+        let json_url = format!("http://127.0.0.1:{port}/json");
+        let xml_url = format!("http://127.0.0.1:{port}/xml");
+
+        (container, json_url, xml_url)
+    }
+
+    // region check_output_folder
+    #[test]
+    fn check_output_folder_returns_ok_with_valid_args() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().to_str().unwrap();
+
+        let (app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], output_path);
+
+        let result = app.check_output_folder(&context);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn check_output_folder_returns_err_with_invalid_file_path() {
+        let non_existing_path = std::env::temp_dir()
+            .join("some_non_existing_output_dir_xyz_123")
+            .to_string_lossy()
+            .into_owned();
+
+        let (app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], &non_existing_path);
+
+        let result = app.check_output_folder(&context);
+        assert_eq!(
+            result,
+            Err(ReplicateStatusCause::PreComputeOutputFolderNotFound)
+        );
+    }
+
+    #[test]
+    fn check_output_folder_uses_injected_filesystem() {
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        let (mut app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/iexec_in_memory");
+        app.filesystem = filesystem.clone();
+
+        assert_eq!(
+            app.check_output_folder(&context),
+            Err(ReplicateStatusCause::PreComputeOutputFolderNotFound)
+        );
+        filesystem
+            .create_dir(Path::new("/iexec_in_memory"))
+            .unwrap();
+        assert_eq!(app.check_output_folder(&context), Ok(()));
+    }
+
+    #[test]
+    fn check_output_folder_creates_task_subdir_when_enabled() {
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        let (mut app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/iexec_out");
+        app.filesystem = filesystem.clone();
+        let context = PreComputeContext::new(
+            CHAIN_TASK_ID,
+            PreComputeArgs {
+                is_task_subdir_enabled: true,
+                ..context.args.clone()
+            },
+        );
+        let task_dir = format!("/iexec_out/{CHAIN_TASK_ID}");
+        assert_eq!(context.args.output_dir, task_dir);
+
+        assert_eq!(
+            app.check_output_folder(&context),
+            Err(ReplicateStatusCause::PreComputeOutputFolderNotFound)
+        );
+        assert!(!filesystem.exists(Path::new(&task_dir)));
+
+        filesystem.create_dir(Path::new("/iexec_out")).unwrap();
+        assert_eq!(app.check_output_folder(&context), Ok(()));
+        assert!(filesystem.stat(Path::new(&task_dir)).unwrap().is_dir);
+        // Retries find the subdirectory already created.
+        assert_eq!(app.check_output_folder(&context), Ok(()));
+    }
+
+    // endregion
+
+    // region check_urls
+    fn start_head_mock_server() -> (tokio::runtime::Runtime, MockServer) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("HEAD"))
+                .and(path("/valid"))
+                .respond_with(ResponseTemplate::new(200))
+                .mount(&server)
+                .await;
+            Mock::given(method("HEAD"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            server
+        });
+        (rt, mock_server)
+    }
+
+    #[test]
+    fn check_urls_succeeds_when_all_urls_are_valid() {
+        let (_rt, mock_server) = start_head_mock_server();
+        let valid_url = format!("{}/valid", mock_server.uri());
+        let (app, mut context) =
+            get_pre_compute_app(CHAIN_TASK_ID, vec![&valid_url, &valid_url], "");
+        context.args.encrypted_dataset_url = valid_url.clone();
+
+        assert_eq!(app.check_urls(&context), Ok(()));
+    }
+
+    #[test]
+    fn check_urls_reports_all_invalid_urls() {
+        testing_logger::setup();
+        let (_rt, mock_server) = start_head_mock_server();
+        let valid_url = format!("{}/valid", mock_server.uri());
+        let missing_url = format!("{}/missing", mock_server.uri());
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&missing_url, &valid_url, &missing_url],
+            "",
+        );
+        context.args.encrypted_dataset_url = missing_url.clone();
+
+        assert_eq!(
+            app.check_urls(&context),
+            Err(ReplicateStatusCause::PreComputePreflightCheckFailed)
+        );
+        testing_logger::validate(|captured_logs| {
+            let invalid_url_logs = captured_logs
+                .iter()
+                .filter(|c| c.body.starts_with("URL is invalid"))
+                .count();
+            assert_eq!(invalid_url_logs, 3);
+        });
+    }
+
+    #[test]
+    fn check_urls_skips_multi_address_dataset() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = IPFS_DATASET_URL.to_string();
+
+        assert_eq!(app.check_urls(&context), Ok(()));
+    }
+    // endregion
+
+    // region resolve_hosts
+    #[test]
+    fn resolve_hosts_succeeds_when_one_gateway_resolves() {
+        let (app, mut context) =
+            get_pre_compute_app(CHAIN_TASK_ID, vec!["http://localhost/input.txt"], "");
+        context.args.encrypted_dataset_url = IPFS_DATASET_URL.to_string();
+        context.args.dataset_gateways = vec![
+            "http://unknown-gateway.invalid".to_string(),
+            "http://localhost:8080".to_string(),
+        ];
+        let cache = DnsCache::new(Duration::from_secs(60));
+
+        assert_eq!(app.resolve_hosts(&context, &cache), Ok(()));
+    }
+
+    #[test]
+    fn resolve_hosts_fails_when_every_gateway_fails() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = IPFS_DATASET_URL.to_string();
+        context.args.dataset_gateways = vec![
+            "http://unknown-gateway.invalid".to_string(),
+            "http://other-gateway.invalid".to_string(),
+        ];
+        let cache = DnsCache::new(Duration::from_secs(60));
+
+        assert_eq!(
+            app.resolve_hosts(&context, &cache),
+            Err(ReplicateStatusCause::PreComputeHostResolutionFailed)
+        );
+    }
+
+    #[test]
+    fn resolve_hosts_reports_every_unresolved_host() {
+        testing_logger::setup();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![
+                "http://localhost/input.txt",
+                "http://unknown-host.invalid/input.txt",
+            ],
+            "",
+        );
+        context.args.encrypted_dataset_url = "http://dataset-host.invalid/dataset".to_string();
+        let cache = DnsCache::new(Duration::from_secs(60));
+
+        assert_eq!(
+            app.resolve_hosts(&context, &cache),
+            Err(ReplicateStatusCause::PreComputeHostResolutionFailed)
+        );
+        testing_logger::validate(|captured_logs| {
+            assert!(captured_logs.iter().any(|c| c.body
+                == format!(
+                    "Host resolution failed [chainTaskId:{CHAIN_TASK_ID}, hosts:dataset-host.invalid,unknown-host.invalid]"
+                )));
+        });
+    }
+    // endregion
+
+    // region download_input_files
+    #[test]
+    fn download_input_files_writes_data_uri_content() {
+        let data_uri = "data:text/plain;base64,aUV4ZWMgY29uZmln";
+        let temp_dir = TempDir::new().unwrap();
+        let (app, context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![data_uri],
+            temp_dir.path().to_str().unwrap(),
+        );
+
+        assert!(app.download_input_files(&context).is_ok());
+
+        let content = fs::read(temp_dir.path().join(sha256(data_uri.to_string()))).unwrap();
+        assert_eq!(content, b"iExec config");
+    }
+
+    #[test]
+    fn download_input_files_sends_headers_of_each_file() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(header("authorization", "Bearer token"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("private"))
+                .mount(&server)
+                .await;
+            server
+        });
+        let url = format!("{}/inputs/private.txt", server.uri());
+        let other_url = format!("{}/inputs/other.txt", server.uri());
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&url, &other_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.is_continue_on_error_enabled = true;
+        context.args.input_file_headers = BTreeMap::from([(
+            1,
+            vec![("Authorization".to_string(), "Bearer token".to_string())],
+        )]);
+
+        assert!(app.download_input_files(&context).is_ok());
+
+        let content = fs::read(temp_dir.path().join(sha256(url))).unwrap();
+        assert_eq!(content, b"private");
+        let skipped = &app.report.borrow().skipped_input_files;
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].url, other_url);
+    }
+
+    #[test]
+    fn download_input_files_success_with_single_file() {
+        let (_container, json_url, _) = start_container();
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&json_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+
+        let result = app.download_input_files(&context);
+        assert!(result.is_ok());
+
+        let url_hash = sha256(json_url);
+        let downloaded_file = temp_dir.path().join(url_hash);
+        assert!(downloaded_file.exists());
+    }
+
+    #[test]
+    fn download_input_files_success_with_multiple_files() {
+        let (_container, json_url, xml_url) = start_container();
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&json_url, &xml_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+
+        let result = app.download_input_files(&context);
+        assert!(result.is_ok());
+
+        let json_hash = sha256(json_url);
+        let xml_hash = sha256(xml_url);
+
+        assert!(temp_dir.path().join(json_hash).exists());
+        assert!(temp_dir.path().join(xml_hash).exists());
+    }
+
+    #[test]
+    fn test_download_failure_returns_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let (app, context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec!["https://invalid-url-that-should-fail.com/file.txt"],
+            temp_dir.path().to_str().unwrap(),
+        );
+
+        let result = app.download_input_files(&context);
+        assert_eq!(
+            result.unwrap_err(),
+            ReplicateStatusCause::PreComputeInputFileDownloadFailed
+        );
+    }
+
+    #[test]
+    fn test_download_failure_redacts_url() {
+        let temp_dir = TempDir::new().unwrap();
+        let (app, _context) =
+            get_pre_compute_app(CHAIN_TASK_ID, vec![], temp_dir.path().to_str().unwrap());
+
+        app.record_download_failure(
+            "https://host/file.txt?X-Amz-Signature=abc",
+            Some(1),
+            &DownloadError::Status(403),
+            2,
+        );
+
+        let failure = app.download_failure().unwrap();
+        assert_eq!(
+            failure.url,
+            "https://host/file.txt?X-Amz-Signature=REDACTED"
+        );
+        assert_eq!(failure.http_status, Some(403));
+    }
+
+    #[test]
+    fn test_partial_failure_stops_on_first_error() {
+        let (_container, json_url, xml_url) = start_container();
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![
+                &json_url,                                           // This should succeed
+                "https://invalid-url-that-should-fail.com/file.txt", // This should fail
+                &xml_url,                                            // This shouldn't be reached
+            ],
+            temp_dir.path().to_str().unwrap(),
+        );
+
+        let result = app.download_input_files(&context);
+        assert_eq!(
+            result.unwrap_err(),
+            ReplicateStatusCause::PreComputeInputFileDownloadFailed
+        );
+
+        // First file should be downloaded with SHA256 filename
+        let json_hash = sha256(json_url);
+        assert!(temp_dir.path().join(json_hash).exists());
+
+        // Third file should NOT be downloaded (stopped on second failure)
+        let xml_hash = sha256(xml_url);
+        assert!(!temp_dir.path().join(xml_hash).exists());
+    }
+
+    fn start_inputs_server(count: usize) -> MockServer {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let server = MockServer::start().await;
+            for i in 1..=count {
+                Mock::given(method("GET"))
+                    .and(path(format!("/inputs/input-{i}.txt")))
+                    .respond_with(
+                        ResponseTemplate::new(200)
+                            .set_body_string(format!("input-{i}"))
+                            .set_delay(Duration::from_millis(50)),
+                    )
+                    .mount(&server)
+                    .await;
+            }
+            server
+        })
+    }
+
+    #[test]
+    fn download_input_files_downloads_files_concurrently() {
+        let server = start_inputs_server(5);
+        let urls: Vec<String> = (1..=5)
+            .map(|i| format!("{}/inputs/input-{i}.txt", server.uri()))
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            urls.iter().map(String::as_str).collect(),
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.input_files_concurrency = 3;
+
+        assert!(app.download_input_files(&context).is_ok());
+        for (i, url) in urls.iter().enumerate() {
+            let content = fs::read_to_string(temp_dir.path().join(sha256(url.clone()))).unwrap();
+            assert_eq!(content, format!("input-{}", i + 1));
+        }
+    }
+
+    #[test]
+    fn download_input_files_bounds_downloads_ahead_of_verification() {
+        let server = start_inputs_server(10);
+        let urls: Vec<String> = (1..=10)
+            .map(|i| match i {
+                2 => format!("{}/inputs/missing.txt", server.uri()),
+                _ => format!("{}/inputs/input-{i}.txt", server.uri()),
+            })
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            urls.iter().map(String::as_str).collect(),
+            temp_dir.path().to_str().unwrap(),
+        );
+
+        assert_eq!(
+            app.download_input_files(&context),
+            Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed)
+        );
+        // Downloads run at most one file past the one being verified, so that the failure
+        // stops them right after the third file.
+        let requests = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(server.received_requests())
+            .unwrap();
+        let mut paths: Vec<&str> = requests.iter().map(|r| r.url.path()).collect();
+        paths.dedup();
+        let downloads = paths.len();
+        assert!((2..=3).contains(&downloads), "{downloads} downloads");
+    }
+
+    #[test]
+    fn download_input_files_stops_downloads_once_later_file_fails() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/inputs/input-1.txt"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string("input-1")
+                        .set_delay(Duration::from_millis(300)),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path_regex("^/inputs/input-[3-9].txt$"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("input"))
+                .mount(&server)
+                .await;
+            server
+        });
+        let urls: Vec<String> = (1..=9)
+            .map(|i| match i {
+                2 => format!("{}/inputs/missing.txt", server.uri()),
+                _ => format!("{}/inputs/input-{i}.txt", server.uri()),
+            })
+            .collect();
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            urls.iter().map(String::as_str).collect(),
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.input_files_concurrency = 2;
+
+        assert_eq!(
+            app.download_input_files(&context),
+            Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed)
+        );
+        // The second file fails while the first one is still downloading, so that no other
+        // download starts.
+        let requests = rt.block_on(server.received_requests()).unwrap();
+        let mut paths: Vec<&str> = requests.iter().map(|r| r.url.path()).collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths, vec!["/inputs/input-1.txt", "/inputs/missing.txt"]);
+    }
+
+    #[test]
+    fn download_input_files_fails_when_one_concurrent_download_fails() {
+        let server = start_inputs_server(2);
+        let urls = [
+            format!("{}/inputs/input-1.txt", server.uri()),
+            format!("{}/inputs/missing.txt", server.uri()),
+            format!("{}/inputs/input-2.txt", server.uri()),
+        ];
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            urls.iter().map(String::as_str).collect(),
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.input_files_concurrency = 2;
+
+        assert_eq!(
+            app.download_input_files(&context),
+            Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed)
+        );
+    }
+
+    fn start_checksums_server(checksums: String) -> MockServer {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/SHA256SUMS"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(checksums))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/inputs/input-1.txt"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("input-1"))
+                .mount(&server)
+                .await;
+            server
+        })
+    }
+
+    #[test]
+    fn download_input_files_success_with_valid_checksums_file() {
+        let checksum = sha256_from_bytes(b"input-1");
+        let server =
+            start_checksums_server(format!("{}  input-1.txt\n", clean_hex_prefix(&checksum)));
+        let input_url = format!("{}/inputs/input-1.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&input_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.input_files_checksum_url = Some(format!("{}/SHA256SUMS", server.uri()));
+
+        assert!(app.download_input_files(&context).is_ok());
+        assert!(temp_dir.path().join(sha256(input_url)).exists());
+    }
+
+    #[test]
+    fn download_input_files_failure_with_mismatching_checksums_file() {
+        let server = start_checksums_server(format!("{}  input-1.txt\n", "0".repeat(64)));
+        let input_url = format!("{}/inputs/input-1.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&input_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.input_files_checksum_url = Some(format!("{}/SHA256SUMS", server.uri()));
+
+        assert_eq!(
+            app.download_input_files(&context),
+            Err(ReplicateStatusCause::PreComputeInvalidInputFileChecksum)
+        );
+        assert!(!temp_dir.path().join(sha256(input_url)).exists());
+    }
+
+    #[test]
+    fn download_input_files_skips_failed_files_when_continue_on_error_enabled() {
+        let checksum = sha256_from_bytes(b"input-1");
+        let server =
+            start_checksums_server(format!("{}  input-1.txt\n", clean_hex_prefix(&checksum)));
+        let missing_url = format!("{}/inputs/missing.txt", server.uri());
+        let input_url = format!("{}/inputs/input-1.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&missing_url, &input_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.is_continue_on_error_enabled = true;
+
+        assert!(app.download_input_files(&context).is_ok());
+        assert!(app.has_skipped_input_files());
+        assert!(temp_dir.path().join(sha256(input_url)).exists());
+        assert_eq!(
+            app.report.borrow().skipped_input_files,
+            vec![SkippedInputFile {
+                url: missing_url,
+                input_file_index: 1,
+                cause: ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+                optional: false,
+                final_url: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn download_input_files_records_redacted_final_url_of_redirected_file() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/inputs/moved.txt"))
+                .respond_with(
+                    ResponseTemplate::new(302)
+                        .insert_header("Location", "/inputs/input-1.txt?token=secret"),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/inputs/input-1.txt"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("input-1"))
+                .mount(&server)
+                .await;
+            server
+        });
+        let input_url = format!("{}/inputs/moved.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&input_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+
+        assert!(app.download_input_files(&context).is_ok());
+        assert_eq!(
+            app.report.borrow().files,
+            vec![ReportedFile {
+                final_url: Some(format!(
+                    "{}/inputs/input-1.txt?token=REDACTED",
+                    server.uri()
+                )),
+                ..ReportedFile::new(&sha256(input_url), b"input-1")
+            }]
+        );
+    }
+
+    #[test]
+    fn download_input_files_skips_failed_optional_files() {
+        let checksum = sha256_from_bytes(b"input-1");
+        let server =
+            start_checksums_server(format!("{}  input-1.txt\n", clean_hex_prefix(&checksum)));
+        let input_url = format!("{}/inputs/input-1.txt", server.uri());
+        let missing_url = format!("{}/inputs/missing.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&input_url, &missing_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.optional_input_files = BTreeSet::from([2]);
+
+        assert!(app.download_input_files(&context).is_ok());
+        assert!(!app.has_skipped_input_files());
+        assert!(temp_dir.path().join(sha256(input_url)).exists());
+        assert_eq!(
+            app.report.borrow().skipped_input_files,
+            vec![SkippedInputFile {
+                url: missing_url,
+                input_file_index: 2,
+                cause: ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+                optional: true,
+                final_url: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn download_input_files_fails_when_required_file_fails_next_to_optional_ones() {
+        let server = start_checksums_server(String::new());
+        let missing_url = format!("{}/inputs/missing.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&missing_url, &missing_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.optional_input_files = BTreeSet::from([2]);
+
+        assert_eq!(
+            app.download_input_files(&context),
+            Err(ReplicateStatusCause::PreComputeInputFileDownloadFailed)
+        );
+    }
+
+    #[test]
+    fn download_input_files_copies_staged_input_file() {
+        let checksum = sha256_from_bytes(b"input-1");
+        let server =
+            start_checksums_server(format!("{}  input-1.txt\n", clean_hex_prefix(&checksum)));
+        // Not served, the input file must come from the input directory.
+        let input_url = format!("{}/staged/input-1.txt", server.uri());
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        filesystem.create_dir(Path::new("/iexec_in")).unwrap();
+        filesystem.create_dir(Path::new("/iexec_out")).unwrap();
+        filesystem
+            .write(Path::new("/iexec_in/input-1.txt"), b"input-1")
+            .unwrap();
+
+        let (mut app, mut context) =
+            get_pre_compute_app(CHAIN_TASK_ID, vec![&input_url], "/iexec_out");
+        app.filesystem = filesystem.clone();
+        context.args.input_dir = Some("/iexec_in".to_string());
+        context.args.input_files_checksum_url = Some(format!("{}/SHA256SUMS", server.uri()));
+
+        assert!(app.download_input_files(&context).is_ok());
+        assert_eq!(
+            filesystem.file(Path::new("/iexec_out").join(sha256(input_url))),
+            Some(b"input-1".to_vec())
+        );
+    }
+
+    #[test]
+    fn download_input_files_ignores_staged_input_file_with_invalid_checksum() {
+        let checksum = sha256_from_bytes(b"input-1");
+        let server =
+            start_checksums_server(format!("{}  input-1.txt\n", clean_hex_prefix(&checksum)));
+        let input_url = format!("{}/inputs/input-1.txt", server.uri());
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        filesystem.create_dir(Path::new("/iexec_in")).unwrap();
+        filesystem.create_dir(Path::new("/iexec_out")).unwrap();
+        filesystem
+            .write(Path::new("/iexec_in/input-1.txt"), b"tampered")
+            .unwrap();
+
+        let (mut app, mut context) =
+            get_pre_compute_app(CHAIN_TASK_ID, vec![&input_url], "/iexec_out");
+        app.filesystem = filesystem.clone();
+        context.args.input_dir = Some("/iexec_in".to_string());
+        context.args.input_files_checksum_url = Some(format!("{}/SHA256SUMS", server.uri()));
+
+        assert!(app.download_input_files(&context).is_ok());
+        assert_eq!(
+            filesystem.file(Path::new("/iexec_out").join(sha256(input_url))),
+            Some(b"input-1".to_vec())
+        );
+    }
+
+    #[test]
+    fn download_input_files_reports_progress_when_enabled() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/inputs/input-1.txt"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("input-1"))
+                .mount(&server)
+                .await;
+            for (status, size) in [("STARTED", None), ("SUCCEEDED", Some(7))] {
+                let mut expected_body = json!({ "inputFileIndex": 1, "status": status });
+                if let Some(size) = size {
+                    expected_body["size"] = json!(size);
+                }
+                Mock::given(method("POST"))
+                    .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/progress")))
+                    .and(body_partial_json(expected_body))
+                    .respond_with(ResponseTemplate::new(200))
+                    .expect(1)
+                    .mount(&server)
+                    .await;
+            }
+            server
+        });
+        let input_url = format!("{}/inputs/input-1.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&input_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.is_progress_reporting_enabled = true;
+
+        let worker_host = server.address().to_string();
+        let env_vars = vec![
+            (SignWorkerAddress.name(), Some("0xabcdef123456789")),
+            (
+                SignTeeChallengePrivateKey.name(),
+                Some("0xdd3b993ec21c71c1f6d63a5240850e0d4d8dd83ff70d29e49247958548c1d479"),
+            ),
+            (WorkerHostEnvVar.name(), Some(worker_host.as_str())),
+        ];
+        let result = temp_env::with_vars(env_vars, || app.download_input_files(&context));
+
+        assert!(result.is_ok());
+        rt.block_on(server.verify());
+    }
+
+    #[test]
+    fn notify_started_sends_configured_inputs_when_enabled() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path(format!("/compute/pre/{CHAIN_TASK_ID}/started")))
+                .and(body_partial_json(json!({
+                    "chainTaskId": CHAIN_TASK_ID,
+                    "isDatasetRequired": true,
+                    "inputFilesNumber": 2,
+                })))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            server
+        });
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec!["https://host/input-1.txt", "https://host/input-2.txt"],
+            "",
+        );
+
+        let worker_host = server.address().to_string();
+        let env_vars = vec![
+            (SignWorkerAddress.name(), Some("0xabcdef123456789")),
+            (
+                SignTeeChallengePrivateKey.name(),
+                Some("0xdd3b993ec21c71c1f6d63a5240850e0d4d8dd83ff70d29e49247958548c1d479"),
+            ),
+            (WorkerHostEnvVar.name(), Some(worker_host.as_str())),
+        ];
+        temp_env::with_vars(env_vars, || {
+            // Disabled by default.
+            app.notify_started(&context);
+            context.args.is_progress_reporting_enabled = true;
+            app.notify_started(&context);
+        });
+
+        rt.block_on(server.verify());
+    }
+
+    #[test]
+    fn download_input_files_failure_with_malformed_checksums_file() {
+        let server = start_checksums_server("not a checksums file".to_string());
+        let input_url = format!("{}/inputs/input-1.txt", server.uri());
+
+        let temp_dir = TempDir::new().unwrap();
+        let (app, mut context) = get_pre_compute_app(
+            CHAIN_TASK_ID,
+            vec![&input_url],
+            temp_dir.path().to_str().unwrap(),
+        );
+        context.args.input_files_checksum_url = Some(format!("{}/SHA256SUMS", server.uri()));
+
+        assert_eq!(
+            app.download_input_files(&context),
+            Err(ReplicateStatusCause::PreComputeInputFilesChecksumDownloadFailed)
+        );
+    }
+    // endregion
+
+    // region download_encrypted_dataset
+    #[test]
+    fn download_encrypted_dataset_success_with_valid_dataset_url() {
+        let (app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+
+        let actual_content = app.download_encrypted_dataset(&context);
+        let expected_content = download_from_url(HTTP_DATASET_URL, &DownloadOptions::default())
+            .map_err(|_| ReplicateStatusCause::PreComputeDatasetDownloadFailed);
+        assert_eq!(actual_content, expected_content);
+    }
+
+    #[test]
+    fn download_encrypted_dataset_failure_with_invalid_dataset_url() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "http://bad-url".to_string();
+        let actual_content = app.download_encrypted_dataset(&context);
+        assert_eq!(
+            actual_content,
+            Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed)
+        );
+    }
+
+    #[test]
+    fn download_encrypted_dataset_success_with_valid_iexec_gateway() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = IPFS_DATASET_URL.to_string();
+        context.args.encrypted_dataset_checksum =
+            "0x323b1637c7999942fbebfe5d42fe15dbfe93737577663afa0181938d7ad4a2ac"
+                .parse()
+                .ok();
+        let actual_content = app.download_encrypted_dataset(&context);
+        let expected_content = Ok(Bytes::from_static(b"hello world !\n"));
+        assert_eq!(actual_content, expected_content);
+    }
+
+    #[test]
+    fn download_encrypted_dataset_failure_with_invalid_gateway() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "/ipfs/INVALID_IPFS_DATASET_URL".to_string();
+        let actual_content = app.download_encrypted_dataset(&context);
+        let expected_content = Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed);
+        assert_eq!(actual_content, expected_content);
+    }
+
+    #[test]
+    fn download_encrypted_dataset_failure_with_invalid_dataset_checksum() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"other"));
+        let actual_content = app.download_encrypted_dataset(&context);
+        let expected_content = Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum);
+        assert_eq!(actual_content, expected_content);
+    }
+
+    #[test]
+    fn download_from_gateways_records_every_attempt() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (failing_server, serving_server) = rt.block_on(async {
+            let failing_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(504))
+                .mount(&failing_server)
+                .await;
+            let serving_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/ipfs/QmDataset"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&serving_server)
+                .await;
+            (failing_server, serving_server)
+        });
+        let failing_gateway = failing_server.uri();
+        let serving_gateway = serving_server.uri();
+
+        let retry_budget = RetryBudget::default();
+
+        let (result, attempts) = download_from_gateways(
+            &[&failing_gateway, &serving_gateway],
+            "{gateway}/ipfs/QmDataset",
+            &retry_budget,
+            |url| download_from_url(url, &DownloadOptions::default()),
+        );
+
+        assert_eq!(result, Ok(Bytes::from_static(b"content")));
+        assert_eq!(
+            attempts,
+            vec![
+                GatewayAttempt {
+                    gateway: failing_gateway,
+                    success: false,
+                    http_status: Some(504),
+                },
+                GatewayAttempt {
+                    gateway: serving_gateway,
+                    success: true,
+                    http_status: None,
+                },
+            ]
+        );
+    }
 
-    const CHAIN_TASK_ID: &str = "0x123456789abcdef";
-    const DATASET_CHECKSUM: &str =
-        "0x02a12ef127dcfbdb294a090c8f0b69a0ca30b7940fc36cabf971f488efd374d7";
-    const ENCRYPTED_DATASET_KEY: &str = "ubA6H9emVPJT91/flYAmnKHC0phSV3cfuqsLxQfgow0=";
-    const HTTP_DATASET_URL: &str = "https://raw.githubusercontent.com/iExecBlockchainComputing/tee-worker-pre-compute-rust/main/src/tests_resources/encrypted-data.bin";
-    const IPFS_DATASET_URL: &str = "/ipfs/QmUVhChbLFiuzNK1g2GsWyWEiad7SXPqARnWzGumgziwEp";
-    const PLAIN_DATA_FILE: &str = "plain-data.txt";
+    #[test]
+    fn download_from_gateways_stops_when_retry_budget_exhausted() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let failing_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(504))
+                .mount(&server)
+                .await;
+            server
+        });
+        let failing_gateway = failing_server.uri();
+        let retry_budget = RetryBudget::new(Some(1), None);
+
+        let (result, attempts) = download_from_gateways(
+            &[&failing_gateway, &failing_gateway, &failing_gateway],
+            "{gateway}/ipfs/QmDataset",
+            &retry_budget,
+            |url| download_from_url(url, &DownloadOptions::default()),
+        );
 
-    fn get_pre_compute_app(
-        chain_task_id: &str,
-        urls: Vec<&str>,
-        output_dir: &str,
-    ) -> PreComputeApp {
-        PreComputeApp {
-            chain_task_id: chain_task_id.to_string(),
-            pre_compute_args: PreComputeArgs {
-                input_files: urls.into_iter().map(String::from).collect(),
-                output_dir: output_dir.to_string(),
-                is_dataset_required: true,
-                encrypted_dataset_url: HTTP_DATASET_URL.to_string(),
-                encrypted_dataset_base64_key: ENCRYPTED_DATASET_KEY.to_string(),
-                encrypted_dataset_checksum: DATASET_CHECKSUM.to_string(),
-                plain_dataset_filename: PLAIN_DATA_FILE.to_string(),
-            },
-        }
+        assert_eq!(result, Err(DownloadError::Status(504)));
+        assert_eq!(attempts.len(), 2);
+        let usage = retry_budget.usage();
+        assert_eq!((usage.attempts, usage.denied), (1, 1));
     }
 
-    fn start_container() -> (Container<GenericImage>, String, String) {
-        let container = GenericImage::new("kennethreitz/httpbin", "latest")
-            .with_wait_for(WaitFor::message_on_stderr("Listening at"))
-            .start()
-            .expect("Failed to start Httpbin");
-        let port = container
-            .get_host_port_ipv4(80)
-            .expect("Could not get host port");
+    fn start_gateway(content: &'static str) -> (tokio::runtime::Runtime, MockServer) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(path("/ipfs/QmDataset"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(content))
+                .mount(&server)
+                .await;
+            server
+        });
+        (rt, server)
+    }
 
-        let json_url = format!("http://127.0.0.1:{port}/json");
-        let xml_url = format!("http://127.0.0.1:{port}/xml");
+    #[test]
+    fn consensus_size_requires_two_agreeing_gateways() {
+        let (_rt_1, corrupted) = start_gateway("corrupted content");
+        let (_rt_2, serving_1) = start_gateway("content");
+        let (_rt_3, serving_2) = start_gateway("content");
+        let (corrupted, serving_1, serving_2) = (corrupted.uri(), serving_1.uri(), serving_2.uri());
+        let template = "{gateway}/ipfs/QmDataset";
 
-        (container, json_url, xml_url)
+        assert_eq!(
+            consensus_size(&[&corrupted, &serving_1, &serving_2], template),
+            Some(7)
+        );
+        assert_eq!(consensus_size(&[&corrupted, &serving_1], template), None);
+        assert_eq!(consensus_size(&[&serving_1], template), None);
     }
 
-    // region check_output_folder
     #[test]
-    fn check_output_folder_returns_ok_with_valid_args() {
-        let temp_dir = TempDir::new().unwrap();
-        let output_path = temp_dir.path().to_str().unwrap();
+    fn rank_gateways_orders_by_latency_and_keeps_failed_probes_last() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (slow, fast) = rt.block_on(async {
+            let slow = MockServer::start().await;
+            Mock::given(path("/ipfs/QmDataset"))
+                .respond_with(
+                    ResponseTemplate::new(206)
+                        .set_body_string("content")
+                        .set_delay(Duration::from_millis(300)),
+                )
+                .mount(&slow)
+                .await;
+            let fast = MockServer::start().await;
+            Mock::given(path("/ipfs/QmDataset"))
+                .respond_with(ResponseTemplate::new(206).set_body_string("content"))
+                .mount(&fast)
+                .await;
+            (slow, fast)
+        });
+        let (slow, fast) = (slow.uri(), fast.uri());
+        let unreachable = "http://127.0.0.1:1";
+
+        let ranked = rank_gateways(&[unreachable, &slow, &fast], "{gateway}/ipfs/QmDataset");
+
+        assert_eq!(ranked, vec![fast.as_str(), slow.as_str(), unreachable]);
+    }
+
+    #[test]
+    fn download_encrypted_dataset_tries_fastest_gateway_first_when_probing() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        // Only GET requests are answered, so that no size consensus is reached and the
+        // dataset is considered large enough to be worth probing.
+        let (slow, fast) = rt.block_on(async {
+            let slow = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/ipfs/QmDataset"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string("content")
+                        .set_delay(Duration::from_millis(300)),
+                )
+                .mount(&slow)
+                .await;
+            let fast = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/ipfs/QmDataset"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&fast)
+                .await;
+            (slow, fast)
+        });
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "{gateway}/ipfs/QmDataset".to_string();
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+        context.args.dataset_gateways = vec![slow.uri(), fast.uri()];
+        context.args.is_gateway_probing_enabled = true;
+
+        let result = app.download_encrypted_dataset(&context);
+
+        assert_eq!(result, Ok(Bytes::from_static(b"content")));
+        let report = app.report.borrow();
+        let dataset = report.dataset.as_ref().unwrap();
+        assert_eq!(dataset.gateway, Some(fast.uri()));
+        assert_eq!(dataset.gateway_attempts.len(), 1);
+    }
 
-        let app = get_pre_compute_app(CHAIN_TASK_ID, vec![], output_path);
+    #[test]
+    fn download_encrypted_dataset_tries_worker_region_gateway_first_when_probing() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        // Only GET requests are answered, so that no size consensus is reached and the
+        // dataset is considered large enough to be worth probing.
+        let (slow, fast) = rt.block_on(async {
+            let slow = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/ipfs/QmDataset"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string("content")
+                        .set_delay(Duration::from_millis(300)),
+                )
+                .mount(&slow)
+                .await;
+            let fast = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/ipfs/QmDataset"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&fast)
+                .await;
+            (slow, fast)
+        });
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "{gateway}/ipfs/QmDataset".to_string();
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+        context.args.dataset_gateways = vec![slow.uri(), fast.uri()];
+        context.args.dataset_local_gateways = 1;
+        context.args.is_gateway_probing_enabled = true;
+
+        let result = app.download_encrypted_dataset(&context);
+
+        assert_eq!(result, Ok(Bytes::from_static(b"content")));
+        let report = app.report.borrow();
+        let dataset = report.dataset.as_ref().unwrap();
+        assert_eq!(dataset.gateway, Some(slow.uri()));
+        assert_eq!(dataset.gateway_attempts.len(), 1);
+    }
 
-        let result = app.check_output_folder();
-        assert!(result.is_ok());
+    #[test]
+    fn download_encrypted_dataset_skips_gateway_diverging_from_consensus() {
+        let (_rt_1, corrupted) = start_gateway("corrupted content");
+        let (_rt_2, serving_1) = start_gateway("content");
+        let (_rt_3, serving_2) = start_gateway("content");
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "{gateway}/ipfs/QmDataset".to_string();
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+        context.args.dataset_gateways = vec![corrupted.uri(), serving_1.uri(), serving_2.uri()];
+
+        let result = app.download_encrypted_dataset(&context);
+
+        assert_eq!(result, Ok(Bytes::from_static(b"content")));
+        let report = app.report.borrow();
+        let attempts = &report.dataset.as_ref().unwrap().gateway_attempts;
+        assert_eq!(attempts.len(), 2);
+        assert!(!attempts[0].success);
+        assert_eq!(attempts[1].gateway, serving_1.uri());
     }
 
     #[test]
-    fn check_output_folder_returns_err_with_invalid_file_path() {
-        let non_existing_path = "/tmp/some_non_existing_output_dir_xyz_123".to_string();
+    fn download_encrypted_dataset_falls_back_when_gateway_content_fails_checksum() {
+        // Both gateways serve 7 bytes, so the size consensus cannot tell them apart.
+        let (_rt_1, corrupted) = start_gateway("c0ntent");
+        let (_rt_2, serving) = start_gateway("content");
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "/ipfs/QmDataset".to_string();
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+        context.args.dataset_gateways = vec![corrupted.uri(), serving.uri()];
+
+        let result = app.download_encrypted_dataset(&context);
+
+        assert_eq!(result, Ok(Bytes::from_static(b"content")));
+        let report = app.report.borrow();
+        let dataset = report.dataset.as_ref().unwrap();
+        assert_eq!(dataset.gateway, Some(serving.uri()));
+        assert_eq!(dataset.gateway_attempts.len(), 2);
+        assert!(!dataset.gateway_attempts[0].success);
+    }
 
-        let app = get_pre_compute_app(CHAIN_TASK_ID, vec![], &non_existing_path);
+    #[test]
+    fn download_encrypted_dataset_fails_checksum_when_every_gateway_is_corrupted() {
+        let (_rt_1, corrupted_1) = start_gateway("c0ntent");
+        let (_rt_2, corrupted_2) = start_gateway("c0ntent");
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "/ipfs/QmDataset".to_string();
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+        context.args.dataset_gateways = vec![corrupted_1.uri(), corrupted_2.uri()];
+
+        let result = app.download_encrypted_dataset(&context);
 
-        let result = app.check_output_folder();
         assert_eq!(
             result,
-            Err(ReplicateStatusCause::PreComputeOutputFolderNotFound)
+            Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum)
+        );
+        assert_eq!(
+            app.report
+                .borrow()
+                .dataset
+                .as_ref()
+                .unwrap()
+                .gateway_attempts
+                .len(),
+            2
         );
+        assert!(app.speculative_decryption.borrow().is_none());
     }
 
-    // endregion
-
-    // region download_input_files
     #[test]
-    fn download_input_files_success_with_single_file() {
-        let (_container, json_url, _) = start_container();
+    fn download_encrypted_dataset_rejects_content_not_matching_expected_size() {
+        let (_rt, server) = start_gateway("content");
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = format!("{}/ipfs/QmDataset", server.uri());
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+
+        for dataset_size in [3, 10] {
+            context.args.dataset_size = Some(dataset_size);
+            assert_eq!(
+                app.download_encrypted_dataset(&context),
+                Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed)
+            );
+            assert_eq!(
+                app.report.borrow().dataset.as_ref().unwrap().expected_size,
+                Some(dataset_size)
+            );
+        }
 
-        let temp_dir = TempDir::new().unwrap();
-        let app = get_pre_compute_app(
-            CHAIN_TASK_ID,
-            vec![&json_url],
-            temp_dir.path().to_str().unwrap(),
+        context.args.dataset_size = Some(7);
+        assert_eq!(
+            app.download_encrypted_dataset(&context),
+            Ok(Bytes::from_static(b"content"))
         );
-
-        let result = app.download_input_files();
-        assert!(result.is_ok());
-
-        let url_hash = sha256(json_url);
-        let downloaded_file = temp_dir.path().join(url_hash);
-        assert!(downloaded_file.exists());
     }
 
     #[test]
-    fn download_input_files_success_with_multiple_files() {
-        let (_container, json_url, xml_url) = start_container();
+    fn download_encrypted_dataset_records_dataset_in_report() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "http://bad-url".to_string();
+        let _ = app.download_encrypted_dataset(&context);
+
+        let report = app.report.borrow();
+        let dataset = report.dataset.as_ref().unwrap();
+        assert_eq!(dataset.url, "http://bad-url");
+        assert_eq!(dataset.gateway, None);
+        assert!(dataset.gateway_attempts.is_empty());
+    }
 
+    #[test]
+    fn record_output_tree_exposes_digest_in_report_and_run_status() {
         let temp_dir = TempDir::new().unwrap();
-        let app = get_pre_compute_app(
-            CHAIN_TASK_ID,
-            vec![&json_url, &xml_url],
-            temp_dir.path().to_str().unwrap(),
-        );
+        fs::write(temp_dir.path().join(PLAIN_DATA_FILE), b"data").unwrap();
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.output_dir = temp_dir.path().to_str().unwrap().to_string();
 
-        let result = app.download_input_files();
-        assert!(result.is_ok());
+        app.record_output_tree(&context);
 
-        let json_hash = sha256(json_url);
-        let xml_hash = sha256(xml_url);
+        let digest = report::output_tree_sha256(temp_dir.path()).unwrap();
+        assert_eq!(app.report.borrow().output_tree_sha256, Some(digest.clone()));
+        assert_eq!(app.run_status().output_tree_sha256, Some(digest));
+    }
 
-        assert!(temp_dir.path().join(json_hash).exists());
-        assert!(temp_dir.path().join(xml_hash).exists());
+    #[test]
+    fn write_report_writes_only_to_configured_report_dir() {
+        let output_dir = TempDir::new().unwrap();
+        let report_dir = output_dir.path().join("reports");
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.output_dir = output_dir.path().to_str().unwrap().to_string();
+
+        temp_env::with_var_unset("IEXEC_PRE_COMPUTE_REPORT_DIR", || {
+            app.write_report(&context);
+        });
+        assert_eq!(fs::read_dir(output_dir.path()).unwrap().count(), 0);
+
+        temp_env::with_var("IEXEC_PRE_COMPUTE_REPORT_DIR", Some(&report_dir), || {
+            app.write_report(&context);
+        });
+        assert!(report_dir.join(report::REPORT_FILENAME).is_file());
+        assert!(!output_dir.path().join(report::REPORT_FILENAME).exists());
     }
 
     #[test]
-    fn test_download_failure_returns_error() {
-        let temp_dir = TempDir::new().unwrap();
-        let app = get_pre_compute_app(
-            CHAIN_TASK_ID,
-            vec!["https://invalid-url-that-should-fail.com/file.txt"],
-            temp_dir.path().to_str().unwrap(),
+    fn download_encrypted_dataset_expands_gateway_placeholder() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mirror = rt.block_on(async {
+            let mirror = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/datasets/dataset.zip"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&mirror)
+                .await;
+            mirror
+        });
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "{gateway}/datasets/dataset.zip".to_string();
+        // Checksums are compared regardless of the 0x prefix and case
+        context.args.encrypted_dataset_checksum = clean_hex_prefix(&sha256_from_bytes(b"content"))
+            .to_uppercase()
+            .parse()
+            .ok();
+        context.args.dataset_gateways = vec!["http://127.0.0.1:1".to_string(), mirror.uri()];
+
+        let content = app.download_encrypted_dataset(&context).unwrap();
+
+        assert_eq!(content, Bytes::from_static(b"content"));
+        let report = app.report.borrow();
+        let dataset = report.dataset.as_ref().unwrap();
+        assert_eq!(dataset.gateway, Some(mirror.uri()));
+        assert_eq!(dataset.gateway_attempts.len(), 2);
+        assert!(!dataset.gateway_attempts[0].success);
+    }
+
+    #[test]
+    fn download_encrypted_dataset_falls_back_between_swarm_gateways() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let swarm = rt.block_on(async {
+            let swarm = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/bzz/abcdef/dataset.zip"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&swarm)
+                .await;
+            swarm
+        });
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = "bzz://abcdef/dataset.zip".to_string();
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+        context.args.dataset_gateways = vec!["http://127.0.0.1:1".to_string()];
+        context.args.dataset_swarm_gateways = vec!["http://127.0.0.1:1".to_string(), swarm.uri()];
+
+        let content = app.download_encrypted_dataset(&context).unwrap();
+
+        assert_eq!(content, Bytes::from_static(b"content"));
+        let report = app.report.borrow();
+        let dataset = report.dataset.as_ref().unwrap();
+        assert_eq!(dataset.gateway, Some(swarm.uri()));
+        assert_eq!(dataset.gateway_attempts.len(), 2);
+        assert_eq!(
+            context.gateways("bzz://abcdef").0,
+            vec!["http://127.0.0.1:1", swarm.uri().as_str()]
         );
+    }
+
+    #[test]
+    fn download_encrypted_dataset_uses_injected_checksum_verifier() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/dataset.zip"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&server)
+                .await;
+            server
+        });
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = format!("{}/dataset.zip", server.uri());
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
+        context.checksum_verifier = Box::new(Blake3Verifier);
 
-        let result = app.download_input_files();
         assert_eq!(
-            result.unwrap_err(),
-            ReplicateStatusCause::PreComputeInputFileDownloadFailed
+            app.download_encrypted_dataset(&context),
+            Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum)
+        );
+
+        context.args.encrypted_dataset_checksum = Some(Blake3Verifier.checksum(b"content"));
+        assert_eq!(
+            app.download_encrypted_dataset(&context),
+            Ok(Bytes::from_static(b"content"))
         );
     }
 
     #[test]
-    fn test_partial_failure_stops_on_first_error() {
-        let (_container, json_url, xml_url) = start_container();
+    fn download_encrypted_dataset_uses_staged_dataset() {
+        let dataset_url = "http://127.0.0.1:1/datasets/dataset.zip";
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        filesystem.create_dir(Path::new("/iexec_in")).unwrap();
+        filesystem
+            .write(
+                &Path::new("/iexec_in").join(sha256(dataset_url.to_string())),
+                b"content",
+            )
+            .unwrap();
+        let (mut app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        app.filesystem = filesystem;
+        context.args.input_dir = Some("/iexec_in".to_string());
+        context.args.encrypted_dataset_url = dataset_url.to_string();
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
 
-        let temp_dir = TempDir::new().unwrap();
-        let app = get_pre_compute_app(
-            CHAIN_TASK_ID,
-            vec![
-                &json_url,                                           // This should succeed
-                "https://invalid-url-that-should-fail.com/file.txt", // This should fail
-                &xml_url,                                            // This shouldn't be reached
-            ],
-            temp_dir.path().to_str().unwrap(),
+        assert_eq!(
+            app.download_encrypted_dataset(&context),
+            Ok(Bytes::from_static(b"content"))
+        );
+        let report = app.report.borrow();
+        let dataset_report = report.dataset.as_ref().unwrap();
+        assert_eq!(dataset_report.url, dataset_url);
+        assert_eq!(
+            dataset_report.final_url.as_deref(),
+            Some(format!("file:///iexec_in/{}", sha256(dataset_url.to_string())).as_str())
         );
+    }
+
+    #[test]
+    fn download_encrypted_dataset_downloads_when_staged_dataset_is_invalid() {
+        let (_rt, server) = start_gateway("content");
+        let dataset_url = format!("{}/ipfs/QmDataset", server.uri());
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        filesystem.create_dir(Path::new("/iexec_in")).unwrap();
+        filesystem
+            .write(Path::new("/iexec_in/QmDataset"), b"tampered")
+            .unwrap();
+        let (mut app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        app.filesystem = filesystem;
+        context.args.input_dir = Some("/iexec_in".to_string());
+        context.args.encrypted_dataset_url = dataset_url;
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"content"));
 
-        let result = app.download_input_files();
         assert_eq!(
-            result.unwrap_err(),
-            ReplicateStatusCause::PreComputeInputFileDownloadFailed
+            app.download_encrypted_dataset(&context),
+            Ok(Bytes::from_static(b"content"))
         );
+    }
 
-        // First file should be downloaded with SHA256 filename
-        let json_hash = sha256(json_url);
-        assert!(temp_dir.path().join(json_hash).exists());
+    #[test]
+    fn is_gateway_url_detects_placeholder_and_multi_address() {
+        assert!(is_gateway_url("{gateway}/datasets/dataset.zip"));
+        assert!(is_gateway_url(
+            "/ipfs/QmUVhChbLFiuzNK1g2GsWyWEiad7SXPqARnWzGumgziwEp"
+        ));
+        assert!(is_gateway_url("/ipns/dataset.eth/dataset.zip"));
+        assert!(is_gateway_url("BZZ://abcdef/dataset.zip"));
+        assert!(!is_gateway_url("https://host/datasets/dataset.zip"));
+    }
 
-        // Third file should NOT be downloaded (stopped on second failure)
-        let xml_hash = sha256(xml_url);
-        assert!(!temp_dir.path().join(xml_hash).exists());
+    #[test]
+    fn gateway_url_template_expands_swarm_references() {
+        assert_eq!(
+            gateway_url_template("bzz://abcdef/datasets/dataset.zip"),
+            "{gateway}/bzz/abcdef/datasets/dataset.zip"
+        );
+        assert_eq!(gateway_url_template("/ipfs/Qm"), "{gateway}/ipfs/Qm");
+        assert_eq!(
+            gateway_url_template("{gateway}/datasets/dataset.zip"),
+            "{gateway}/datasets/dataset.zip"
+        );
     }
     // endregion
 
-    // region download_encrypted_dataset
+    // region decrypt_dataset
+    fn start_dataset_server() -> MockServer {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/encrypted-data.bin"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_bytes(include_bytes!("../tests_resources/encrypted-data.bin")),
+                )
+                .mount(&server)
+                .await;
+            server
+        })
+    }
+
     #[test]
-    fn download_encrypted_dataset_success_with_valid_dataset_url() {
-        let app = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+    fn decrypt_dataset_uses_speculative_decryption_once_checksum_verified() {
+        testing_logger::setup();
+        let server = start_dataset_server();
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = format!("{}/encrypted-data.bin", server.uri());
+        context.args.is_speculative_decryption_enabled = true;
 
-        let actual_content = app.download_encrypted_dataset();
-        let expected_content = download_from_url(HTTP_DATASET_URL)
-            .ok_or(ReplicateStatusCause::PreComputeDatasetDownloadFailed);
-        assert_eq!(actual_content, expected_content);
+        let encrypted_data = app.download_encrypted_dataset(&context).unwrap();
+        assert!(app.speculative_decryption.borrow().is_some());
+        let plain_data = app.decrypt_dataset(&context, encrypted_data);
+
+        assert_eq!(
+            plain_data,
+            Ok(Bytes::from_static(b"Some very useful data."))
+        );
+        assert!(app.speculative_decryption.borrow().is_none());
+        testing_logger::validate(|captured_logs| {
+            assert!(captured_logs.iter().any(|c| {
+                c.body
+                    .starts_with("Using dataset decrypted while downloading")
+            }));
+        });
     }
 
     #[test]
-    fn download_encrypted_dataset_failure_with_invalid_dataset_url() {
-        let mut app = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
-        app.pre_compute_args.encrypted_dataset_url = "http://bad-url".to_string();
-        let actual_content = app.download_encrypted_dataset();
+    fn download_encrypted_dataset_discards_speculative_decryption_on_invalid_checksum() {
+        let server = start_dataset_server();
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_url = format!("{}/encrypted-data.bin", server.uri());
+        context.args.encrypted_dataset_checksum = Some(Checksum::sha256_of(b"other content"));
+        context.args.is_speculative_decryption_enabled = true;
+
         assert_eq!(
-            actual_content,
-            Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed)
+            app.download_encrypted_dataset(&context),
+            Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum)
         );
+        assert!(app.speculative_decryption.borrow().is_none());
     }
 
     #[test]
-    fn download_encrypted_dataset_success_with_valid_iexec_gateway() {
-        let mut app = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
-        app.pre_compute_args.encrypted_dataset_url = IPFS_DATASET_URL.to_string();
-        app.pre_compute_args.encrypted_dataset_checksum =
-            "0x323b1637c7999942fbebfe5d42fe15dbfe93737577663afa0181938d7ad4a2ac".to_string();
-        let actual_content = app.download_encrypted_dataset();
-        let expected_content = Ok("hello world !\n".as_bytes().to_vec());
-        assert_eq!(actual_content, expected_content);
+    fn decrypt_dataset_success_with_valid_dataset() {
+        let (app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+
+        let encrypted_data = app.download_encrypted_dataset(&context).unwrap();
+        let expected_plain_data = Ok(Bytes::from_static(b"Some very useful data."));
+        let actual_plain_data = app.decrypt_dataset(&context, encrypted_data);
+
+        assert_eq!(actual_plain_data, expected_plain_data);
     }
 
     #[test]
-    fn download_encrypted_dataset_failure_with_invalid_gateway() {
-        let mut app = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
-        app.pre_compute_args.encrypted_dataset_url = "/ipfs/INVALID_IPFS_DATASET_URL".to_string();
-        let actual_content = app.download_encrypted_dataset();
-        let expected_content = Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed);
-        assert_eq!(actual_content, expected_content);
+    fn decrypt_dataset_success_with_age_identity() {
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.dataset_age_identity =
+            Some(include_str!("../tests_resources/dataset-age-identity.txt").to_string());
+        let encrypted_data = Bytes::from_static(include_bytes!("../tests_resources/dataset.age"));
+
+        assert_eq!(
+            app.decrypt_dataset(&context, encrypted_data.clone()),
+            Ok(Bytes::from_static(
+                b"Some very useful data, encrypted with age.\n"
+            ))
+        );
+
+        context.args.dataset_age_identity = Some("AGE-SECRET-KEY-1".to_string());
+        assert_eq!(
+            app.decrypt_dataset(&context, encrypted_data),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
     }
 
     #[test]
-    fn download_encrypted_dataset_failure_with_invalid_dataset_checksum() {
-        let mut app = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
-        app.pre_compute_args.encrypted_dataset_checksum = "invalid_dataset_checksum".to_string();
-        let actual_content = app.download_encrypted_dataset();
-        let expected_content = Err(ReplicateStatusCause::PreComputeInvalidDatasetChecksum);
-        assert_eq!(actual_content, expected_content);
+    fn decrypt_dataset_failure_when_hmac_trailer_missing() {
+        let key = general_purpose::STANDARD
+            .decode(ENCRYPTED_DATASET_KEY)
+            .unwrap();
+        let encrypted_data = Bytes::from(encrypt_aes256_cbc(
+            &key.try_into().unwrap(),
+            &[0u8; 16],
+            b"Some very useful data with no MAC.",
+        ));
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+
+        context.args.is_dataset_hmac_enabled = true;
+        assert_eq!(
+            app.decrypt_dataset(&context, encrypted_data.clone()),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+
+        context.args.is_dataset_hmac_enabled = false;
+        assert_eq!(
+            app.decrypt_dataset(&context, encrypted_data),
+            Ok(Bytes::from_static(b"Some very useful data with no MAC."))
+        );
     }
-    // endregion
 
-    // region decrypt_dataset
     #[test]
-    fn decrypt_dataset_success_with_valid_dataset() {
-        let app = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+    fn decrypt_dataset_failure_when_decrypted_dataset_too_large() {
+        let key = general_purpose::STANDARD
+            .decode(ENCRYPTED_DATASET_KEY)
+            .unwrap();
+        let encrypted_data = Bytes::from(encrypt_aes256_cbc(
+            &key.try_into().unwrap(),
+            &[0u8; 16],
+            b"Some very useful data.",
+        ));
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+
+        context.args.dataset_max_size = Some(8);
+        assert_eq!(
+            app.decrypt_dataset(&context, encrypted_data.clone()),
+            Err(ReplicateStatusCause::PreComputeDatasetTooLarge)
+        );
 
-        let encrypted_data = app.download_encrypted_dataset().unwrap();
-        let expected_plain_data = Ok("Some very useful data.".as_bytes().to_vec());
-        let actual_plain_data = app.decrypt_dataset(&encrypted_data);
+        context.args.dataset_max_size = None;
+        context.args.dataset_max_expansion_ratio = Some(0.25);
+        assert_eq!(
+            app.decrypt_dataset(&context, encrypted_data.clone()),
+            Err(ReplicateStatusCause::PreComputeDatasetTooLarge)
+        );
 
-        assert_eq!(actual_plain_data, expected_plain_data);
+        context.args.dataset_max_expansion_ratio = Some(1.0);
+        assert_eq!(
+            app.decrypt_dataset(&context, encrypted_data),
+            Ok(Bytes::from_static(b"Some very useful data."))
+        );
     }
 
     #[test]
     fn decrypt_dataset_failure_with_bad_key() {
-        let mut app = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
-        app.pre_compute_args.encrypted_dataset_base64_key = "bad_key".to_string();
-        let encrypted_data = app.download_encrypted_dataset().unwrap();
-        let actual_plain_data = app.decrypt_dataset(&encrypted_data);
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "");
+        context.args.encrypted_dataset_base64_key = "bad_key".to_string();
+        let encrypted_data = app.download_encrypted_dataset(&context).unwrap();
+        let actual_plain_data = app.decrypt_dataset(&context, encrypted_data);
 
         assert_eq!(
             actual_plain_data,
@@ -536,15 +3629,106 @@ mod tests {
     // endregion
 
     // region save_plain_dataset_file
+    #[test]
+    fn save_plain_dataset_file_moves_complete_file_into_place() {
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        filesystem.create_dir(Path::new("/iexec_out")).unwrap();
+        let (mut app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/iexec_out");
+        app.filesystem = filesystem.clone();
+
+        assert_eq!(
+            app.save_plain_dataset_file(&context, b"Some very useful data."),
+            Ok(())
+        );
+
+        let path = Path::new("/iexec_out").join(PLAIN_DATA_FILE);
+        assert_eq!(
+            filesystem.file(&path),
+            Some(b"Some very useful data.".to_vec())
+        );
+        assert!(!filesystem.exists(&path.with_file_name(format!("{PLAIN_DATA_FILE}.part"))));
+        assert_eq!(
+            app.report.borrow().files,
+            vec![ReportedFile::new(
+                PLAIN_DATA_FILE,
+                b"Some very useful data."
+            )]
+        );
+    }
+
+    #[test]
+    fn save_plain_dataset_file_writes_partial_file_in_workspace() {
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        filesystem.create_dir(Path::new("/iexec_out")).unwrap();
+        let (mut app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/iexec_out");
+        app.filesystem = filesystem.clone();
+        // A missing workspace fails the write, showing that the partial file goes there.
+        context.staging_dir = Some(PathBuf::from("/iexec_out/.workspace"));
+
+        assert_eq!(
+            app.save_plain_dataset_file(&context, b"Some very useful data."),
+            Err(ReplicateStatusCause::PreComputeSavingPlainDatasetFailed)
+        );
+
+        filesystem
+            .create_dir(Path::new("/iexec_out/.workspace"))
+            .unwrap();
+        assert_eq!(
+            app.save_plain_dataset_file(&context, b"Some very useful data."),
+            Ok(())
+        );
+        assert_eq!(
+            filesystem.file(Path::new("/iexec_out").join(PLAIN_DATA_FILE)),
+            Some(b"Some very useful data.".to_vec())
+        );
+    }
+
+    #[test]
+    fn create_workspace_fails_when_dataset_does_not_fit() {
+        let filesystem = Rc::new(MemoryFilesystem::with_capacity(8));
+        filesystem.create_dir(Path::new("/iexec_out")).unwrap();
+        let (mut app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/iexec_out");
+        app.filesystem = filesystem.clone();
+        context.args.dataset_size = Some(9);
+
+        assert_eq!(
+            app.create_workspace(&context).err(),
+            Some(ReplicateStatusCause::PreComputeNotEnoughDiskSpace)
+        );
+
+        context.args.dataset_size = Some(8);
+        let workspace = app.create_workspace(&context).unwrap();
+        assert_eq!(workspace.path().parent(), Some(Path::new("/iexec_out")));
+        assert!(filesystem.exists(workspace.path()));
+    }
+
+    #[test]
+    fn save_plain_dataset_file_failure_when_disk_is_full() {
+        let filesystem = Rc::new(MemoryFilesystem::with_capacity(8));
+        filesystem.create_dir(Path::new("/iexec_out")).unwrap();
+        let (mut app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/iexec_out");
+        app.filesystem = filesystem.clone();
+
+        assert_eq!(
+            app.save_plain_dataset_file(&context, b"Some very useful data."),
+            Err(ReplicateStatusCause::PreComputeNotEnoughDiskSpace)
+        );
+        assert!(
+            filesystem
+                .file(Path::new("/iexec_out").join(PLAIN_DATA_FILE))
+                .is_none()
+        );
+    }
+
     #[test]
     fn save_plain_dataset_file_success_with_valid_output_dir() {
         let temp_dir = TempDir::new().unwrap();
         let output_path = temp_dir.path().to_str().unwrap();
 
-        let app = get_pre_compute_app(CHAIN_TASK_ID, vec![], output_path);
+        let (app, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], output_path);
 
         let plain_dataset = "Some very useful data.".as_bytes().to_vec();
-        let saved_dataset = app.save_plain_dataset_file(&plain_dataset);
+        let saved_dataset = app.save_plain_dataset_file(&context, &plain_dataset);
 
         assert!(saved_dataset.is_ok());
 
@@ -562,15 +3746,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn save_plain_dataset_file_reencrypts_dataset_when_key_path_set() {
+        let temp_dir = TempDir::new().unwrap();
+        let output_path = temp_dir.path().to_str().unwrap();
+        let key_dir = TempDir::new().unwrap();
+        let key_path = key_dir.path().join("dataset.key");
+
+        let (mut app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], output_path);
+        context.args.dataset_reencryption_key_path = Some(key_path.to_str().unwrap().to_string());
+        app.rng = RefCell::new(Box::new(StdRng::seed_from_u64(42)));
+
+        let plain_dataset = b"Some very useful data.";
+        assert!(app.save_plain_dataset_file(&context, plain_dataset).is_ok());
+
+        let file_content = fs::read(temp_dir.path().join(PLAIN_DATA_FILE)).unwrap();
+        assert_ne!(file_content, plain_dataset);
+        let key = general_purpose::STANDARD
+            .decode(fs::read(&key_path).unwrap())
+            .unwrap();
+        let (expected_key, expected_iv) =
+            generate_aes256_key_and_iv(&mut StdRng::seed_from_u64(42));
+        assert_eq!(key, expected_key);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(
+            file_content,
+            encrypt_aes256_cbc(&expected_key, &expected_iv, plain_dataset)
+        );
+    }
+
     #[test]
     fn save_plain_dataset_file_failure_with_invalid_output_dir() {
         let temp_dir = TempDir::new().unwrap();
         let output_path = temp_dir.path().to_str().unwrap();
 
-        let mut app = get_pre_compute_app(CHAIN_TASK_ID, vec![], output_path);
-        app.pre_compute_args.plain_dataset_filename = "/some-folder-123/not-found".to_string();
+        let (app, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], output_path);
+        context.args.plain_dataset_filename = "/some-folder-123/not-found".to_string();
         let plain_dataset = "Some very useful data.".as_bytes().to_vec();
-        let saved_dataset = app.save_plain_dataset_file(&plain_dataset);
+        let saved_dataset = app.save_plain_dataset_file(&context, &plain_dataset);
 
         assert_eq!(
             saved_dataset,
@@ -578,4 +3796,108 @@ mod tests {
         );
     }
     // endregion
+
+    // region prepare_files
+    #[test]
+    fn prepare_files_composes_fetchers_and_writer() {
+        let (_, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/tmp");
+        let status = StatusFile::new(CHAIN_TASK_ID, None);
+        let mut dataset_fetcher = MockDatasetFetcher::new();
+        dataset_fetcher
+            .expect_download_encrypted_dataset()
+            .times(1)
+            .returning(|_| Ok(Bytes::from_static(b"encrypted")));
+        dataset_fetcher
+            .expect_decrypt_dataset()
+            .withf(|_, content| content.as_ref() == b"encrypted")
+            .times(1)
+            .returning(|_, _| Ok(Bytes::from_static(b"plain")));
+        let mut input_fetcher = MockInputFetcher::new();
+        input_fetcher.expect_check_urls().never();
+        input_fetcher
+            .expect_download_input_files()
+            .times(1)
+            .returning(|_| Ok(()));
+        let mut output_writer = MockOutputWriter::new();
+        output_writer
+            .expect_save_plain_dataset_file()
+            .withf(|_, content| content == b"plain")
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let result = prepare_files(
+            &context,
+            &status,
+            &dataset_fetcher,
+            &input_fetcher,
+            &output_writer,
+        );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(status.status().stage, Stage::DownloadingInputFiles);
+    }
+
+    #[test]
+    fn prepare_files_stops_before_input_files_when_dataset_fails() {
+        let (_, mut context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/tmp");
+        context.args.is_preflight_check_enabled = true;
+        let status = StatusFile::new(CHAIN_TASK_ID, None);
+        let mut dataset_fetcher = MockDatasetFetcher::new();
+        dataset_fetcher
+            .expect_download_encrypted_dataset()
+            .returning(|_| Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed));
+        dataset_fetcher.expect_decrypt_dataset().never();
+        let mut input_fetcher = MockInputFetcher::new();
+        input_fetcher
+            .expect_check_urls()
+            .times(1)
+            .returning(|_| Ok(()));
+        input_fetcher.expect_download_input_files().never();
+        let mut output_writer = MockOutputWriter::new();
+        output_writer.expect_save_plain_dataset_file().never();
+
+        let result = prepare_files(
+            &context,
+            &status,
+            &dataset_fetcher,
+            &input_fetcher,
+            &output_writer,
+        );
+
+        assert_eq!(
+            result,
+            Err(ReplicateStatusCause::PreComputeDatasetDownloadFailed)
+        );
+    }
+
+    #[test]
+    fn prepare_files_stops_at_next_step_when_cancelled() {
+        let (_, context) = get_pre_compute_app(CHAIN_TASK_ID, vec![], "/tmp");
+        let context = context.with_cancellation(CancellationToken::new());
+        let status = StatusFile::new(CHAIN_TASK_ID, None);
+        let mut dataset_fetcher = MockDatasetFetcher::new();
+        let cancellation = context.cancellation.clone();
+        dataset_fetcher
+            .expect_download_encrypted_dataset()
+            .times(1)
+            .returning(move |_| {
+                cancellation.cancel(ExitMode::Timeout);
+                Ok(Bytes::from_static(b"encrypted"))
+            });
+        dataset_fetcher.expect_decrypt_dataset().never();
+        let mut input_fetcher = MockInputFetcher::new();
+        input_fetcher.expect_download_input_files().never();
+        let output_writer = MockOutputWriter::new();
+
+        let result = prepare_files(
+            &context,
+            &status,
+            &dataset_fetcher,
+            &input_fetcher,
+            &output_writer,
+        );
+
+        assert_eq!(result, Err(ReplicateStatusCause::PreComputeCancelled));
+    }
+    // endregion
 }
@@ -1,5 +1,22 @@
 use crate::compute::errors::ReplicateStatusCause;
-use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::signer::{SignatureAlgorithm, verify_signature};
+use crate::compute::types::{InputUrl, MultiAddress};
+use crate::compute::utils::age_utils::Recipient;
+use crate::compute::utils::crypto_utils::{KeyEncoding, decode_key_share};
+use crate::compute::utils::env_utils::{
+    TeeSessionEnvironmentVariable, get_env_var_or_error, is_session_variable, session_variables,
+};
+use crate::compute::utils::hash_utils::{Checksum, hex_string_to_byte_array};
+use crate::compute::utils::shamir_utils::combine_shares;
+use crate::compute::utils::tls_utils::parse_pins;
+use crate::compute::verifier::ChecksumAlgorithm;
+use base64::{Engine as _, engine::general_purpose};
+use log::{error, info, warn};
+use reqwest::header::{HeaderName, HeaderValue};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
 
 /// Represents parameters required for pre-compute tasks in a Trusted Execution Environment (TEE).
 ///
@@ -9,14 +26,57 @@ use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_va
 #[derive(Clone, Default)]
 pub struct PreComputeArgs {
     pub output_dir: String,
+    pub is_task_subdir_enabled: bool,
     // Dataset related fields
     pub is_dataset_required: bool,
     pub encrypted_dataset_url: String,
     pub encrypted_dataset_base64_key: String,
-    pub encrypted_dataset_checksum: String,
+    pub dataset_key_encoding: KeyEncoding,
+    pub is_dataset_hmac_enabled: bool,
+    pub dataset_age_identity: Option<String>,
+    pub encrypted_dataset_checksum: Option<Checksum>,
     pub plain_dataset_filename: String,
+    pub dataset_gateways: Vec<String>,
+    /// Number of leading `dataset_gateways` tagged with the region advertised through
+    /// `IEXEC_WORKER_REGION`.
+    pub dataset_local_gateways: usize,
+    /// Gateways serving `bzz://` datasets, the public Swarm gateway when empty.
+    pub dataset_swarm_gateways: Vec<String>,
+    pub is_gateway_probing_enabled: bool,
+    pub is_speculative_decryption_enabled: bool,
+    pub dataset_reencryption_key_path: Option<String>,
+    pub dataset_size: Option<u64>,
+    pub dataset_max_size: Option<u64>,
+    pub dataset_max_expansion_ratio: Option<f64>,
+    pub dataset_tls_pins: Vec<String>,
     // Input files
-    pub input_files: Vec<String>,
+    pub input_files: Vec<InputUrl>,
+    /// 1-based indexes of the input files whose failure does not fail the run.
+    pub optional_input_files: BTreeSet<usize>,
+    /// Headers sent with the HTTP(S) requests of the input files, by 1-based index, those of
+    /// `IEXEC_INPUT_FILE_HEADERS_<N>` overriding those of `IEXEC_INPUT_FILE_HEADERS`. Files
+    /// without any header have no entry.
+    pub input_file_headers: BTreeMap<usize, Vec<(String, String)>>,
+    pub input_files_checksum_url: Option<String>,
+    /// Maximum number of input files downloaded at the same time, at least 1.
+    pub input_files_concurrency: usize,
+    // Directory of files staged by the worker
+    pub input_dir: Option<String>,
+    // Integrity policy of the dataset and input files
+    pub checksum_algorithm: ChecksumAlgorithm,
+    // Pre-flight check
+    pub is_preflight_check_enabled: bool,
+    // Progress reporting
+    pub is_progress_reporting_enabled: bool,
+    // Failure policy of the input files
+    pub is_continue_on_error_enabled: bool,
+    // Hash of the output directory tree
+    pub is_output_tree_hash_enabled: bool,
+    // Retry budget shared by all downloads
+    pub retry_budget_attempts: Option<u32>,
+    pub retry_budget_secs: Option<u64>,
+    // Encryption of the report and events file
+    pub artifacts_recipient: Option<Recipient>,
 }
 
 impl PreComputeArgs {
@@ -27,19 +87,92 @@ impl PreComputeArgs {
     /// - Required for all tasks:
     ///   - `IEXEC_PRE_COMPUTE_OUT`: Output directory path
     ///   - `IEXEC_DATASET_REQUIRED`: Boolean ("true"/"false") indicating dataset requirement
-    ///   - `IEXEC_INPUT_FILES_NUMBER`: Number of input files to load
+    ///   - `IEXEC_INPUT_FILES_NUMBER`: Number of input files to load, at most
+    ///     `IEXEC_MAX_INPUT_FILES` when the worker operator sets it
     /// - Required when `IEXEC_DATASET_REQUIRED` = "true":
     ///   - `IEXEC_DATASET_URL`: Encrypted dataset URL
-    ///   - `IEXEC_DATASET_KEY`: Base64-encoded dataset encryption key
+    ///   - `IEXEC_DATASET_KEY`: Base64 or hex-encoded dataset encryption key, unless an age
+    ///     identity or key shares are provided
     ///   - `IEXEC_DATASET_CHECKSUM`: Encrypted dataset checksum
     ///   - `IEXEC_DATASET_FILENAME`: Decrypted dataset filename
     /// - Input file URLs (`IEXEC_INPUT_FILE_URL_1`, `IEXEC_INPUT_FILE_URL_2`, etc.)
+    /// - Optional:
+    ///   - `IEXEC_PRE_COMPUTE_TASK_SUBDIR`: Boolean ("true"/"false") writing all outputs to
+    ///     `<IEXEC_PRE_COMPUTE_OUT>/<chainTaskId>/`, created if needed, instead of directly to
+    ///     `IEXEC_PRE_COMPUTE_OUT` (defaults to "false")
+    ///   - `IEXEC_PRE_COMPUTE_PREFLIGHT_CHECK`: Boolean ("true"/"false") enabling HEAD checks
+    ///     of all URLs before any download (defaults to "false")
+    ///   - `IEXEC_PRE_COMPUTE_PROGRESS_REPORTING`: Boolean ("true"/"false") enabling a start
+    ///     notification and per-file progress updates to the worker API (defaults to "false")
+    ///   - `IEXEC_PRE_COMPUTE_CONTINUE_ON_ERROR`: Boolean ("true"/"false") skipping input files
+    ///     which fail to download or to verify instead of failing the whole run (defaults
+    ///     to "false")
+    ///   - `IEXEC_PRE_COMPUTE_OUTPUT_TREE_HASH`: Boolean ("true"/"false") adding a hash of the
+    ///     whole output directory tree to the report and the exit summary once the run
+    ///     succeeds (defaults to "false")
+    ///   - `IEXEC_DATASET_KEY_ENCODING`: Encoding of `IEXEC_DATASET_KEY`, one of `base64`,
+    ///     `hex` or `auto` (defaults to `auto`, detecting hex keys)
+    ///   - `IEXEC_DATASET_GATEWAYS`: Comma-separated list of gateways used to expand IPFS
+    ///     multi-addresses and `{gateway}` placeholders in `IEXEC_DATASET_URL` (defaults to
+    ///     the iExec IPFS gateways)
+    ///   - `IEXEC_DATASET_GATEWAY_PROBING`: Boolean ("true"/"false") probing the latency of
+    ///     every gateway before downloading a large dataset, the fastest ones being tried
+    ///     first (defaults to "false")
+    ///   - `IEXEC_DATASET_REENCRYPTION_KEY_PATH`: Path of a file only readable by the
+    ///     application enclave. When set, the dataset is saved re-encrypted with an ephemeral
+    ///     key written to this path instead of in clear
+    ///   - `IEXEC_DATASET_SIZE`: Expected size in bytes of the encrypted dataset. The download
+    ///     is aborted as soon as more bytes are received
+    ///   - `IEXEC_DATASET_MAX_SIZE`: Maximum size in bytes of the downloaded and decrypted
+    ///     dataset (unlimited by default)
+    ///   - `IEXEC_DATASET_MAX_EXPANSION_RATIO`: Maximum ratio between the decrypted and the
+    ///     encrypted dataset sizes (unlimited by default)
+    ///   - `IEXEC_DATASET_TLS_PINS`: Comma-separated list of base64-encoded SHA-256 hashes of
+    ///     the SubjectPublicKeyInfo of the servers allowed to serve the dataset, optionally
+    ///     prefixed by `sha256/`. When set, the dataset can only be downloaded over HTTPS from
+    ///     a server presenting one of these keys
+    ///   - `IEXEC_DATASET_AGE_IDENTITY` or `IEXEC_DATASET_AGE_IDENTITY_FILE`: X25519 identity
+    ///     (`AGE-SECRET-KEY-1...`), or path of an identity file, of a dataset encrypted with
+    ///     age instead of AES-CBC
+    ///   - `IEXEC_DATASET_KEY_SHARES_THRESHOLD`: Number of Shamir shares needed to reassemble
+    ///     the dataset key instead of reading `IEXEC_DATASET_KEY`. The shares are read from
+    ///     `IEXEC_DATASET_KEY_SHARE_1`, `IEXEC_DATASET_KEY_SHARE_2`, etc., or from the files at
+    ///     `IEXEC_DATASET_KEY_SHARE_FILE_1`, `IEXEC_DATASET_KEY_SHARE_FILE_2`, etc., each share
+    ///     being the key bytes followed by the share x coordinate, encoded like the key
+    ///   - `IEXEC_DATASET_HMAC`: Boolean ("true"/"false") indicating that the encrypted dataset
+    ///     ends with an HMAC-SHA256 trailer, verified before decryption (defaults to "false")
+    ///   - `IEXEC_DATASET_SPECULATIVE_DECRYPTION`: Boolean ("true"/"false") decrypting an
+    ///     AES-CBC dataset while it downloads, the plaintext being used only once the checksum
+    ///     is verified. This holds both the encrypted and the plain dataset in memory, and
+    ///     does not apply to age or HMAC-authenticated datasets (defaults to "false")
+    ///   - `IEXEC_INPUT_FILES_CHECKSUM_URL`: URL of a `SHA256SUMS`-style file used to verify
+    ///     every input file
+    ///   - `IEXEC_PRE_COMPUTE_IN`: Directory of files already staged by the worker. A staged
+    ///     dataset or input file matching its checksum is copied instead of downloaded
+    ///   - `IEXEC_CHECKSUM_ALGORITHM`: Hash function of the dataset and input files checksums,
+    ///     one of `sha256`, `keccak256`, `blake3` or `cid` (defaults to `sha256`)
+    ///   - `IEXEC_RETRY_BUDGET_ATTEMPTS` and `IEXEC_RETRY_BUDGET_SECS`: Maximum number of extra
+    ///     download attempts, and maximum time spent in them, shared by the dataset and all
+    ///     input files (unlimited by default)
+    ///   - `IEXEC_PRE_COMPUTE_ARTIFACTS_RECIPIENT`: age X25519 recipient (`age1...`), such as
+    ///     the requester or enclave public key, the report and the events file are encrypted
+    ///     to, since they may reveal dataset URLs
+    ///   - `IEXEC_PRE_COMPUTE_CONFIG_SIGNATURE`: Signature of the session configuration, required
+    ///     when the build pins a configuration signer. No variable is read before it is
+    ///     verified, see [`verify_config_signature`]
     ///
     /// # Errors
     /// Returns `ReplicateStatusCause` error variants for:
     /// - Missing required environment variables
     /// - Invalid boolean values in `IEXEC_DATASET_REQUIRED`
     /// - Invalid numeric format in `IEXEC_INPUT_FILES_NUMBER`
+    /// - Malformed `IEXEC_DATASET_CHECKSUM` or input file URLs
+    /// - Unsupported `IEXEC_CHECKSUM_ALGORITHM` or `IEXEC_DATASET_KEY_ENCODING`
+    /// - Invalid numeric format in `IEXEC_DATASET_SIZE`, `IEXEC_DATASET_MAX_SIZE`,
+    ///   `IEXEC_DATASET_MAX_EXPANSION_RATIO`, `IEXEC_RETRY_BUDGET_ATTEMPTS` or
+    ///   `IEXEC_RETRY_BUDGET_SECS`
+    /// - Invalid age recipient in `IEXEC_PRE_COMPUTE_ARTIFACTS_RECIPIENT`
+    /// - Missing or invalid `IEXEC_PRE_COMPUTE_CONFIG_SIGNATURE` when a signer is pinned
     /// - Missing dataset parameters when required
     /// - Missing input file URLs
     ///
@@ -51,6 +184,8 @@ impl PreComputeArgs {
     /// let args = PreComputeArgs::read_args("task-1234".to_string())?;
     /// ```
     pub fn read_args() -> Result<Self, ReplicateStatusCause> {
+        verify_config_signature()?;
+
         let output_dir = get_env_var_or_error(
             TeeSessionEnvironmentVariable::IexecPreComputeOut,
             ReplicateStatusCause::PreComputeOutputPathMissing,
@@ -67,26 +202,140 @@ impl PreComputeArgs {
 
         let mut encrypted_dataset_url = String::new();
         let mut encrypted_dataset_base64_key = String::new();
-        let mut encrypted_dataset_checksum = String::new();
+        let mut dataset_key_encoding = KeyEncoding::default();
+        let mut is_dataset_hmac_enabled = false;
+        let mut dataset_age_identity = None;
+        let mut encrypted_dataset_checksum = None;
         let mut plain_dataset_filename = String::new();
+        let mut dataset_gateways = Vec::new();
+        let mut dataset_local_gateways = 0;
+        let mut dataset_swarm_gateways = Vec::new();
+        let mut is_gateway_probing_enabled = false;
+        let mut is_speculative_decryption_enabled = false;
+        let mut dataset_reencryption_key_path = None;
+        let mut dataset_size = None;
+        let mut dataset_max_size = None;
+        let mut dataset_max_expansion_ratio = None;
+        let mut dataset_tls_pins = Vec::new();
 
         if is_dataset_required {
-            encrypted_dataset_url = get_env_var_or_error(
+            let dataset_url = get_env_var_or_error(
                 TeeSessionEnvironmentVariable::IexecDatasetUrl,
                 ReplicateStatusCause::PreComputeDatasetUrlMissing,
             )?;
-            encrypted_dataset_base64_key = get_env_var_or_error(
+            encrypted_dataset_url = if dataset_url.starts_with('/') {
+                match dataset_url.parse::<MultiAddress>()? {
+                    MultiAddress::Content(path) => path,
+                    MultiAddress::Http(url) => {
+                        info!("Converted dataset multiaddr [address:{dataset_url}, url:{url}]");
+                        url
+                    }
+                }
+            } else {
+                dataset_url
+            };
+            dataset_age_identity = read_secret(
+                TeeSessionEnvironmentVariable::IexecDatasetAgeIdentity,
+                TeeSessionEnvironmentVariable::IexecDatasetAgeIdentityFile,
+            )?;
+            let key_shares_threshold = read_optional_limit::<usize>(
+                TeeSessionEnvironmentVariable::IexecDatasetKeySharesThreshold,
+            )?
+            .filter(|threshold| *threshold > 0);
+            encrypted_dataset_base64_key = match get_env_var_or_error(
                 TeeSessionEnvironmentVariable::IexecDatasetKey,
                 ReplicateStatusCause::PreComputeDatasetKeyMissing,
-            )?;
-            encrypted_dataset_checksum = get_env_var_or_error(
+            ) {
+                Ok(key) if key_shares_threshold.is_none() => key,
+                Err(_) if dataset_age_identity.is_some() => String::new(),
+                Err(e) if key_shares_threshold.is_none() => return Err(e),
+                _ => String::new(),
+            };
+            dataset_key_encoding = match get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetKeyEncoding,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            ) {
+                Ok(encoding) if !encoding.trim().is_empty() => encoding.trim().parse()?,
+                _ => KeyEncoding::default(),
+            };
+            if let Some(threshold) = key_shares_threshold {
+                let key = read_dataset_key_from_shares(threshold, dataset_key_encoding)?;
+                encrypted_dataset_base64_key = general_purpose::STANDARD.encode(key);
+                dataset_key_encoding = KeyEncoding::Base64;
+            }
+            let checksum = get_env_var_or_error(
                 TeeSessionEnvironmentVariable::IexecDatasetChecksum,
                 ReplicateStatusCause::PreComputeDatasetChecksumMissing,
             )?;
+            encrypted_dataset_checksum = Some(
+                checksum
+                    .parse::<Checksum>()
+                    .map_err(|_| ReplicateStatusCause::PreComputeInvalidDatasetChecksum)?,
+            );
             plain_dataset_filename = get_env_var_or_error(
                 TeeSessionEnvironmentVariable::IexecDatasetFilename,
                 ReplicateStatusCause::PreComputeDatasetFilenameMissing,
             )?;
+            let worker_region = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecWorkerRegion,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .ok()
+            .map(|region| region.trim().to_string())
+            .filter(|region| !region.is_empty());
+            let tagged_gateways = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetGateways,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .map(|value| parse_tagged_gateways(&value))
+            .unwrap_or_default();
+            (dataset_gateways, dataset_local_gateways) =
+                prefer_region(tagged_gateways, worker_region.as_deref());
+            if let Some(region) = &worker_region {
+                info!(
+                    "Preferring gateways of worker region [region:{region}, gateways:{dataset_local_gateways}]"
+                );
+            }
+            dataset_swarm_gateways = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetSwarmGateways,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .map(|value| parse_gateways(&value))
+            .unwrap_or_default();
+            is_gateway_probing_enabled = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetGatewayProbing,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+            is_speculative_decryption_enabled = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetSpeculativeDecryption,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+            dataset_reencryption_key_path = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetReencryptionKeyPath,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .ok()
+            .filter(|path| !path.trim().is_empty());
+            dataset_size = read_optional_limit(TeeSessionEnvironmentVariable::IexecDatasetSize)?;
+            dataset_max_size =
+                read_optional_limit(TeeSessionEnvironmentVariable::IexecDatasetMaxSize)?;
+            dataset_max_expansion_ratio = read_optional_limit::<f64>(
+                TeeSessionEnvironmentVariable::IexecDatasetMaxExpansionRatio,
+            )?
+            .filter(|ratio| *ratio > 0.0);
+            dataset_tls_pins = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetTlsPins,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .map(|value| parse_pins(&value))
+            .unwrap_or_default();
+            is_dataset_hmac_enabled = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDatasetHmac,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
         }
 
         let input_files_nb_str = get_env_var_or_error(
@@ -96,39 +345,485 @@ impl PreComputeArgs {
         let input_files_nb = input_files_nb_str
             .parse::<usize>()
             .map_err(|_| ReplicateStatusCause::PreComputeInputFilesNumberMissing)?;
+        // Rejected before any input file variable is read, whatever the number of files.
+        if let Some(max_input_files) =
+            read_optional_limit::<usize>(TeeSessionEnvironmentVariable::IexecMaxInputFiles)?
+            && input_files_nb > max_input_files
+        {
+            error!(
+                "Too many input files [inputFilesNumber:{input_files_nb}, maxInputFiles:{max_input_files}]"
+            );
+            return Err(ReplicateStatusCause::PreComputeTooManyInputFiles);
+        }
 
         let mut input_files = Vec::with_capacity(input_files_nb);
+        let mut optional_input_files = BTreeSet::new();
+        let mut input_file_headers = BTreeMap::new();
+        let common_headers = read_headers(TeeSessionEnvironmentVariable::IexecInputFileHeaders)?;
         for i in 1..=input_files_nb {
             let url = get_env_var_or_error(
                 TeeSessionEnvironmentVariable::IexecInputFileUrlPrefix(i),
                 ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing,
             )?;
-            input_files.push(url);
+            input_files.push(url.parse::<InputUrl>()?);
+            let is_optional = get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecInputFileOptionalPrefix(i),
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+            if is_optional {
+                optional_input_files.insert(i);
+            }
+            let headers = merge_headers(
+                &common_headers,
+                read_headers(TeeSessionEnvironmentVariable::IexecInputFileHeadersPrefix(
+                    i,
+                ))?,
+            );
+            if !headers.is_empty() {
+                input_file_headers.insert(i, headers);
+            }
         }
 
+        let input_files_checksum_url = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecInputFilesChecksumUrl,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .filter(|url| !url.trim().is_empty());
+
+        let input_files_concurrency = read_optional_limit::<NonZeroUsize>(
+            TeeSessionEnvironmentVariable::IexecInputFilesConcurrency,
+        )?
+        .map_or(1, NonZeroUsize::get);
+
+        let input_dir = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeIn,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .filter(|dir| !dir.trim().is_empty());
+
+        let checksum_algorithm = match get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecChecksumAlgorithm,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        ) {
+            Ok(algorithm) if !algorithm.trim().is_empty() => algorithm.trim().parse()?,
+            _ => ChecksumAlgorithm::default(),
+        };
+
+        let is_task_subdir_enabled = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeTaskSubdir,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+
+        let is_preflight_check_enabled = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputePreflightCheck,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+
+        let is_progress_reporting_enabled = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeProgressReporting,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+
+        let is_continue_on_error_enabled = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeContinueOnError,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+
+        let is_output_tree_hash_enabled = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeOutputTreeHash,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+
+        let retry_budget_attempts =
+            read_optional_limit(TeeSessionEnvironmentVariable::IexecRetryBudgetAttempts)?;
+        let retry_budget_secs =
+            read_optional_limit(TeeSessionEnvironmentVariable::IexecRetryBudgetSecs)?;
+        let artifacts_recipient = read_artifacts_recipient()?;
+
         Ok(PreComputeArgs {
             output_dir,
+            is_task_subdir_enabled,
             is_dataset_required,
             encrypted_dataset_url,
             encrypted_dataset_base64_key,
+            dataset_key_encoding,
+            is_dataset_hmac_enabled,
+            dataset_age_identity,
             encrypted_dataset_checksum,
             plain_dataset_filename,
+            dataset_gateways,
+            dataset_local_gateways,
+            dataset_swarm_gateways,
+            is_gateway_probing_enabled,
+            is_speculative_decryption_enabled,
+            dataset_reencryption_key_path,
+            dataset_size,
+            dataset_max_size,
+            dataset_max_expansion_ratio,
+            dataset_tls_pins,
             input_files,
+            optional_input_files,
+            input_file_headers,
+            input_files_checksum_url,
+            input_files_concurrency,
+            input_dir,
+            checksum_algorithm,
+            is_preflight_check_enabled,
+            is_progress_reporting_enabled,
+            is_continue_on_error_enabled,
+            is_output_tree_hash_enabled,
+            retry_budget_attempts,
+            retry_budget_secs,
+            artifacts_recipient,
+        })
+    }
+}
+
+/// Reads the age recipient configured by `IEXEC_PRE_COMPUTE_ARTIFACTS_RECIPIENT`.
+///
+/// # Returns
+///
+/// * `Ok(Some(Recipient))` if artifacts must be encrypted.
+/// * `Ok(None)` if the variable is unset or blank.
+/// * `Err(ReplicateStatusCause::PreComputeInvalidArtifactsRecipient)` if the variable is not
+///   an `age1...` recipient.
+pub fn read_artifacts_recipient() -> Result<Option<Recipient>, ReplicateStatusCause> {
+    get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecPreComputeArtifactsRecipient,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+    )
+    .ok()
+    .filter(|value| !value.trim().is_empty())
+    .map(|value| {
+        Recipient::parse(&value).map_err(|e| {
+            error!("Invalid artifacts recipient: {e}");
+            ReplicateStatusCause::PreComputeInvalidArtifactsRecipient
         })
+    })
+    .transpose()
+}
+
+/// Address or public key of the SMS or worker signing the session configuration, in the
+/// format expected by [`verify_signature`].
+///
+/// It is pinned at build time from the `IEXEC_PRE_COMPUTE_CONFIG_SIGNER` variable, so that it
+/// is part of the enclave measurement and cannot be replaced by the host.
+const CONFIG_SIGNER: Option<&str> = option_env!("IEXEC_PRE_COMPUTE_CONFIG_SIGNER");
+/// Algorithm of [`CONFIG_SIGNER`], pinned at build time from the
+/// `IEXEC_PRE_COMPUTE_CONFIG_SIGNER_ALGORITHM` variable (`secp256k1` by default).
+const CONFIG_SIGNER_ALGORITHM: Option<&str> =
+    option_env!("IEXEC_PRE_COMPUTE_CONFIG_SIGNER_ALGORITHM");
+
+/// Returns whether the variable `name` is covered by the configuration signature: every
+/// session variable read by the pre-compute, except `IEXEC_PRE_COMPUTE_CONFIG_SIGNATURE`
+/// itself.
+fn is_signed_variable(name: &str) -> bool {
+    is_session_variable(name)
+        && name != TeeSessionEnvironmentVariable::IexecPreComputeConfigSignature.name()
+}
+
+/// Builds the configuration manifest signed by the SMS or the worker from `variables`.
+///
+/// The manifest lists the name and value of every signed variable, sorted by name, each of
+/// them encoded as `<length>:<bytes>,` with its length in bytes, so that no value can be read
+/// as the end of a variable and the start of another one.
+///
+/// # Example
+///
+/// ```
+/// let manifest = config_manifest(session_variables());
+/// assert_eq!(manifest, "24:IEXEC_INPUT_FILES_NUMBER,1:0,19:IS_DATASET_REQUIRED,5:false,");
+/// ```
+fn config_manifest(variables: impl IntoIterator<Item = (String, String)>) -> String {
+    let mut variables: Vec<(String, String)> = variables
+        .into_iter()
+        .filter(|(name, _)| is_signed_variable(name))
+        .collect();
+    variables.sort();
+    variables
+        .iter()
+        .map(|(name, value)| format!("{}:{name},{}:{value},", name.len(), value.len()))
+        .collect()
+}
+
+/// Verifies the session configuration against the signature of the [`CONFIG_SIGNER`] pinned
+/// in this build, so that variables tampered with on the host are rejected before any of
+/// them is trusted.
+///
+/// Builds without a pinned signer do not verify the configuration, which is logged.
+///
+/// # Returns
+///
+/// * `Ok(())` if no signer is pinned or the signature is valid.
+/// * `Err(ReplicateStatusCause::PreComputeInvalidConfigSignature)` if the signature is missing
+///   or invalid, see [`verify_config_signature_by`].
+pub fn verify_config_signature() -> Result<(), ReplicateStatusCause> {
+    match CONFIG_SIGNER.filter(|signer| !signer.is_empty()) {
+        Some(signer) => verify_config_signature_by(signer, CONFIG_SIGNER_ALGORITHM),
+        None => {
+            warn!("No configuration signer pinned in this build, session variables are trusted");
+            Ok(())
+        }
+    }
+}
+
+/// Verifies that `IEXEC_PRE_COMPUTE_CONFIG_SIGNATURE` holds the signature by `signer` of the
+/// SHA-256 digest of the [`config_manifest`] of the session variables, with `algorithm`
+/// (`secp256k1` when `None`).
+///
+/// # Returns
+///
+/// * `Ok(())` if the signature is valid.
+/// * `Err(ReplicateStatusCause::PreComputeInvalidConfigSignature)` if the signature is missing
+///   or invalid, or the algorithm is unknown.
+fn verify_config_signature_by(
+    signer: &str,
+    algorithm: Option<&str>,
+) -> Result<(), ReplicateStatusCause> {
+    let invalid_signature = ReplicateStatusCause::PreComputeInvalidConfigSignature;
+    let algorithm = match algorithm {
+        Some(value) => value
+            .parse::<SignatureAlgorithm>()
+            .map_err(|_| invalid_signature.clone())?,
+        None => SignatureAlgorithm::default(),
+    };
+    let signature = get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecPreComputeConfigSignature,
+        invalid_signature.clone(),
+    )
+    .inspect_err(|_| error!("Missing configuration signature"))?;
+
+    let digest = hex_string_to_byte_array(&sha256::digest(config_manifest(session_variables())));
+    if !verify_signature(algorithm, &digest, &signature, signer) {
+        error!("Invalid configuration signature [signer:{signer}]");
+        return Err(invalid_signature);
+    }
+    Ok(())
+}
+
+/// Reads a secret given either inline in `value_variable` or as the path of a file in
+/// `file_variable`, so that secrets can be provisioned as files by the session.
+fn read_secret(
+    value_variable: TeeSessionEnvironmentVariable,
+    file_variable: TeeSessionEnvironmentVariable,
+) -> Result<Option<String>, ReplicateStatusCause> {
+    let read_non_blank = |variable| {
+        get_env_var_or_error(variable, ReplicateStatusCause::PreComputeFailedUnknownIssue)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+    };
+    if let Some(secret) = read_non_blank(value_variable) {
+        return Ok(Some(secret));
+    }
+    match read_non_blank(file_variable) {
+        Some(path) => fs::read_to_string(&path).map(Some).map_err(|e| {
+            error!("Failed to read secret file [path:{path}]: {e}");
+            ReplicateStatusCause::PreComputeDatasetKeyMissing
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Reassembles the dataset key from its Shamir shares, read from `IEXEC_DATASET_KEY_SHARE_<n>`
+/// or `IEXEC_DATASET_KEY_SHARE_FILE_<n>` with `n` starting at 1 and stopping at the first
+/// missing share.
+///
+/// Each share can be provisioned through a different channel, so that none of them holds
+/// the full key. The first `threshold` shares are combined.
+fn read_dataset_key_from_shares(
+    threshold: usize,
+    encoding: KeyEncoding,
+) -> Result<Vec<u8>, ReplicateStatusCause> {
+    let mut shares = Vec::new();
+    for index in 1.. {
+        let Some(share) = read_secret(
+            TeeSessionEnvironmentVariable::IexecDatasetKeyShare(index),
+            TeeSessionEnvironmentVariable::IexecDatasetKeyShareFile(index),
+        )?
+        else {
+            break;
+        };
+        shares.push(decode_key_share(&share, encoding).map_err(|e| {
+            error!("Invalid dataset key share [index:{index}]: {e}");
+            ReplicateStatusCause::PreComputeDatasetKeyMissing
+        })?);
+    }
+    if shares.len() < threshold {
+        error!(
+            "Not enough dataset key shares [threshold:{threshold}, shares:{}]",
+            shares.len()
+        );
+        return Err(ReplicateStatusCause::PreComputeDatasetKeyMissing);
+    }
+    combine_shares(&shares[..threshold]).ok_or_else(|| {
+        error!("Dataset key shares are inconsistent");
+        ReplicateStatusCause::PreComputeDatasetKeyMissing
+    })
+}
+
+/// Reads an optional numeric limit, failing when the variable is set to an invalid value so
+/// that a misconfigured limit is never silently lifted.
+fn read_optional_limit<T: FromStr>(
+    variable: TeeSessionEnvironmentVariable,
+) -> Result<Option<T>, ReplicateStatusCause> {
+    let name = variable.name();
+    match get_env_var_or_error(variable, ReplicateStatusCause::PreComputeFailedUnknownIssue) {
+        Ok(value) if !value.trim().is_empty() => value.trim().parse().map(Some).map_err(|_| {
+            error!("Invalid limit [variable:{name}, value:{value}]");
+            ReplicateStatusCause::PreComputeFailedUnknownIssue
+        }),
+        _ => Ok(None),
+    }
+}
+
+/// Reads the request headers configured by `variable`, none when it is unset or blank.
+fn read_headers(
+    variable: TeeSessionEnvironmentVariable,
+) -> Result<Vec<(String, String)>, ReplicateStatusCause> {
+    let name = variable.name();
+    match get_env_var_or_error(variable, ReplicateStatusCause::PreComputeFailedUnknownIssue) {
+        Ok(value) if !value.trim().is_empty() => parse_headers(&value).inspect_err(|_| {
+            error!("Invalid request headers [variable:{name}]");
+        }),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Parses request headers given as a JSON object mapping header names to values.
+///
+/// Values are credentials more often than not, so they are never logged.
+///
+/// # Returns
+///
+/// * `Ok(Vec<(String, String)>)` with the headers, sorted by name.
+/// * `Err(ReplicateStatusCause::PreComputeInvalidInputFileHeaders)` if `value` is not a JSON
+///   object of strings, or holds an invalid header name or value.
+///
+/// # Example
+///
+/// ```
+/// let headers = parse_headers(r#"{"Authorization":"Bearer token","X-Api-Key":"key"}"#)?;
+/// assert_eq!(headers[1], ("X-Api-Key".to_string(), "key".to_string()));
+/// ```
+pub fn parse_headers(value: &str) -> Result<Vec<(String, String)>, ReplicateStatusCause> {
+    let headers: BTreeMap<String, String> = serde_json::from_str(value).map_err(|e| {
+        error!(
+            "Request headers are not a JSON object of strings [line:{}, column:{}]",
+            e.line(),
+            e.column()
+        );
+        ReplicateStatusCause::PreComputeInvalidInputFileHeaders
+    })?;
+    for (name, value) in &headers {
+        if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(value).is_err()
+        {
+            error!("Invalid request header [name:{name}]");
+            return Err(ReplicateStatusCause::PreComputeInvalidInputFileHeaders);
+        }
     }
+    Ok(headers.into_iter().collect())
+}
+
+/// Returns `common` headers overridden by the `specific` ones, header names being compared
+/// case-insensitively.
+fn merge_headers(
+    common: &[(String, String)],
+    specific: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = common
+        .iter()
+        .filter(|(name, _)| {
+            !specific
+                .iter()
+                .any(|(specific_name, _)| specific_name.eq_ignore_ascii_case(name))
+        })
+        .cloned()
+        .collect();
+    headers.extend(specific);
+    headers
+}
+
+/// Parses a comma-separated list of gateways, dropping blank entries, trailing slashes and
+/// region tags.
+pub fn parse_gateways(value: &str) -> Vec<String> {
+    parse_tagged_gateways(value)
+        .into_iter()
+        .map(|(gateway, _)| gateway)
+        .collect()
+}
+
+/// Parses a comma-separated list of gateways, each optionally annotated with the region
+/// it is located in, as in `https://mirror.net;region=eu-west`.
+///
+/// # Returns
+///
+/// * `Vec<(String, Option<String>)>` - The gateways with their region tag, in the configured order
+fn parse_tagged_gateways(value: &str) -> Vec<(String, Option<String>)> {
+    value
+        .split(',')
+        .map(|entry| match entry.split_once(";region=") {
+            Some((gateway, region)) => (gateway, Some(region.trim().to_string())),
+            None => (entry, None),
+        })
+        .map(|(gateway, region)| {
+            let gateway = gateway.trim().trim_end_matches('/').to_string();
+            (gateway, region.filter(|region| !region.is_empty()))
+        })
+        .filter(|(gateway, _)| !gateway.is_empty())
+        .collect()
+}
+
+/// Moves the gateways located in the worker region ahead of the others, keeping the
+/// configured order within both groups. Regions are compared case-insensitively.
+///
+/// # Arguments
+///
+/// * `gateways` - The gateways with their optional region tag
+/// * `region` - The region advertised by the worker, if any
+///
+/// # Returns
+///
+/// * `(Vec<String>, usize)` - The reordered gateways and how many of them are in the worker region
+fn prefer_region(
+    gateways: Vec<(String, Option<String>)>,
+    region: Option<&str>,
+) -> (Vec<String>, usize) {
+    let (local, remote): (Vec<_>, Vec<_>) = gateways.into_iter().partition(|(_, tag)| {
+        region.is_some_and(|region| {
+            tag.as_deref()
+                .is_some_and(|tag| tag.eq_ignore_ascii_case(region))
+        })
+    });
+    let local_count = local.len();
+    let gateways = local
+        .into_iter()
+        .chain(remote)
+        .map(|(gateway, _)| gateway)
+        .collect();
+    (gateways, local_count)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::compute::errors::ReplicateStatusCause;
+    use crate::compute::signer::challenge_signer;
     use crate::compute::utils::env_utils::TeeSessionEnvironmentVariable::*;
     use std::collections::HashMap;
 
     const OUTPUT_DIR: &str = "/iexec_out";
     const DATASET_URL: &str = "https://dataset.url";
     const DATASET_KEY: &str = "datasetKey123";
-    const DATASET_CHECKSUM: &str = "0x123checksum";
+    const DATASET_CHECKSUM: &str =
+        "0x02a12ef127dcfbdb294a090c8f0b69a0ca30b7940fc36cabf971f488efd374d7";
     const DATASET_FILENAME: &str = "dataset.txt";
 
     fn setup_basic_env_vars() -> HashMap<String, String> {
@@ -181,7 +876,7 @@ mod tests {
             assert!(!args.is_dataset_required);
             assert_eq!(args.encrypted_dataset_url, "");
             assert_eq!(args.encrypted_dataset_base64_key, "");
-            assert_eq!(args.encrypted_dataset_checksum, "");
+            assert_eq!(args.encrypted_dataset_checksum, None);
             assert_eq!(args.plain_dataset_filename, "");
             assert_eq!(args.input_files.len(), 1);
             assert_eq!(args.input_files[0], "https://input-1.txt");
@@ -207,7 +902,7 @@ mod tests {
             assert_eq!(args.encrypted_dataset_base64_key, DATASET_KEY.to_string());
             assert_eq!(
                 args.encrypted_dataset_checksum,
-                DATASET_CHECKSUM.to_string()
+                Some(DATASET_CHECKSUM.parse().unwrap())
             );
             assert_eq!(args.plain_dataset_filename, DATASET_FILENAME.to_string());
             assert_eq!(args.input_files.len(), 0);
@@ -232,7 +927,7 @@ mod tests {
             assert!(!args.is_dataset_required);
             assert_eq!(args.encrypted_dataset_url, "");
             assert_eq!(args.encrypted_dataset_base64_key, "");
-            assert_eq!(args.encrypted_dataset_checksum, "");
+            assert_eq!(args.encrypted_dataset_checksum, None);
             assert_eq!(args.plain_dataset_filename, "");
             assert_eq!(args.input_files.len(), 3);
             assert_eq!(args.input_files[0], "https://input-1.txt");
@@ -240,116 +935,945 @@ mod tests {
             assert_eq!(args.input_files[2], "https://input-3.txt");
         });
     }
-    // endregion
-
-    // region parsing tests
     #[test]
-    fn read_args_succeeds_when_insensitive_bool_parsing() {
-        let test_values = vec!["false", "FALSE", "False", "fAlSe"];
-        for value_str in test_values {
-            let mut env_vars = setup_basic_env_vars();
-            env_vars.insert(IsDatasetRequired.name(), value_str.to_string());
+    fn read_args_succeeds_with_input_files_checksum_url() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.extend(setup_input_files_env_vars(2));
+        env_vars.insert(
+            IexecInputFilesChecksumUrl.name(),
+            "https://host/SHA256SUMS".to_string(),
+        );
 
-            temp_env::with_vars(to_temp_env_vars(env_vars), || {
-                let result = PreComputeArgs::read_args();
-                assert!(result.is_ok());
-                let args = result.unwrap();
-                assert!(!args.is_dataset_required);
-            });
-        }
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.input_files_checksum_url,
+                Some("https://host/SHA256SUMS".to_string())
+            );
+        });
     }
 
     #[test]
-    fn read_args_fails_when_invalid_bool_format() {
+    fn read_args_reads_input_dir() {
         let mut env_vars = setup_basic_env_vars();
-        env_vars.insert("IS_DATASET_REQUIRED".to_string(), "not-a-bool".to_string());
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            assert_eq!(PreComputeArgs::read_args().unwrap().input_dir, None);
+        });
 
+        env_vars.insert(IexecPreComputeIn.name(), "/iexec_in".to_string());
         temp_env::with_vars(to_temp_env_vars(env_vars), || {
-            let result = PreComputeArgs::read_args();
-            assert!(result.is_err());
             assert_eq!(
-                result.unwrap_err(),
-                ReplicateStatusCause::PreComputeIsDatasetRequiredMissing
+                PreComputeArgs::read_args().unwrap().input_dir,
+                Some("/iexec_in".to_string())
             );
         });
     }
 
     #[test]
-    fn read_args_fails_when_invalid_input_files_number_format() {
+    fn read_args_reads_dataset_size_limits() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(IexecDatasetSize.name(), "4096".to_string());
+        env_vars.insert(IexecDatasetMaxSize.name(), "1048576".to_string());
+        env_vars.insert(IexecDatasetMaxExpansionRatio.name(), "1.5".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.dataset_size, Some(4096));
+            assert_eq!(args.dataset_max_size, Some(1_048_576));
+            assert_eq!(args.dataset_max_expansion_ratio, Some(1.5));
+        });
+    }
+
+    #[test]
+    fn read_args_reads_dataset_tls_pins() {
         let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
         env_vars.insert(
-            "IEXEC_INPUT_FILES_NUMBER".to_string(),
-            "not-a-number".to_string(),
+            IexecDatasetTlsPins.name(),
+            "sha256/7sJ/oG4X1OXwestb3XqGJlD4WE45Zlt++MKjbEToc/I=, backupPin=".to_string(),
         );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().unwrap().dataset_tls_pins,
+                vec![
+                    "7sJ/oG4X1OXwestb3XqGJlD4WE45Zlt++MKjbEToc/I=".to_string(),
+                    "backupPin=".to_string()
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_fails_when_dataset_size_limit_invalid() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(IexecDatasetMaxSize.name(), "1GB".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_reads_checksum_algorithm() {
+        let mut env_vars = setup_basic_env_vars();
         env_vars.insert(IsDatasetRequired.name(), "false".to_string());
 
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.checksum_algorithm, ChecksumAlgorithm::Sha256);
+        });
+
+        env_vars.insert(IexecChecksumAlgorithm.name(), "BLAKE3".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.checksum_algorithm, ChecksumAlgorithm::Blake3);
+        });
+    }
+
+    #[test]
+    fn read_args_fails_when_checksum_algorithm_unsupported() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.insert(IexecChecksumAlgorithm.name(), "md5".to_string());
+
         temp_env::with_vars(to_temp_env_vars(env_vars), || {
-            let result = PreComputeArgs::read_args();
-            assert!(result.is_err());
             assert_eq!(
-                result.unwrap_err(),
-                ReplicateStatusCause::PreComputeInputFilesNumberMissing
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeInvalidChecksumAlgorithm)
             );
         });
     }
-    // endregion
 
-    // region dataset environment variables
     #[test]
-    fn read_args_fails_when_dataset_env_var_missing() {
-        let missing_env_var_causes = vec![
-            (
-                IexecPreComputeOut,
-                ReplicateStatusCause::PreComputeOutputPathMissing,
-            ),
-            (
-                IsDatasetRequired,
-                ReplicateStatusCause::PreComputeIsDatasetRequiredMissing,
-            ),
-            (
-                IexecInputFilesNumber,
-                ReplicateStatusCause::PreComputeInputFilesNumberMissing,
-            ),
-            (
-                IexecDatasetUrl,
-                ReplicateStatusCause::PreComputeDatasetUrlMissing,
-            ),
-            (
-                IexecDatasetKey,
-                ReplicateStatusCause::PreComputeDatasetKeyMissing,
-            ),
-            (
-                IexecDatasetChecksum,
-                ReplicateStatusCause::PreComputeDatasetChecksumMissing,
-            ),
-            (
-                IexecDatasetFilename,
-                ReplicateStatusCause::PreComputeDatasetFilenameMissing,
-            ),
-            (
-                IexecInputFileUrlPrefix(1),
-                ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing,
-            ),
-        ];
-        for (env_var, error) in missing_env_var_causes {
-            test_read_args_fails_with_missing_env_var(env_var, error);
-        }
+    fn read_args_reads_age_identity_instead_of_dataset_key() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let identity_file = temp_dir.path().join("identity.txt");
+        std::fs::write(&identity_file, "# comment\nAGE-SECRET-KEY-1ABC\n").unwrap();
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.remove(&IexecDatasetKey.name());
+        env_vars.insert(
+            IexecDatasetAgeIdentityFile.name(),
+            identity_file.to_str().unwrap().to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.dataset_age_identity.as_deref(),
+                Some("# comment\nAGE-SECRET-KEY-1ABC\n")
+            );
+            assert_eq!(args.encrypted_dataset_base64_key, "");
+        });
+
+        env_vars.insert(
+            IexecDatasetAgeIdentity.name(),
+            "AGE-SECRET-KEY-1XYZ".to_string(),
+        );
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.dataset_age_identity.as_deref(),
+                Some("AGE-SECRET-KEY-1XYZ")
+            );
+        });
+
+        env_vars.remove(&IexecDatasetAgeIdentity.name());
+        env_vars.insert(
+            IexecDatasetAgeIdentityFile.name(),
+            "/some-missing-identity-file-123".to_string(),
+        );
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeDatasetKeyMissing)
+            );
+        });
     }
 
-    fn test_read_args_fails_with_missing_env_var(
-        env_var: TeeSessionEnvironmentVariable,
-        error: ReplicateStatusCause,
-    ) {
-        //Set up environment variables
+    #[test]
+    fn read_args_reassembles_dataset_key_from_shares() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let share_file = temp_dir.path().join("share-3");
+        std::fs::write(&share_file, "1c52fc8abf51271a5ce28cf15f213c4a03\n").unwrap();
         let mut env_vars = setup_basic_env_vars();
         env_vars.extend(setup_dataset_env_vars());
-        env_vars.extend(setup_input_files_env_vars(1));
-        env_vars.remove(&env_var.name());
+        env_vars.insert(IexecDatasetKeySharesThreshold.name(), "2".to_string());
+        env_vars.insert(
+            IexecDatasetKeyShare(1).name(),
+            "0a32567e9ac2ee063a5276aecae21e2601".to_string(),
+        );
+        env_vars.insert(
+            IexecDatasetKeyShareFile(2).name(),
+            share_file.to_str().unwrap().to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.dataset_key_encoding, KeyEncoding::Base64);
+            assert_eq!(
+                general_purpose::STANDARD
+                    .decode(args.encrypted_dataset_base64_key)
+                    .unwrap(),
+                (1..=16).collect::<Vec<u8>>()
+            );
+        });
 
+        env_vars.insert(IexecDatasetKeySharesThreshold.name(), "3".to_string());
         temp_env::with_vars(to_temp_env_vars(env_vars), || {
-            let result = PreComputeArgs::read_args();
-            assert!(result.is_err());
-            assert_eq!(result.unwrap_err(), error);
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeDatasetKeyMissing)
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_reads_dataset_key_encoding() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.dataset_key_encoding, KeyEncoding::Auto);
+        });
+
+        env_vars.insert(IexecDatasetKeyEncoding.name(), "hex".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.dataset_key_encoding, KeyEncoding::Hex);
+        });
+
+        env_vars.insert(IexecDatasetKeyEncoding.name(), "base58".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_succeeds_when_preflight_check_enabled() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.insert(IexecPreComputePreflightCheck.name(), "TRUE".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert!(args.is_preflight_check_enabled);
+        });
+    }
+
+    #[test]
+    fn read_args_disables_preflight_check_by_default() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert!(!args.is_preflight_check_enabled);
+            assert!(!args.is_progress_reporting_enabled);
+            assert!(!args.is_continue_on_error_enabled);
+        });
+    }
+
+    #[test]
+    fn read_args_succeeds_when_progress_reporting_enabled() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.insert(IexecPreComputeProgressReporting.name(), "true".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert!(args.is_progress_reporting_enabled);
+        });
+    }
+
+    #[test]
+    fn read_args_reads_input_files_concurrency() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            assert_eq!(
+                PreComputeArgs::read_args().unwrap().input_files_concurrency,
+                1
+            );
+        });
+        env_vars.insert(IexecInputFilesConcurrency.name(), "4".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            assert_eq!(
+                PreComputeArgs::read_args().unwrap().input_files_concurrency,
+                4
+            );
+        });
+        env_vars.insert(IexecInputFilesConcurrency.name(), "0".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_reads_optional_input_files() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.extend(setup_input_files_env_vars(3));
+        env_vars.insert(IexecInputFileOptionalPrefix(1).name(), "TRUE".to_string());
+        env_vars.insert(IexecInputFileOptionalPrefix(2).name(), "no".to_string());
+        env_vars.insert(IexecInputFileOptionalPrefix(3).name(), "true".to_string());
+        env_vars.insert(IexecInputFileOptionalPrefix(4).name(), "true".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.optional_input_files, BTreeSet::from([1, 3]));
+        });
+    }
+
+    #[test]
+    fn read_args_merges_input_file_headers() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.extend(setup_input_files_env_vars(3));
+        env_vars.insert(
+            IexecInputFileHeaders.name(),
+            r#"{"Authorization":"Bearer common","X-Tenant":"iexec"}"#.to_string(),
+        );
+        env_vars.insert(
+            IexecInputFileHeadersPrefix(2).name(),
+            r#"{"authorization":"Bearer file-2"}"#.to_string(),
+        );
+        env_vars.insert(IexecInputFileHeadersPrefix(3).name(), " ".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            let common = vec![
+                ("Authorization".to_string(), "Bearer common".to_string()),
+                ("X-Tenant".to_string(), "iexec".to_string()),
+            ];
+            assert_eq!(args.input_file_headers.get(&1), Some(&common));
+            assert_eq!(
+                args.input_file_headers.get(&2),
+                Some(&vec![
+                    ("X-Tenant".to_string(), "iexec".to_string()),
+                    ("authorization".to_string(), "Bearer file-2".to_string()),
+                ])
+            );
+            assert_eq!(args.input_file_headers.get(&3), Some(&common));
+        });
+    }
+
+    #[test]
+    fn read_args_fails_when_input_file_headers_are_invalid() {
+        for headers in [
+            "Authorization: Bearer token",
+            r#"{"Authorization":42}"#,
+            r#"{"Bad Name":"value"}"#,
+            r#"{"Authorization":"Bearer
+token"}"#,
+        ] {
+            let mut env_vars = setup_basic_env_vars();
+            env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+            env_vars.extend(setup_input_files_env_vars(1));
+            env_vars.insert(IexecInputFileHeadersPrefix(1).name(), headers.to_string());
+
+            temp_env::with_vars(to_temp_env_vars(env_vars), || {
+                assert_eq!(
+                    PreComputeArgs::read_args().err(),
+                    Some(ReplicateStatusCause::PreComputeInvalidInputFileHeaders),
+                    "{headers}"
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn read_args_succeeds_when_continue_on_error_enabled() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.insert(IexecPreComputeContinueOnError.name(), "true".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert!(args.is_continue_on_error_enabled);
+        });
+    }
+
+    #[test]
+    fn read_args_reads_task_subdir_flag() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            assert!(!PreComputeArgs::read_args().unwrap().is_task_subdir_enabled);
+        });
+
+        env_vars.insert(IexecPreComputeTaskSubdir.name(), "true".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert!(args.is_task_subdir_enabled);
+            assert_eq!(args.output_dir, OUTPUT_DIR);
+        });
+    }
+
+    #[test]
+    fn read_args_accepts_input_files_up_to_maximum() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.extend(setup_input_files_env_vars(3));
+        env_vars.insert(IexecMaxInputFiles.name(), "3".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(PreComputeArgs::read_args().unwrap().input_files.len(), 3);
+        });
+    }
+
+    #[test]
+    fn read_args_fails_when_input_files_exceed_maximum() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        // No URL is set, the number alone must be rejected.
+        env_vars.insert(IexecInputFilesNumber.name(), "100000".to_string());
+        env_vars.insert(IexecMaxInputFiles.name(), "100".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeTooManyInputFiles)
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_reads_output_tree_hash_flag() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            assert!(
+                !PreComputeArgs::read_args()
+                    .unwrap()
+                    .is_output_tree_hash_enabled
+            );
+        });
+
+        env_vars.insert(IexecPreComputeOutputTreeHash.name(), "TRUE".to_string());
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert!(
+                PreComputeArgs::read_args()
+                    .unwrap()
+                    .is_output_tree_hash_enabled
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_reads_retry_budget() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.insert(IexecRetryBudgetAttempts.name(), "5".to_string());
+        env_vars.insert(IexecRetryBudgetSecs.name(), "30".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.retry_budget_attempts, Some(5));
+            assert_eq!(args.retry_budget_secs, Some(30));
+        });
+    }
+
+    #[test]
+    fn read_args_reads_artifacts_recipient() {
+        let recipient = "age14e7vfx33lmgd5e7wnqgrrq0npxhxzw3scq2makln9x39cf4t09vqffpks3";
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.insert(
+            IexecPreComputeArtifactsRecipient.name(),
+            recipient.to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars.clone()), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.artifacts_recipient,
+                Some(Recipient::parse(recipient).unwrap())
+            );
+        });
+
+        env_vars.insert(
+            IexecPreComputeArtifactsRecipient.name(),
+            "AGE-SECRET-KEY-1ABC".to_string(),
+        );
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeInvalidArtifactsRecipient)
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_succeeds_with_dataset_reencryption_key_path() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(
+            IexecDatasetReencryptionKeyPath.name(),
+            "/app-secrets/dataset.key".to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.dataset_reencryption_key_path,
+                Some("/app-secrets/dataset.key".to_string())
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_succeeds_with_dataset_gateways() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(
+            IexecDatasetGateways.name(),
+            "https://mirror-1.net/, ,https://mirror-2.net".to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.dataset_gateways,
+                vec!["https://mirror-1.net", "https://mirror-2.net"]
+            );
+            assert!(!args.is_gateway_probing_enabled);
+        });
+    }
+
+    #[test]
+    fn read_args_succeeds_with_swarm_dataset() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(
+            IexecDatasetUrl.name(),
+            "bzz://abcdef/dataset.zip".to_string(),
+        );
+        env_vars.insert(
+            IexecDatasetSwarmGateways.name(),
+            "https://swarm-1.net/,https://swarm-2.net".to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.encrypted_dataset_url, "bzz://abcdef/dataset.zip");
+            assert_eq!(
+                args.dataset_swarm_gateways,
+                vec!["https://swarm-1.net", "https://swarm-2.net"]
+            );
+            assert!(args.dataset_gateways.is_empty());
+        });
+    }
+
+    #[test]
+    fn read_args_prefers_dataset_gateways_of_worker_region() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(
+            IexecDatasetGateways.name(),
+            "https://mirror-us.net;region=us-east,https://mirror.net,https://mirror-eu.net/;region=EU-West"
+                .to_string(),
+        );
+        env_vars.insert(IexecWorkerRegion.name(), " eu-west ".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.dataset_gateways,
+                vec![
+                    "https://mirror-eu.net",
+                    "https://mirror-us.net",
+                    "https://mirror.net"
+                ]
+            );
+            assert_eq!(args.dataset_local_gateways, 1);
+        });
+    }
+
+    #[test]
+    fn read_args_keeps_dataset_gateways_order_without_worker_region() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(
+            IexecDatasetGateways.name(),
+            "https://mirror-us.net;region=us-east,https://mirror-eu.net;region=eu-west".to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.dataset_gateways,
+                vec!["https://mirror-us.net", "https://mirror-eu.net"]
+            );
+            assert_eq!(args.dataset_local_gateways, 0);
+        });
+    }
+
+    #[test]
+    fn read_args_converts_http_dataset_multiaddr_to_url() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(
+            IexecDatasetUrl.name(),
+            "/dns4/datasets.example.com/tcp/8443/https/encrypted/dataset.zip".to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(
+                args.encrypted_dataset_url,
+                "https://datasets.example.com:8443/encrypted/dataset.zip"
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_keeps_ipns_dataset_path() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(
+            IexecDatasetUrl.name(),
+            "/ipns/datasets.eth/dataset.zip".to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert_eq!(args.encrypted_dataset_url, "/ipns/datasets.eth/dataset.zip");
+        });
+    }
+
+    #[test]
+    fn read_args_fails_when_dataset_multiaddr_unsupported() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(IexecDatasetUrl.name(), "/ip4/10.0.0.1/tcp/4001".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().err(),
+                Some(ReplicateStatusCause::PreComputeUnsupportedDatasetAddress)
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_succeeds_with_gateway_probing_enabled() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(IexecDatasetGatewayProbing.name(), "TRUE".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert!(args.is_gateway_probing_enabled);
+            assert!(!args.is_speculative_decryption_enabled);
+        });
+    }
+
+    #[test]
+    fn read_args_succeeds_with_speculative_decryption_enabled() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.insert(IexecDatasetSpeculativeDecryption.name(), "true".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let args = PreComputeArgs::read_args().unwrap();
+            assert!(args.is_speculative_decryption_enabled);
+        });
+    }
+    // endregion
+
+    // region parsing tests
+    #[test]
+    fn read_args_succeeds_when_insensitive_bool_parsing() {
+        let test_values = vec!["false", "FALSE", "False", "fAlSe"];
+        for value_str in test_values {
+            let mut env_vars = setup_basic_env_vars();
+            env_vars.insert(IsDatasetRequired.name(), value_str.to_string());
+
+            temp_env::with_vars(to_temp_env_vars(env_vars), || {
+                let result = PreComputeArgs::read_args();
+                assert!(result.is_ok());
+                let args = result.unwrap();
+                assert!(!args.is_dataset_required);
+            });
+        }
+    }
+
+    #[test]
+    fn read_args_fails_when_invalid_bool_format() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert("IS_DATASET_REQUIRED".to_string(), "not-a-bool".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let result = PreComputeArgs::read_args();
+            assert!(result.is_err());
+            assert_eq!(
+                result.unwrap_err(),
+                ReplicateStatusCause::PreComputeIsDatasetRequiredMissing
+            );
+        });
+    }
+
+    #[test]
+    fn read_args_fails_when_invalid_input_files_number_format() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.insert(
+            "IEXEC_INPUT_FILES_NUMBER".to_string(),
+            "not-a-number".to_string(),
+        );
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let result = PreComputeArgs::read_args();
+            assert!(result.is_err());
+            assert_eq!(
+                result.unwrap_err(),
+                ReplicateStatusCause::PreComputeInputFilesNumberMissing
+            );
+        });
+    }
+    // endregion
+
+    // region dataset environment variables
+    #[test]
+    fn read_args_fails_when_dataset_env_var_missing() {
+        let missing_env_var_causes = vec![
+            (
+                IexecPreComputeOut,
+                ReplicateStatusCause::PreComputeOutputPathMissing,
+            ),
+            (
+                IsDatasetRequired,
+                ReplicateStatusCause::PreComputeIsDatasetRequiredMissing,
+            ),
+            (
+                IexecInputFilesNumber,
+                ReplicateStatusCause::PreComputeInputFilesNumberMissing,
+            ),
+            (
+                IexecDatasetUrl,
+                ReplicateStatusCause::PreComputeDatasetUrlMissing,
+            ),
+            (
+                IexecDatasetKey,
+                ReplicateStatusCause::PreComputeDatasetKeyMissing,
+            ),
+            (
+                IexecDatasetChecksum,
+                ReplicateStatusCause::PreComputeDatasetChecksumMissing,
+            ),
+            (
+                IexecDatasetFilename,
+                ReplicateStatusCause::PreComputeDatasetFilenameMissing,
+            ),
+            (
+                IexecInputFileUrlPrefix(1),
+                ReplicateStatusCause::PreComputeAtLeastOneInputFileUrlMissing,
+            ),
+        ];
+        for (env_var, error) in missing_env_var_causes {
+            test_read_args_fails_with_missing_env_var(env_var, error);
+        }
+    }
+
+    fn test_read_args_fails_with_missing_env_var(
+        env_var: TeeSessionEnvironmentVariable,
+        error: ReplicateStatusCause,
+    ) {
+        //Set up environment variables
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.extend(setup_input_files_env_vars(1));
+        env_vars.remove(&env_var.name());
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            let result = PreComputeArgs::read_args();
+            assert!(result.is_err());
+            assert_eq!(result.unwrap_err(), error);
+        });
+    }
+
+    #[test]
+    fn read_args_fails_with_malformed_input_file_url() {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_dataset_env_vars());
+        env_vars.extend(setup_input_files_env_vars(1));
+        env_vars.insert(
+            IexecInputFileUrlPrefix(1).name(),
+            "gopher://host/input.txt".to_string(),
+        );
+
+        temp_env::with_vars(to_temp_env_vars(env_vars), || {
+            assert_eq!(
+                PreComputeArgs::read_args().unwrap_err(),
+                ReplicateStatusCause::PreComputeInvalidInputFileUrl
+            );
+        });
+    }
+    // endregion
+
+    // region config signature
+    const CONFIG_SIGNER_PRIVATE_KEY: &str =
+        "0xdd3b993ec21c71c1f6d63a5240850e0d4d8dd83ff70d29e49247958548c1d479";
+    const CONFIG_SIGNER_ADDRESS: &str = "0x1Ff7d6F1d3D9e1c4d3C4ad3b0F1b2e9c3d7A0e21";
+
+    fn sign_config() -> String {
+        let digest =
+            hex_string_to_byte_array(&sha256::digest(config_manifest(session_variables())));
+        challenge_signer(SignatureAlgorithm::Secp256k1, CONFIG_SIGNER_PRIVATE_KEY)
+            .unwrap()
+            .sign(&digest)
+            .unwrap()
+    }
+
+    fn signer_address() -> String {
+        CONFIG_SIGNER_PRIVATE_KEY
+            .parse::<alloy_signer_local::PrivateKeySigner>()
+            .unwrap()
+            .address()
+            .to_string()
+    }
+
+    fn signed_env_vars() -> HashMap<String, String> {
+        let mut env_vars = setup_basic_env_vars();
+        env_vars.extend(setup_input_files_env_vars(1));
+        env_vars.insert(IsDatasetRequired.name(), "false".to_string());
+        env_vars.insert(SignWorkerAddress.name(), CONFIG_SIGNER_ADDRESS.to_string());
+        env_vars.insert(WorkerHostEnvVar.name(), "worker:13100".to_string());
+        env_vars
+    }
+
+    #[test]
+    fn config_manifest_lists_sorted_signed_variables() {
+        let manifest = config_manifest([
+            (IsDatasetRequired.name(), "false".to_string()),
+            (IexecPreComputeConfigSignature.name(), "0xabc".to_string()),
+            (SignTeeChallengePrivateKey.name(), "0x123".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+            (IexecInputFilesNumber.name(), "0".to_string()),
+            (IexecDatasetUrl.name(), String::new()),
+        ]);
+
+        assert_eq!(
+            manifest,
+            "17:IEXEC_DATASET_URL,0:,24:IEXEC_INPUT_FILES_NUMBER,1:0,\
+             19:IS_DATASET_REQUIRED,5:false,30:SIGN_TEE_CHALLENGE_PRIVATE_KEY,5:0x123,"
+        );
+    }
+
+    #[test]
+    fn config_manifest_keeps_values_apart_from_other_variables() {
+        let forged = config_manifest([(
+            IexecDatasetUrl.name(),
+            "https://host/a,24:IEXEC_INPUT_FILES_NUMBER,1:0,".to_string(),
+        )]);
+        let genuine = config_manifest([
+            (IexecDatasetUrl.name(), "https://host/a".to_string()),
+            (IexecInputFilesNumber.name(), "0".to_string()),
+        ]);
+
+        assert_ne!(forged, genuine);
+    }
+
+    #[test]
+    fn is_signed_variable_covers_every_session_variable() {
+        for name in [
+            SignWorkerAddress.name(),
+            WorkerHostEnvVar.name(),
+            SignTeeChallengePrivateKey.name(),
+            IsDatasetRequired.name(),
+            IexecInputFileUrlPrefix(12).name(),
+        ] {
+            assert!(is_signed_variable(&name), "{name}");
+        }
+        assert!(!is_signed_variable(&IexecPreComputeConfigSignature.name()));
+        assert!(!is_signed_variable("IEXEC_INPUT_FILE_URL_"));
+        assert!(!is_signed_variable("PATH"));
+    }
+
+    #[test]
+    fn verify_config_signature_by_accepts_valid_signature() {
+        temp_env::with_vars(to_temp_env_vars(signed_env_vars()), || {
+            let signature = sign_config();
+            temp_env::with_var(
+                IexecPreComputeConfigSignature.name(),
+                Some(signature),
+                || {
+                    assert_eq!(verify_config_signature_by(&signer_address(), None), Ok(()));
+                    assert!(PreComputeArgs::read_args().is_ok());
+                },
+            );
+        });
+    }
+
+    #[test]
+    fn verify_config_signature_by_rejects_tampered_config() {
+        temp_env::with_vars(to_temp_env_vars(signed_env_vars()), || {
+            let signature = sign_config();
+            for (name, value) in [
+                (
+                    IexecInputFileUrlPrefix(1).name(),
+                    "https://attacker.host/input.txt",
+                ),
+                (SignWorkerAddress.name(), CONFIG_SIGNER_PRIVATE_KEY),
+                (WorkerHostEnvVar.name(), "attacker.host:13100"),
+            ] {
+                let tampered = vec![
+                    (
+                        IexecPreComputeConfigSignature.name(),
+                        Some(signature.clone()),
+                    ),
+                    (name, Some(value.to_string())),
+                ];
+                temp_env::with_vars(tampered, || {
+                    assert_eq!(
+                        verify_config_signature_by(&signer_address(), None),
+                        Err(ReplicateStatusCause::PreComputeInvalidConfigSignature)
+                    );
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn verify_config_signature_by_rejects_missing_or_invalid_signature() {
+        temp_env::with_vars(to_temp_env_vars(signed_env_vars()), || {
+            assert_eq!(
+                verify_config_signature_by(&signer_address(), None),
+                Err(ReplicateStatusCause::PreComputeInvalidConfigSignature)
+            );
+            let signature = sign_config();
+            temp_env::with_var(
+                IexecPreComputeConfigSignature.name(),
+                Some(signature),
+                || {
+                    assert_eq!(
+                        verify_config_signature_by(CONFIG_SIGNER_ADDRESS, None),
+                        Err(ReplicateStatusCause::PreComputeInvalidConfigSignature)
+                    );
+                    assert_eq!(
+                        verify_config_signature_by(&signer_address(), Some("rsa")),
+                        Err(ReplicateStatusCause::PreComputeInvalidConfigSignature)
+                    );
+                },
+            );
         });
     }
     // endregion
@@ -0,0 +1,863 @@
+use crate::compute::app_runner::ExitMode;
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::schema;
+use crate::compute::signer::{self, SignatureAlgorithm};
+use crate::compute::utils::age_utils::{self, Recipient};
+use crate::compute::utils::file_utils::{hash_file, write_file};
+use crate::compute::utils::fs_utils::StdFilesystem;
+use crate::compute::utils::hash_utils::{Checksum, clean_hex_prefix, hex_string_to_byte_array};
+use crate::compute::utils::retry_utils::RetryUsage;
+use crate::compute::verifier::ContentHasher;
+use crate::compute::workspace::WORKSPACE_PREFIX;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub const REPORT_FILENAME: &str = "pre-compute-report.json";
+pub const ENCRYPTED_REPORT_FILENAME: &str = "pre-compute-report.json.age";
+
+/// Outcome of one attempt to download the dataset from an IPFS gateway.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayAttempt {
+    pub gateway: String,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+}
+
+/// Provenance of the dataset downloaded during the run.
+///
+/// `gateway` is the IPFS gateway which ultimately served the dataset, and
+/// `gateway_attempts` lists every gateway tried, in order. Both are absent when the
+/// dataset URL is a plain HTTP(S) URL. `final_url` is the URL which actually served the
+/// dataset, after gateway expansion and redirects, and is absent when the download failed.
+/// `expected_size` is the size announced through `IEXEC_DATASET_SIZE`, if any.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetReport {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub gateway_attempts: Vec<GatewayAttempt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_size: Option<u64>,
+}
+
+/// Input file skipped because it is optional or `IEXEC_PRE_COMPUTE_CONTINUE_ON_ERROR` is
+/// enabled.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedInputFile {
+    pub url: String,
+    pub input_file_index: usize,
+    pub cause: ReplicateStatusCause,
+    /// Whether the file is marked optional with `IEXEC_INPUT_FILE_OPTIONAL_<i>`.
+    pub optional: bool,
+    /// Redacted URL which served the file after redirects, absent when the download failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+}
+
+/// File produced in the output folder, with the SHA-256 digest of its content.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedFile {
+    /// Path of the file relative to the output folder.
+    pub name: String,
+    /// `0x`-prefixed hex SHA-256 digest of the file content.
+    pub sha256: String,
+    /// Redacted URL which served a downloaded input file after redirects, absent for the
+    /// other files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+}
+
+impl ReportedFile {
+    pub fn new(name: &str, content: &[u8]) -> Self {
+        ReportedFile {
+            name: name.to_string(),
+            sha256: format!("0x{}", sha256::digest(content)),
+            final_url: None,
+        }
+    }
+
+    /// Creates the entry of a file whose SHA-256 `checksum` was computed while it streamed.
+    pub fn with_checksum(name: &str, checksum: &Checksum) -> Self {
+        ReportedFile {
+            name: name.to_string(),
+            sha256: checksum.to_string(),
+            final_url: None,
+        }
+    }
+}
+
+/// Signature of a report by the enclave challenge key.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSignature {
+    pub algorithm: String,
+    pub value: String,
+}
+
+/// Reasons for which [`load_and_verify`] rejects a report.
+#[derive(Debug, Error, PartialEq)]
+pub enum ReportError {
+    #[error("report cannot be read: {0}")]
+    Unreadable(String),
+    #[error("report is malformed: {0}")]
+    Malformed(String),
+    #[error("report is not signed")]
+    Unsigned,
+    #[error("report signature is invalid")]
+    InvalidSignature,
+    #[error("reported file is missing: {0}")]
+    MissingFile(String),
+    #[error("reported file does not match its digest: {0}")]
+    FileMismatch(String),
+    #[error("output directory tree does not match its digest")]
+    TreeMismatch,
+}
+
+/// Content of a report accepted by [`load_and_verify`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedReport {
+    pub chain_task_id: String,
+    pub files: Vec<ReportedFile>,
+}
+
+/// Report of a pre-compute run, written as JSON next to the produced files.
+///
+/// The JSON structure is:
+/// ```json
+/// {
+///   "chainTaskId": "0x123",
+///   "dataset": {
+///     "url": "/ipfs/Qm...",
+///     "gateway": "https://gateway.ipfs.io",
+///     "gatewayAttempts": [
+///       { "gateway": "https://ipfs-gateway.v8-bellecour.iex.ec", "success": false, "httpStatus": 504 },
+///       { "gateway": "https://gateway.ipfs.io", "success": true }
+///     ],
+///     "finalUrl": "https://gateway.ipfs.io/ipfs/Qm...",
+///     "expectedSize": 1048576
+///   },
+///   "skippedInputFiles": [
+///     { "url": "https://host/input.txt", "inputFileIndex": 2, "cause": "PRE_COMPUTE_INPUT_FILE_DOWNLOAD_FAILED" }
+///   ],
+///   "retries": { "maxAttempts": 5, "attempts": 1, "durationMs": 1200, "denied": 0 },
+///   "configFingerprint": "0x...",
+///   "outputTreeSha256": "0x...",
+///   "files": [
+///     { "name": "dataset.zip", "sha256": "0x..." },
+///     { "name": "5f3a...", "sha256": "0x...", "finalUrl": "https://cdn.host/input.txt?token=REDACTED" }
+///   ],
+///   "signature": { "algorithm": "secp256k1", "value": "0x..." }
+/// }
+/// ```
+///
+/// `retries` is the consumed retry budget, absent when no limit is configured and no
+/// download was retried. `configFingerprint` is the digest of the task parameters, see
+/// [`crate::compute::diagnostics::config_fingerprint`]. `outputTreeSha256` is the digest of
+/// every file of the output folder once the run succeeded, see [`output_tree_sha256`], and is
+/// only present when `IEXEC_PRE_COMPUTE_OUTPUT_TREE_HASH` is enabled. `files` lists the files produced in the output folder, and
+/// `signature` signs the SHA-256 digest of the canonical form of the report, see
+/// [`signing_digest`]. Both let the next stages detect a tampered output folder with
+/// [`load_and_verify`].
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PreComputeReport {
+    pub chain_task_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dataset: Option<DatasetReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_input_files: Vec<SkippedInputFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<RetryUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config_fingerprint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_tree_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<ReportedFile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ReportSignature>,
+}
+
+impl PreComputeReport {
+    pub fn new(chain_task_id: &str) -> Self {
+        PreComputeReport {
+            chain_task_id: chain_task_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Signs the report with `sign`, which receives the digest returned by [`signing_digest`]
+    /// and returns the signature algorithm and the hex-encoded signature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut report = PreComputeReport::new("0x123456789abcdef");
+    /// report.sign(signer::sign_with_challenge_key)?;
+    /// ```
+    pub fn sign<E>(
+        &mut self,
+        sign: impl FnOnce(&[u8]) -> Result<(SignatureAlgorithm, String), E>,
+    ) -> Result<(), E> {
+        self.signature = None;
+        let document = schema::to_vec(self)
+            .ok()
+            .and_then(|content| serde_json::from_slice::<Value>(&content).ok())
+            .unwrap_or_default();
+        let (algorithm, value) = sign(&signing_digest(&document))?;
+        self.signature = Some(ReportSignature {
+            algorithm: algorithm.name().to_string(),
+            value,
+        });
+        Ok(())
+    }
+
+    /// Writes the report to [`REPORT_FILENAME`] inside `output_dir`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PathBuf)` with the path of the written report.
+    /// * `Err(())` if the report cannot be serialized or written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let report = PreComputeReport::new("0x123456789abcdef");
+    /// report.write("/iexec_out")?;
+    /// ```
+    pub fn write(&self, output_dir: &str) -> Result<PathBuf, ()> {
+        let content = self.serialize()?;
+        self.write_content(&content, &Path::new(output_dir).join(REPORT_FILENAME))
+    }
+
+    /// Writes the report encrypted with age to `recipient`, to [`ENCRYPTED_REPORT_FILENAME`]
+    /// inside `output_dir`, so that only the recipient can read the URLs it contains.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PathBuf)` with the path of the written report.
+    /// * `Err(())` if the report cannot be serialized or written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let report = PreComputeReport::new("0x123456789abcdef");
+    /// report.write_encrypted("/iexec_out", &recipient)?;
+    /// ```
+    pub fn write_encrypted(&self, output_dir: &str, recipient: &Recipient) -> Result<PathBuf, ()> {
+        let content = age_utils::encrypt(recipient, &self.serialize()?);
+        self.write_content(
+            &content,
+            &Path::new(output_dir).join(ENCRYPTED_REPORT_FILENAME),
+        )
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>, ()> {
+        schema::to_vec_pretty(self).map_err(|e| {
+            error!("Failed to serialize pre-compute report: {e}");
+        })
+    }
+
+    fn write_content(&self, content: &[u8], path: &Path) -> Result<PathBuf, ()> {
+        write_file(
+            content,
+            path,
+            &format!("chainTaskId:{}", self.chain_task_id),
+        )
+        .map_err(|_| ())?;
+        Ok(path.to_path_buf())
+    }
+}
+
+/// Returns the SHA-256 digest of the canonical form of a report `document`, that is the
+/// compact JSON serialization of the document without its `signature` field and with object
+/// keys sorted.
+pub fn signing_digest(document: &Value) -> [u8; 32] {
+    let mut document = document.clone();
+    if let Some(fields) = document.as_object_mut() {
+        fields.remove("signature");
+    }
+    let canonical = serde_json::to_vec(&document).unwrap_or_default();
+    hex_string_to_byte_array(&sha256::digest(canonical))
+        .try_into()
+        .unwrap_or([0; 32])
+}
+
+/// Returns the digest of the whole tree of `output_dir`, so that the next stages can check
+/// that no file of the shared volume was added, removed or modified after the run.
+///
+/// Every regular file is listed with its path relative to `output_dir`, `/`-separated, and the
+/// listing is sorted by path. The digest is the `0x`-prefixed SHA-256 of the listing written in
+/// the `sha256sum` format, one `<hex sha256>  <path>` line per file, so that it can be
+/// recomputed with standard tools. Symbolic links are not followed. The report files and the
+/// temporary workspace of the run, at the root of `output_dir`, are not part of the tree.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the digest of the tree.
+/// * `Err(io::Error)` if a directory cannot be listed or a file cannot be read.
+///
+/// # Example
+///
+/// ```
+/// let digest = output_tree_sha256(Path::new("/iexec_in"))?;
+/// ```
+pub fn output_tree_sha256(output_dir: &Path) -> io::Result<String> {
+    let mut names = Vec::new();
+    list_tree_files(output_dir, "", &mut names)?;
+    names.sort();
+    let mut listing = String::new();
+    for name in &names {
+        let mut hasher: Box<dyn ContentHasher> = Box::new(Sha256::new());
+        hash_file(&StdFilesystem, &output_dir.join(name), hasher.as_mut())?;
+        let digest = hasher.finalize().to_string();
+        listing.push_str(&format!("{}  {name}\n", clean_hex_prefix(&digest)));
+    }
+    Ok(format!("0x{}", sha256::digest(listing)))
+}
+
+/// Appends the paths of the regular files below `dir` to `names`, prefixed with `prefix`.
+fn list_tree_files(dir: &Path, prefix: &str, names: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if prefix.is_empty()
+            && (name == REPORT_FILENAME
+                || name == ENCRYPTED_REPORT_FILENAME
+                || name.starts_with(WORKSPACE_PREFIX))
+        {
+            continue;
+        }
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            list_tree_files(&entry.path(), &path, names)?;
+        } else if file_type.is_file() {
+            names.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Loads the plain [`REPORT_FILENAME`] of a previous run from `report_dir` and verifies it
+/// against `output_dir`, so that the worker can detect an output folder tampered with between
+/// stages.
+///
+/// The report signature must have been produced by `expected_signer`, see
+/// [`signer::verify_signature`], and every reported file must still exist in `output_dir`
+/// with the reported digest. When the report holds an `outputTreeSha256`, the tree of
+/// `output_dir` must also still match it, see [`output_tree_sha256`].
+///
+/// # Returns
+///
+/// * `Ok(VerifiedReport)` with the task ID and the files of the report.
+/// * `Err(ReportError)` describing the first check which failed.
+///
+/// # Example
+///
+/// ```
+/// let report = load_and_verify(
+///     "/iexec_report",
+///     "/iexec_in",
+///     "0x1Ff7d6F1d3D9e1c4d3C4ad3b0F1b2e9c3d7A0e21",
+/// )?;
+/// ```
+pub fn load_and_verify(
+    report_dir: &str,
+    output_dir: &str,
+    expected_signer: &str,
+) -> Result<VerifiedReport, ReportError> {
+    let output_dir = Path::new(output_dir);
+    let content = fs::read(Path::new(report_dir).join(REPORT_FILENAME))
+        .map_err(|e| ReportError::Unreadable(e.to_string()))?;
+    let document: Value =
+        serde_json::from_slice(&content).map_err(|e| ReportError::Malformed(e.to_string()))?;
+    let version = document["schemaVersion"].as_str().unwrap_or_default();
+    if !schema::is_compatible(version) {
+        return Err(ReportError::Malformed(format!(
+            "unsupported schema version {version}"
+        )));
+    }
+
+    let signature = &document["signature"];
+    let (Some(algorithm), Some(value)) =
+        (signature["algorithm"].as_str(), signature["value"].as_str())
+    else {
+        return Err(ReportError::Unsigned);
+    };
+    let algorithm = algorithm
+        .parse::<SignatureAlgorithm>()
+        .map_err(|_| ReportError::Malformed(format!("unsupported algorithm {algorithm}")))?;
+    if !signer::verify_signature(
+        algorithm,
+        &signing_digest(&document),
+        value,
+        expected_signer,
+    ) {
+        return Err(ReportError::InvalidSignature);
+    }
+
+    let chain_task_id = document["chainTaskId"]
+        .as_str()
+        .ok_or_else(|| ReportError::Malformed("missing chainTaskId".to_string()))?
+        .to_string();
+    let files: Vec<ReportedFile> = match document.get("files") {
+        Some(files) => serde_json::from_value(files.clone())
+            .map_err(|e| ReportError::Malformed(e.to_string()))?,
+        None => Vec::new(),
+    };
+    for file in &files {
+        // Reported names are relative to the output folder and must stay inside it.
+        let name = Path::new(&file.name);
+        if name.is_absolute() || name.components().any(|c| c.as_os_str() == "..") {
+            return Err(ReportError::Malformed(format!(
+                "invalid file name {}",
+                file.name
+            )));
+        }
+        let content = fs::read(output_dir.join(name))
+            .map_err(|_| ReportError::MissingFile(file.name.clone()))?;
+        if ReportedFile::new(&file.name, &content).sha256 != file.sha256 {
+            return Err(ReportError::FileMismatch(file.name.clone()));
+        }
+    }
+    if let Some(expected) = document["outputTreeSha256"].as_str() {
+        let digest =
+            output_tree_sha256(output_dir).map_err(|e| ReportError::Unreadable(e.to_string()))?;
+        if digest != expected {
+            return Err(ReportError::TreeMismatch);
+        }
+    }
+    Ok(VerifiedReport {
+        chain_task_id,
+        files,
+    })
+}
+
+/// Entry point of the `--verify-report <dir> <signer> [<report-dir>]` mode: verifies the report
+/// of `report_dir` against `output_dir` with [`load_and_verify`].
+///
+/// # Returns
+///
+/// * `ExitMode::Success` if the report and the files it lists are intact.
+/// * `ExitMode::UnreportedFailure` otherwise, the reason being logged.
+pub fn verify(report_dir: &str, output_dir: &str, expected_signer: &str) -> ExitMode {
+    match load_and_verify(report_dir, output_dir, expected_signer) {
+        Ok(report) => {
+            info!(
+                "Pre-compute report verified [chainTaskId:{}, files:{}]",
+                report.chain_task_id,
+                report.files.len()
+            );
+            ExitMode::Success
+        }
+        Err(e) => {
+            error!("Pre-compute report verification failed [outputDir:{output_dir}]: {e}");
+            ExitMode::UnreportedFailure
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::schema::SCHEMA_VERSION;
+    use crate::compute::signer::challenge_signer;
+    use crate::compute::utils::age_utils::Identity;
+    use alloy_signer_local::PrivateKeySigner;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn should_serialize_report_with_gateway_attempts() {
+        let report = PreComputeReport {
+            chain_task_id: "0x123".to_string(),
+            dataset: Some(DatasetReport {
+                url: "/ipfs/Qm".to_string(),
+                gateway: Some("https://gateway-2".to_string()),
+                gateway_attempts: vec![
+                    GatewayAttempt {
+                        gateway: "https://gateway-1".to_string(),
+                        success: false,
+                        http_status: Some(504),
+                    },
+                    GatewayAttempt {
+                        gateway: "https://gateway-2".to_string(),
+                        success: true,
+                        http_status: None,
+                    },
+                ],
+                final_url: Some("https://gateway-2/ipfs/Qm".to_string()),
+                expected_size: Some(1024),
+            }),
+            skipped_input_files: vec![SkippedInputFile {
+                url: "https://host/input.txt".to_string(),
+                input_file_index: 2,
+                cause: ReplicateStatusCause::PreComputeInputFileDownloadFailed,
+                optional: true,
+                final_url: None,
+            }],
+            retries: Some(RetryUsage {
+                max_attempts: Some(5),
+                max_secs: None,
+                attempts: 1,
+                duration_ms: 1200,
+                denied: 0,
+            }),
+            config_fingerprint: Some("0xcd".to_string()),
+            output_tree_sha256: Some("0xef".to_string()),
+            files: vec![ReportedFile::new("dataset.txt", b"data")],
+            signature: Some(ReportSignature {
+                algorithm: "secp256k1".to_string(),
+                value: "0xabc".to_string(),
+            }),
+        };
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "chainTaskId": "0x123",
+                "dataset": {
+                    "url": "/ipfs/Qm",
+                    "gateway": "https://gateway-2",
+                    "gatewayAttempts": [
+                        { "gateway": "https://gateway-1", "success": false, "httpStatus": 504 },
+                        { "gateway": "https://gateway-2", "success": true }
+                    ],
+                    "finalUrl": "https://gateway-2/ipfs/Qm",
+                    "expectedSize": 1024
+                },
+                "skippedInputFiles": [
+                    {
+                        "url": "https://host/input.txt",
+                        "inputFileIndex": 2,
+                        "cause": "PRE_COMPUTE_INPUT_FILE_DOWNLOAD_FAILED",
+                        "optional": true
+                    }
+                ],
+                "retries": { "maxAttempts": 5, "attempts": 1, "durationMs": 1200, "denied": 0 },
+                "configFingerprint": "0xcd",
+                "outputTreeSha256": "0xef",
+                "files": [
+                    {
+                        "name": "dataset.txt",
+                        "sha256": "0x3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7"
+                    }
+                ],
+                "signature": { "algorithm": "secp256k1", "value": "0xabc" }
+            })
+        );
+    }
+
+    #[test]
+    fn should_write_report_to_output_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = PreComputeReport::new("0x123");
+
+        let path = report.write(temp_dir.path().to_str().unwrap()).unwrap();
+
+        assert_eq!(path, temp_dir.path().join(REPORT_FILENAME));
+        let written: serde_json::Value = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            json!({ "schemaVersion": SCHEMA_VERSION, "chainTaskId": "0x123" })
+        );
+    }
+
+    #[test]
+    fn should_write_encrypted_report_to_output_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let report = PreComputeReport::new("0x123");
+        let identity =
+            Identity::parse(include_str!("../tests_resources/dataset-age-identity.txt")).unwrap();
+        let recipient =
+            Recipient::parse("age14e7vfx33lmgd5e7wnqgrrq0npxhxzw3scq2makln9x39cf4t09vqffpks3")
+                .unwrap();
+
+        let path = report
+            .write_encrypted(temp_dir.path().to_str().unwrap(), &recipient)
+            .unwrap();
+
+        assert_eq!(path, temp_dir.path().join(ENCRYPTED_REPORT_FILENAME));
+        assert!(!temp_dir.path().join(REPORT_FILENAME).exists());
+        let plain = age_utils::decrypt(&identity, &fs::read(path).unwrap()).unwrap();
+        let written: serde_json::Value = serde_json::from_slice(&plain).unwrap();
+        assert_eq!(written["chainTaskId"], "0x123");
+    }
+
+    #[test]
+    fn should_fail_to_write_report_to_missing_dir() {
+        let report = PreComputeReport::new("0x123");
+        assert!(report.write("/some-missing-folder-123").is_err());
+    }
+
+    // region load_and_verify
+    const PRIVATE_KEY: &str = "0xdd3b993ec21c71c1f6d63a5240850e0d4d8dd83ff70d29e49247958548c1d479";
+
+    fn signer_address() -> String {
+        PRIVATE_KEY
+            .parse::<PrivateKeySigner>()
+            .unwrap()
+            .address()
+            .to_string()
+    }
+
+    fn sign(message_hash: &[u8]) -> Result<(SignatureAlgorithm, String), ReplicateStatusCause> {
+        let signature =
+            challenge_signer(SignatureAlgorithm::Secp256k1, PRIVATE_KEY)?.sign(message_hash)?;
+        Ok((SignatureAlgorithm::Secp256k1, signature))
+    }
+
+    fn write_signed_report(output_dir: &Path) {
+        fs::write(output_dir.join("dataset.txt"), b"data").unwrap();
+        let mut report = PreComputeReport::new("0x123");
+        report.files = vec![ReportedFile::new("dataset.txt", b"data")];
+        report.sign(sign).unwrap();
+        report.write(output_dir.to_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn should_load_and_verify_signed_report() {
+        let temp_dir = TempDir::new().unwrap();
+        write_signed_report(temp_dir.path());
+
+        let report = load_and_verify(
+            temp_dir.path().to_str().unwrap(),
+            temp_dir.path().to_str().unwrap(),
+            &signer_address(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            report,
+            VerifiedReport {
+                chain_task_id: "0x123".to_string(),
+                files: vec![ReportedFile::new("dataset.txt", b"data")],
+            }
+        );
+        assert_eq!(
+            verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            ExitMode::Success
+        );
+    }
+
+    #[test]
+    fn should_load_and_verify_report_written_apart_from_output_dir() {
+        let output_dir = TempDir::new().unwrap();
+        let report_dir = TempDir::new().unwrap();
+        write_signed_report(output_dir.path());
+        fs::rename(
+            output_dir.path().join(REPORT_FILENAME),
+            report_dir.path().join(REPORT_FILENAME),
+        )
+        .unwrap();
+
+        assert!(
+            load_and_verify(
+                report_dir.path().to_str().unwrap(),
+                output_dir.path().to_str().unwrap(),
+                &signer_address()
+            )
+            .is_ok()
+        );
+        assert!(matches!(
+            load_and_verify(
+                output_dir.path().to_str().unwrap(),
+                output_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            Err(ReportError::Unreadable(_))
+        ));
+    }
+
+    #[test]
+    fn should_load_and_verify_report_with_input_file_final_url() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("input.txt"), b"input").unwrap();
+        let input_file = ReportedFile {
+            final_url: Some("https://cdn.host/input.txt?token=REDACTED".to_string()),
+            ..ReportedFile::new("input.txt", b"input")
+        };
+        let mut report = PreComputeReport::new("0x123");
+        report.files = vec![input_file.clone()];
+        report.sign(sign).unwrap();
+        report.write(temp_dir.path().to_str().unwrap()).unwrap();
+
+        let report = load_and_verify(
+            temp_dir.path().to_str().unwrap(),
+            temp_dir.path().to_str().unwrap(),
+            &signer_address(),
+        )
+        .unwrap();
+
+        assert_eq!(report.files, vec![input_file]);
+    }
+
+    #[test]
+    fn should_reject_tampered_file() {
+        let temp_dir = TempDir::new().unwrap();
+        write_signed_report(temp_dir.path());
+        fs::write(temp_dir.path().join("dataset.txt"), b"tampered").unwrap();
+
+        assert_eq!(
+            load_and_verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            Err(ReportError::FileMismatch("dataset.txt".to_string()))
+        );
+        fs::remove_file(temp_dir.path().join("dataset.txt")).unwrap();
+        assert_eq!(
+            load_and_verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            Err(ReportError::MissingFile("dataset.txt".to_string()))
+        );
+    }
+
+    #[test]
+    fn should_reject_tampered_report() {
+        let temp_dir = TempDir::new().unwrap();
+        write_signed_report(temp_dir.path());
+        let path = temp_dir.path().join(REPORT_FILENAME);
+        let mut document: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        document["chainTaskId"] = json!("0x456");
+        fs::write(&path, serde_json::to_vec(&document).unwrap()).unwrap();
+
+        assert_eq!(
+            load_and_verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            Err(ReportError::InvalidSignature)
+        );
+        assert_eq!(
+            verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            ExitMode::UnreportedFailure
+        );
+    }
+
+    fn write_signed_report_with_tree(output_dir: &Path) {
+        fs::write(output_dir.join("dataset.txt"), b"data").unwrap();
+        let mut report = PreComputeReport::new("0x123");
+        report.output_tree_sha256 = Some(output_tree_sha256(output_dir).unwrap());
+        report.sign(sign).unwrap();
+        report.write(output_dir.to_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn output_tree_sha256_matches_sha256sum_listing() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("inputs")).unwrap();
+        fs::write(temp_dir.path().join("inputs/b.txt"), b"b").unwrap();
+        fs::write(temp_dir.path().join("a.txt"), b"a").unwrap();
+        fs::write(temp_dir.path().join(REPORT_FILENAME), b"{}").unwrap();
+        let workspace = temp_dir.path().join(format!("{WORKSPACE_PREFIX}0123"));
+        fs::create_dir(&workspace).unwrap();
+        fs::write(workspace.join("file.part"), b"partial").unwrap();
+
+        let listing = format!(
+            "{}  a.txt\n{}  inputs/b.txt\n",
+            sha256::digest("a"),
+            sha256::digest("b")
+        );
+        assert_eq!(
+            output_tree_sha256(temp_dir.path()).unwrap(),
+            format!("0x{}", sha256::digest(listing))
+        );
+    }
+
+    #[test]
+    fn should_verify_output_tree_of_signed_report() {
+        let temp_dir = TempDir::new().unwrap();
+        write_signed_report_with_tree(temp_dir.path());
+
+        assert!(
+            load_and_verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn should_reject_file_added_to_output_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        write_signed_report_with_tree(temp_dir.path());
+        fs::write(temp_dir.path().join("injected.txt"), b"injected").unwrap();
+
+        assert_eq!(
+            load_and_verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            Err(ReportError::TreeMismatch)
+        );
+    }
+
+    #[test]
+    fn should_reject_report_of_other_signer() {
+        let temp_dir = TempDir::new().unwrap();
+        write_signed_report(temp_dir.path());
+
+        assert_eq!(
+            load_and_verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                "0x0000000000000000000000000000000000000001"
+            ),
+            Err(ReportError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn should_reject_unsigned_report() {
+        let temp_dir = TempDir::new().unwrap();
+        PreComputeReport::new("0x123")
+            .write(temp_dir.path().to_str().unwrap())
+            .unwrap();
+
+        assert_eq!(
+            load_and_verify(
+                temp_dir.path().to_str().unwrap(),
+                temp_dir.path().to_str().unwrap(),
+                &signer_address()
+            ),
+            Err(ReportError::Unsigned)
+        );
+    }
+    // endregion
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+/// Version of the JSON documents produced by the pre-compute stage: the payloads sent to the
+/// worker API, the report, `status.json`, the event and exit summary lines, and the service
+/// mode results.
+///
+/// The compatibility policy is:
+/// - The minor version is increased when fields are added. Parsers must ignore unknown fields
+///   and accept any minor version of a major version they support.
+/// - The major version is increased when fields are removed, renamed or change type.
+pub const SCHEMA_VERSION: &str = "1.0";
+
+/// A document tagged with [`SCHEMA_VERSION`], serialized as the fields of the document with an
+/// additional `schemaVersion` field.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Versioned<'a, T: ?Sized> {
+    schema_version: &'static str,
+    #[serde(flatten)]
+    content: &'a T,
+}
+
+impl<'a, T: ?Sized> Versioned<'a, T> {
+    fn new(content: &'a T) -> Self {
+        Versioned {
+            schema_version: SCHEMA_VERSION,
+            content,
+        }
+    }
+}
+
+/// Serializes `value` as a single line JSON document tagged with [`SCHEMA_VERSION`].
+///
+/// `value` must serialize as a JSON object.
+///
+/// # Example
+///
+/// ```
+/// let line = schema::to_string(&record)?;
+/// ```
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string(&Versioned::new(value))
+}
+
+/// Serializes `value` as a JSON document tagged with [`SCHEMA_VERSION`].
+///
+/// `value` must serialize as a JSON object.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&Versioned::new(value))
+}
+
+/// Serializes `value` as a pretty-printed JSON document tagged with [`SCHEMA_VERSION`].
+///
+/// `value` must serialize as a JSON object.
+pub fn to_vec_pretty<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec_pretty(&Versioned::new(value))
+}
+
+/// Returns whether a document of schema `version` can be read by this version, that is when
+/// both share the same major version.
+///
+/// # Example
+///
+/// ```
+/// assert!(is_compatible("1.3"));
+/// assert!(!is_compatible("2.0"));
+/// ```
+pub fn is_compatible(version: &str) -> bool {
+    let major = |version: &str| version.trim().split('.').next().map(str::to_string);
+    major(version).is_some_and(|major_version| Some(major_version) == major(SCHEMA_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Document {
+        chain_task_id: &'static str,
+    }
+
+    #[test]
+    fn to_string_adds_schema_version() {
+        let json = to_string(&Document {
+            chain_task_id: "0x123",
+        })
+        .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!({"schemaVersion": SCHEMA_VERSION, "chainTaskId": "0x123"})
+        );
+        assert!(json.starts_with(r#"{"schemaVersion":"#));
+    }
+
+    #[test]
+    fn is_compatible_accepts_same_major_version() {
+        assert!(is_compatible("1.0"));
+        assert!(is_compatible("1.7"));
+        assert!(is_compatible("1"));
+        assert!(!is_compatible("2.0"));
+        assert!(!is_compatible("0.9"));
+        assert!(!is_compatible(""));
+    }
+}
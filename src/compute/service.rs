@@ -0,0 +1,444 @@
+use crate::compute::app_runner::ExitMode;
+use crate::compute::schema;
+use crate::compute::utils::env_utils::{
+    PROCESS_VARIABLES, TeeSessionEnvironmentVariable, with_job_environment,
+};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+const JOB_EXTENSION: &str = "json";
+const RESULT_SUFFIX: &str = ".result.json";
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pre-compute job received in service mode.
+///
+/// A job carries the TEE session environment variables of one task, which are the only
+/// session variables read for the duration of the job, see [`with_job_environment`]. Jobs may
+/// declare the `schemaVersion` they were written
+/// for, jobs of an incompatible version are rejected. Jobs setting a variable read once per
+/// process, such as the events file or the HTTP client settings, are rejected too, as the
+/// value of the first job would silently apply to every other one.
+///
+/// The JSON structure of a job line is:
+/// ```json
+/// {"env":{"IEXEC_TASK_ID":"0x123","IEXEC_PRE_COMPUTE_OUT":"/iexec_in","IS_DATASET_REQUIRED":"false","IEXEC_INPUT_FILES_NUMBER":"0"}}
+/// ```
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRequest {
+    #[serde(default)]
+    pub schema_version: Option<String>,
+    pub env: HashMap<String, String>,
+}
+
+impl JobRequest {
+    /// Parses a JSON job, checking that its schema version is supported and that it only
+    /// sets variables which can change from one job to another.
+    fn parse(content: &[u8]) -> Result<Self, String> {
+        let job: JobRequest = serde_json::from_slice(content).map_err(|e| e.to_string())?;
+        if let Some(version) = &job.schema_version
+            && !schema::is_compatible(version)
+        {
+            return Err(format!(
+                "unsupported schema version {version}, expected {}",
+                schema::SCHEMA_VERSION
+            ));
+        }
+        match PROCESS_VARIABLES
+            .iter()
+            .map(TeeSessionEnvironmentVariable::name)
+            .find(|name| job.env.contains_key(name))
+        {
+            Some(name) => Err(format!("{name} is read once per process, not per job")),
+            None => Ok(job),
+        }
+    }
+}
+
+/// Outcome of one job, written as a JSON line once the job is done.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JobResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_task_id: Option<String>,
+    pub exit_code: i32,
+}
+
+/// Processes pre-compute jobs read as JSON lines from `input`, one after another.
+///
+/// Each job is run by `run_job` with the environment variables of the job, see
+/// [`run_with_env`], then a [`JobResult`] line is written to `output`. Blank
+/// lines are skipped and malformed lines produce an `InitializationFailure` result. The
+/// service stops at the end of `input`.
+///
+/// Nothing but job results should be written to `output`, logs and exit summaries being
+/// written to stderr in service mode.
+///
+/// # Example
+///
+/// ```
+/// let exit_mode = serve(io::stdin().lock(), io::stdout(), app_runner::start);
+/// ```
+pub fn serve<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+    mut run_job: impl FnMut() -> ExitMode,
+) -> ExitMode {
+    info!("TEE pre-compute service started");
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Failed to read job request: {e}");
+                return ExitMode::InitializationFailure;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = match JobRequest::parse(line.as_bytes()) {
+            Ok(job) => run_with_env(&job, &mut run_job),
+            Err(e) => {
+                error!("Ignoring malformed job request: {e}");
+                JobResult {
+                    chain_task_id: None,
+                    exit_code: ExitMode::InitializationFailure as i32,
+                }
+            }
+        };
+        let written = schema::to_string(&result)
+            .map_err(|e| e.to_string())
+            .and_then(|json| writeln!(output, "{json}").map_err(|e| e.to_string()))
+            .and_then(|_| output.flush().map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            error!("Failed to write job result: {e}");
+            return ExitMode::UnreportedFailure;
+        }
+    }
+    info!("TEE pre-compute service stopped");
+    ExitMode::Success
+}
+
+/// Processes pre-compute jobs dropped as JSON files in `dir`, for setups without sockets.
+///
+/// The directory is polled every second. Each `<name>.json` file holds a [`JobRequest`]; once
+/// the job is done, a [`JobResult`] receipt is written next to it as `<name>.result.json`.
+/// Jobs which already have a receipt are not run again. The watcher only stops if `dir`
+/// cannot be read.
+///
+/// A job file is read as soon as it shows up, so it must appear in `dir` complete: it has to
+/// be written under another name, such as `<name>.json.part` or a hidden `.<name>.json`, and
+/// then renamed to `<name>.json` within the same file system. Files with any other extension
+/// and hidden files are ignored, so partially written jobs are never run.
+///
+/// # Example
+///
+/// ```
+/// let exit_mode = watch(Path::new("/iexec_jobs"), app_runner::start);
+/// ```
+pub fn watch(dir: &Path, mut run_job: impl FnMut() -> ExitMode) -> ExitMode {
+    info!("TEE pre-compute watcher started [dir:{}]", dir.display());
+    loop {
+        if let Err(e) = process_pending_jobs(dir, &mut run_job) {
+            error!("Failed to read jobs directory [dir:{}]: {e}", dir.display());
+            return ExitMode::InitializationFailure;
+        }
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// Runs every job of `dir` which has no receipt yet, in file name order.
+///
+/// # Returns
+///
+/// * `Ok(usize)` with the number of jobs processed.
+/// * `Err(io::Error)` if `dir` cannot be listed.
+fn process_pending_jobs(dir: &Path, run_job: &mut impl FnMut() -> ExitMode) -> io::Result<usize> {
+    let mut jobs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_job_file(path) && !result_path(path).exists())
+        .collect();
+    jobs.sort();
+
+    for job in &jobs {
+        info!("Processing job file [path:{}]", job.display());
+        let result = match fs::read(job)
+            .map_err(|e| e.to_string())
+            .and_then(|content| JobRequest::parse(&content))
+        {
+            Ok(request) => run_with_env(&request, run_job),
+            Err(e) => {
+                error!("Ignoring malformed job file [path:{}]: {e}", job.display());
+                JobResult {
+                    chain_task_id: None,
+                    exit_code: ExitMode::InitializationFailure as i32,
+                }
+            }
+        };
+        write_result(&result_path(job), &result)?;
+    }
+    Ok(jobs.len())
+}
+
+fn is_job_file(path: &Path) -> bool {
+    path.is_file()
+        && !path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        && path
+            .extension()
+            .is_some_and(|extension| extension == JOB_EXTENSION)
+        && !path.to_string_lossy().ends_with(RESULT_SUFFIX)
+}
+
+fn result_path(job: &Path) -> PathBuf {
+    let stem = job.file_stem().unwrap_or_default().to_string_lossy();
+    job.with_file_name(format!("{stem}{RESULT_SUFFIX}"))
+}
+
+/// Writes the receipt under a temporary name first, so that the worker never reads a
+/// partial receipt.
+fn write_result(path: &Path, result: &JobResult) -> io::Result<()> {
+    let partial_path = path.with_extension("json.part");
+    fs::write(&partial_path, schema::to_vec(result)?)?;
+    fs::rename(&partial_path, path)
+}
+
+/// Runs `run_job` with session variables read from the environment variables of `job`, see
+/// [`with_job_environment`].
+pub fn run_with_env(job: &JobRequest, run_job: &mut impl FnMut() -> ExitMode) -> JobResult {
+    let chain_task_id = job
+        .env
+        .get(&TeeSessionEnvironmentVariable::IexecTaskId.name())
+        .cloned();
+    info!("Processing job [chainTaskId:{chain_task_id:?}]");
+
+    let exit_code = with_job_environment(&job.env, run_job) as i32;
+    JobResult {
+        chain_task_id,
+        exit_code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::errors::ReplicateStatusCause;
+    use crate::compute::schema::SCHEMA_VERSION;
+    use crate::compute::utils::env_utils::get_env_var_or_error;
+    use serde_json::{Value, json};
+    use std::env;
+
+    const JOB_ENV_VAR: &str = "IEXEC_PRE_COMPUTE_OUT";
+
+    fn read_job_env_var() -> Option<String> {
+        get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeOut,
+            ReplicateStatusCause::PreComputeOutputPathMissing,
+        )
+        .ok()
+    }
+
+    fn results(output: &[u8]) -> Vec<Value> {
+        String::from_utf8_lossy(output)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn serve_runs_jobs_sequentially_with_their_environment() {
+        let input = format!(
+            "{}\n\n{}\n",
+            json!({"env": {"IEXEC_TASK_ID": "0x1", JOB_ENV_VAR: "first"}}),
+            json!({"env": {"IEXEC_TASK_ID": "0x2", JOB_ENV_VAR: "second"}})
+        );
+        let mut output = Vec::new();
+        let mut seen = Vec::new();
+
+        temp_env::with_vars_unset(vec![JOB_ENV_VAR], || {
+            let exit_mode = serve(input.as_bytes(), &mut output, || {
+                seen.push(read_job_env_var());
+                ExitMode::ReportedFailure
+            });
+            assert_eq!(exit_mode, ExitMode::Success);
+            assert!(env::var(JOB_ENV_VAR).is_err());
+            assert_eq!(read_job_env_var(), None);
+        });
+
+        assert_eq!(
+            seen,
+            vec![Some("first".to_string()), Some("second".to_string())]
+        );
+        assert_eq!(
+            results(&output),
+            vec![
+                json!({"schemaVersion": SCHEMA_VERSION, "chainTaskId": "0x1", "exitCode": 1}),
+                json!({"schemaVersion": SCHEMA_VERSION, "chainTaskId": "0x2", "exitCode": 1}),
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_leaves_process_environment_untouched() {
+        let input = json!({"env": {JOB_ENV_VAR: "job"}}).to_string();
+        let mut output = Vec::new();
+        let mut seen = None;
+
+        temp_env::with_var(JOB_ENV_VAR, Some("host"), || {
+            serve(input.as_bytes(), &mut output, || {
+                seen = env::var(JOB_ENV_VAR).ok();
+                ExitMode::Success
+            });
+            assert_eq!(read_job_env_var(), Some("host".to_string()));
+        });
+        assert_eq!(seen, Some("host".to_string()));
+        assert_eq!(
+            results(&output),
+            vec![json!({"schemaVersion": SCHEMA_VERSION, "exitCode": 0})]
+        );
+    }
+
+    #[test]
+    fn serve_only_reads_process_variables_from_process_environment() {
+        let input = json!({"env": {"IEXEC_TASK_ID": "0x1"}}).to_string();
+        let mut output = Vec::new();
+        let mut seen = (None, None);
+
+        temp_env::with_vars(
+            [
+                (JOB_ENV_VAR, Some("host")),
+                ("IEXEC_MAX_REDIRECTS", Some("3")),
+            ],
+            || {
+                serve(input.as_bytes(), &mut output, || {
+                    let max_redirects = get_env_var_or_error(
+                        TeeSessionEnvironmentVariable::IexecMaxRedirects,
+                        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+                    );
+                    seen = (read_job_env_var(), max_redirects.ok());
+                    ExitMode::Success
+                });
+            },
+        );
+        assert_eq!(seen, (None, Some("3".to_string())));
+    }
+
+    #[test]
+    fn process_pending_jobs_writes_receipts_once() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("task-1.json"),
+            json!({"env": {"IEXEC_TASK_ID": "0x1"}}).to_string(),
+        )
+        .unwrap();
+        fs::write(dir.join("task.2.json"), "not-json").unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        // Jobs still being written, before their rename to `<name>.json`.
+        fs::write(dir.join("task-3.json.part"), "{\"env\":").unwrap();
+        fs::write(dir.join(".task-4.json"), "{\"env\":").unwrap();
+        let mut runs = 0;
+
+        let processed = process_pending_jobs(dir, &mut || {
+            runs += 1;
+            ExitMode::Success
+        })
+        .unwrap();
+
+        assert_eq!((processed, runs), (2, 1));
+        let receipt = |name: &str| -> Value {
+            serde_json::from_slice(&fs::read(dir.join(name)).unwrap()).unwrap()
+        };
+        assert_eq!(
+            receipt("task-1.result.json"),
+            json!({"schemaVersion": SCHEMA_VERSION, "chainTaskId": "0x1", "exitCode": 0})
+        );
+        assert_eq!(
+            receipt("task.2.result.json"),
+            json!({"schemaVersion": SCHEMA_VERSION, "exitCode": 3})
+        );
+        assert!(!dir.join("task-3.json.result.json").exists());
+        assert!(!dir.join(".task-4.result.json").exists());
+        assert_eq!(
+            process_pending_jobs(dir, &mut || ExitMode::Success).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn watch_stops_when_directory_is_missing() {
+        assert_eq!(
+            watch(Path::new("/some-missing-jobs-dir-123"), || {
+                ExitMode::Success
+            }),
+            ExitMode::InitializationFailure
+        );
+    }
+
+    #[test]
+    fn serve_rejects_jobs_with_incompatible_schema_version() {
+        let input = [
+            json!({"schemaVersion": "2.0", "env": {}}).to_string(),
+            json!({"schemaVersion": "1.9", "env": {}}).to_string(),
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        let mut runs = 0;
+
+        serve(input.as_bytes(), &mut output, || {
+            runs += 1;
+            ExitMode::Success
+        });
+
+        assert_eq!(runs, 1);
+        assert_eq!(
+            results(&output),
+            vec![
+                json!({"schemaVersion": SCHEMA_VERSION, "exitCode": 3}),
+                json!({"schemaVersion": SCHEMA_VERSION, "exitCode": 0}),
+            ]
+        );
+    }
+
+    #[test]
+    fn serve_rejects_jobs_setting_process_variables() {
+        let input = json!({"env": {"IEXEC_MAX_REDIRECTS": "0"}}).to_string();
+        let mut output = Vec::new();
+        let mut runs = 0;
+
+        serve(input.as_bytes(), &mut output, || {
+            runs += 1;
+            ExitMode::Success
+        });
+
+        assert_eq!(runs, 0);
+        assert_eq!(
+            results(&output),
+            vec![json!({"schemaVersion": SCHEMA_VERSION, "exitCode": 3})]
+        );
+    }
+
+    #[test]
+    fn serve_reports_malformed_job_requests() {
+        let mut output = Vec::new();
+        let mut runs = 0;
+
+        serve("not-json\n".as_bytes(), &mut output, || {
+            runs += 1;
+            ExitMode::Success
+        });
+
+        assert_eq!(runs, 0);
+        assert_eq!(
+            results(&output),
+            vec![json!({"schemaVersion": SCHEMA_VERSION, "exitCode": 3})]
+        );
+    }
+}
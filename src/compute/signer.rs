@@ -1,8 +1,205 @@
 use crate::compute::errors::ReplicateStatusCause;
 use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
-use crate::compute::utils::hash_utils::{concatenate_and_hash, hex_string_to_byte_array};
+use crate::compute::utils::hash_utils::{
+    clean_hex_prefix, concatenate_and_hash, hex_string_to_byte_array,
+};
 use alloy_signer::{Signature, SignerSync};
 use alloy_signer_local::PrivateKeySigner;
+use log::error;
+use p256::ecdsa::signature::Signer;
+use std::cell::OnceCell;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Signs challenge message hashes with an enclave challenge private key.
+///
+/// Implementations differ by the curve used, so that the challenge can be verified by
+/// non-EVM verification backends. The algorithm is selected with `SIGN_TEE_CHALLENGE_ALGORITHM`.
+pub trait ChallengeSigner: Send + Sync {
+    /// Signs the bytes of `message_hash` and returns the hex-encoded signature, prefixed with `0x`.
+    fn sign(&self, message_hash: &[u8]) -> Result<String, ReplicateStatusCause>;
+}
+
+/// Signature algorithms supported for the enclave challenge.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SignatureAlgorithm {
+    /// EIP-191 personal message signature on secp256k1, as expected by the iExec contracts.
+    #[default]
+    Secp256k1,
+    /// ECDSA signature on secp256r1 (P-256) with SHA-256, encoded as `r || s`.
+    Secp256r1,
+    /// Ed25519 signature.
+    Ed25519,
+}
+
+impl SignatureAlgorithm {
+    /// Returns the name of the algorithm, as accepted by `SIGN_TEE_CHALLENGE_ALGORITHM`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Secp256k1 => "secp256k1",
+            SignatureAlgorithm::Secp256r1 => "secp256r1",
+            SignatureAlgorithm::Ed25519 => "ed25519",
+        }
+    }
+}
+
+impl FromStr for SignatureAlgorithm {
+    type Err = ReplicateStatusCause;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "secp256k1" => Ok(SignatureAlgorithm::Secp256k1),
+            "secp256r1" | "p256" => Ok(SignatureAlgorithm::Secp256r1),
+            "ed25519" => Ok(SignatureAlgorithm::Ed25519),
+            _ => {
+                error!("Unsupported challenge signature algorithm [algorithm:{value}]");
+                Err(ReplicateStatusCause::PreComputeInvalidTeeSignature)
+            }
+        }
+    }
+}
+
+/// [`ChallengeSigner`] producing EIP-191 signatures on secp256k1.
+pub struct Secp256k1Signer(PrivateKeySigner);
+
+impl ChallengeSigner for Secp256k1Signer {
+    fn sign(&self, message_hash: &[u8]) -> Result<String, ReplicateStatusCause> {
+        let signature: Signature = self
+            .0
+            .sign_message_sync(message_hash)
+            .map_err(|_| ReplicateStatusCause::PreComputeInvalidTeeSignature)?;
+        Ok(signature.to_string())
+    }
+}
+
+/// [`ChallengeSigner`] producing ECDSA signatures on secp256r1.
+pub struct Secp256r1Signer(p256::ecdsa::SigningKey);
+
+impl ChallengeSigner for Secp256r1Signer {
+    fn sign(&self, message_hash: &[u8]) -> Result<String, ReplicateStatusCause> {
+        let signature: p256::ecdsa::Signature = self
+            .0
+            .try_sign(message_hash)
+            .map_err(|_| ReplicateStatusCause::PreComputeInvalidTeeSignature)?;
+        Ok(to_hex(&signature.to_bytes()))
+    }
+}
+
+/// [`ChallengeSigner`] producing Ed25519 signatures.
+pub struct Ed25519Signer(ed25519_dalek::SigningKey);
+
+impl ChallengeSigner for Ed25519Signer {
+    fn sign(&self, message_hash: &[u8]) -> Result<String, ReplicateStatusCause> {
+        Ok(to_hex(&self.0.sign(message_hash).to_bytes()))
+    }
+}
+
+/// Builds the [`ChallengeSigner`] of `algorithm` from a hex-encoded 32-byte private key.
+///
+/// # Errors
+///
+/// Returns `PreComputeTeeChallengePrivateKeyMissing` if the private key is not a valid key
+/// for the selected algorithm.
+///
+/// # Example
+///
+/// ```
+/// let private_key = "0xdd3b993ec21c71c1f6d63a5240850e0d4d8dd83ff70d29e49247958548c1d479";
+/// let signer = challenge_signer(SignatureAlgorithm::Ed25519, private_key)?;
+/// let signature = signer.sign(&[0u8; 32])?;
+/// ```
+pub fn challenge_signer(
+    algorithm: SignatureAlgorithm,
+    private_key: &str,
+) -> Result<Box<dyn ChallengeSigner>, ReplicateStatusCause> {
+    let invalid_key = ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing;
+    if algorithm == SignatureAlgorithm::Secp256k1 {
+        let signer = private_key
+            .parse::<PrivateKeySigner>()
+            .map_err(|_| invalid_key)?;
+        return Ok(Box::new(Secp256k1Signer(signer)));
+    }
+
+    let clean_key = clean_hex_prefix(private_key);
+    if clean_key.len() != 64 || !clean_key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid_key);
+    }
+    let key_bytes: [u8; 32] = hex_string_to_byte_array(clean_key)
+        .try_into()
+        .map_err(|_| invalid_key.clone())?;
+    match algorithm {
+        SignatureAlgorithm::Secp256r1 => {
+            let signing_key =
+                p256::ecdsa::SigningKey::from_bytes(&key_bytes.into()).map_err(|_| invalid_key)?;
+            Ok(Box::new(Secp256r1Signer(signing_key)))
+        }
+        _ => Ok(Box::new(Ed25519Signer(
+            ed25519_dalek::SigningKey::from_bytes(&key_bytes),
+        ))),
+    }
+}
+
+/// Verifies a signature produced by the [`ChallengeSigner`] of `algorithm`.
+///
+/// `signer` identifies the expected signer: the address for `secp256k1`, the hex-encoded SEC1
+/// public key for `secp256r1` and the hex-encoded 32-byte public key for `ed25519`.
+///
+/// # Returns
+///
+/// * `true` if `signature` is a valid signature of `message_hash` by `signer`.
+/// * `false` if the signature is invalid, was produced by another key, or if `signature` or
+///   `signer` cannot be parsed.
+///
+/// # Example
+///
+/// ```
+/// let signature = challenge_signer(SignatureAlgorithm::Secp256k1, private_key)?.sign(&hash)?;
+/// assert!(verify_signature(SignatureAlgorithm::Secp256k1, &hash, &signature, address));
+/// ```
+pub fn verify_signature(
+    algorithm: SignatureAlgorithm,
+    message_hash: &[u8],
+    signature: &str,
+    signer: &str,
+) -> bool {
+    let signature_bytes = hex_string_to_byte_array(signature);
+    let signer_bytes = hex_string_to_byte_array(signer);
+    match algorithm {
+        SignatureAlgorithm::Secp256k1 => Signature::from_str(signature)
+            .and_then(|signature| signature.recover_address_from_msg(message_hash))
+            .is_ok_and(|address| address.to_string().eq_ignore_ascii_case(signer)),
+        SignatureAlgorithm::Secp256r1 => {
+            use p256::ecdsa::signature::Verifier;
+            let (Ok(key), Ok(signature)) = (
+                p256::ecdsa::VerifyingKey::from_sec1_bytes(&signer_bytes),
+                p256::ecdsa::Signature::from_slice(&signature_bytes),
+            ) else {
+                return false;
+            };
+            key.verify(message_hash, &signature).is_ok()
+        }
+        SignatureAlgorithm::Ed25519 => {
+            let (Ok(key_bytes), Ok(signature_bytes)) = (
+                <[u8; 32]>::try_from(signer_bytes),
+                <[u8; 64]>::try_from(signature_bytes),
+            ) else {
+                return false;
+            };
+            ed25519_dalek::VerifyingKey::from_bytes(&key_bytes).is_ok_and(|key| {
+                key.verify_strict(
+                    message_hash,
+                    &ed25519_dalek::Signature::from_bytes(&signature_bytes),
+                )
+                .is_ok()
+            })
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{hex}")
+}
 
 /// Signs a message hash using the provided enclave challenge private key.
 ///
@@ -45,11 +242,7 @@ pub fn sign_enclave_challenge(
         .parse::<PrivateKeySigner>()
         .map_err(|_| ReplicateStatusCause::PreComputeWorkerAddressMissing)?;
 
-    let signature: Signature = signer
-        .sign_message_sync(&hex_string_to_byte_array(message_hash))
-        .map_err(|_| ReplicateStatusCause::PreComputeInvalidTeeSignature)?;
-
-    Ok(signature.to_string())
+    Secp256k1Signer(signer).sign(&hex_string_to_byte_array(message_hash))
 }
 
 /// Generates a challenge signature for a given chain task ID.
@@ -78,6 +271,8 @@ pub fn sign_enclave_challenge(
 ///
 /// * `SIGN_WORKER_ADDRESS` - The worker's address used in message hash calculation
 /// * `SIGN_TEE_CHALLENGE_PRIVATE_KEY` - The private key used for signing the challenge
+/// * `SIGN_TEE_CHALLENGE_ALGORITHM` - Optional signature algorithm: `secp256k1` (default),
+///   `secp256r1` or `ed25519`
 ///
 /// # Example
 ///
@@ -99,13 +294,133 @@ pub fn get_challenge(chain_task_id: &str) -> Result<String, ReplicateStatusCause
         ReplicateStatusCause::PreComputeWorkerAddressMissing,
     )?;
 
-    let tee_challenge_private_key = get_env_var_or_error(
+    let (algorithm, tee_challenge_private_key) = read_signer_config()?;
+
+    let message_hash = concatenate_and_hash(&[chain_task_id, &worker_address]);
+    if let Some(preloaded) = PRELOADED_SIGNER.get().filter(|preloaded| {
+        preloaded.algorithm == algorithm && preloaded.private_key == tee_challenge_private_key
+    }) {
+        return preloaded
+            .signer
+            .sign(&hex_string_to_byte_array(&message_hash));
+    }
+    match algorithm {
+        SignatureAlgorithm::Secp256k1 => {
+            sign_enclave_challenge(&message_hash, &tee_challenge_private_key)
+        }
+        _ => challenge_signer(algorithm, &tee_challenge_private_key)?
+            .sign(&hex_string_to_byte_array(&message_hash)),
+    }
+}
+
+fn read_signer_config() -> Result<(SignatureAlgorithm, String), ReplicateStatusCause> {
+    let private_key = get_env_var_or_error(
         TeeSessionEnvironmentVariable::SignTeeChallengePrivateKey,
         ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing,
     )?;
+    let algorithm = match get_env_var_or_error(
+        TeeSessionEnvironmentVariable::SignTeeChallengeAlgorithm,
+        ReplicateStatusCause::PreComputeInvalidTeeSignature,
+    ) {
+        Ok(value) => value.parse::<SignatureAlgorithm>()?,
+        Err(_) => SignatureAlgorithm::default(),
+    };
+    Ok((algorithm, private_key))
+}
 
-    let message_hash = concatenate_and_hash(&[chain_task_id, &worker_address]);
-    sign_enclave_challenge(&message_hash, &tee_challenge_private_key)
+/// Signs `message_hash` with the enclave challenge private key of the environment, using the
+/// configured `SIGN_TEE_CHALLENGE_ALGORITHM`.
+///
+/// The signer parsed by [`preload_challenge_signer`] is reused when the configuration is
+/// unchanged.
+///
+/// # Returns
+///
+/// * `Ok((SignatureAlgorithm, String))` - The algorithm used and the hex-encoded signature
+/// * `Err(ReplicateStatusCause)` - An error if the private key is missing or invalid, or if
+///   signing fails
+pub fn sign_with_challenge_key(
+    message_hash: &[u8],
+) -> Result<(SignatureAlgorithm, String), ReplicateStatusCause> {
+    let (algorithm, private_key) = read_signer_config()?;
+    let signature = match PRELOADED_SIGNER.get().filter(|preloaded| {
+        preloaded.algorithm == algorithm && preloaded.private_key == private_key
+    }) {
+        Some(preloaded) => preloaded.signer.sign(message_hash)?,
+        None => challenge_signer(algorithm, &private_key)?.sign(message_hash)?,
+    };
+    Ok((algorithm, signature))
+}
+
+/// Challenge signer parsed ahead of time by [`preload_challenge_signer`].
+struct PreloadedSigner {
+    algorithm: SignatureAlgorithm,
+    private_key: String,
+    signer: Box<dyn ChallengeSigner>,
+}
+
+static PRELOADED_SIGNER: OnceLock<PreloadedSigner> = OnceLock::new();
+
+/// Parses the challenge private key of the environment ahead of time.
+///
+/// [`get_challenge`] then reuses the parsed signer as long as the configured key and
+/// algorithm are unchanged, instead of parsing the key again for every task.
+///
+/// # Errors
+///
+/// Returns the error [`get_challenge`] would return for a missing or invalid key.
+pub fn preload_challenge_signer() -> Result<(), ReplicateStatusCause> {
+    let (algorithm, private_key) = read_signer_config()?;
+    let signer = challenge_signer(algorithm, &private_key)?;
+    let _ = PRELOADED_SIGNER.set(PreloadedSigner {
+        algorithm,
+        private_key,
+        signer,
+    });
+    Ok(())
+}
+
+/// Challenge of a task, computed with [`get_challenge`] on first use and cached for the rest
+/// of the run.
+///
+/// A single instance is shared by every feature talking to the worker API (exit reporting,
+/// progress updates) so that environment variables are read and the challenge is signed
+/// only once.
+///
+/// # Example
+///
+/// ```
+/// let challenge = TaskChallenge::new("0x123456789abcdef");
+/// let authorization = challenge.get()?;
+/// ```
+pub struct TaskChallenge {
+    chain_task_id: String,
+    challenge: OnceCell<Result<String, ReplicateStatusCause>>,
+}
+
+impl TaskChallenge {
+    pub fn new(chain_task_id: &str) -> Self {
+        TaskChallenge {
+            chain_task_id: chain_task_id.to_string(),
+            challenge: OnceCell::new(),
+        }
+    }
+
+    pub fn chain_task_id(&self) -> &str {
+        &self.chain_task_id
+    }
+
+    /// Returns the challenge of the task, signing it on first call.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - The cached challenge signature
+    /// * `Err(ReplicateStatusCause)` - The cached error returned by [`get_challenge`]
+    pub fn get(&self) -> Result<String, ReplicateStatusCause> {
+        self.challenge
+            .get_or_init(|| get_challenge(&self.chain_task_id))
+            .clone()
+    }
 }
 
 #[cfg(test)]
@@ -147,6 +462,40 @@ mod env_utils_tests {
         );
     }
 
+    #[test]
+    fn get_challenge_reuses_preloaded_signer() {
+        with_vars(
+            vec![
+                ("SIGN_WORKER_ADDRESS", Some(WORKER_ADDRESS)),
+                (
+                    "SIGN_TEE_CHALLENGE_PRIVATE_KEY",
+                    Some(ENCLAVE_CHALLENGE_PRIVATE_KEY),
+                ),
+                ("SIGN_TEE_CHALLENGE_ALGORITHM", None),
+            ],
+            || {
+                assert_eq!(preload_challenge_signer(), Ok(()));
+                assert!(PRELOADED_SIGNER.get().is_some());
+
+                let message_hash = concatenate_and_hash(&[CHAIN_TASK_ID, WORKER_ADDRESS]);
+                assert_eq!(
+                    get_challenge(CHAIN_TASK_ID),
+                    sign_enclave_challenge(&message_hash, ENCLAVE_CHALLENGE_PRIVATE_KEY)
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn preload_challenge_signer_fails_without_private_key() {
+        temp_env::with_vars_unset(vec!["SIGN_TEE_CHALLENGE_PRIVATE_KEY"], || {
+            assert_eq!(
+                preload_challenge_signer(),
+                Err(ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing)
+            );
+        });
+    }
+
     #[test]
     fn error_when_worker_address_missing() {
         with_vars(
@@ -171,4 +520,238 @@ mod env_utils_tests {
             );
         });
     }
+
+    #[test]
+    fn task_challenge_is_computed_once() {
+        let challenge = TaskChallenge::new(CHAIN_TASK_ID);
+        let first = with_vars(
+            vec![
+                ("SIGN_WORKER_ADDRESS", Some(WORKER_ADDRESS)),
+                (
+                    "SIGN_TEE_CHALLENGE_PRIVATE_KEY",
+                    Some(ENCLAVE_CHALLENGE_PRIVATE_KEY),
+                ),
+            ],
+            || challenge.get(),
+        );
+        let second = temp_env::with_vars_unset(
+            vec!["SIGN_WORKER_ADDRESS", "SIGN_TEE_CHALLENGE_PRIVATE_KEY"],
+            || challenge.get(),
+        );
+
+        assert!(first.is_ok());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn task_challenge_caches_error() {
+        let challenge = TaskChallenge::new(CHAIN_TASK_ID);
+        temp_env::with_vars_unset(vec!["SIGN_WORKER_ADDRESS"], || {
+            assert_eq!(
+                challenge.get(),
+                Err(ReplicateStatusCause::PreComputeWorkerAddressMissing)
+            );
+        });
+        assert_eq!(
+            challenge.get(),
+            Err(ReplicateStatusCause::PreComputeWorkerAddressMissing)
+        );
+    }
+
+    #[test]
+    fn parse_signature_algorithm() {
+        assert_eq!(
+            "SECP256K1".parse::<SignatureAlgorithm>(),
+            Ok(SignatureAlgorithm::Secp256k1)
+        );
+        assert_eq!(
+            "secp256r1".parse::<SignatureAlgorithm>(),
+            Ok(SignatureAlgorithm::Secp256r1)
+        );
+        assert_eq!(
+            "ed25519".parse::<SignatureAlgorithm>(),
+            Ok(SignatureAlgorithm::Ed25519)
+        );
+        assert_eq!(
+            "rsa".parse::<SignatureAlgorithm>(),
+            Err(ReplicateStatusCause::PreComputeInvalidTeeSignature)
+        );
+    }
+
+    #[test]
+    fn secp256k1_challenge_signer_matches_sign_enclave_challenge() {
+        let signer =
+            challenge_signer(SignatureAlgorithm::Secp256k1, ENCLAVE_CHALLENGE_PRIVATE_KEY).unwrap();
+        let signature = signer
+            .sign(&hex_string_to_byte_array(MESSAGE_HASH))
+            .unwrap();
+        assert_eq!(signature, EXPECTED_CHALLENGE);
+    }
+
+    #[test]
+    fn secp256r1_challenge_signer_produces_verifiable_signature() {
+        use p256::ecdsa::{SigningKey, VerifyingKey, signature::Verifier};
+
+        let signer =
+            challenge_signer(SignatureAlgorithm::Secp256r1, ENCLAVE_CHALLENGE_PRIVATE_KEY).unwrap();
+        let message = hex_string_to_byte_array(MESSAGE_HASH);
+        let signature = signer.sign(&message).unwrap();
+
+        let signature_bytes = hex_string_to_byte_array(&signature);
+        assert_eq!(signature_bytes.len(), 64);
+        let key_bytes: [u8; 32] = hex_string_to_byte_array(ENCLAVE_CHALLENGE_PRIVATE_KEY)
+            .try_into()
+            .unwrap();
+        let verifying_key = VerifyingKey::from(&SigningKey::from_bytes(&key_bytes.into()).unwrap());
+        let signature = p256::ecdsa::Signature::from_slice(&signature_bytes).unwrap();
+        assert!(verifying_key.verify(&message, &signature).is_ok());
+    }
+
+    #[test]
+    fn ed25519_challenge_signer_produces_verifiable_signature() {
+        use ed25519_dalek::{SigningKey, Verifier};
+
+        let signer =
+            challenge_signer(SignatureAlgorithm::Ed25519, ENCLAVE_CHALLENGE_PRIVATE_KEY).unwrap();
+        let message = hex_string_to_byte_array(MESSAGE_HASH);
+        let signature = signer.sign(&message).unwrap();
+
+        let signature_bytes: [u8; 64] = hex_string_to_byte_array(&signature).try_into().unwrap();
+        let key_bytes: [u8; 32] = hex_string_to_byte_array(ENCLAVE_CHALLENGE_PRIVATE_KEY)
+            .try_into()
+            .unwrap();
+        let verifying_key = SigningKey::from_bytes(&key_bytes).verifying_key();
+        let signature = ed25519_dalek::Signature::from_bytes(&signature_bytes);
+        assert!(verifying_key.verify(&message, &signature).is_ok());
+    }
+
+    #[test]
+    fn challenge_signer_rejects_invalid_private_key() {
+        for algorithm in [SignatureAlgorithm::Secp256r1, SignatureAlgorithm::Ed25519] {
+            assert!(matches!(
+                challenge_signer(algorithm, "0x1234"),
+                Err(ReplicateStatusCause::PreComputeTeeChallengePrivateKeyMissing)
+            ));
+        }
+    }
+
+    #[test]
+    fn get_challenge_uses_configured_algorithm() {
+        with_vars(
+            vec![
+                ("SIGN_WORKER_ADDRESS", Some(WORKER_ADDRESS)),
+                (
+                    "SIGN_TEE_CHALLENGE_PRIVATE_KEY",
+                    Some(ENCLAVE_CHALLENGE_PRIVATE_KEY),
+                ),
+                ("SIGN_TEE_CHALLENGE_ALGORITHM", Some("ed25519")),
+            ],
+            || {
+                let message_hash = concatenate_and_hash(&[CHAIN_TASK_ID, WORKER_ADDRESS]);
+                let expected_signature =
+                    challenge_signer(SignatureAlgorithm::Ed25519, ENCLAVE_CHALLENGE_PRIVATE_KEY)
+                        .unwrap()
+                        .sign(&hex_string_to_byte_array(&message_hash))
+                        .unwrap();
+
+                assert_eq!(get_challenge(CHAIN_TASK_ID).unwrap(), expected_signature);
+            },
+        );
+    }
+
+    fn public_key(algorithm: SignatureAlgorithm) -> String {
+        let key_bytes: [u8; 32] = hex_string_to_byte_array(ENCLAVE_CHALLENGE_PRIVATE_KEY)
+            .try_into()
+            .unwrap();
+        match algorithm {
+            SignatureAlgorithm::Secp256k1 => ENCLAVE_CHALLENGE_PRIVATE_KEY
+                .parse::<PrivateKeySigner>()
+                .unwrap()
+                .address()
+                .to_string(),
+            SignatureAlgorithm::Secp256r1 => to_hex(
+                p256::ecdsa::SigningKey::from_bytes(&key_bytes.into())
+                    .unwrap()
+                    .verifying_key()
+                    .to_sec1_bytes()
+                    .as_ref(),
+            ),
+            SignatureAlgorithm::Ed25519 => to_hex(
+                ed25519_dalek::SigningKey::from_bytes(&key_bytes)
+                    .verifying_key()
+                    .as_bytes(),
+            ),
+        }
+    }
+
+    #[test]
+    fn verify_signature_accepts_signatures_of_every_algorithm() {
+        let message = hex_string_to_byte_array(MESSAGE_HASH);
+        for algorithm in [
+            SignatureAlgorithm::Secp256k1,
+            SignatureAlgorithm::Secp256r1,
+            SignatureAlgorithm::Ed25519,
+        ] {
+            let signature = challenge_signer(algorithm, ENCLAVE_CHALLENGE_PRIVATE_KEY)
+                .unwrap()
+                .sign(&message)
+                .unwrap();
+            assert!(
+                verify_signature(algorithm, &message, &signature, &public_key(algorithm)),
+                "{algorithm:?}"
+            );
+            assert!(
+                !verify_signature(algorithm, &[0u8; 32], &signature, &public_key(algorithm)),
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn verify_signature_rejects_other_signer() {
+        let message = hex_string_to_byte_array(MESSAGE_HASH);
+        let signature =
+            challenge_signer(SignatureAlgorithm::Secp256k1, ENCLAVE_CHALLENGE_PRIVATE_KEY)
+                .unwrap()
+                .sign(&message)
+                .unwrap();
+
+        assert!(!verify_signature(
+            SignatureAlgorithm::Secp256k1,
+            &message,
+            &signature,
+            "0x0000000000000000000000000000000000000001"
+        ));
+        assert!(!verify_signature(
+            SignatureAlgorithm::Ed25519,
+            &message,
+            "0x1234",
+            &public_key(SignatureAlgorithm::Ed25519)
+        ));
+    }
+
+    #[test]
+    fn sign_with_challenge_key_uses_configured_algorithm() {
+        with_vars(
+            vec![
+                (
+                    "SIGN_TEE_CHALLENGE_PRIVATE_KEY",
+                    Some(ENCLAVE_CHALLENGE_PRIVATE_KEY),
+                ),
+                ("SIGN_TEE_CHALLENGE_ALGORITHM", Some("secp256r1")),
+            ],
+            || {
+                let message = hex_string_to_byte_array(MESSAGE_HASH);
+                let (algorithm, signature) = sign_with_challenge_key(&message).unwrap();
+
+                assert_eq!(algorithm, SignatureAlgorithm::Secp256r1);
+                assert!(verify_signature(
+                    algorithm,
+                    &message,
+                    &signature,
+                    &public_key(algorithm)
+                ));
+            },
+        );
+    }
 }
@@ -0,0 +1,373 @@
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::schema;
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::utils::resource_utils::{self, ResourceUsage};
+use crate::compute::utils::time_utils::{Clock, SystemClock};
+use log::error;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const STATUS_FILENAME: &str = "status.json";
+const MIN_WRITE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Stage of a pre-compute run, as exposed in the status file.
+#[derive(Serialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    #[default]
+    Starting,
+    CheckingUrls,
+    DownloadingDataset,
+    DecryptingDataset,
+    SavingDataset,
+    DownloadingInputFiles,
+    Completed,
+    Failed,
+}
+
+/// Content of the status file.
+///
+/// The JSON structure is:
+/// ```json
+/// {
+///   "chainTaskId": "0x123",
+///   "stage": "downloading_input_files",
+///   "percentComplete": 50,
+///   "bytes": 1048576,
+///   "lastError": null,
+///   "updatedAt": 1718000000000
+/// }
+/// ```
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RunStatus {
+    pub chain_task_id: String,
+    pub stage: Stage,
+    pub percent_complete: u8,
+    pub bytes: u64,
+    pub last_error: Option<String>,
+    pub updated_at: u128,
+    #[serde(skip)]
+    pub cause: Option<ReplicateStatusCause>,
+    #[serde(skip)]
+    pub stages: Vec<StageUsage>,
+    /// Digest of the output directory tree once the run succeeded, see
+    /// [`crate::compute::report::output_tree_sha256`].
+    #[serde(skip)]
+    pub output_tree_sha256: Option<String>,
+}
+
+/// Resources consumed during a stage of the run.
+///
+/// `peak_rss_kb` is the peak resident set size of the process when the stage ended, so it
+/// only grows from one stage to the next.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StageUsage {
+    pub stage: Stage,
+    pub cpu_ms: u128,
+    pub peak_rss_kb: u64,
+}
+
+#[derive(Default)]
+struct Progress {
+    status: RunStatus,
+    total_steps: usize,
+    done_steps: usize,
+    last_write: Option<Instant>,
+    stage_start: Option<ResourceUsage>,
+}
+
+impl Progress {
+    /// Moves to `stage`, recording the resources consumed by the stage being left.
+    ///
+    /// Nothing is recorded if resource usage could not be sampled.
+    fn enter(&mut self, stage: Stage, usage: Option<ResourceUsage>) {
+        if stage == self.status.stage {
+            return;
+        }
+        if let (Some(start), Some(end)) = (&self.stage_start, &usage) {
+            self.status.stages.push(StageUsage {
+                stage: self.status.stage,
+                cpu_ms: end.cpu_time_since(start).as_millis(),
+                peak_rss_kb: end.peak_rss_kb,
+            });
+        }
+        self.stage_start = usage;
+        self.status.stage = stage;
+    }
+}
+
+/// Status file refreshed during the run, so that worker-side tooling can poll progress.
+///
+/// The file is written to [`STATUS_FILENAME`] inside the directory named by
+/// `IEXEC_PRE_COMPUTE_STATUS_DIR`, and is disabled when the variable is not set. Stage
+/// changes are written immediately, progress updates at most every few seconds. Each write
+/// replaces the file atomically, so readers never see a partial document.
+///
+/// CPU time and peak RSS are sampled at each stage change and exposed in
+/// [`RunStatus::stages`], to help sizing the enclave running the pre-compute.
+pub struct StatusFile {
+    path: Option<PathBuf>,
+    progress: Mutex<Progress>,
+    clock: Box<dyn Clock>,
+    sampler: fn() -> Option<ResourceUsage>,
+}
+
+impl StatusFile {
+    pub fn new(chain_task_id: &str, status_dir: Option<&Path>) -> Self {
+        StatusFile {
+            path: status_dir.map(|dir| dir.join(STATUS_FILENAME)),
+            progress: Mutex::new(Progress {
+                status: RunStatus {
+                    chain_task_id: chain_task_id.to_string(),
+                    ..Default::default()
+                },
+                stage_start: resource_utils::sample(),
+                ..Default::default()
+            }),
+            clock: Box::new(SystemClock),
+            sampler: resource_utils::sample,
+        }
+    }
+
+    /// Replaces the clock used to timestamp updates.
+    #[cfg(test)]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Replaces the function sampling resource usage, and takes a first sample with it.
+    #[cfg(test)]
+    pub fn with_sampler(mut self, sampler: fn() -> Option<ResourceUsage>) -> Self {
+        self.sampler = sampler;
+        self.progress.get_mut().unwrap().stage_start = sampler();
+        self
+    }
+
+    /// Creates the status file configured by `IEXEC_PRE_COMPUTE_STATUS_DIR`.
+    pub fn from_env(chain_task_id: &str) -> Self {
+        let status_dir = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeStatusDir,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .filter(|dir| !dir.trim().is_empty());
+        StatusFile::new(chain_task_id, status_dir.as_deref().map(Path::new))
+    }
+
+    /// Sets the number of steps (dataset and input files) making up the run.
+    pub fn set_total_steps(&self, total_steps: usize) {
+        self.update(false, |progress| progress.total_steps = total_steps);
+    }
+
+    /// Moves the run to `stage` and writes the status file.
+    pub fn stage(&self, stage: Stage) {
+        let usage = (self.sampler)();
+        self.update(true, |progress| {
+            progress.enter(stage, usage);
+            if stage == Stage::Completed {
+                progress.done_steps = progress.total_steps;
+            }
+        });
+    }
+
+    /// Records a completed step which produced `bytes` bytes.
+    ///
+    /// The status file is only rewritten if the last write is old enough.
+    pub fn step_done(&self, bytes: u64) {
+        self.update(false, |progress| {
+            progress.done_steps += 1;
+            progress.status.bytes += bytes;
+        });
+    }
+
+    /// Moves the run to [`Stage::Failed`], records `cause` and writes the status file.
+    pub fn fail(&self, cause: &ReplicateStatusCause) {
+        let usage = (self.sampler)();
+        self.update(true, |progress| {
+            progress.enter(Stage::Failed, usage);
+            progress.status.last_error = Some(cause.to_string());
+            progress.status.cause = Some(cause.clone());
+        });
+    }
+
+    /// Returns a copy of the current status.
+    pub fn status(&self) -> RunStatus {
+        self.progress
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .status
+            .clone()
+    }
+
+    fn update(&self, force_write: bool, apply: impl FnOnce(&mut Progress)) {
+        let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        apply(&mut progress);
+        progress.status.percent_complete = match progress.total_steps {
+            0 if progress.status.stage == Stage::Completed => 100,
+            0 => 0,
+            total => (progress.done_steps.min(total) * 100 / total) as u8,
+        };
+        progress.status.updated_at = self.clock.unix_millis();
+
+        let Some(path) = &self.path else {
+            return;
+        };
+        let is_due = progress
+            .last_write
+            .is_none_or(|last_write| last_write.elapsed() >= MIN_WRITE_INTERVAL);
+        if force_write || is_due {
+            progress.last_write = Some(Instant::now());
+            write_status(path, &progress.status);
+        }
+    }
+}
+
+/// Writes `status` next to `path` then renames it into place.
+fn write_status(path: &Path, status: &RunStatus) {
+    let partial_path = path.with_extension("json.part");
+    let result = schema::to_vec_pretty(status)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(&partial_path, content).map_err(|e| e.to_string()))
+        .and_then(|_| fs::rename(&partial_path, path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        error!("Failed to write status file [path:{}]: {e}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::schema::SCHEMA_VERSION;
+    use crate::compute::utils::time_utils::FixedClock;
+    use serde_json::{Value, json};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::UNIX_EPOCH;
+    use tempfile::TempDir;
+
+    const CHAIN_TASK_ID: &str = "0x123456789abcdef";
+
+    fn read_status(dir: &TempDir) -> Value {
+        serde_json::from_slice(&fs::read(dir.path().join(STATUS_FILENAME)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn stage_change_writes_status_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let status_file = StatusFile::new(CHAIN_TASK_ID, Some(temp_dir.path())).with_clock(
+            FixedClock(UNIX_EPOCH + Duration::from_millis(1_718_000_000_000)),
+        );
+
+        status_file.set_total_steps(2);
+        status_file.stage(Stage::DownloadingDataset);
+
+        assert_eq!(
+            read_status(&temp_dir),
+            json!({
+                "schemaVersion": SCHEMA_VERSION,
+                "chainTaskId": CHAIN_TASK_ID,
+                "stage": "downloading_dataset",
+                "percentComplete": 0,
+                "bytes": 0,
+                "lastError": null,
+                "updatedAt": 1_718_000_000_000u64,
+            })
+        );
+        assert!(!temp_dir.path().join("status.json.part").exists());
+    }
+
+    #[test]
+    fn step_done_updates_progress_without_forcing_write() {
+        let temp_dir = TempDir::new().unwrap();
+        let status_file = StatusFile::new(CHAIN_TASK_ID, Some(temp_dir.path()));
+
+        status_file.set_total_steps(4);
+        status_file.stage(Stage::DownloadingInputFiles);
+        status_file.step_done(10);
+        status_file.step_done(20);
+
+        let status = status_file.status();
+        assert_eq!(status.percent_complete, 50);
+        assert_eq!(status.bytes, 30);
+        // Written by the stage change, progress updates are throttled.
+        assert_eq!(read_status(&temp_dir)["percentComplete"], 0);
+    }
+
+    #[test]
+    fn fail_records_last_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let status_file = StatusFile::new(CHAIN_TASK_ID, Some(temp_dir.path()));
+
+        status_file.fail(&ReplicateStatusCause::PreComputeDatasetDownloadFailed);
+
+        let status = read_status(&temp_dir);
+        assert_eq!(status["stage"], "failed");
+        assert_eq!(
+            status["lastError"],
+            "Failed to download encrypted dataset file"
+        );
+    }
+
+    #[test]
+    fn completed_run_is_at_full_progress() {
+        let status_file = StatusFile::new(CHAIN_TASK_ID, None);
+
+        status_file.stage(Stage::Completed);
+
+        assert_eq!(status_file.status().percent_complete, 100);
+    }
+
+    // region stage usage
+    static CPU_MILLIS: AtomicU64 = AtomicU64::new(0);
+
+    /// Returns a sample whose CPU time grows by 10ms more at each call.
+    fn growing_sampler() -> Option<ResourceUsage> {
+        let call = CPU_MILLIS.fetch_add(1, Ordering::SeqCst);
+        Some(ResourceUsage {
+            cpu_time: Duration::from_millis(call * (call + 1) * 5),
+            peak_rss_kb: 1024 * (call + 1),
+        })
+    }
+
+    #[test]
+    fn stage_changes_record_usage_of_previous_stage() {
+        let status_file = StatusFile::new(CHAIN_TASK_ID, None).with_sampler(growing_sampler);
+
+        status_file.stage(Stage::DownloadingDataset);
+        status_file.stage(Stage::DownloadingDataset);
+        status_file.fail(&ReplicateStatusCause::PreComputeDatasetDownloadFailed);
+
+        assert_eq!(
+            status_file.status().stages,
+            vec![
+                StageUsage {
+                    stage: Stage::Starting,
+                    cpu_ms: 10,
+                    peak_rss_kb: 2048,
+                },
+                StageUsage {
+                    stage: Stage::DownloadingDataset,
+                    cpu_ms: 50,
+                    peak_rss_kb: 4096,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stage_usage_is_not_recorded_when_sampling_is_unsupported() {
+        let status_file = StatusFile::new(CHAIN_TASK_ID, None).with_sampler(|| None);
+
+        status_file.stage(Stage::CheckingUrls);
+        status_file.stage(Stage::Completed);
+
+        assert!(status_file.status().stages.is_empty());
+        assert_eq!(status_file.status().stage, Stage::Completed);
+    }
+    // endregion
+}
@@ -0,0 +1,181 @@
+use crate::compute::app_runner::ExitMode;
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::schema;
+use crate::compute::status::{RunStatus, Stage, StageUsage};
+use log::error;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::time::Duration;
+
+pub const EXIT_SUMMARY_PREFIX: &str = "EXIT_SUMMARY";
+
+/// Structured outcome of a pre-compute run, printed on stdout when the run ends, or on stderr
+/// in service mode where stdout carries the job results.
+///
+/// The summary is printed on every exit path, including when the task context is missing and
+/// after a panic. Logs share the same stream, so workers which only capture it should look for
+/// the line starting with [`EXIT_SUMMARY_PREFIX`] to get the result of the run without an API
+/// callback, rather than rely on it being the last line.
+///
+/// The line format is:
+/// ```text
+/// EXIT_SUMMARY {"chainTaskId":"0x123","exitMode":"REPORTED_FAILURE","exitCode":1,"cause":"PRE_COMPUTE_DATASET_DOWNLOAD_FAILED","stage":"failed","taskMs":1250,"warmStartSavedMs":0,"bytes":0,"stages":[{"stage":"starting","cpuMs":12,"peakRssKb":8192},{"stage":"checking_urls","cpuMs":3,"peakRssKb":8192},{"stage":"downloading_dataset","cpuMs":410,"peakRssKb":65536}]}
+/// ```
+///
+/// `outputTreeSha256` is added once the run succeeded when `IEXEC_PRE_COMPUTE_OUTPUT_TREE_HASH`
+/// is enabled, see [`crate::compute::report::output_tree_sha256`].
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitSummary {
+    pub chain_task_id: String,
+    pub exit_mode: ExitMode,
+    pub exit_code: i32,
+    pub cause: Option<ReplicateStatusCause>,
+    pub stage: Stage,
+    pub task_ms: u128,
+    pub warm_start_saved_ms: u128,
+    pub bytes: u64,
+    pub stages: Vec<StageUsage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_tree_sha256: Option<String>,
+}
+
+impl ExitSummary {
+    /// Builds the summary of a run from its final status.
+    ///
+    /// # Arguments
+    ///
+    /// * `exit_mode` - The exit mode of the run.
+    /// * `status` - The status of the run when it ended.
+    /// * `task_duration` - The time spent running the task.
+    /// * `warm_start_saved` - The time saved by warming up before the task, if any.
+    pub fn new(
+        exit_mode: ExitMode,
+        status: RunStatus,
+        task_duration: Duration,
+        warm_start_saved: Duration,
+    ) -> Self {
+        ExitSummary {
+            chain_task_id: status.chain_task_id,
+            exit_mode,
+            exit_code: exit_mode as i32,
+            cause: status.cause,
+            stage: status.stage,
+            task_ms: task_duration.as_millis(),
+            warm_start_saved_ms: warm_start_saved.as_millis(),
+            bytes: status.bytes,
+            stages: status.stages,
+            output_tree_sha256: status.output_tree_sha256,
+        }
+    }
+
+    /// Builds the summary of a run whose status is unknown, such as when the task context is
+    /// missing or after a panic.
+    ///
+    /// # Arguments
+    ///
+    /// * `exit_mode` - The exit mode of the run.
+    /// * `chain_task_id` - The task ID as configured, empty when it is missing.
+    /// * `cause` - The cause the run failed with.
+    pub fn failure(
+        exit_mode: ExitMode,
+        chain_task_id: String,
+        cause: ReplicateStatusCause,
+    ) -> Self {
+        let status = RunStatus {
+            chain_task_id,
+            stage: Stage::Failed,
+            cause: Some(cause),
+            ..Default::default()
+        };
+        Self::new(exit_mode, status, Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Returns the summary line, without trailing newline.
+    pub fn line(&self) -> Result<String, serde_json::Error> {
+        Ok(format!(
+            "{EXIT_SUMMARY_PREFIX} {}",
+            schema::to_string(self)?
+        ))
+    }
+
+    /// Writes the summary line to `output`.
+    ///
+    /// Failing to write the summary is logged but does not change the outcome of the run.
+    pub fn write_to(&self, output: &mut dyn Write) {
+        let result = self.line().map_err(io::Error::other).and_then(|line| {
+            writeln!(output, "{line}")?;
+            output.flush()
+        });
+        if let Err(e) = result {
+            error!(
+                "Failed to print exit summary [chainTaskId:{}]: {e}",
+                self.chain_task_id
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::schema::SCHEMA_VERSION;
+    use serde_json::{Value, json};
+
+    #[test]
+    fn line_contains_prefixed_json_summary() {
+        let status = RunStatus {
+            chain_task_id: "0x123".to_string(),
+            stage: Stage::Failed,
+            bytes: 2048,
+            cause: Some(ReplicateStatusCause::PreComputeDatasetDownloadFailed),
+            stages: vec![StageUsage {
+                stage: Stage::DownloadingDataset,
+                cpu_ms: 410,
+                peak_rss_kb: 65536,
+            }],
+            ..Default::default()
+        };
+        let summary = ExitSummary::new(
+            ExitMode::ReportedFailure,
+            status,
+            Duration::from_millis(1250),
+            Duration::from_millis(300),
+        );
+
+        let line = summary.line().unwrap();
+        let json_part = line.strip_prefix("EXIT_SUMMARY ").unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(json_part).unwrap(),
+            json!({
+                "schemaVersion": SCHEMA_VERSION,
+                "chainTaskId": "0x123",
+                "exitMode": "REPORTED_FAILURE",
+                "exitCode": 1,
+                "cause": "PRE_COMPUTE_DATASET_DOWNLOAD_FAILED",
+                "stage": "failed",
+                "taskMs": 1250,
+                "warmStartSavedMs": 300,
+                "bytes": 2048,
+                "stages": [
+                    {"stage": "downloading_dataset", "cpuMs": 410, "peakRssKb": 65536},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn successful_run_has_no_cause() {
+        let status = RunStatus {
+            chain_task_id: "0x123".to_string(),
+            stage: Stage::Completed,
+            ..Default::default()
+        };
+        let summary = ExitSummary::new(ExitMode::Success, status, Duration::ZERO, Duration::ZERO);
+
+        let line = summary.line().unwrap();
+        assert!(!line.contains('\n'));
+        assert!(line.contains(r#""exitCode":0,"cause":null"#));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::compute::app_runner::ExitMode;
+use crate::compute::cancellation::CancellationToken;
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::workspace;
+use log::error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Interval at which the supervisor checks the deadline and pending signals.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Time given to a cancelled task to reach a safe point before the process exits anyway.
+const CANCELLATION_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Last termination signal received by the process, zero if none.
+static RECEIVED_SIGNAL: AtomicI32 = AtomicI32::new(0);
+/// Whether a task is supervised, and will therefore handle termination signals itself.
+static IS_SUPERVISING: AtomicBool = AtomicBool::new(false);
+
+/// Installs handlers of `SIGTERM` and `SIGINT` recording the signal for the [`Supervisor`].
+///
+/// When no task is running, the process exits right away with [`ExitMode::Aborted`].
+#[cfg(target_os = "linux")]
+pub fn install_signal_handlers() {
+    let handler = handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    for signal in [libc::SIGTERM, libc::SIGINT] {
+        // SAFETY: the handler only performs async-signal-safe operations.
+        if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+            error!("Failed to install signal handler [signal:{signal}]");
+        }
+    }
+}
+
+/// Signals keep their default behaviour outside Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn install_signal_handlers() {}
+
+#[cfg(target_os = "linux")]
+extern "C" fn handle_signal(signal: libc::c_int) {
+    RECEIVED_SIGNAL.store(signal, Ordering::SeqCst);
+    if !IS_SUPERVISING.load(Ordering::SeqCst) {
+        // SAFETY: `_exit` is async-signal-safe.
+        unsafe { libc::_exit(ExitMode::Aborted as i32) };
+    }
+}
+
+/// Ends the process when the running task exceeds its deadline or receives a termination
+/// signal.
+///
+/// The deadline is read from `IEXEC_PRE_COMPUTE_TIMEOUT_SECS` and disabled when the variable
+/// is unset or zero. Once the deadline passes the task is cancelled with [`ExitMode::Timeout`],
+/// and after a `SIGTERM` or `SIGINT` with [`ExitMode::Aborted`], so that the worker can tell
+/// these outcomes apart from failures. A cancelled task stops at its next safe point, and the
+/// process exits with the same mode if it is still running after a grace period, for
+/// instance because a thread is blocked on the network. Supervision stops when the supervisor
+/// is dropped.
+pub struct Supervisor {
+    is_finished: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Starts supervising the task `chain_task_id`, calling `exit` if it must be ended.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_task_id` - The task being supervised, for logging.
+    /// * `timeout` - The maximum duration of the task, if any.
+    /// * `signal` - The last termination signal received, zero if none.
+    /// * `cancellation` - The token cancelled when the task must be ended.
+    /// * `grace_period` - The time given to the task to stop once cancelled.
+    /// * `exit` - The function ending the process with the given exit mode.
+    pub fn start(
+        chain_task_id: &str,
+        timeout: Option<Duration>,
+        signal: &'static AtomicI32,
+        cancellation: CancellationToken,
+        grace_period: Duration,
+        exit: impl Fn(ExitMode) + Send + 'static,
+    ) -> Self {
+        let is_finished = Arc::new(AtomicBool::new(false));
+        let deadline = timeout.map(|timeout| (Instant::now() + timeout, timeout));
+        let chain_task_id = chain_task_id.to_string();
+        let thread = {
+            let is_finished = is_finished.clone();
+            thread::spawn(move || {
+                let exit_mode = loop {
+                    if is_finished.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    let received_signal = signal.load(Ordering::SeqCst);
+                    if received_signal != 0 {
+                        error!(
+                            "TEE pre-compute aborted by signal [chainTaskId:{chain_task_id}, signal:{received_signal}]"
+                        );
+                        break ExitMode::Aborted;
+                    }
+                    if let Some((deadline, timeout)) = deadline
+                        && Instant::now() >= deadline
+                    {
+                        error!(
+                            "TEE pre-compute timed out [chainTaskId:{chain_task_id}, timeoutSecs:{}]",
+                            timeout.as_secs()
+                        );
+                        break ExitMode::Timeout;
+                    }
+                    thread::park_timeout(POLL_INTERVAL);
+                };
+                cancellation.cancel(exit_mode);
+                let grace_deadline = Instant::now() + grace_period;
+                while !is_finished.load(Ordering::SeqCst) {
+                    if Instant::now() >= grace_deadline {
+                        error!(
+                            "TEE pre-compute did not stop after cancellation [chainTaskId:{chain_task_id}, gracePeriodMs:{}]",
+                            grace_period.as_millis()
+                        );
+                        return exit(exit_mode);
+                    }
+                    thread::park_timeout(POLL_INTERVAL);
+                }
+            })
+        };
+        Supervisor {
+            is_finished,
+            thread: Some(thread),
+        }
+    }
+
+    /// Starts supervising the task `chain_task_id` with the deadline configured by
+    /// `IEXEC_PRE_COMPUTE_TIMEOUT_SECS`, cancelling `cancellation` then exiting the process if
+    /// it must be ended, once the temporary workspace of the task is removed.
+    pub fn from_env(chain_task_id: &str, cancellation: CancellationToken) -> Self {
+        let timeout = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecPreComputeTimeoutSecs,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .and_then(|timeout| timeout.trim().parse::<u64>().ok())
+        .filter(|timeout| *timeout > 0)
+        .map(Duration::from_secs);
+        IS_SUPERVISING.store(true, Ordering::SeqCst);
+        Supervisor::start(
+            chain_task_id,
+            timeout,
+            &RECEIVED_SIGNAL,
+            cancellation,
+            CANCELLATION_GRACE_PERIOD,
+            |exit_mode| {
+                workspace::remove_active();
+                log::logger().flush();
+                std::process::exit(exit_mode as i32);
+            },
+        )
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.is_finished.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+        IS_SUPERVISING.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    const CHAIN_TASK_ID: &str = "0x123456789abcdef";
+    const GRACE_PERIOD: Duration = Duration::from_millis(200);
+
+    fn start(
+        timeout: Option<Duration>,
+        signal: &'static AtomicI32,
+    ) -> (Supervisor, CancellationToken, mpsc::Receiver<ExitMode>) {
+        let (sender, receiver) = mpsc::channel();
+        let cancellation = CancellationToken::new();
+        let supervisor = Supervisor::start(
+            CHAIN_TASK_ID,
+            timeout,
+            signal,
+            cancellation.clone(),
+            GRACE_PERIOD,
+            move |exit_mode| sender.send(exit_mode).unwrap(),
+        );
+        (supervisor, cancellation, receiver)
+    }
+
+    #[test]
+    fn supervisor_exits_with_timeout_after_deadline() {
+        static SIGNAL: AtomicI32 = AtomicI32::new(0);
+        let (_supervisor, cancellation, receiver) = start(Some(Duration::from_millis(50)), &SIGNAL);
+
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(2)),
+            Ok(ExitMode::Timeout)
+        );
+        assert_eq!(cancellation.exit_mode(), Some(ExitMode::Timeout));
+    }
+
+    #[test]
+    fn supervisor_exits_with_aborted_after_signal() {
+        static SIGNAL: AtomicI32 = AtomicI32::new(0);
+        let (_supervisor, cancellation, receiver) = start(None, &SIGNAL);
+
+        SIGNAL.store(15, Ordering::SeqCst);
+
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(2)),
+            Ok(ExitMode::Aborted)
+        );
+        assert_eq!(cancellation.exit_mode(), Some(ExitMode::Aborted));
+    }
+
+    #[test]
+    fn supervisor_does_not_exit_when_cancelled_task_stops_in_time() {
+        static SIGNAL: AtomicI32 = AtomicI32::new(0);
+        let (supervisor, cancellation, receiver) = start(Some(Duration::from_millis(50)), &SIGNAL);
+
+        while !cancellation.is_cancelled() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        drop(supervisor);
+
+        assert!(receiver.recv_timeout(GRACE_PERIOD * 2).is_err());
+        assert_eq!(cancellation.exit_mode(), Some(ExitMode::Timeout));
+    }
+
+    #[test]
+    fn supervisor_stops_when_dropped() {
+        static SIGNAL: AtomicI32 = AtomicI32::new(0);
+        let (supervisor, cancellation, receiver) = start(Some(Duration::from_millis(200)), &SIGNAL);
+
+        drop(supervisor);
+
+        assert!(receiver.recv_timeout(Duration::from_millis(400)).is_err());
+        assert!(!cancellation.is_cancelled());
+    }
+}
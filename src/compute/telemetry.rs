@@ -0,0 +1,514 @@
+use crate::api::worker_api::{ComputeStage, WorkerApiClient};
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::events::EventRecord;
+use crate::compute::pre_compute_args::read_artifacts_recipient;
+use crate::compute::signer::TaskChallenge;
+use crate::compute::utils::age_utils::{self, Recipient};
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::utils::file_utils::http_client;
+use base64::{Engine as _, engine::general_purpose};
+use log::{error, info};
+use serde_json::{Value, json};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Name under which the pre-compute stage reports to OpenTelemetry collectors.
+const SERVICE_NAME: &str = "tee-worker-pre-compute";
+/// Time allowed to a remote sink to accept an event, so that reporting never stalls a run.
+const SINK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Backend receiving the lifecycle events of the runs, see [`crate::compute::events`].
+///
+/// Sinks must not fail the run: errors are logged and the event is dropped.
+pub trait TelemetrySink: Send + Sync {
+    /// Sends one event, `line` being the JSON line of `record` tagged with its schema version.
+    fn send(&self, record: &EventRecord, line: &str);
+}
+
+/// Telemetry backends which can be selected with `IEXEC_PRE_COMPUTE_TELEMETRY_SINKS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkKind {
+    /// Events logged at info level.
+    Log,
+    /// Events appended to `IEXEC_PRE_COMPUTE_EVENTS_FILE`.
+    File,
+    /// Events printed to stdout.
+    Stdout,
+    /// Events exported as OTLP/HTTP JSON log records to `IEXEC_PRE_COMPUTE_OTLP_ENDPOINT`.
+    Otlp,
+    /// Events posted to the worker API.
+    WorkerApi,
+}
+
+impl FromStr for SinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "log" => Ok(SinkKind::Log),
+            "file" => Ok(SinkKind::File),
+            "stdout" => Ok(SinkKind::Stdout),
+            "otlp" => Ok(SinkKind::Otlp),
+            "worker_api" => Ok(SinkKind::WorkerApi),
+            other => Err(format!("unknown telemetry sink {other}")),
+        }
+    }
+}
+
+/// Sink logging each event line at info level.
+pub struct LogSink;
+
+impl TelemetrySink for LogSink {
+    fn send(&self, _record: &EventRecord, line: &str) {
+        info!("Pre-compute event {line}");
+    }
+}
+
+/// Sink printing each event line to stdout.
+pub struct StdoutSink;
+
+impl TelemetrySink for StdoutSink {
+    fn send(&self, _record: &EventRecord, line: &str) {
+        println!("{line}");
+    }
+}
+
+/// Sink appending events as newline-delimited JSON (NDJSON) to a file.
+///
+/// When a recipient is set, each line of the file is instead the base64-encoded age
+/// encryption of the JSON line to that recipient, so that the file can still be appended to
+/// while only the recipient can read the URLs it contains.
+pub struct FileSink {
+    file: Mutex<File>,
+    recipient: Option<Recipient>,
+}
+
+impl FileSink {
+    /// Opens the file at `path` in append mode, creating it if needed.
+    pub fn open(path: &Path, recipient: Option<Recipient>) -> io::Result<Self> {
+        Ok(FileSink {
+            file: Mutex::new(OpenOptions::new().create(true).append(true).open(path)?),
+            recipient,
+        })
+    }
+}
+
+impl TelemetrySink for FileSink {
+    fn send(&self, record: &EventRecord, line: &str) {
+        let file_line = match &self.recipient {
+            Some(recipient) => {
+                let encrypted = age_utils::encrypt(recipient, line.as_bytes());
+                format!("{}\n", general_purpose::STANDARD.encode(encrypted))
+            }
+            None => format!("{line}\n"),
+        };
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = file.write_all(file_line.as_bytes()) {
+            error!("Failed to write event [event:{:?}]: {e}", record.event);
+        }
+    }
+}
+
+/// Sink exporting each event as an OpenTelemetry log record, with the OTLP/HTTP JSON
+/// encoding, to the `/v1/logs` path of a collector.
+pub struct OtlpSink {
+    url: String,
+}
+
+impl OtlpSink {
+    /// Creates a sink exporting to the collector at `endpoint`, such as
+    /// `http://otel-collector:4318`.
+    pub fn new(endpoint: &str) -> Self {
+        OtlpSink {
+            url: format!("{}/v1/logs", endpoint.trim_end_matches('/')),
+        }
+    }
+}
+
+/// Returns the OTLP JSON export request holding `record` as a single log record.
+fn otlp_logs_request(record: &EventRecord, line: &str) -> Value {
+    let event = serde_json::to_value(record.event).unwrap_or_default();
+    json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": SERVICE_NAME } },
+                    { "key": "service.version", "value": { "stringValue": env!("CARGO_PKG_VERSION") } }
+                ]
+            },
+            "scopeLogs": [{
+                "scope": { "name": SERVICE_NAME },
+                "logRecords": [{
+                    "timeUnixNano": (record.timestamp * 1_000_000).to_string(),
+                    "severityText": "INFO",
+                    "body": { "stringValue": line },
+                    "attributes": [
+                        { "key": "chainTaskId", "value": { "stringValue": record.chain_task_id } },
+                        { "key": "event", "value": { "stringValue": event["event"] } }
+                    ]
+                }]
+            }]
+        }]
+    })
+}
+
+impl TelemetrySink for OtlpSink {
+    fn send(&self, record: &EventRecord, line: &str) {
+        let result = http_client()
+            .post(&self.url)
+            .timeout(SINK_TIMEOUT)
+            .json(&otlp_logs_request(record, line))
+            .send();
+        match result {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => error!(
+                "OTLP collector rejected event [url:{}, status:{}]",
+                self.url,
+                resp.status()
+            ),
+            Err(e) => error!("Failed to export event [url:{}]: {e}", self.url),
+        }
+    }
+}
+
+/// Sink posting each event to the worker API, authenticated with the challenge of the task
+/// the event belongs to.
+pub struct WorkerApiSink {
+    client: WorkerApiClient,
+    /// Challenge of the task of the last event, most events of a process belonging to the
+    /// same task.
+    challenge: Mutex<Option<TaskChallenge>>,
+}
+
+impl WorkerApiSink {
+    pub fn new(client: WorkerApiClient) -> Self {
+        WorkerApiSink {
+            client,
+            challenge: Mutex::new(None),
+        }
+    }
+
+    fn authorization(&self, chain_task_id: &str) -> Result<String, ReplicateStatusCause> {
+        let mut challenge = self.challenge.lock().unwrap_or_else(|e| e.into_inner());
+        if challenge
+            .as_ref()
+            .is_some_and(|challenge| challenge.chain_task_id() != chain_task_id)
+        {
+            *challenge = None;
+        }
+        challenge
+            .get_or_insert_with(|| TaskChallenge::new(chain_task_id))
+            .get()
+    }
+}
+
+impl TelemetrySink for WorkerApiSink {
+    fn send(&self, record: &EventRecord, _line: &str) {
+        match self.authorization(record.chain_task_id) {
+            Ok(authorization) => {
+                let _ = self.client.send_event(
+                    ComputeStage::Pre,
+                    &authorization,
+                    record.chain_task_id,
+                    record,
+                );
+            }
+            Err(cause) => error!(
+                "Failed to sign event for worker API [chainTaskId:{}, cause:{cause:?}]",
+                record.chain_task_id
+            ),
+        }
+    }
+}
+
+/// Returns the sinks selected by `IEXEC_PRE_COMPUTE_TELEMETRY_SINKS`, a comma-separated list
+/// of `log`, `file`, `stdout`, `otlp` and `worker_api`.
+///
+/// For compatibility, the `file` sink is also selected when `IEXEC_PRE_COMPUTE_EVENTS_FILE` is
+/// set and the `stdout` sink when `IEXEC_PRE_COMPUTE_EVENTS_STDOUT` is "true". Unknown sinks
+/// and sinks which cannot be set up are logged and ignored.
+pub fn sinks_from_env() -> Vec<Box<dyn TelemetrySink>> {
+    let mut kinds: Vec<SinkKind> =
+        read_env(TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks)
+            .map(|value| {
+                value
+                    .split(',')
+                    .filter(|name| !name.trim().is_empty())
+                    .filter_map(|name| {
+                        name.parse::<SinkKind>()
+                            .inspect_err(|e| error!("Ignoring telemetry sink: {e}"))
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    let events_file = read_env(TeeSessionEnvironmentVariable::IexecPreComputeEventsFile);
+    if events_file.is_some() {
+        kinds.push(SinkKind::File);
+    }
+    let stdout = get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecPreComputeEventsStdout,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+    )
+    .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false));
+    if stdout {
+        kinds.push(SinkKind::Stdout);
+    }
+
+    let mut sinks: Vec<Box<dyn TelemetrySink>> = Vec::new();
+    let mut selected = Vec::new();
+    for kind in kinds {
+        if selected.contains(&kind) {
+            continue;
+        }
+        selected.push(kind);
+        let sink: Option<Box<dyn TelemetrySink>> = match kind {
+            SinkKind::Log => Some(Box::new(LogSink)),
+            SinkKind::Stdout => Some(Box::new(StdoutSink)),
+            SinkKind::File => file_sink(events_file.as_deref()),
+            SinkKind::Otlp => {
+                match read_env(TeeSessionEnvironmentVariable::IexecPreComputeOtlpEndpoint) {
+                    Some(endpoint) => Some(Box::new(OtlpSink::new(&endpoint))),
+                    None => {
+                        error!("OTLP telemetry sink disabled, no collector endpoint configured");
+                        None
+                    }
+                }
+            }
+            SinkKind::WorkerApi => Some(Box::new(WorkerApiSink::new(WorkerApiClient::from_env()))),
+        };
+        sinks.extend(sink);
+    }
+    sinks
+}
+
+/// Opens the events file sink at `path`, encrypted to the artifacts recipient if any.
+///
+/// An events file which cannot be opened is logged and ignored, as well as an events file
+/// which should be encrypted to an invalid recipient.
+fn file_sink(path: Option<&str>) -> Option<Box<dyn TelemetrySink>> {
+    let Some(path) = path else {
+        error!("File telemetry sink disabled, no events file configured");
+        return None;
+    };
+    let Ok(recipient) = read_artifacts_recipient() else {
+        error!("Events file disabled, its content cannot be encrypted [path:{path}]");
+        return None;
+    };
+    match FileSink::open(Path::new(path), recipient) {
+        Ok(sink) => Some(Box::new(sink)),
+        Err(e) => {
+            error!("Failed to open events file [path:{path}]: {e}");
+            None
+        }
+    }
+}
+
+/// Returns the value of `variable`, if set and not blank.
+fn read_env(variable: TeeSessionEnvironmentVariable) -> Option<String> {
+    get_env_var_or_error(variable, ReplicateStatusCause::PreComputeFailedUnknownIssue)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::events::Event;
+    use crate::compute::utils::age_utils::Identity;
+    use std::fs;
+    use tempfile::TempDir;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const EVENT: Event = Event::DecryptDone { size: 42 };
+
+    fn record() -> EventRecord<'static> {
+        EventRecord {
+            timestamp: 1_718_000_000_000,
+            chain_task_id: "0x123",
+            event: &EVENT,
+        }
+    }
+
+    #[test]
+    fn sink_kind_parses_known_names() {
+        assert_eq!(" Log ".parse::<SinkKind>(), Ok(SinkKind::Log));
+        assert_eq!("worker_api".parse::<SinkKind>(), Ok(SinkKind::WorkerApi));
+        assert_eq!("OTLP".parse::<SinkKind>(), Ok(SinkKind::Otlp));
+        assert!("kafka".parse::<SinkKind>().is_err());
+    }
+
+    // region FileSink
+    #[test]
+    fn file_sink_encrypts_each_line_to_recipient() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.ndjson");
+        let identity =
+            Identity::parse(include_str!("../tests_resources/dataset-age-identity.txt")).unwrap();
+        let recipient =
+            Recipient::parse("age14e7vfx33lmgd5e7wnqgrrq0npxhxzw3scq2makln9x39cf4t09vqffpks3")
+                .unwrap();
+        let sink = FileSink::open(&path, Some(recipient)).unwrap();
+
+        for url in ["https://host/input-1.txt", "https://host/input-2.txt"] {
+            sink.send(&record(), &format!("{{\"url\":\"{url}\"}}"));
+        }
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("https://host"));
+        let lines: Vec<Value> = content
+            .lines()
+            .map(|line| {
+                let encrypted = general_purpose::STANDARD.decode(line).unwrap();
+                serde_json::from_slice(&age_utils::decrypt(&identity, &encrypted).unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["url"], "https://host/input-2.txt");
+    }
+    // endregion
+
+    // region OtlpSink
+    #[test]
+    fn otlp_logs_request_holds_event_as_log_record() {
+        let request = otlp_logs_request(&record(), "{\"event\":\"decrypt_done\"}");
+
+        let log_record = &request["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(log_record["timeUnixNano"], "1718000000000000000");
+        assert_eq!(
+            log_record["body"]["stringValue"],
+            "{\"event\":\"decrypt_done\"}"
+        );
+        assert_eq!(
+            log_record["attributes"],
+            json!([
+                { "key": "chainTaskId", "value": { "stringValue": "0x123" } },
+                { "key": "event", "value": { "stringValue": "decrypt_done" } }
+            ])
+        );
+        assert_eq!(
+            request["resourceLogs"][0]["resource"]["attributes"][0]["value"]["stringValue"],
+            SERVICE_NAME
+        );
+    }
+
+    #[test]
+    fn otlp_sink_exports_to_collector() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/v1/logs"))
+                .and(body_partial_json(json!({
+                    "resourceLogs": [{ "scopeLogs": [{ "scope": { "name": SERVICE_NAME } }] }]
+                })))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(1)
+                .mount(&server)
+                .await;
+            server
+        });
+
+        OtlpSink::new(&format!("{}/", server.uri())).send(&record(), "{}");
+
+        rt.block_on(server.verify());
+    }
+    // endregion
+
+    // region sinks_from_env
+    fn sinks_with(vars: Vec<(TeeSessionEnvironmentVariable, Option<&str>)>) -> usize {
+        let mut vars: Vec<(String, Option<&str>)> = vars
+            .into_iter()
+            .map(|(variable, value)| (variable.name(), value))
+            .collect();
+        for variable in [
+            TeeSessionEnvironmentVariable::IexecPreComputeArtifactsRecipient,
+            TeeSessionEnvironmentVariable::IexecPreComputeEventsFile,
+            TeeSessionEnvironmentVariable::IexecPreComputeEventsStdout,
+            TeeSessionEnvironmentVariable::IexecPreComputeOtlpEndpoint,
+            TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks,
+        ] {
+            if !vars.iter().any(|(name, _)| *name == variable.name()) {
+                vars.push((variable.name(), None));
+            }
+        }
+        temp_env::with_vars(vars, || sinks_from_env().len())
+    }
+
+    #[test]
+    fn sinks_from_env_selects_legacy_file_and_stdout() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.ndjson");
+
+        let sinks = sinks_with(vec![
+            (
+                TeeSessionEnvironmentVariable::IexecPreComputeEventsFile,
+                Some(path.to_str().unwrap()),
+            ),
+            (
+                TeeSessionEnvironmentVariable::IexecPreComputeEventsStdout,
+                Some("TRUE"),
+            ),
+        ]);
+
+        assert_eq!(sinks, 2);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn sinks_from_env_selects_listed_sinks_once() {
+        let sinks = sinks_with(vec![
+            (
+                TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks,
+                Some("log, otlp,worker_api,log,,kafka"),
+            ),
+            (
+                TeeSessionEnvironmentVariable::IexecPreComputeOtlpEndpoint,
+                Some("http://otel-collector:4318"),
+            ),
+        ]);
+
+        assert_eq!(sinks, 3);
+    }
+
+    #[test]
+    fn sinks_from_env_ignores_sinks_without_configuration() {
+        let sinks = sinks_with(vec![(
+            TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks,
+            Some("file,otlp,stdout"),
+        )]);
+
+        assert_eq!(sinks, 1);
+    }
+
+    #[test]
+    fn sinks_from_env_disables_file_with_invalid_recipient() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("events.ndjson");
+
+        let sinks = sinks_with(vec![
+            (
+                TeeSessionEnvironmentVariable::IexecPreComputeEventsFile,
+                Some(path.to_str().unwrap()),
+            ),
+            (
+                TeeSessionEnvironmentVariable::IexecPreComputeArtifactsRecipient,
+                Some("not-a-recipient"),
+            ),
+        ]);
+
+        assert_eq!(sinks, 0);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn sinks_from_env_is_empty_by_default() {
+        assert_eq!(sinks_with(Vec::new()), 0);
+    }
+    // endregion
+}
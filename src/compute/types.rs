@@ -0,0 +1,308 @@
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::utils::ftp_utils;
+use cid::Cid;
+use log::error;
+use multiaddr::{Multiaddr, Protocol};
+use reqwest::Url;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// Identifier of an on-chain task: a `0x`-prefixed hexadecimal string.
+///
+/// # Example
+///
+/// ```
+/// let chain_task_id: TaskId = "0x123456789abcdef".parse()?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskId(String);
+
+impl FromStr for TaskId {
+    type Err = ReplicateStatusCause;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.strip_prefix("0x") {
+            Some(hex) if !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+                Ok(TaskId(value.to_string()))
+            }
+            _ => Err(ReplicateStatusCause::PreComputeInvalidTaskId),
+        }
+    }
+}
+
+impl Deref for TaskId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TaskId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// URL of an input file: an absolute `http`, `https` or `s3` URL, an `ftp` or `sftp` URL when
+/// the `ftp` or `sftp` feature is enabled, or an RFC 2397 `data:` URI carrying the content
+/// inline.
+///
+/// The original string is kept as is, since it is also used to name the downloaded file.
+///
+/// # Example
+///
+/// ```
+/// let url: InputUrl = "https://host/input.txt".parse()?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputUrl(String);
+
+impl FromStr for InputUrl {
+    type Err = ReplicateStatusCause;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match Url::parse(value) {
+            Ok(url)
+                if matches!(url.scheme(), "http" | "https" | "s3" | "data")
+                    || (url.scheme() == "ftp" && ftp_utils::is_ftp_supported())
+                    || (url.scheme() == "sftp" && ftp_utils::is_sftp_supported()) =>
+            {
+                Ok(InputUrl(value.to_string()))
+            }
+            _ => Err(ReplicateStatusCause::PreComputeInvalidInputFileUrl),
+        }
+    }
+}
+
+impl Deref for InputUrl {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for InputUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<&str> for InputUrl {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+/// Location of the dataset given as a multiaddr.
+///
+/// Content paths are downloaded through the gateways, while HTTP addresses are converted to
+/// the URL they designate. As multiaddrs have no path protocol, the segments following
+/// `/http` or `/https` are taken as the path of the URL.
+///
+/// # Example
+///
+/// ```
+/// let address: MultiAddress = "/dns4/host.net/tcp/8443/https/datasets/dataset.zip".parse()?;
+/// assert_eq!(
+///     address,
+///     MultiAddress::Http("https://host.net:8443/datasets/dataset.zip".to_string())
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiAddress {
+    /// An `/ipfs/<cid>` or `/ipns/<name>` content path, optionally followed by a file path.
+    Content(String),
+    /// The URL of an address such as `/dns4/<host>/tcp/<port>/https/<path>`.
+    Http(String),
+}
+
+impl FromStr for MultiAddress {
+    type Err = ReplicateStatusCause;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let unsupported = |reason: &str| {
+            error!("Unsupported dataset multiaddr [address:{value}]: {reason}");
+            Err(ReplicateStatusCause::PreComputeUnsupportedDatasetAddress)
+        };
+        let Some(segments) = value.strip_prefix('/') else {
+            return unsupported("a multiaddr starts with '/'");
+        };
+        let segments: Vec<&str> = segments.split('/').collect();
+        match segments.as_slice() {
+            ["ipfs", cid, ..] if Cid::try_from(*cid).is_ok() => {
+                return Ok(MultiAddress::Content(value.to_string()));
+            }
+            ["ipfs", ..] => return unsupported("invalid CID"),
+            ["ipns", name, ..] if !name.is_empty() => {
+                return Ok(MultiAddress::Content(value.to_string()));
+            }
+            _ => {}
+        }
+
+        let Some(http_index) = segments
+            .iter()
+            .position(|segment| matches!(*segment, "http" | "https"))
+        else {
+            return match Multiaddr::from_str(value) {
+                Ok(_) => unsupported(
+                    "only /ipfs, /ipns and HTTP addresses can be downloaded, append /http or /https to a server address",
+                ),
+                Err(e) => unsupported(&e.to_string()),
+            };
+        };
+        let address = match Multiaddr::from_str(&format!("/{}", segments[..=http_index].join("/")))
+        {
+            Ok(address) => address,
+            Err(e) => return unsupported(&e.to_string()),
+        };
+        let (mut host, mut port, mut is_tls) = (None, None, false);
+        for protocol in address.iter() {
+            match protocol {
+                Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name) => {
+                    host = Some(name.to_string())
+                }
+                Protocol::Ip4(ip) => host = Some(ip.to_string()),
+                Protocol::Ip6(ip) => host = Some(format!("[{ip}]")),
+                Protocol::Tcp(number) => port = Some(number),
+                Protocol::Tls | Protocol::Https => is_tls = true,
+                Protocol::Http => {}
+                other => return unsupported(&format!("unsupported protocol {other}")),
+            }
+        }
+        let Some(host) = host else {
+            return unsupported("missing /dns, /dns4, /dns6, /ip4 or /ip6 host");
+        };
+        let scheme = if is_tls { "https" } else { "http" };
+        let port = port.map(|port| format!(":{port}")).unwrap_or_default();
+        let path = segments[http_index + 1..].join("/");
+        Ok(MultiAddress::Http(format!(
+            "{scheme}://{host}{port}/{path}"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_id_accepts_hex_values() {
+        let task_id: TaskId = "0x123456789abcdef".parse().unwrap();
+        assert_eq!(&*task_id, "0x123456789abcdef");
+    }
+
+    #[test]
+    fn task_id_rejects_malformed_values() {
+        for value in ["", "0x", "123456789abcdef", "0xnot-hex"] {
+            assert_eq!(
+                value.parse::<TaskId>(),
+                Err(ReplicateStatusCause::PreComputeInvalidTaskId),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn input_url_keeps_original_value() {
+        let url: InputUrl = "https://input-1.txt".parse().unwrap();
+        assert_eq!(url, "https://input-1.txt");
+    }
+
+    #[test]
+    fn input_url_accepts_s3_urls() {
+        let url: InputUrl = "s3://bucket/input.txt".parse().unwrap();
+        assert_eq!(url, "s3://bucket/input.txt");
+    }
+
+    #[test]
+    fn input_url_accepts_data_uris() {
+        let url: InputUrl = "data:text/plain;base64,aUV4ZWM=".parse().unwrap();
+        assert_eq!(url, "data:text/plain;base64,aUV4ZWM=");
+    }
+
+    #[test]
+    fn input_url_accepts_ftp_urls_and_sftp_urls_when_supported() {
+        assert_eq!(
+            "ftp://host/inputs/input.csv".parse::<InputUrl>().is_ok(),
+            cfg!(feature = "ftp")
+        );
+        assert_eq!(
+            "sftp://user@host/input.csv".parse::<InputUrl>().is_ok(),
+            cfg!(feature = "sftp")
+        );
+    }
+
+    #[test]
+    fn multi_address_accepts_content_paths() {
+        for value in [
+            "/ipfs/QmUVhChbLFiuzNK1g2GsWyWEiad7SXPqARnWzGumgziwEp",
+            "/ipfs/bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy/dataset.zip",
+            "/ipns/dataset.eth",
+        ] {
+            assert_eq!(
+                value.parse::<MultiAddress>(),
+                Ok(MultiAddress::Content(value.to_string())),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn multi_address_converts_http_addresses_to_urls() {
+        for (value, url) in [
+            (
+                "/dns4/host.net/tcp/8443/https/datasets/dataset.zip",
+                "https://host.net:8443/datasets/dataset.zip",
+            ),
+            ("/dns/host.net/https", "https://host.net/"),
+            (
+                "/ip4/127.0.0.1/tcp/80/http/dataset.zip",
+                "http://127.0.0.1:80/dataset.zip",
+            ),
+            (
+                "/ip6/::1/tcp/443/tls/http/dataset",
+                "https://[::1]:443/dataset",
+            ),
+        ] {
+            assert_eq!(
+                value.parse::<MultiAddress>(),
+                Ok(MultiAddress::Http(url.to_string())),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn multi_address_rejects_unsupported_addresses() {
+        for value in [
+            "https://host/dataset.zip",
+            "/ipfs/not-a-cid",
+            "/ipns/",
+            "/dns4/host.net/tcp/443",
+            "/ip4/127.0.0.1/udp/443/quic-v1",
+            "/tcp/443/https/dataset.zip",
+            "/dns4/host.net/udp/443/https",
+            "/unknown/https",
+        ] {
+            assert_eq!(
+                value.parse::<MultiAddress>(),
+                Err(ReplicateStatusCause::PreComputeUnsupportedDatasetAddress),
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn input_url_rejects_malformed_values() {
+        for value in ["", "input.txt", "gopher://host/input.txt", "https://"] {
+            assert_eq!(
+                value.parse::<InputUrl>(),
+                Err(ReplicateStatusCause::PreComputeInvalidInputFileUrl),
+                "{value}"
+            );
+        }
+    }
+}
@@ -1,3 +1,17 @@
+pub mod age_utils;
+pub mod crypto_utils;
+pub mod data_uri_utils;
+pub mod dns_utils;
+pub mod enclave_utils;
 pub mod env_utils;
 pub mod file_utils;
+pub mod fs_utils;
+pub mod ftp_utils;
 pub mod hash_utils;
+pub mod log_utils;
+pub mod resource_utils;
+pub mod retry_utils;
+pub mod s3_utils;
+pub mod shamir_utils;
+pub mod time_utils;
+pub mod tls_utils;
@@ -0,0 +1,278 @@
+use age::{DecryptError, Decryptor, x25519};
+use std::io::Read;
+use std::iter;
+use thiserror::Error;
+
+/// Reason why an age file could not be decrypted, or a key could not be parsed.
+#[derive(Debug, PartialEq, Error)]
+pub enum AgeError {
+    #[error("invalid age identity")]
+    InvalidIdentity,
+    #[error("invalid age recipient")]
+    InvalidRecipient,
+    #[error("invalid age header")]
+    InvalidHeader,
+    #[error("no X25519 recipient of the file matches the identity")]
+    NoMatchingRecipient,
+    #[error("invalid age header MAC")]
+    InvalidHeaderMac,
+    #[error("invalid age payload")]
+    InvalidPayload,
+}
+
+impl From<DecryptError> for AgeError {
+    fn from(error: DecryptError) -> Self {
+        match error {
+            DecryptError::NoMatchingKeys => AgeError::NoMatchingRecipient,
+            DecryptError::InvalidMac => AgeError::InvalidHeaderMac,
+            _ => AgeError::InvalidHeader,
+        }
+    }
+}
+
+/// X25519 identity, the private key an age file is encrypted to.
+pub struct Identity(x25519::Identity);
+
+impl Identity {
+    /// Parses an identity in the format written by `age-keygen`.
+    ///
+    /// Blank lines and `#` comments are ignored, the first remaining line must be an
+    /// `AGE-SECRET-KEY-1...` Bech32 identity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let identity = Identity::parse(&fs::read_to_string("key.txt")?)?;
+    /// ```
+    pub fn parse(value: &str) -> Result<Self, AgeError> {
+        value
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .and_then(|line| line.parse().ok())
+            .map(Identity)
+            .ok_or(AgeError::InvalidIdentity)
+    }
+}
+
+/// X25519 recipient, the public key an age file is encrypted to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recipient(x25519::Recipient);
+
+impl Recipient {
+    /// Parses an `age1...` Bech32 recipient, as printed by `age-keygen -y`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let recipient = Recipient::parse("age14e7vfx33lmgd5e7wnqgrrq0npxhxzw3scq2makln9x39cf4t09vqffpks3")?;
+    /// ```
+    pub fn parse(value: &str) -> Result<Self, AgeError> {
+        value
+            .trim()
+            .parse()
+            .map(Recipient)
+            .map_err(|_| AgeError::InvalidRecipient)
+    }
+}
+
+/// Decrypts a binary age v1 file encrypted to an X25519 recipient.
+///
+/// The header MAC is verified once the file key is unwrapped, then the payload is decrypted
+/// chunk by chunk, each chunk being authenticated before its plaintext is used.
+///
+/// # Arguments
+///
+/// * `identity` - The identity the file is encrypted to.
+/// * `content` - The age file content. ASCII armor is not supported.
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` containing the plaintext.
+/// * `Err(AgeError)` if the file is malformed, not encrypted to `identity` or tampered with.
+///
+/// # Example
+///
+/// ```
+/// let plain = decrypt(&identity, &fs::read("dataset.age")?)?;
+/// ```
+pub fn decrypt(identity: &Identity, content: &[u8]) -> Result<Vec<u8>, AgeError> {
+    let mut reader =
+        Decryptor::new_buffered(content)?.decrypt(iter::once(&identity.0 as &dyn age::Identity))?;
+    let mut plain = Vec::new();
+    reader
+        .read_to_end(&mut plain)
+        .map_err(|_| AgeError::InvalidPayload)?;
+    Ok(plain)
+}
+
+/// Encrypts `content` into a binary age v1 file for an X25519 recipient.
+///
+/// The file can be decrypted with `age -d -i <identity file>` or with [`decrypt`].
+///
+/// # Arguments
+///
+/// * `recipient` - The recipient the file is encrypted to.
+/// * `content` - The plaintext.
+///
+/// # Example
+///
+/// ```
+/// let encrypted = encrypt(&recipient, b"{\"chainTaskId\":\"0x123\"}");
+/// ```
+pub fn encrypt(recipient: &Recipient, content: &[u8]) -> Vec<u8> {
+    age::encrypt(&recipient.0, content).expect("encryption to an X25519 recipient cannot fail")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATASET: &[u8] = include_bytes!("../../tests_resources/dataset.age");
+    const IDENTITY: &str = include_str!("../../tests_resources/dataset-age-identity.txt");
+    const RECIPIENT: &str = "age14e7vfx33lmgd5e7wnqgrrq0npxhxzw3scq2makln9x39cf4t09vqffpks3";
+    const PLAIN_DATASET: &[u8] = b"Some very useful data, encrypted with age.\n";
+    const OTHER_IDENTITY: &str =
+        "AGE-SECRET-KEY-1QQQSYQCYQ5RQWZQFPG9SCRGWPUGPZYSNZS23V9CCRYDPK8QARC0SWRYDWG";
+    const CHUNK_SIZE: usize = 64 * 1024;
+    const TAG_LENGTH: usize = 16;
+    const PAYLOAD_NONCE_LENGTH: usize = 16;
+
+    /// Length of the header of `encrypted`, up to and including the MAC line.
+    fn header_length(encrypted: &[u8]) -> usize {
+        let mac_start = encrypted
+            .windows(5)
+            .position(|window| window == b"\n--- ")
+            .unwrap();
+        mac_start
+            + encrypted[mac_start + 1..]
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .unwrap()
+            + 2
+    }
+
+    #[test]
+    fn decrypt_returns_plain_content() {
+        let identity = Identity::parse(IDENTITY).unwrap();
+        assert_eq!(decrypt(&identity, DATASET), Ok(PLAIN_DATASET.to_vec()));
+    }
+
+    #[test]
+    fn encrypt_round_trips_through_decrypt() {
+        let identity = Identity::parse(IDENTITY).unwrap();
+        let recipient = Recipient::parse(RECIPIENT).unwrap();
+        assert_eq!(recipient, Recipient(identity.0.to_public()));
+
+        for plain in [
+            vec![],
+            b"{\"chainTaskId\":\"0x123\"}".to_vec(),
+            vec![7u8; CHUNK_SIZE],
+            vec![7u8; 2 * CHUNK_SIZE + 1],
+        ] {
+            let encrypted = encrypt(&recipient, &plain);
+            assert!(encrypted.starts_with(b"age-encryption.org/v1\n-> X25519 "));
+            assert_eq!(decrypt(&identity, &encrypted), Ok(plain));
+        }
+
+        let other = Identity::parse(OTHER_IDENTITY).unwrap();
+        let encrypted = encrypt(&recipient, b"secret");
+        assert_eq!(
+            decrypt(&other, &encrypted),
+            Err(AgeError::NoMatchingRecipient)
+        );
+    }
+
+    #[test]
+    fn recipient_parse_rejects_identities() {
+        assert_eq!(
+            Recipient::parse(IDENTITY.lines().last().unwrap()),
+            Err(AgeError::InvalidRecipient)
+        );
+        assert_eq!(Recipient::parse("age1"), Err(AgeError::InvalidRecipient));
+    }
+
+    #[test]
+    fn decrypt_fails_with_other_identity() {
+        let other = Identity::parse(OTHER_IDENTITY).unwrap();
+        assert_eq!(decrypt(&other, DATASET), Err(AgeError::NoMatchingRecipient));
+    }
+
+    #[test]
+    fn identity_parse_rejects_invalid_identities() {
+        assert!(Identity::parse("# only a comment\n").is_err());
+        assert!(Identity::parse(&OTHER_IDENTITY.replace("SECRET-KEY", "PUBLIC-KEY")).is_err());
+        assert!(Identity::parse(RECIPIENT).is_err());
+
+        let mut corrupted = OTHER_IDENTITY.to_string();
+        corrupted.pop();
+        corrupted.push('Q');
+        assert!(Identity::parse(&corrupted).is_err());
+    }
+
+    #[test]
+    fn decrypt_fails_with_malformed_header() {
+        let identity = Identity::parse(IDENTITY).unwrap();
+        let header_length = header_length(DATASET);
+
+        for malformed in [
+            b"not an age file\n".to_vec(),
+            b"age-encryption.org/v2\n".to_vec(),
+            DATASET[..header_length - 2].to_vec(),
+            b"age-encryption.org/v1\n-> X25519\n\n".to_vec(),
+        ] {
+            assert_eq!(decrypt(&identity, &malformed), Err(AgeError::InvalidHeader));
+        }
+
+        let mut tampered_header = DATASET.to_vec();
+        let mac_start = DATASET
+            .windows(4)
+            .position(|window| window == b"--- ")
+            .unwrap();
+        tampered_header.splice(mac_start..mac_start, b"-> grease\n\n".iter().copied());
+        assert_eq!(
+            decrypt(&identity, &tampered_header),
+            Err(AgeError::InvalidHeaderMac)
+        );
+    }
+
+    #[test]
+    fn decrypt_fails_when_payload_is_tampered() {
+        let identity = Identity::parse(IDENTITY).unwrap();
+        let mut tampered_payload = DATASET.to_vec();
+        tampered_payload[header_length(DATASET) + PAYLOAD_NONCE_LENGTH] ^= 0x01;
+        assert_eq!(
+            decrypt(&identity, &tampered_payload),
+            Err(AgeError::InvalidPayload)
+        );
+    }
+
+    #[test]
+    fn decrypt_checks_chunk_count_and_last_chunk_flag() {
+        let identity = Identity::parse(IDENTITY).unwrap();
+        let plain: Vec<u8> = (0..CHUNK_SIZE * 2 + 10).map(|i| (i % 251) as u8).collect();
+        let encrypted = encrypt(&Recipient::parse(RECIPIENT).unwrap(), &plain);
+        let payload_start = header_length(&encrypted) + PAYLOAD_NONCE_LENGTH;
+        assert_eq!(
+            encrypted.len(),
+            payload_start + plain.len() + 3 * TAG_LENGTH
+        );
+        assert_eq!(decrypt(&identity, &encrypted), Ok(plain));
+
+        // Dropping the last chunk leaves a full chunk not flagged as the last one.
+        let without_last_chunk = &encrypted[..payload_start + 2 * (CHUNK_SIZE + TAG_LENGTH)];
+        // Cutting a chunk short breaks its tag.
+        let truncated_chunk = &encrypted[..encrypted.len() - 1];
+        let without_payload = &encrypted[..payload_start];
+        // Nothing may follow the chunk flagged as the last one.
+        let trailing_data = [encrypted.as_slice(), &[0u8; TAG_LENGTH + 1]].concat();
+        for invalid in [
+            without_last_chunk,
+            truncated_chunk,
+            without_payload,
+            &trailing_data,
+        ] {
+            assert_eq!(decrypt(&identity, invalid), Err(AgeError::InvalidPayload));
+        }
+    }
+}
@@ -0,0 +1,951 @@
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::utils::hash_utils::{clean_hex_prefix, hex_string_to_byte_array};
+use aes::{Aes128, Aes192, Aes256};
+use base64::{
+    Engine as _, alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use bytes::{Bytes, BytesMut};
+use cbc::{
+    Decryptor, Encryptor,
+    cipher::{
+        BlockCipher, BlockDecryptMut, BlockEncryptMut, KeyInit, KeyIvInit,
+        block_padding::{NoPadding, Pkcs7},
+        generic_array::GenericArray,
+    },
+};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use log::{error, info};
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+use std::fmt;
+use std::str::FromStr;
+use std::thread;
+use subtle::{ConstantTimeEq, ConstantTimeGreater, ConstantTimeLess, CtOption};
+use thiserror::Error;
+
+type Aes256CbcEnc = Encryptor<Aes256>;
+pub const AES_KEY_LENGTH: usize = 32;
+/// Key lengths accepted for decryption, selecting AES-128, AES-192 or AES-256.
+pub const AES_KEY_LENGTHS: [usize; 3] = [16, 24, 32];
+pub const AES_IV_LENGTH: usize = 16;
+const AES_BLOCK_SIZE: usize = 16;
+const PARALLEL_DECRYPTION_MIN_SIZE: usize = 4 * 1024 * 1024;
+pub const HMAC_SHA256_LENGTH: usize = 32;
+const DATASET_MAC_KEY_INFO: &[u8] = b"iexec-dataset-hmac-sha256";
+const BYTE_ORDER_MARK: char = '\u{feff}';
+const TOLERANT_BASE64_CONFIG: GeneralPurposeConfig =
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+const TOLERANT_STANDARD_BASE64: GeneralPurpose =
+    GeneralPurpose::new(&alphabet::STANDARD, TOLERANT_BASE64_CONFIG);
+const TOLERANT_URL_SAFE_BASE64: GeneralPurpose =
+    GeneralPurpose::new(&alphabet::URL_SAFE, TOLERANT_BASE64_CONFIG);
+
+/// Text encoding of a key.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum KeyEncoding {
+    /// Hexadecimal when the key only contains hex digits and has the length of a hex-encoded
+    /// key, base64 otherwise.
+    #[default]
+    Auto,
+    Base64,
+    /// Hexadecimal digits, optionally prefixed by `0x`.
+    Hex,
+}
+
+impl FromStr for KeyEncoding {
+    type Err = ReplicateStatusCause;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "auto" => Ok(KeyEncoding::Auto),
+            "base64" => Ok(KeyEncoding::Base64),
+            "hex" => Ok(KeyEncoding::Hex),
+            _ => {
+                error!("Unsupported key encoding [encoding:{value}]");
+                Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyEncoding::Auto => write!(f, "auto"),
+            KeyEncoding::Base64 => write!(f, "base64"),
+            KeyEncoding::Hex => write!(f, "hex"),
+        }
+    }
+}
+
+/// Reason why an encoded key could not be decoded.
+#[derive(Debug, PartialEq, Error)]
+pub enum KeyError {
+    #[error("key is not valid {0}")]
+    Malformed(KeyEncoding),
+    #[error("key has an unsupported length of {0} bytes")]
+    WrongLength(usize),
+}
+
+/// Decodes a base64 or hex-encoded AES key.
+///
+/// Keys are often copied from text files, so decoding is tolerant: a byte order mark and
+/// whitespace anywhere in the value (trailing newline, line wrapping) are ignored. Base64
+/// padding is optional and the URL-safe alphabet is accepted.
+///
+/// # Arguments
+///
+/// * `value` - The encoded key.
+/// * `encoding` - The encoding of `value`, [`KeyEncoding::Auto`] to detect it.
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` containing the raw key.
+/// * `Err(KeyError::Malformed)` if the value is not valid in the (detected) encoding.
+/// * `Err(KeyError::WrongLength)` if the decoded key length is not one of
+///   [`AES_KEY_LENGTHS`].
+///
+/// # Example
+///
+/// ```
+/// let key = decode_key(
+///     "\u{feff}ubA6H9emVPJT91/flYAmnKHC0phSV3cfuqsLxQfgow0=\n",
+///     KeyEncoding::Auto,
+/// )?;
+/// ```
+pub fn decode_key(value: &str, encoding: KeyEncoding) -> Result<Vec<u8>, KeyError> {
+    decode_key_material(value, encoding, &AES_KEY_LENGTHS)
+}
+
+/// Decodes a base64 or hex-encoded Shamir share of an AES key, as tolerantly as
+/// [`decode_key`].
+///
+/// A share holds one byte per key byte followed by its x coordinate, so it is one byte
+/// longer than the key.
+pub fn decode_key_share(value: &str, encoding: KeyEncoding) -> Result<Vec<u8>, KeyError> {
+    decode_key_material(value, encoding, &AES_KEY_LENGTHS.map(|length| length + 1))
+}
+
+fn decode_key_material(
+    value: &str,
+    encoding: KeyEncoding,
+    lengths: &[usize],
+) -> Result<Vec<u8>, KeyError> {
+    let value: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != BYTE_ORDER_MARK)
+        .collect();
+    let encoding = match encoding {
+        KeyEncoding::Auto if is_hex_key(&value, lengths) => KeyEncoding::Hex,
+        KeyEncoding::Auto => KeyEncoding::Base64,
+        encoding => encoding,
+    };
+    let key = match encoding {
+        KeyEncoding::Hex => decode_hex(&value),
+        _ => decode_base64(&value),
+    }
+    .ok_or(KeyError::Malformed(encoding))?;
+    if !lengths.contains(&key.len()) {
+        return Err(KeyError::WrongLength(key.len()));
+    }
+    Ok(key)
+}
+
+/// Returns whether `value` looks like a hex-encoded key: only hex digits, as many as needed
+/// to encode a key of one of the `lengths`.
+fn is_hex_key(value: &str, lengths: &[usize]) -> bool {
+    let digits = clean_hex_prefix(value);
+    lengths.contains(&(digits.len() / 2))
+        && digits.len().is_multiple_of(2)
+        && digits.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let digits = clean_hex_prefix(value);
+    (digits.len().is_multiple_of(2) && digits.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| hex_string_to_byte_array(digits))
+}
+
+fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let engine = if value.contains(['-', '_']) {
+        &TOLERANT_URL_SAFE_BASE64
+    } else {
+        &TOLERANT_STANDARD_BASE64
+    };
+    engine.decode(value).ok()
+}
+
+/// Decrypts an AES-CBC payload laid out as `IV || ciphertext` with PKCS7 padding.
+///
+/// The cipher is chosen from the key length: AES-128 for 16 bytes, AES-192 for 24 bytes and
+/// AES-256 for 32 bytes, so that datasets encrypted by older tooling can still be processed.
+///
+/// Decryption happens in place: when `encrypted_content` is the only handle on its buffer,
+/// the returned plaintext reuses the same allocation instead of copying it.
+/// Payloads larger than 4 MiB are split across [`decryption_threads`] threads.
+///
+/// # Arguments
+///
+/// * `key` - The raw 16, 24 or 32-byte AES key.
+/// * `encrypted_content` - Full encrypted payload, including the 16-byte IV prefix.
+///
+/// # Returns
+///
+/// * `Ok(Bytes)` containing the plaintext if decryption succeeds.
+/// * `Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)` if the key or payload
+///   length is invalid, or if the padding is incorrect.
+///
+/// # Example
+///
+/// ```
+/// let plain = decrypt_aes_cbc(&key, Bytes::from(encrypted))?;
+/// ```
+pub fn decrypt_aes_cbc(
+    key: &[u8],
+    encrypted_content: Bytes,
+) -> Result<Bytes, ReplicateStatusCause> {
+    let threads = if encrypted_content.len() < PARALLEL_DECRYPTION_MIN_SIZE {
+        1
+    } else {
+        decryption_threads()
+    };
+    decrypt_aes_cbc_with_threads(key, encrypted_content, threads)
+}
+
+/// Decrypts an AES-CBC payload using up to `threads` threads.
+///
+/// CBC decryption of a block only depends on the previous ciphertext block, so the
+/// ciphertext is cut into contiguous segments decrypted concurrently, each one using the
+/// last ciphertext block of the preceding segment as its IV. PKCS7 padding is checked in
+/// constant time once every segment is decrypted.
+///
+/// # Arguments
+///
+/// * `key` - The raw 16, 24 or 32-byte AES key.
+/// * `encrypted_content` - Full encrypted payload, including the 16-byte IV prefix.
+/// * `threads` - Maximum number of segments decrypted concurrently. `0` behaves like `1`.
+///
+/// # Returns
+///
+/// * `Ok(Bytes)` containing the plaintext if decryption succeeds.
+/// * `Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)` if the key or payload
+///   length is invalid, or if the padding is incorrect.
+pub fn decrypt_aes_cbc_with_threads(
+    key: &[u8],
+    encrypted_content: Bytes,
+    threads: usize,
+) -> Result<Bytes, ReplicateStatusCause> {
+    decrypt_in_place(key, encrypted_content, threads)
+        .map_err(|_| ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+}
+
+/// Reason why a payload could not be decrypted.
+///
+/// The reason is only used internally: every variant is reported as
+/// [`ReplicateStatusCause::PreComputeDatasetDecryptionFailed`] and nothing distinguishes a
+/// padding failure from a valid payload until the padding check, which runs in constant
+/// time, so that the enclave cannot be used as a padding oracle.
+#[derive(Debug, PartialEq)]
+enum DecryptionError {
+    KeyLength,
+    PayloadLength,
+    Padding,
+}
+
+fn decrypt_in_place(
+    key: &[u8],
+    encrypted_content: Bytes,
+    threads: usize,
+) -> Result<Bytes, DecryptionError> {
+    if !AES_KEY_LENGTHS.contains(&key.len()) {
+        return Err(DecryptionError::KeyLength);
+    }
+    if encrypted_content.len() < AES_IV_LENGTH {
+        return Err(DecryptionError::PayloadLength);
+    }
+
+    let mut buffer = BytesMut::from(encrypted_content);
+    let iv = buffer.split_to(AES_IV_LENGTH);
+
+    let block_count = buffer.len() / AES_BLOCK_SIZE;
+    if block_count == 0 || !buffer.len().is_multiple_of(AES_BLOCK_SIZE) {
+        return Err(DecryptionError::PayloadLength);
+    }
+    let segment_length = block_count.div_ceil(threads.clamp(1, block_count)) * AES_BLOCK_SIZE;
+
+    let segment_ivs: Vec<Vec<u8>> = (0..buffer.len())
+        .step_by(segment_length)
+        .map(|start| match start {
+            0 => iv.to_vec(),
+            _ => buffer[start - AES_BLOCK_SIZE..start].to_vec(),
+        })
+        .collect();
+    let segment_count = segment_ivs.len();
+    if segment_count > 1 {
+        info!("Decrypting dataset in parallel [segments:{segment_count}]");
+    }
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = buffer
+            .chunks_mut(segment_length)
+            .zip(segment_ivs.iter())
+            .map(|(segment, segment_iv)| {
+                scope.spawn(move || match key.len() {
+                    16 => decrypt_segment::<Aes128>(key, segment_iv, segment),
+                    24 => decrypt_segment::<Aes192>(key, segment_iv, segment),
+                    _ => decrypt_segment::<Aes256>(key, segment_iv, segment),
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().ok().flatten())
+            .collect::<Option<Vec<()>>>()
+    })
+    .ok_or(DecryptionError::KeyLength)?;
+
+    let padding = pkcs7_padding_length(&buffer[buffer.len() - AES_BLOCK_SIZE..])
+        .ok_or(DecryptionError::Padding)?;
+    buffer.truncate(buffer.len() - padding);
+    Ok(buffer.freeze())
+}
+
+/// Decrypts an AES-CBC payload laid out as `IV || ciphertext` as it is received.
+///
+/// Every complete block is decrypted as soon as it arrives, so that decryption overlaps with
+/// the download of the rest of the payload. The plaintext is only returned by
+/// [`CbcStreamDecryptor::finalize`], once the whole payload is received and its padding
+/// checked in constant time, like [`decrypt_aes_cbc`].
+///
+/// # Example
+///
+/// ```
+/// let mut decryptor = CbcStreamDecryptor::new(&key)?;
+/// for chunk in chunks {
+///     decryptor.update(chunk);
+/// }
+/// let plain = decryptor.finalize()?;
+/// ```
+pub struct CbcStreamDecryptor {
+    key: Vec<u8>,
+    cipher: Option<CbcDecryptor>,
+    pending: BytesMut,
+    plain: BytesMut,
+    received: usize,
+}
+
+/// CBC decryptor of the AES variant selected by the key length.
+enum CbcDecryptor {
+    Aes128(Decryptor<Aes128>),
+    Aes192(Decryptor<Aes192>),
+    Aes256(Decryptor<Aes256>),
+}
+
+impl CbcStreamDecryptor {
+    /// Creates a decryptor with the raw 16, 24 or 32-byte AES `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PreComputeDatasetDecryptionFailed` if the key length is invalid.
+    pub fn new(key: &[u8]) -> Result<Self, ReplicateStatusCause> {
+        if !AES_KEY_LENGTHS.contains(&key.len()) {
+            return Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed);
+        }
+        Ok(CbcStreamDecryptor {
+            key: key.to_vec(),
+            cipher: None,
+            pending: BytesMut::new(),
+            plain: BytesMut::new(),
+            received: 0,
+        })
+    }
+
+    /// Returns the number of payload bytes received so far.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Adds the next `chunk` of the payload, decrypting every block it completes.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.received += chunk.len();
+        self.pending.extend_from_slice(chunk);
+        if self.cipher.is_none() {
+            if self.pending.len() < AES_IV_LENGTH {
+                return;
+            }
+            let iv = self.pending.split_to(AES_IV_LENGTH);
+            self.cipher = match self.key.len() {
+                16 => Decryptor::new_from_slices(&self.key, &iv)
+                    .ok()
+                    .map(CbcDecryptor::Aes128),
+                24 => Decryptor::new_from_slices(&self.key, &iv)
+                    .ok()
+                    .map(CbcDecryptor::Aes192),
+                _ => Decryptor::new_from_slices(&self.key, &iv)
+                    .ok()
+                    .map(CbcDecryptor::Aes256),
+            };
+        }
+        let complete_length = self.pending.len() - self.pending.len() % AES_BLOCK_SIZE;
+        let mut blocks = self.pending.split_to(complete_length);
+        match &mut self.cipher {
+            Some(CbcDecryptor::Aes128(decryptor)) => decrypt_blocks(decryptor, &mut blocks),
+            Some(CbcDecryptor::Aes192(decryptor)) => decrypt_blocks(decryptor, &mut blocks),
+            Some(CbcDecryptor::Aes256(decryptor)) => decrypt_blocks(decryptor, &mut blocks),
+            None => return,
+        }
+        self.plain.extend_from_slice(&blocks);
+    }
+
+    /// Checks that the payload ends on a block boundary with a valid padding, and returns
+    /// the plaintext without its padding.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PreComputeDatasetDecryptionFailed` if the payload is truncated or the padding
+    /// is incorrect.
+    pub fn finalize(self) -> Result<Bytes, ReplicateStatusCause> {
+        let decryption_failed = ReplicateStatusCause::PreComputeDatasetDecryptionFailed;
+        if self.cipher.is_none() || !self.pending.is_empty() || self.plain.is_empty() {
+            return Err(decryption_failed);
+        }
+        let mut plain = self.plain;
+        let padding = pkcs7_padding_length(&plain[plain.len() - AES_BLOCK_SIZE..])
+            .ok_or(decryption_failed)?;
+        plain.truncate(plain.len() - padding);
+        Ok(plain.freeze())
+    }
+}
+
+/// Decrypts complete `blocks` in place with `decryptor`, which keeps the CBC chaining state
+/// for the next blocks.
+fn decrypt_blocks<C>(decryptor: &mut Decryptor<C>, blocks: &mut [u8])
+where
+    C: BlockCipher + BlockDecryptMut,
+{
+    for block in blocks.chunks_exact_mut(AES_BLOCK_SIZE) {
+        decryptor.decrypt_block_mut(GenericArray::from_mut_slice(block));
+    }
+}
+
+/// Decrypts one segment in place with the block cipher `C`, leaving the padding in place.
+fn decrypt_segment<C>(key: &[u8], iv: &[u8], segment: &mut [u8]) -> Option<()>
+where
+    C: BlockCipher + BlockDecryptMut + KeyInit,
+{
+    Decryptor::<C>::new_from_slices(key, iv)
+        .ok()?
+        .decrypt_padded_mut::<NoPadding>(segment)
+        .ok()
+        .map(|_| ())
+}
+
+/// Returns the length of the PKCS7 padding ending `last_block`.
+///
+/// Every byte of the block is checked whatever the padding value, without branching on the
+/// decrypted content, so that the time taken does not reveal why the padding is invalid.
+fn pkcs7_padding_length(last_block: &[u8]) -> Option<usize> {
+    let padding = last_block[last_block.len() - 1];
+    let mut is_valid = !padding.ct_eq(&0) & !padding.ct_gt(&(AES_BLOCK_SIZE as u8));
+    for (index, byte) in last_block.iter().rev().enumerate() {
+        let is_padding = (index as u8).ct_lt(&padding);
+        is_valid &= !is_padding | byte.ct_eq(&padding);
+    }
+    CtOption::new(padding as usize, is_valid).into()
+}
+
+/// Verifies and removes the trailer of an encrypt-then-MAC payload.
+///
+/// The payload is laid out as `IV || ciphertext || tag`, where `tag` is the HMAC-SHA256 of
+/// `IV || ciphertext`. The MAC key is derived from the dataset key with HKDF-SHA256 (no salt,
+/// info `iexec-dataset-hmac-sha256`), so that the same key is never used for both
+/// encryption and authentication. The tag is compared in constant time, before any
+/// decryption is attempted.
+///
+/// # Arguments
+///
+/// * `key` - The raw AES key of the dataset.
+/// * `payload` - The encrypted payload, including the HMAC-SHA256 trailer.
+///
+/// # Returns
+///
+/// * `Ok(Bytes)` containing `IV || ciphertext` if the tag is valid.
+/// * `Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)` if the payload is too
+///   short or the tag is invalid.
+///
+/// # Example
+///
+/// ```
+/// let encrypted = verify_hmac_sha256_trailer(&key, payload)?;
+/// let plain = decrypt_aes_cbc(&key, encrypted)?;
+/// ```
+pub fn verify_hmac_sha256_trailer(
+    key: &[u8],
+    mut payload: Bytes,
+) -> Result<Bytes, ReplicateStatusCause> {
+    let Some(content_length) = payload.len().checked_sub(HMAC_SHA256_LENGTH) else {
+        return Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed);
+    };
+    let mut mac = dataset_mac(key);
+    mac.update(&payload[..content_length]);
+    mac.verify_slice(&payload[content_length..])
+        .map_err(|_| ReplicateStatusCause::PreComputeDatasetDecryptionFailed)?;
+    payload.truncate(content_length);
+    Ok(payload)
+}
+
+/// Returns the HMAC-SHA256 instance keyed for the dataset encrypted with `key`.
+fn dataset_mac(key: &[u8]) -> Hmac<Sha256> {
+    let mut mac_key = [0u8; HMAC_SHA256_LENGTH];
+    Hkdf::<Sha256>::new(None, key)
+        .expand(DATASET_MAC_KEY_INFO, &mut mac_key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    <Hmac<Sha256> as Mac>::new_from_slice(&mac_key).expect("HMAC accepts keys of any length")
+}
+
+/// Encrypts `plain_content` with AES-256-CBC and PKCS7 padding.
+///
+/// The output is laid out as `IV || ciphertext`, the same layout [`decrypt_aes_cbc`]
+/// expects.
+///
+/// # Example
+///
+/// ```
+/// let (key, iv) = generate_aes256_key_and_iv(&mut OsRng);
+/// let encrypted = encrypt_aes256_cbc(&key, &iv, b"Some very useful data.");
+/// ```
+pub fn encrypt_aes256_cbc(
+    key: &[u8; AES_KEY_LENGTH],
+    iv: &[u8; AES_IV_LENGTH],
+    plain_content: &[u8],
+) -> Vec<u8> {
+    let mut encrypted_content =
+        Vec::with_capacity(AES_IV_LENGTH + plain_content.len() + AES_BLOCK_SIZE);
+    encrypted_content.extend_from_slice(iv);
+    encrypted_content.extend(
+        Aes256CbcEnc::new(key.into(), iv.into()).encrypt_padded_vec_mut::<Pkcs7>(plain_content),
+    );
+    encrypted_content
+}
+
+/// Cryptographically secure random number generator.
+///
+/// Code generating keys or nonces takes a `SecureRng` instead of using [`rand::rngs::OsRng`]
+/// directly, so that tests can use a seeded generator.
+pub trait SecureRng: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng> SecureRng for R {}
+
+/// Generates a random AES-256 key and IV from `rng`.
+///
+/// # Example
+///
+/// ```
+/// let (key, iv) = generate_aes256_key_and_iv(&mut OsRng);
+/// ```
+pub fn generate_aes256_key_and_iv(
+    rng: &mut dyn SecureRng,
+) -> ([u8; AES_KEY_LENGTH], [u8; AES_IV_LENGTH]) {
+    let mut key = [0u8; AES_KEY_LENGTH];
+    let mut iv = [0u8; AES_IV_LENGTH];
+    rng.fill_bytes(&mut key);
+    rng.fill_bytes(&mut iv);
+    (key, iv)
+}
+
+/// Returns the number of threads used to decrypt large payloads.
+///
+/// The value is read from the `IEXEC_DECRYPTION_THREADS` environment variable and defaults
+/// to the available parallelism of the enclave when unset or invalid.
+pub fn decryption_threads() -> usize {
+    get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecDecryptionThreads,
+        ReplicateStatusCause::PreComputeDatasetDecryptionFailed,
+    )
+    .ok()
+    .and_then(|threads| threads.parse::<usize>().ok())
+    .filter(|threads| *threads > 0)
+    .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::engine::general_purpose;
+    use rand::SeedableRng;
+    use rand::rngs::{OsRng, StdRng};
+
+    const KEY: [u8; AES_KEY_LENGTH] = [7u8; AES_KEY_LENGTH];
+    const IV: [u8; AES_IV_LENGTH] = [3u8; AES_IV_LENGTH];
+
+    fn encrypt(plain: &[u8]) -> Bytes {
+        let mut encrypted = IV.to_vec();
+        encrypted.extend(
+            Encryptor::<Aes256>::new(&KEY.into(), &IV.into())
+                .encrypt_padded_vec_mut::<Pkcs7>(plain),
+        );
+        Bytes::from(encrypted)
+    }
+
+    #[test]
+    fn encrypt_aes256_cbc_round_trips_with_random_key() {
+        let plain = b"Some very useful data.";
+        let (key, iv) = generate_aes256_key_and_iv(&mut OsRng);
+        let encrypted = encrypt_aes256_cbc(&key, &iv, plain);
+
+        assert_eq!(&encrypted[..AES_IV_LENGTH], &iv);
+        assert_eq!(
+            decrypt_aes_cbc(&key, Bytes::from(encrypted)),
+            Ok(Bytes::from_static(plain))
+        );
+        assert_ne!(generate_aes256_key_and_iv(&mut OsRng).0, key);
+    }
+
+    #[test]
+    fn generate_aes256_key_and_iv_is_deterministic_with_seeded_rng() {
+        let first = generate_aes256_key_and_iv(&mut StdRng::seed_from_u64(42));
+        let second = generate_aes256_key_and_iv(&mut StdRng::seed_from_u64(42));
+
+        assert_eq!(first, second);
+        assert_ne!(first.0[..AES_IV_LENGTH], first.1);
+    }
+
+    #[test]
+    fn decrypt_aes_cbc_returns_plain_content() {
+        let plain = b"Some very useful data.";
+        assert_eq!(
+            decrypt_aes_cbc(&KEY, encrypt(plain)),
+            Ok(Bytes::from_static(plain))
+        );
+    }
+
+    #[test]
+    fn decrypt_aes_cbc_selects_cipher_from_key_length() {
+        let plain = b"Some very useful data encrypted by older tooling.";
+        let encrypted_with = |encrypted: Vec<u8>| {
+            let mut payload = IV.to_vec();
+            payload.extend(encrypted);
+            Bytes::from(payload)
+        };
+        let aes128 = encrypted_with(
+            Encryptor::<Aes128>::new_from_slices(&KEY[..16], &IV)
+                .unwrap()
+                .encrypt_padded_vec_mut::<Pkcs7>(plain),
+        );
+        let aes192 = encrypted_with(
+            Encryptor::<Aes192>::new_from_slices(&KEY[..24], &IV)
+                .unwrap()
+                .encrypt_padded_vec_mut::<Pkcs7>(plain),
+        );
+
+        assert_eq!(
+            decrypt_aes_cbc(&KEY[..16], aes128.clone()),
+            Ok(Bytes::from_static(plain))
+        );
+        assert_eq!(
+            decrypt_aes_cbc_with_threads(&KEY[..24], aes192, 3),
+            Ok(Bytes::from_static(plain))
+        );
+        assert_eq!(
+            decrypt_aes_cbc(&KEY, aes128),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn decrypt_aes_cbc_with_threads_matches_single_threaded_result() {
+        let plain: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let encrypted = encrypt(&plain);
+        for threads in [1, 2, 3, 8, 1000] {
+            assert_eq!(
+                decrypt_aes_cbc_with_threads(&KEY, encrypted.clone(), threads),
+                Ok(Bytes::from(plain.clone())),
+                "Decryption with {threads} threads should match the plain content"
+            );
+        }
+    }
+
+    // region CbcStreamDecryptor
+    #[test]
+    fn cbc_stream_decryptor_matches_decrypt_aes_cbc_whatever_the_chunks() {
+        let plain: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let encrypted = encrypt(&plain);
+        for chunk_size in [1, 7, 16, 17, 4096, encrypted.len()] {
+            let mut decryptor = CbcStreamDecryptor::new(&KEY).unwrap();
+            for chunk in encrypted.chunks(chunk_size) {
+                decryptor.update(chunk);
+            }
+            assert_eq!(decryptor.received(), encrypted.len());
+            assert_eq!(
+                decryptor.finalize(),
+                Ok(Bytes::from(plain.clone())),
+                "Decryption with {chunk_size}-byte chunks should match the plain content"
+            );
+        }
+    }
+
+    #[test]
+    fn cbc_stream_decryptor_fails_with_truncated_payload_or_bad_padding() {
+        let encrypted = encrypt(b"Some very useful data.");
+        let decrypt = |payload: &[u8]| {
+            let mut decryptor = CbcStreamDecryptor::new(&KEY).unwrap();
+            decryptor.update(payload);
+            decryptor.finalize()
+        };
+
+        for payload in [
+            &encrypted[..encrypted.len() - 1],
+            &encrypted[..AES_IV_LENGTH],
+            &encrypted[..4],
+        ] {
+            assert_eq!(
+                decrypt(payload),
+                Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+            );
+        }
+        let mut tampered = encrypted.to_vec();
+        let last = tampered.len() - AES_BLOCK_SIZE - 1;
+        tampered[last] ^= 0x01;
+        assert_eq!(
+            decrypt(&tampered),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+        assert!(CbcStreamDecryptor::new(&[0u8; 20]).is_err());
+    }
+    // endregion
+
+    // region verify_hmac_sha256_trailer
+    fn with_hmac_trailer(payload: &[u8], key: &[u8]) -> Bytes {
+        let mut mac = dataset_mac(key);
+        mac.update(payload);
+        let mut authenticated = payload.to_vec();
+        authenticated.extend(mac.finalize().into_bytes());
+        Bytes::from(authenticated)
+    }
+
+    #[test]
+    fn verify_hmac_sha256_trailer_strips_valid_tag() {
+        let encrypted = encrypt(b"Some very useful data.");
+        let authenticated = with_hmac_trailer(&encrypted, &KEY);
+
+        assert_eq!(
+            verify_hmac_sha256_trailer(&KEY, authenticated),
+            Ok(encrypted)
+        );
+    }
+
+    #[test]
+    fn verify_hmac_sha256_trailer_rejects_tampered_payload() {
+        let encrypted = encrypt(b"Some very useful data.");
+        let mut tampered = with_hmac_trailer(&encrypted, &KEY).to_vec();
+        tampered[AES_IV_LENGTH] ^= 0x01;
+
+        assert_eq!(
+            verify_hmac_sha256_trailer(&KEY, Bytes::from(tampered)),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+        assert_eq!(
+            verify_hmac_sha256_trailer(&KEY[..16], with_hmac_trailer(&encrypted, &KEY)),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+        assert_eq!(
+            verify_hmac_sha256_trailer(&KEY, Bytes::from_static(&[0u8; 20])),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+    }
+    // endregion
+
+    // region padding oracle
+    #[test]
+    fn decrypt_in_place_distinguishes_failures_internally() {
+        let mut bad_padding = encrypt(&[1u8; 100]).to_vec();
+        let last = bad_padding.len() - 1;
+        bad_padding[last] ^= 0xff;
+
+        assert_eq!(
+            decrypt_in_place(&KEY[..20], encrypt(b"data"), 1),
+            Err(DecryptionError::KeyLength)
+        );
+        assert_eq!(
+            decrypt_in_place(&KEY, Bytes::copy_from_slice(&[0u8; 20]), 1),
+            Err(DecryptionError::PayloadLength)
+        );
+        assert_eq!(
+            decrypt_in_place(&KEY, Bytes::from(bad_padding.clone()), 2),
+            Err(DecryptionError::Padding)
+        );
+        assert_eq!(
+            decrypt_aes_cbc(&KEY, Bytes::from(bad_padding)),
+            decrypt_aes_cbc(&KEY[..20], encrypt(b"data"))
+        );
+    }
+
+    #[test]
+    fn pkcs7_padding_length_checks_every_padding_byte() {
+        let mut block = [0xaau8; AES_BLOCK_SIZE];
+        block[AES_BLOCK_SIZE - 3..].fill(3);
+        assert_eq!(pkcs7_padding_length(&block), Some(3));
+        assert_eq!(pkcs7_padding_length(&[16u8; AES_BLOCK_SIZE]), Some(16));
+
+        block[AES_BLOCK_SIZE - 3] = 2;
+        assert_eq!(pkcs7_padding_length(&block), None);
+        block[AES_BLOCK_SIZE - 1] = 0;
+        assert_eq!(pkcs7_padding_length(&block), None);
+        block[AES_BLOCK_SIZE - 1] = 17;
+        assert_eq!(pkcs7_padding_length(&block), None);
+    }
+    // endregion
+
+    #[test]
+    fn decrypt_aes_cbc_with_threads_fails_with_bad_padding() {
+        let mut encrypted = encrypt(&[1u8; 100]).to_vec();
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 0xff;
+        assert_eq!(
+            decrypt_aes_cbc_with_threads(&KEY, Bytes::from(encrypted), 4),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn decrypt_aes_cbc_with_threads_fails_with_truncated_block() {
+        let encrypted = encrypt(&[1u8; 100]);
+        assert_eq!(
+            decrypt_aes_cbc_with_threads(&KEY, encrypted.slice(..encrypted.len() - 1), 4),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn decryption_threads_reads_env_var() {
+        temp_env::with_var("IEXEC_DECRYPTION_THREADS", Some("3"), || {
+            assert_eq!(decryption_threads(), 3);
+        });
+        temp_env::with_var("IEXEC_DECRYPTION_THREADS", Some("0"), || {
+            assert!(decryption_threads() >= 1);
+        });
+    }
+
+    #[test]
+    fn decrypt_aes_cbc_fails_with_short_payload() {
+        assert_eq!(
+            decrypt_aes_cbc(&KEY, Bytes::copy_from_slice(&IV[..8])),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+    }
+
+    // region decode_key
+    #[test]
+    fn decode_key_ignores_bom_and_whitespace() {
+        let encoded = general_purpose::STANDARD.encode(KEY);
+        let wrapped = format!("\u{feff} {}\r\n{}\n", &encoded[..20], &encoded[20..]);
+        assert_eq!(decode_key(&wrapped, KeyEncoding::Auto), Ok(KEY.to_vec()));
+    }
+
+    #[test]
+    fn decode_key_accepts_url_safe_alphabet_without_padding() {
+        let key = [0xfbu8; AES_KEY_LENGTH];
+        let encoded = general_purpose::URL_SAFE_NO_PAD.encode(key);
+        assert!(encoded.contains('-') || encoded.contains('_'));
+        assert_eq!(decode_key(&encoded, KeyEncoding::Base64), Ok(key.to_vec()));
+    }
+
+    #[test]
+    fn decode_key_detects_hex_keys() {
+        let hex: String = KEY.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(decode_key(&hex, KeyEncoding::Auto), Ok(KEY.to_vec()));
+        assert_eq!(
+            decode_key(&format!("0x{}\n", hex.to_uppercase()), KeyEncoding::Hex),
+            Ok(KEY.to_vec())
+        );
+        assert_eq!(
+            decode_key(&hex, KeyEncoding::Base64),
+            Err(KeyError::WrongLength(48))
+        );
+    }
+
+    #[test]
+    fn decode_key_distinguishes_malformed_and_wrong_length() {
+        assert_eq!(
+            decode_key("not a key!", KeyEncoding::Auto),
+            Err(KeyError::Malformed(KeyEncoding::Base64))
+        );
+        assert_eq!(
+            decode_key("ab+c-d==", KeyEncoding::Auto),
+            Err(KeyError::Malformed(KeyEncoding::Base64))
+        );
+        assert_eq!(
+            decode_key("0xabz", KeyEncoding::Hex),
+            Err(KeyError::Malformed(KeyEncoding::Hex))
+        );
+        assert_eq!(
+            decode_key(
+                &general_purpose::STANDARD.encode(&KEY[..20]),
+                KeyEncoding::Auto
+            ),
+            Err(KeyError::WrongLength(20))
+        );
+        assert_eq!(
+            decode_key("", KeyEncoding::Auto),
+            Err(KeyError::WrongLength(0))
+        );
+    }
+
+    #[test]
+    fn decode_key_accepts_aes128_and_aes192_keys() {
+        for length in AES_KEY_LENGTHS {
+            let hex: String = KEY[..length]
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            assert_eq!(
+                decode_key(&hex, KeyEncoding::Auto),
+                Ok(KEY[..length].to_vec())
+            );
+            assert_eq!(
+                decode_key(
+                    &general_purpose::STANDARD.encode(&KEY[..length]),
+                    KeyEncoding::Base64
+                ),
+                Ok(KEY[..length].to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn decode_key_share_expects_one_more_byte_than_key() {
+        let share = [KEY.as_slice(), &[3]].concat();
+        let hex: String = share.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(decode_key_share(&hex, KeyEncoding::Auto), Ok(share.clone()));
+        assert_eq!(
+            decode_key_share(&general_purpose::STANDARD.encode(&share), KeyEncoding::Auto),
+            Ok(share)
+        );
+        assert_eq!(
+            decode_key_share(&general_purpose::STANDARD.encode(KEY), KeyEncoding::Auto),
+            Err(KeyError::WrongLength(AES_KEY_LENGTH))
+        );
+    }
+
+    #[test]
+    fn key_encoding_parses_supported_values() {
+        assert_eq!("HEX".parse(), Ok(KeyEncoding::Hex));
+        assert_eq!("base64".parse(), Ok(KeyEncoding::Base64));
+        assert_eq!(
+            "base58".parse::<KeyEncoding>(),
+            Err(ReplicateStatusCause::PreComputeFailedUnknownIssue)
+        );
+    }
+    // endregion
+
+    #[test]
+    fn decrypt_aes_cbc_fails_with_invalid_key_length() {
+        assert_eq!(
+            decrypt_aes_cbc(&KEY[..20], encrypt(b"data")),
+            Err(ReplicateStatusCause::PreComputeDatasetDecryptionFailed)
+        );
+    }
+}
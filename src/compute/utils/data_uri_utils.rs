@@ -0,0 +1,106 @@
+use crate::compute::utils::file_utils::DownloadError;
+use base64::{Engine as _, engine::general_purpose};
+use log::error;
+use percent_encoding::percent_decode_str;
+
+const DATA_URI_PREFIX: &str = "data:";
+const BASE64_PARAMETER: &str = "base64";
+
+/// Returns whether `url` is an RFC 2397 `data:` URI, which carries its content inline.
+pub fn is_data_uri(url: &str) -> bool {
+    url.get(..DATA_URI_PREFIX.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(DATA_URI_PREFIX))
+}
+
+/// Decodes the content carried by an RFC 2397 `data:` URI.
+///
+/// The URI is laid out as `data:[<media type>][;base64],<data>`. The data is percent-decoded,
+/// then base64-decoded when the `;base64` parameter is present. The media type is ignored.
+///
+/// # Returns
+///
+/// * `Ok(Vec<u8>)` with the decoded content.
+/// * `Err(DownloadError::InvalidUrl)` if `uri` is not a `data:` URI, has no `,` separator or
+///   carries invalid base64 data.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(decode_data_uri("data:;base64,aUV4ZWM=")?, b"iExec");
+/// assert_eq!(decode_data_uri("data:text/plain,iExec%20input")?, b"iExec input");
+/// ```
+pub fn decode_data_uri(uri: &str) -> Result<Vec<u8>, DownloadError> {
+    let Some((metadata, data)) = is_data_uri(uri)
+        .then(|| uri[DATA_URI_PREFIX.len()..].split_once(','))
+        .flatten()
+    else {
+        error!("Invalid data URI, expected data:[<media type>][;base64],<data>");
+        return Err(DownloadError::InvalidUrl);
+    };
+    let data: Vec<u8> = percent_decode_str(data).collect();
+    let is_base64 = metadata
+        .rsplit(';')
+        .next()
+        .is_some_and(|parameter| parameter.eq_ignore_ascii_case(BASE64_PARAMETER));
+    if !is_base64 {
+        return Ok(data);
+    }
+    let data: Vec<u8> = data
+        .into_iter()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    general_purpose::STANDARD.decode(data).map_err(|e| {
+        error!("Invalid base64 data in data URI: {e}");
+        DownloadError::InvalidUrl
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_data_uri_matches_scheme_case_insensitively() {
+        assert!(is_data_uri("data:,iExec"));
+        assert!(is_data_uri("DATA:text/plain,iExec"));
+        assert!(!is_data_uri("https://host/data:"));
+        assert!(!is_data_uri("dat"));
+    }
+
+    #[test]
+    fn decode_data_uri_decodes_percent_encoded_data() {
+        assert_eq!(
+            decode_data_uri("data:text/plain;charset=utf-8,iExec%20input%2C%0A").unwrap(),
+            b"iExec input,\n"
+        );
+        assert_eq!(decode_data_uri("data:,").unwrap(), b"");
+    }
+
+    #[test]
+    fn decode_data_uri_decodes_base64_data() {
+        assert_eq!(
+            decode_data_uri("data:application/json;base64,eyJrZXkiOiJ2YWx1ZSJ9").unwrap(),
+            br#"{"key":"value"}"#
+        );
+        assert_eq!(
+            decode_data_uri("data:;BASE64,aUV4%0AZWM=").unwrap(),
+            b"iExec"
+        );
+    }
+
+    #[test]
+    fn decode_data_uri_rejects_malformed_uris() {
+        assert_eq!(
+            decode_data_uri("data:text/plain"),
+            Err(DownloadError::InvalidUrl)
+        );
+        assert_eq!(
+            decode_data_uri("data:;base64,not base64!"),
+            Err(DownloadError::InvalidUrl)
+        );
+        assert_eq!(
+            decode_data_uri("https://host/input.txt"),
+            Err(DownloadError::InvalidUrl)
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use reqwest::blocking::ClientBuilder;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+static DNS_CACHE: OnceLock<Option<Arc<DnsCache>>> = OnceLock::new();
+
+/// Addresses of a host, with the time they were resolved at.
+struct CachedAddresses {
+    addresses: Vec<SocketAddr>,
+    resolved_at: Instant,
+}
+
+/// Cache of host resolutions, kept for a fixed time to live.
+///
+/// Resolving a host again on every retry multiplies the latency of a slow resolver and lets
+/// a flaky one fail a download midway through a task. The cache answers every lookup of a
+/// host made within `ttl` of its resolution, and failed resolutions are never cached.
+///
+/// # Example
+///
+/// ```
+/// let cache = DnsCache::new(Duration::from_secs(300));
+/// let addresses = cache.resolve("ipfs-gateway.v8-bellecour.iex.ec")?;
+/// ```
+pub struct DnsCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CachedAddresses>>,
+}
+
+impl DnsCache {
+    pub fn new(ttl: Duration) -> Self {
+        DnsCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the addresses of `host`, from the cache while they are fresh.
+    ///
+    /// Addresses are returned with port `0`, to be replaced by the port of the URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns the resolver error, or `io::ErrorKind::NotFound` if `host` has no address.
+    pub fn resolve(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        if let Some(cached) = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(host)
+            .filter(|cached| cached.resolved_at.elapsed() < self.ttl)
+        {
+            return Ok(cached.addresses.clone());
+        }
+        let addresses: Vec<SocketAddr> = (host, 0).to_socket_addrs()?.collect();
+        if addresses.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no address found for {host}"),
+            ));
+        }
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                host.to_string(),
+                CachedAddresses {
+                    addresses: addresses.clone(),
+                    resolved_at: Instant::now(),
+                },
+            );
+        Ok(addresses)
+    }
+
+    /// Resolves every distinct host of `hosts` concurrently, filling the cache.
+    ///
+    /// # Returns
+    ///
+    /// The hosts which could not be resolved, in their order in `hosts`, with the reason.
+    pub fn pre_resolve(&self, hosts: &[&str]) -> Vec<(String, io::Error)> {
+        let mut seen = HashSet::new();
+        let hosts: Vec<&str> = hosts
+            .iter()
+            .copied()
+            .filter(|host| seen.insert(*host))
+            .collect();
+        thread::scope(|scope| {
+            let handles: Vec<_> = hosts
+                .iter()
+                .map(|host| scope.spawn(move || self.resolve(host).err()))
+                .collect();
+            hosts
+                .iter()
+                .zip(handles)
+                .filter_map(|(host, handle)| {
+                    let error = handle
+                        .join()
+                        .unwrap_or_else(|_| Some(io::Error::other("resolver thread panicked")));
+                    error.map(|error| (host.to_string(), error))
+                })
+                .collect()
+        })
+    }
+}
+
+/// [`Resolve`] implementation answering the requests of the HTTP client from a [`DnsCache`].
+struct CachingResolver(Arc<DnsCache>);
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.0.clone();
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addresses = cache.resolve(&host)?;
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Returns the DNS cache shared by every request of the process, as configured by
+/// `IEXEC_DNS_CACHE_TTL_SECS`.
+///
+/// # Returns
+///
+/// * `Some(&DnsCache)` caching resolutions for the configured number of seconds.
+/// * `None` if the variable is unset, invalid or zero, hosts then being resolved by the
+///   default resolver on every connection.
+pub fn dns_cache() -> Option<&'static Arc<DnsCache>> {
+    DNS_CACHE
+        .get_or_init(|| {
+            get_env_var_or_error(
+                TeeSessionEnvironmentVariable::IexecDnsCacheTtlSecs,
+                ReplicateStatusCause::PreComputeFailedUnknownIssue,
+            )
+            .ok()
+            .and_then(|ttl| ttl.trim().parse::<u64>().ok())
+            .filter(|ttl| *ttl > 0)
+            .map(|ttl| Arc::new(DnsCache::new(Duration::from_secs(ttl))))
+        })
+        .as_ref()
+}
+
+/// Makes the client built by `builder` resolve hosts through [`dns_cache`], if enabled.
+pub fn with_dns_cache(builder: ClientBuilder) -> ClientBuilder {
+    match dns_cache() {
+        Some(cache) => builder.dns_resolver(Arc::new(CachingResolver(cache.clone()))),
+        None => builder,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_caches_addresses_within_ttl() {
+        let cache = DnsCache::new(Duration::from_secs(60));
+
+        let addresses = cache.resolve("localhost").unwrap();
+
+        assert!(!addresses.is_empty());
+        let cached = cache.entries.lock().unwrap()["localhost"].resolved_at;
+        assert_eq!(cache.resolve("localhost").unwrap(), addresses);
+        assert_eq!(
+            cache.entries.lock().unwrap()["localhost"].resolved_at,
+            cached
+        );
+    }
+
+    #[test]
+    fn resolve_refreshes_expired_addresses() {
+        let cache = DnsCache::new(Duration::ZERO);
+
+        cache.resolve("localhost").unwrap();
+        let first = cache.entries.lock().unwrap()["localhost"].resolved_at;
+        cache.resolve("localhost").unwrap();
+
+        assert!(cache.entries.lock().unwrap()["localhost"].resolved_at > first);
+    }
+
+    #[test]
+    fn pre_resolve_returns_every_failed_host_once() {
+        let cache = DnsCache::new(Duration::from_secs(60));
+
+        let failures = cache.pre_resolve(&[
+            "unknown-host.invalid",
+            "localhost",
+            "other-host.invalid",
+            "unknown-host.invalid",
+        ]);
+
+        let failed_hosts: Vec<&str> = failures.iter().map(|(host, _)| host.as_str()).collect();
+        assert_eq!(
+            failed_hosts,
+            vec!["unknown-host.invalid", "other-host.invalid"]
+        );
+        assert!(cache.entries.lock().unwrap().contains_key("localhost"));
+        assert!(
+            !cache
+                .entries
+                .lock()
+                .unwrap()
+                .contains_key("unknown-host.invalid")
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use log::warn;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Target info of the running enclave, exposed by Gramine's attestation pseudo-filesystem.
+pub const TARGET_INFO_PATH: &str = "/dev/attestation/my_target_info";
+/// MRENCLAVE is the first field of the SGX `TARGETINFO` structure.
+const MR_ENCLAVE_LENGTH: usize = 32;
+
+/// Returns the measurement (MRENCLAVE) of the running enclave.
+///
+/// # Returns
+///
+/// * `Some(String)` with the hex-encoded MRENCLAVE, prefixed with `0x`.
+/// * `None` when not running inside an enclave exposing [`TARGET_INFO_PATH`].
+pub fn mr_enclave() -> Option<String> {
+    read_mr_enclave(Path::new(TARGET_INFO_PATH))
+}
+
+/// Reads the MRENCLAVE from an SGX `TARGETINFO` structure stored at `path`.
+///
+/// # Example
+///
+/// ```
+/// let mr_enclave = read_mr_enclave(Path::new("/dev/attestation/my_target_info"));
+/// ```
+pub fn read_mr_enclave(path: &Path) -> Option<String> {
+    let target_info = match fs::read(path) {
+        Ok(target_info) => target_info,
+        Err(e) if e.kind() == ErrorKind::NotFound => return None,
+        Err(e) => {
+            warn!(
+                "Failed to read enclave target info [path:{}]: {e}",
+                path.display()
+            );
+            return None;
+        }
+    };
+    let mr_enclave = target_info.get(..MR_ENCLAVE_LENGTH).or_else(|| {
+        warn!("Enclave target info is too short [path:{}]", path.display());
+        None
+    })?;
+    let hex: String = mr_enclave
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Some(format!("0x{hex}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn read_mr_enclave_returns_first_32_bytes() {
+        let file = NamedTempFile::new().unwrap();
+        let mut target_info = vec![0xab; MR_ENCLAVE_LENGTH];
+        target_info.extend([0x00; 480]);
+        fs::write(file.path(), &target_info).unwrap();
+
+        assert_eq!(
+            read_mr_enclave(file.path()),
+            Some(format!("0x{}", "ab".repeat(MR_ENCLAVE_LENGTH)))
+        );
+    }
+
+    #[test]
+    fn read_mr_enclave_returns_none_outside_enclave() {
+        assert_eq!(
+            read_mr_enclave(Path::new("/some-missing-target-info")),
+            None
+        );
+    }
+
+    #[test]
+    fn read_mr_enclave_returns_none_when_truncated() {
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), [0xab; 8]).unwrap();
+
+        assert_eq!(read_mr_enclave(file.path()), None);
+    }
+}
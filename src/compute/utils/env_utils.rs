@@ -1,16 +1,147 @@
 use crate::compute::errors::ReplicateStatusCause;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::env;
+use std::sync::{PoisonError, RwLock};
 
+/// Variables read once per process, whose value cannot change from one service mode job to
+/// another and is always read from the process environment.
+pub const PROCESS_VARIABLES: [TeeSessionEnvironmentVariable; 9] = [
+    TeeSessionEnvironmentVariable::IexecDnsCacheTtlSecs,
+    TeeSessionEnvironmentVariable::IexecDownloadCompression,
+    TeeSessionEnvironmentVariable::IexecDownloadStallTimeoutMs,
+    TeeSessionEnvironmentVariable::IexecHostRequestIntervalMs,
+    TeeSessionEnvironmentVariable::IexecMaxRedirects,
+    TeeSessionEnvironmentVariable::IexecPreComputeEventsFile,
+    TeeSessionEnvironmentVariable::IexecPreComputeEventsStdout,
+    TeeSessionEnvironmentVariable::IexecPreComputeOtlpEndpoint,
+    TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks,
+];
+
+/// Variables of the service mode job being run, read instead of the process environment.
+static JOB_ENVIRONMENT: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+/// Type of the value expected in a session environment variable.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvVarType {
+    Bool,
+    Decimal,
+    Integer,
+    List,
+    Path,
+    String,
+    Url,
+}
+
+/// Whether a session environment variable must be set.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Requirement {
+    Required,
+    /// Required depending on other variables, as detailed in the description.
+    Conditional,
+    Optional,
+}
+
+/// Entry of the environment variables registry, printed by `--print-env-spec`.
+///
+/// Indexed variables, such as input file URLs, are named with a `<N>` placeholder.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVarSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub value_type: EnvVarType,
+    pub requirement: Requirement,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<&'static str>,
+    pub description: &'static str,
+}
+
+/// Placeholder of the index in the name of indexed variables.
+const INDEX_PLACEHOLDER: &str = "<N>";
+
+/// Session environment variables read by the pre-compute.
+///
+/// Every variable is described by [`spec()`](Self::spec), which is the reference for the
+/// session templates, see [`registry`].
 pub enum TeeSessionEnvironmentVariable {
+    IexecChecksumAlgorithm,
+    IexecDatasetAgeIdentity,
+    IexecDatasetAgeIdentityFile,
     IexecDatasetChecksum,
     IexecDatasetFilename,
+    IexecDatasetGatewayProbing,
+    IexecDatasetGateways,
+    IexecDatasetHmac,
     IexecDatasetKey,
+    IexecDatasetKeyEncoding,
+    IexecDatasetKeyShare(usize),
+    IexecDatasetKeyShareFile(usize),
+    IexecDatasetKeySharesThreshold,
+    IexecDatasetMaxExpansionRatio,
+    IexecDatasetMaxSize,
+    IexecDatasetReencryptionKeyPath,
+    IexecDatasetSize,
+    IexecDatasetSpeculativeDecryption,
+    IexecDatasetSwarmGateways,
+    IexecDatasetTlsPins,
     IexecDatasetUrl,
+    IexecDecryptionThreads,
+    IexecDnsCacheTtlSecs,
+    IexecDownloadCompression,
+    IexecDownloadMemoryThreshold,
+    IexecDownloadStallTimeoutMs,
+    IexecFtpPassword,
+    IexecFtpUsername,
+    IexecHostRequestIntervalMs,
+    IexecInputFileHeaders,
+    IexecInputFileHeadersPrefix(usize),
+    IexecInputFileOptionalPrefix(usize),
     IexecInputFileUrlPrefix(usize),
+    IexecInputFilesChecksumUrl,
+    IexecInputFilesConcurrency,
     IexecInputFilesNumber,
+    IexecMaxInputFiles,
+    IexecMaxRedirects,
+    IexecOutputFileGid,
+    IexecOutputFileMode,
+    IexecOutputFileUid,
+    IexecPreComputeArtifactsRecipient,
+    IexecPreComputeConfigSignature,
+    IexecPreComputeContinueOnError,
+    IexecPreComputeDiagnosticsDir,
+    IexecPreComputeEventsFile,
+    IexecPreComputeEventsStdout,
+    IexecPreComputeIn,
+    IexecPreComputeOtlpEndpoint,
     IexecPreComputeOut,
+    IexecPreComputeOutputTreeHash,
+    IexecPreComputePreflightCheck,
+    IexecPreComputeProgressReporting,
+    IexecPreComputeReportDir,
+    IexecPreComputeStatusDir,
+    IexecPreComputeTaskSubdir,
+    IexecPreComputeTelemetrySinks,
+    IexecPreComputeTimeoutSecs,
+    IexecRetryBudgetAttempts,
+    IexecRetryBudgetSecs,
+    IexecS3AccessKeyId,
+    IexecS3Endpoint,
+    IexecS3Region,
+    IexecS3SecretAccessKey,
+    IexecS3SessionToken,
+    IexecSftpHostKeySha256,
+    IexecSftpPrivateKeyPath,
     IexecTaskId,
+    IexecWorkerApiCompression,
+    IexecWorkerApiRateLimitWindowSecs,
+    IexecWorkerHealthPath,
+    IexecWorkerRegion,
+    IexecWriteBufferSize,
     IsDatasetRequired,
+    SignTeeChallengeAlgorithm,
     SignTeeChallengePrivateKey,
     SignWorkerAddress,
     WorkerHostEnvVar,
@@ -19,25 +150,205 @@ pub enum TeeSessionEnvironmentVariable {
 impl TeeSessionEnvironmentVariable {
     pub fn name(&self) -> String {
         match self {
+            TeeSessionEnvironmentVariable::IexecChecksumAlgorithm => {
+                "IEXEC_CHECKSUM_ALGORITHM".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetAgeIdentity => {
+                "IEXEC_DATASET_AGE_IDENTITY".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetAgeIdentityFile => {
+                "IEXEC_DATASET_AGE_IDENTITY_FILE".to_string()
+            }
             TeeSessionEnvironmentVariable::IexecDatasetChecksum => {
                 "IEXEC_DATASET_CHECKSUM".to_string()
             }
             TeeSessionEnvironmentVariable::IexecDatasetFilename => {
                 "IEXEC_DATASET_FILENAME".to_string()
             }
+            TeeSessionEnvironmentVariable::IexecDatasetGatewayProbing => {
+                "IEXEC_DATASET_GATEWAY_PROBING".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetGateways => {
+                "IEXEC_DATASET_GATEWAYS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetHmac => "IEXEC_DATASET_HMAC".to_string(),
             TeeSessionEnvironmentVariable::IexecDatasetKey => "IEXEC_DATASET_KEY".to_string(),
+            TeeSessionEnvironmentVariable::IexecDatasetKeyEncoding => {
+                "IEXEC_DATASET_KEY_ENCODING".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetKeyShare(index) => {
+                format!("IEXEC_DATASET_KEY_SHARE_{index}")
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetKeyShareFile(index) => {
+                format!("IEXEC_DATASET_KEY_SHARE_FILE_{index}")
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetKeySharesThreshold => {
+                "IEXEC_DATASET_KEY_SHARES_THRESHOLD".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetMaxExpansionRatio => {
+                "IEXEC_DATASET_MAX_EXPANSION_RATIO".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetMaxSize => {
+                "IEXEC_DATASET_MAX_SIZE".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetReencryptionKeyPath => {
+                "IEXEC_DATASET_REENCRYPTION_KEY_PATH".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetSize => "IEXEC_DATASET_SIZE".to_string(),
+            TeeSessionEnvironmentVariable::IexecDatasetSpeculativeDecryption => {
+                "IEXEC_DATASET_SPECULATIVE_DECRYPTION".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetSwarmGateways => {
+                "IEXEC_DATASET_SWARM_GATEWAYS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDatasetTlsPins => {
+                "IEXEC_DATASET_TLS_PINS".to_string()
+            }
             TeeSessionEnvironmentVariable::IexecDatasetUrl => "IEXEC_DATASET_URL".to_string(),
+            TeeSessionEnvironmentVariable::IexecDecryptionThreads => {
+                "IEXEC_DECRYPTION_THREADS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDnsCacheTtlSecs => {
+                "IEXEC_DNS_CACHE_TTL_SECS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDownloadCompression => {
+                "IEXEC_DOWNLOAD_COMPRESSION".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDownloadMemoryThreshold => {
+                "IEXEC_DOWNLOAD_MEMORY_THRESHOLD".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecDownloadStallTimeoutMs => {
+                "IEXEC_DOWNLOAD_STALL_TIMEOUT_MS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecFtpPassword => "IEXEC_FTP_PASSWORD".to_string(),
+            TeeSessionEnvironmentVariable::IexecFtpUsername => "IEXEC_FTP_USERNAME".to_string(),
+            TeeSessionEnvironmentVariable::IexecHostRequestIntervalMs => {
+                "IEXEC_HOST_REQUEST_INTERVAL_MS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecInputFileHeaders => {
+                "IEXEC_INPUT_FILE_HEADERS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecInputFileHeadersPrefix(index) => {
+                format!("IEXEC_INPUT_FILE_HEADERS_{index}")
+            }
+            TeeSessionEnvironmentVariable::IexecInputFileOptionalPrefix(index) => {
+                format!("IEXEC_INPUT_FILE_OPTIONAL_{index}")
+            }
             TeeSessionEnvironmentVariable::IexecInputFileUrlPrefix(index) => {
                 format!("IEXEC_INPUT_FILE_URL_{index}")
             }
+            TeeSessionEnvironmentVariable::IexecInputFilesChecksumUrl => {
+                "IEXEC_INPUT_FILES_CHECKSUM_URL".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecInputFilesConcurrency => {
+                "IEXEC_INPUT_FILES_CONCURRENCY".to_string()
+            }
             TeeSessionEnvironmentVariable::IexecInputFilesNumber => {
                 "IEXEC_INPUT_FILES_NUMBER".to_string()
             }
+            TeeSessionEnvironmentVariable::IexecMaxInputFiles => {
+                "IEXEC_MAX_INPUT_FILES".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecMaxRedirects => "IEXEC_MAX_REDIRECTS".to_string(),
+            TeeSessionEnvironmentVariable::IexecOutputFileGid => {
+                "IEXEC_OUTPUT_FILE_GID".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecOutputFileMode => {
+                "IEXEC_OUTPUT_FILE_MODE".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecOutputFileUid => {
+                "IEXEC_OUTPUT_FILE_UID".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeArtifactsRecipient => {
+                "IEXEC_PRE_COMPUTE_ARTIFACTS_RECIPIENT".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeConfigSignature => {
+                "IEXEC_PRE_COMPUTE_CONFIG_SIGNATURE".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeContinueOnError => {
+                "IEXEC_PRE_COMPUTE_CONTINUE_ON_ERROR".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeDiagnosticsDir => {
+                "IEXEC_PRE_COMPUTE_DIAGNOSTICS_DIR".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeEventsFile => {
+                "IEXEC_PRE_COMPUTE_EVENTS_FILE".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeEventsStdout => {
+                "IEXEC_PRE_COMPUTE_EVENTS_STDOUT".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeIn => "IEXEC_PRE_COMPUTE_IN".to_string(),
+            TeeSessionEnvironmentVariable::IexecPreComputeOtlpEndpoint => {
+                "IEXEC_PRE_COMPUTE_OTLP_ENDPOINT".to_string()
+            }
             TeeSessionEnvironmentVariable::IexecPreComputeOut => {
                 "IEXEC_PRE_COMPUTE_OUT".to_string()
             }
+            TeeSessionEnvironmentVariable::IexecPreComputeOutputTreeHash => {
+                "IEXEC_PRE_COMPUTE_OUTPUT_TREE_HASH".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputePreflightCheck => {
+                "IEXEC_PRE_COMPUTE_PREFLIGHT_CHECK".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeProgressReporting => {
+                "IEXEC_PRE_COMPUTE_PROGRESS_REPORTING".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeTimeoutSecs => {
+                "IEXEC_PRE_COMPUTE_TIMEOUT_SECS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeTaskSubdir => {
+                "IEXEC_PRE_COMPUTE_TASK_SUBDIR".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks => {
+                "IEXEC_PRE_COMPUTE_TELEMETRY_SINKS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeReportDir => {
+                "IEXEC_PRE_COMPUTE_REPORT_DIR".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecPreComputeStatusDir => {
+                "IEXEC_PRE_COMPUTE_STATUS_DIR".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecRetryBudgetAttempts => {
+                "IEXEC_RETRY_BUDGET_ATTEMPTS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecRetryBudgetSecs => {
+                "IEXEC_RETRY_BUDGET_SECS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecS3AccessKeyId => {
+                "IEXEC_S3_ACCESS_KEY_ID".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecS3Endpoint => "IEXEC_S3_ENDPOINT".to_string(),
+            TeeSessionEnvironmentVariable::IexecS3Region => "IEXEC_S3_REGION".to_string(),
+            TeeSessionEnvironmentVariable::IexecS3SecretAccessKey => {
+                "IEXEC_S3_SECRET_ACCESS_KEY".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecS3SessionToken => {
+                "IEXEC_S3_SESSION_TOKEN".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecSftpHostKeySha256 => {
+                "IEXEC_SFTP_HOST_KEY_SHA256".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecSftpPrivateKeyPath => {
+                "IEXEC_SFTP_PRIVATE_KEY_PATH".to_string()
+            }
             TeeSessionEnvironmentVariable::IexecTaskId => "IEXEC_TASK_ID".to_string(),
+            TeeSessionEnvironmentVariable::IexecWorkerApiCompression => {
+                "IEXEC_WORKER_API_COMPRESSION".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecWorkerApiRateLimitWindowSecs => {
+                "IEXEC_WORKER_API_RATE_LIMIT_WINDOW_SECS".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecWorkerHealthPath => {
+                "IEXEC_WORKER_HEALTH_PATH".to_string()
+            }
+            TeeSessionEnvironmentVariable::IexecWorkerRegion => "IEXEC_WORKER_REGION".to_string(),
+            TeeSessionEnvironmentVariable::IexecWriteBufferSize => {
+                "IEXEC_WRITE_BUFFER_SIZE".to_string()
+            }
             TeeSessionEnvironmentVariable::IsDatasetRequired => "IS_DATASET_REQUIRED".to_string(),
+            TeeSessionEnvironmentVariable::SignTeeChallengeAlgorithm => {
+                "SIGN_TEE_CHALLENGE_ALGORITHM".to_string()
+            }
             TeeSessionEnvironmentVariable::SignTeeChallengePrivateKey => {
                 "SIGN_TEE_CHALLENGE_PRIVATE_KEY".to_string()
             }
@@ -45,14 +356,792 @@ impl TeeSessionEnvironmentVariable {
             TeeSessionEnvironmentVariable::WorkerHostEnvVar => "WORKER_HOST_ENV_VAR".to_string(),
         }
     }
+
+    /// Describes the variable in the registry printed by `--print-env-spec`.
+    ///
+    /// # Returns
+    ///
+    /// * `EnvVarSpec` - The name, type, requirement, default and description of the variable
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let spec = TeeSessionEnvironmentVariable::IexecInputFileUrlPrefix(1).spec();
+    /// assert_eq!(spec.name, "IEXEC_INPUT_FILE_URL_<N>");
+    /// ```
+    pub fn spec(&self) -> EnvVarSpec {
+        let (value_type, requirement, default, description) = match self {
+            TeeSessionEnvironmentVariable::IexecChecksumAlgorithm => (
+                EnvVarType::String,
+                Requirement::Optional,
+                Some("sha256"),
+                "Algorithm of the dataset and input files checksums: sha256, keccak256, blake3 or cid.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetAgeIdentity => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "age identity decrypting an age-encrypted dataset, replacing the dataset key.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetAgeIdentityFile => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "File holding the age identity, read when IEXEC_DATASET_AGE_IDENTITY is unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetChecksum => (
+                EnvVarType::String,
+                Requirement::Conditional,
+                None,
+                "Checksum of the encrypted dataset. Required when IS_DATASET_REQUIRED is true.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetFilename => (
+                EnvVarType::String,
+                Requirement::Conditional,
+                None,
+                "Name of the decrypted dataset file. Required when IS_DATASET_REQUIRED is true.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetGatewayProbing => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether gateways are probed to download the dataset from the fastest one first.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetGateways => (
+                EnvVarType::List,
+                Requirement::Optional,
+                None,
+                "Comma-separated dataset gateways, each optionally tagged with `;region=<region>`. Defaults to public IPFS gateways.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetHmac => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether the encrypted dataset is authenticated by an HMAC.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetKey => (
+                EnvVarType::String,
+                Requirement::Conditional,
+                None,
+                "Key of the encrypted dataset. Required when IS_DATASET_REQUIRED is true, unless key shares or an age identity are set.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetKeyEncoding => (
+                EnvVarType::String,
+                Requirement::Optional,
+                Some("auto"),
+                "Encoding of the dataset key and key shares: auto, base64 or hex.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetKeyShare(_) => (
+                EnvVarType::String,
+                Requirement::Conditional,
+                None,
+                "Dataset key share <N>, starting at 1. Required up to IEXEC_DATASET_KEY_SHARES_THRESHOLD.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetKeyShareFile(_) => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "File holding dataset key share <N>, read when the share itself is unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetKeySharesThreshold => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Number of key shares combined into the dataset key.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetMaxExpansionRatio => (
+                EnvVarType::Decimal,
+                Requirement::Optional,
+                None,
+                "Maximum ratio between the decrypted and encrypted dataset sizes.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetMaxSize => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Maximum size of the encrypted dataset, in bytes.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetReencryptionKeyPath => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "File holding the key the decrypted dataset is re-encrypted with.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetSize => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Expected size of the encrypted dataset, in bytes.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetSpeculativeDecryption => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether the dataset is decrypted while it is downloaded.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetSwarmGateways => (
+                EnvVarType::List,
+                Requirement::Optional,
+                None,
+                "Comma-separated gateways serving `bzz://` datasets. Defaults to the public Swarm gateway.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetTlsPins => (
+                EnvVarType::List,
+                Requirement::Optional,
+                None,
+                "Comma-separated `sha256/<base64>` public key pins of the dataset hosts.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDatasetUrl => (
+                EnvVarType::Url,
+                Requirement::Conditional,
+                None,
+                "URL, `bzz://` Swarm reference or multiaddr (/ipfs, /ipns, /http, /https) of the encrypted dataset. Required when IS_DATASET_REQUIRED is true.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDecryptionThreads => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Number of threads decrypting the dataset. Defaults to the available parallelism.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDnsCacheTtlSecs => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Time during which host resolutions are cached, in seconds. Caching is disabled when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDownloadCompression => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether compressed transfers are requested for downloads.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDownloadMemoryThreshold => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                Some("67108864"),
+                "Size from which downloads are streamed to disk instead of kept in memory, in bytes.",
+            ),
+            TeeSessionEnvironmentVariable::IexecDownloadStallTimeoutMs => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Time after which a download receiving no data is restarted, in milliseconds. Disabled when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecFtpPassword => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Password of sftp:// downloads whose URL has no credentials.",
+            ),
+            TeeSessionEnvironmentVariable::IexecFtpUsername => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Username of sftp:// downloads whose URL has no credentials. ftp:// downloads always log in anonymously.",
+            ),
+            TeeSessionEnvironmentVariable::IexecHostRequestIntervalMs => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Minimum interval between two requests to the same host, in milliseconds. Disabled when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecInputFileHeaders => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "JSON object of the headers sent with the HTTP(S) requests of every input file, such as {\"Authorization\":\"Bearer <token>\"}.",
+            ),
+            TeeSessionEnvironmentVariable::IexecInputFileHeadersPrefix(_) => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "JSON object of the headers sent with the HTTP(S) requests of input file <N>, overriding those of IEXEC_INPUT_FILE_HEADERS.",
+            ),
+            TeeSessionEnvironmentVariable::IexecInputFileOptionalPrefix(_) => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether input file <N> is skipped instead of failing the task when it cannot be downloaded.",
+            ),
+            TeeSessionEnvironmentVariable::IexecInputFileUrlPrefix(_) => (
+                EnvVarType::Url,
+                Requirement::Conditional,
+                None,
+                "URL of input file <N>, starting at 1, ftp:// or sftp:// URL (with the ftp or sftp feature), or data: URI carrying its content inline. Required up to IEXEC_INPUT_FILES_NUMBER.",
+            ),
+            TeeSessionEnvironmentVariable::IexecInputFilesChecksumUrl => (
+                EnvVarType::Url,
+                Requirement::Optional,
+                None,
+                "URL of the checksums manifest of the input files.",
+            ),
+            TeeSessionEnvironmentVariable::IexecInputFilesConcurrency => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                Some("1"),
+                "Maximum number of input files downloaded at the same time.",
+            ),
+            TeeSessionEnvironmentVariable::IexecInputFilesNumber => (
+                EnvVarType::Integer,
+                Requirement::Required,
+                None,
+                "Number of input files.",
+            ),
+            TeeSessionEnvironmentVariable::IexecMaxInputFiles => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Maximum value of IEXEC_INPUT_FILES_NUMBER accepted by the worker, unlimited when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecMaxRedirects => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                Some("10"),
+                "Maximum number of redirects followed by a download.",
+            ),
+            TeeSessionEnvironmentVariable::IexecOutputFileGid => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Group owning the written files.",
+            ),
+            TeeSessionEnvironmentVariable::IexecOutputFileMode => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Octal mode of the written files, such as 0640.",
+            ),
+            TeeSessionEnvironmentVariable::IexecOutputFileUid => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "User owning the written files.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeArtifactsRecipient => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "age recipient the report and events file are encrypted to.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeConfigSignature => (
+                EnvVarType::String,
+                Requirement::Conditional,
+                None,
+                "Signature of the configuration manifest. Required when the build pins a configuration signer.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeContinueOnError => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether input files which fail to download are skipped instead of failing the task.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeDiagnosticsDir => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "Directory the diagnostic bundle is written to when a task fails.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeEventsFile => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "File the events are appended to, enabling the file telemetry sink.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeEventsStdout => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether events are printed on the standard output, enabling the stdout telemetry sink.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeIn => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "Directory of the input files staged by the worker.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeOtlpEndpoint => (
+                EnvVarType::Url,
+                Requirement::Optional,
+                None,
+                "OpenTelemetry collector endpoint of the otlp telemetry sink.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeOut => (
+                EnvVarType::Path,
+                Requirement::Required,
+                None,
+                "Directory the dataset and input files are written to.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeOutputTreeHash => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether a hash of the whole output directory tree is added to the report and the exit summary.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputePreflightCheck => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether the inputs are checked to be reachable before being downloaded.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeProgressReporting => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether input files progress is reported to the worker.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeReportDir => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "Directory the run report is written to, no report is written when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeStatusDir => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "Directory the status file is written to.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeTaskSubdir => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether files are written to a subdirectory named after the task.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks => (
+                EnvVarType::List,
+                Requirement::Optional,
+                Some("log"),
+                "Comma-separated telemetry sinks: log, file, stdout, otlp or worker_api.",
+            ),
+            TeeSessionEnvironmentVariable::IexecPreComputeTimeoutSecs => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Deadline of the task, in seconds. No deadline is enforced when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecRetryBudgetAttempts => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Maximum number of retries shared by all downloads.",
+            ),
+            TeeSessionEnvironmentVariable::IexecRetryBudgetSecs => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                None,
+                "Maximum time spent retrying downloads, in seconds.",
+            ),
+            TeeSessionEnvironmentVariable::IexecS3AccessKeyId => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Access key ID signing s3:// downloads, anonymous when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecS3Endpoint => (
+                EnvVarType::Url,
+                Requirement::Optional,
+                None,
+                "Endpoint of the S3-compatible storage serving s3:// URLs, addressed in path style. AWS S3 of IEXEC_S3_REGION when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecS3Region => (
+                EnvVarType::String,
+                Requirement::Optional,
+                Some("us-east-1"),
+                "Region of the buckets of s3:// URLs.",
+            ),
+            TeeSessionEnvironmentVariable::IexecS3SecretAccessKey => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Secret access key signing s3:// downloads with IEXEC_S3_ACCESS_KEY_ID.",
+            ),
+            TeeSessionEnvironmentVariable::IexecS3SessionToken => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Session token of temporary S3 credentials.",
+            ),
+            TeeSessionEnvironmentVariable::IexecSftpHostKeySha256 => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "SHA-256 fingerprint (`SHA256:<base64>`) of the host key of SFTP servers. `sftp://` URLs are refused when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecSftpPrivateKeyPath => (
+                EnvVarType::Path,
+                Requirement::Optional,
+                None,
+                "Private key logging in to SFTP servers, with IEXEC_FTP_PASSWORD as passphrase. Password login when unset.",
+            ),
+            TeeSessionEnvironmentVariable::IexecTaskId => (
+                EnvVarType::String,
+                Requirement::Required,
+                None,
+                "Chain task ID.",
+            ),
+            TeeSessionEnvironmentVariable::IexecWorkerApiCompression => (
+                EnvVarType::Bool,
+                Requirement::Optional,
+                Some("false"),
+                "Whether large worker API requests are gzip-compressed.",
+            ),
+            TeeSessionEnvironmentVariable::IexecWorkerApiRateLimitWindowSecs => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                Some("30"),
+                "Time during which a rate-limited exit report is re-sent, in seconds.",
+            ),
+            TeeSessionEnvironmentVariable::IexecWorkerHealthPath => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Worker API path probed before reporting, such as /health.",
+            ),
+            TeeSessionEnvironmentVariable::IexecWorkerRegion => (
+                EnvVarType::String,
+                Requirement::Optional,
+                None,
+                "Region of the worker, whose dataset gateways are tried first.",
+            ),
+            TeeSessionEnvironmentVariable::IexecWriteBufferSize => (
+                EnvVarType::Integer,
+                Requirement::Optional,
+                Some("1048576"),
+                "Size of the chunks written to disk, in bytes.",
+            ),
+            TeeSessionEnvironmentVariable::IsDatasetRequired => (
+                EnvVarType::Bool,
+                Requirement::Required,
+                None,
+                "Whether the task uses a dataset.",
+            ),
+            TeeSessionEnvironmentVariable::SignTeeChallengeAlgorithm => (
+                EnvVarType::String,
+                Requirement::Optional,
+                Some("secp256k1"),
+                "Algorithm of the enclave challenge signature: secp256k1, secp256r1 or ed25519.",
+            ),
+            TeeSessionEnvironmentVariable::SignTeeChallengePrivateKey => (
+                EnvVarType::String,
+                Requirement::Required,
+                None,
+                "Private key signing the enclave challenge.",
+            ),
+            TeeSessionEnvironmentVariable::SignWorkerAddress => (
+                EnvVarType::String,
+                Requirement::Required,
+                None,
+                "Address of the worker running the task.",
+            ),
+            TeeSessionEnvironmentVariable::WorkerHostEnvVar => (
+                EnvVarType::List,
+                Requirement::Optional,
+                Some("worker:13100"),
+                "Comma-separated worker API hosts, tried in order.",
+            ),
+        };
+        let name = match self {
+            TeeSessionEnvironmentVariable::IexecDatasetKeyShare(_)
+            | TeeSessionEnvironmentVariable::IexecDatasetKeyShareFile(_)
+            | TeeSessionEnvironmentVariable::IexecInputFileHeadersPrefix(_)
+            | TeeSessionEnvironmentVariable::IexecInputFileOptionalPrefix(_)
+            | TeeSessionEnvironmentVariable::IexecInputFileUrlPrefix(_) => {
+                let name = self.name();
+                format!(
+                    "{}{INDEX_PLACEHOLDER}",
+                    name.trim_end_matches(|c: char| c.is_ascii_digit())
+                )
+            }
+            _ => self.name(),
+        };
+        EnvVarSpec {
+            name,
+            value_type,
+            requirement,
+            default,
+            description,
+        }
+    }
+}
+
+/// Returns the specification of every session environment variable, sorted by name.
+///
+/// A variable added to [`TeeSessionEnvironmentVariable`] must be listed here so that
+/// session templates can be generated from `--print-env-spec`, which the tests check against
+/// the declaration of the enum.
+pub fn registry() -> Vec<EnvVarSpec> {
+    let mut specs: Vec<EnvVarSpec> = [
+        TeeSessionEnvironmentVariable::IexecChecksumAlgorithm,
+        TeeSessionEnvironmentVariable::IexecDatasetAgeIdentity,
+        TeeSessionEnvironmentVariable::IexecDatasetAgeIdentityFile,
+        TeeSessionEnvironmentVariable::IexecDatasetChecksum,
+        TeeSessionEnvironmentVariable::IexecDatasetFilename,
+        TeeSessionEnvironmentVariable::IexecDatasetGatewayProbing,
+        TeeSessionEnvironmentVariable::IexecDatasetGateways,
+        TeeSessionEnvironmentVariable::IexecDatasetHmac,
+        TeeSessionEnvironmentVariable::IexecDatasetKey,
+        TeeSessionEnvironmentVariable::IexecDatasetKeyEncoding,
+        TeeSessionEnvironmentVariable::IexecDatasetKeyShare(1),
+        TeeSessionEnvironmentVariable::IexecDatasetKeyShareFile(1),
+        TeeSessionEnvironmentVariable::IexecDatasetKeySharesThreshold,
+        TeeSessionEnvironmentVariable::IexecDatasetMaxExpansionRatio,
+        TeeSessionEnvironmentVariable::IexecDatasetMaxSize,
+        TeeSessionEnvironmentVariable::IexecDatasetReencryptionKeyPath,
+        TeeSessionEnvironmentVariable::IexecDatasetSize,
+        TeeSessionEnvironmentVariable::IexecDatasetSpeculativeDecryption,
+        TeeSessionEnvironmentVariable::IexecDatasetSwarmGateways,
+        TeeSessionEnvironmentVariable::IexecDatasetTlsPins,
+        TeeSessionEnvironmentVariable::IexecDatasetUrl,
+        TeeSessionEnvironmentVariable::IexecDecryptionThreads,
+        TeeSessionEnvironmentVariable::IexecDnsCacheTtlSecs,
+        TeeSessionEnvironmentVariable::IexecDownloadCompression,
+        TeeSessionEnvironmentVariable::IexecDownloadMemoryThreshold,
+        TeeSessionEnvironmentVariable::IexecDownloadStallTimeoutMs,
+        TeeSessionEnvironmentVariable::IexecFtpPassword,
+        TeeSessionEnvironmentVariable::IexecFtpUsername,
+        TeeSessionEnvironmentVariable::IexecHostRequestIntervalMs,
+        TeeSessionEnvironmentVariable::IexecInputFileHeaders,
+        TeeSessionEnvironmentVariable::IexecInputFileHeadersPrefix(1),
+        TeeSessionEnvironmentVariable::IexecInputFileOptionalPrefix(1),
+        TeeSessionEnvironmentVariable::IexecInputFileUrlPrefix(1),
+        TeeSessionEnvironmentVariable::IexecInputFilesChecksumUrl,
+        TeeSessionEnvironmentVariable::IexecInputFilesConcurrency,
+        TeeSessionEnvironmentVariable::IexecInputFilesNumber,
+        TeeSessionEnvironmentVariable::IexecMaxInputFiles,
+        TeeSessionEnvironmentVariable::IexecMaxRedirects,
+        TeeSessionEnvironmentVariable::IexecOutputFileGid,
+        TeeSessionEnvironmentVariable::IexecOutputFileMode,
+        TeeSessionEnvironmentVariable::IexecOutputFileUid,
+        TeeSessionEnvironmentVariable::IexecPreComputeArtifactsRecipient,
+        TeeSessionEnvironmentVariable::IexecPreComputeConfigSignature,
+        TeeSessionEnvironmentVariable::IexecPreComputeContinueOnError,
+        TeeSessionEnvironmentVariable::IexecPreComputeDiagnosticsDir,
+        TeeSessionEnvironmentVariable::IexecPreComputeEventsFile,
+        TeeSessionEnvironmentVariable::IexecPreComputeEventsStdout,
+        TeeSessionEnvironmentVariable::IexecPreComputeIn,
+        TeeSessionEnvironmentVariable::IexecPreComputeOtlpEndpoint,
+        TeeSessionEnvironmentVariable::IexecPreComputeOut,
+        TeeSessionEnvironmentVariable::IexecPreComputeOutputTreeHash,
+        TeeSessionEnvironmentVariable::IexecPreComputePreflightCheck,
+        TeeSessionEnvironmentVariable::IexecPreComputeProgressReporting,
+        TeeSessionEnvironmentVariable::IexecPreComputeReportDir,
+        TeeSessionEnvironmentVariable::IexecPreComputeStatusDir,
+        TeeSessionEnvironmentVariable::IexecPreComputeTaskSubdir,
+        TeeSessionEnvironmentVariable::IexecPreComputeTelemetrySinks,
+        TeeSessionEnvironmentVariable::IexecPreComputeTimeoutSecs,
+        TeeSessionEnvironmentVariable::IexecRetryBudgetAttempts,
+        TeeSessionEnvironmentVariable::IexecRetryBudgetSecs,
+        TeeSessionEnvironmentVariable::IexecS3AccessKeyId,
+        TeeSessionEnvironmentVariable::IexecS3Endpoint,
+        TeeSessionEnvironmentVariable::IexecS3Region,
+        TeeSessionEnvironmentVariable::IexecS3SecretAccessKey,
+        TeeSessionEnvironmentVariable::IexecS3SessionToken,
+        TeeSessionEnvironmentVariable::IexecSftpHostKeySha256,
+        TeeSessionEnvironmentVariable::IexecSftpPrivateKeyPath,
+        TeeSessionEnvironmentVariable::IexecTaskId,
+        TeeSessionEnvironmentVariable::IexecWorkerApiCompression,
+        TeeSessionEnvironmentVariable::IexecWorkerApiRateLimitWindowSecs,
+        TeeSessionEnvironmentVariable::IexecWorkerHealthPath,
+        TeeSessionEnvironmentVariable::IexecWorkerRegion,
+        TeeSessionEnvironmentVariable::IexecWriteBufferSize,
+        TeeSessionEnvironmentVariable::IsDatasetRequired,
+        TeeSessionEnvironmentVariable::SignTeeChallengeAlgorithm,
+        TeeSessionEnvironmentVariable::SignTeeChallengePrivateKey,
+        TeeSessionEnvironmentVariable::SignWorkerAddress,
+        TeeSessionEnvironmentVariable::WorkerHostEnvVar,
+    ]
+    .iter()
+    .map(TeeSessionEnvironmentVariable::spec)
+    .collect();
+    specs.sort_by(|a, b| a.name.cmp(&b.name));
+    specs
+}
+
+/// Returns whether `name` is the name of a session environment variable of the [`registry`],
+/// indexed variables matching with any index.
+pub fn is_session_variable(name: &str) -> bool {
+    registry()
+        .iter()
+        .any(|spec| match spec.name.split_once(INDEX_PLACEHOLDER) {
+            Some((prefix, suffix)) => name
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .is_some_and(|index| {
+                    !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+                }),
+            None => spec.name == name,
+        })
 }
 
+/// Returns the registry of session environment variables as pretty-printed JSON.
+pub fn env_spec_json() -> String {
+    serde_json::to_string_pretty(&registry()).unwrap_or_default()
+}
+
+/// Reads `env_var`, from the variables of the running job in service mode, see
+/// [`with_job_environment`], or from the process environment otherwise.
+///
+/// # Returns
+///
+/// * `Ok(String)` with the value of the variable.
+/// * `Err(status_cause_if_missing)` if the variable is unset or empty.
 pub fn get_env_var_or_error(
     env_var: TeeSessionEnvironmentVariable,
     status_cause_if_missing: ReplicateStatusCause,
 ) -> Result<String, ReplicateStatusCause> {
-    match env::var(env_var.name()) {
-        Ok(value) if !value.is_empty() => Ok(value),
+    let name = env_var.name();
+    let job_environment = JOB_ENVIRONMENT
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    let value = match job_environment.as_ref() {
+        Some(variables) if !is_process_variable(&name) => variables.get(&name).cloned(),
+        _ => env::var(&name).ok(),
+    };
+    match value {
+        Some(value) if !value.is_empty() => Ok(value),
         _ => Err(status_cause_if_missing),
     }
 }
+
+fn is_process_variable(name: &str) -> bool {
+    PROCESS_VARIABLES
+        .iter()
+        .any(|variable| variable.name() == name)
+}
+
+/// Returns the variables visible to [`get_env_var_or_error`]: those of the running job in
+/// service mode along with the [`PROCESS_VARIABLES`] of the process, or the whole process
+/// environment otherwise.
+pub fn session_variables() -> Vec<(String, String)> {
+    let job_environment = JOB_ENVIRONMENT
+        .read()
+        .unwrap_or_else(PoisonError::into_inner);
+    match job_environment.as_ref() {
+        Some(variables) => variables
+            .iter()
+            .filter(|(name, _)| !is_process_variable(name))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .chain(env::vars().filter(|(name, _)| is_process_variable(name)))
+            .collect(),
+        None => env::vars().collect(),
+    }
+}
+
+/// Runs `f` with session variables read from `variables` only, instead of the process
+/// environment, except for the [`PROCESS_VARIABLES`].
+///
+/// The process environment is never modified, so that threads still running from a previous
+/// job are not exposed to a concurrent change, and the variables of the service process do
+/// not leak into jobs which do not set them.
+///
+/// # Example
+///
+/// ```
+/// let exit_mode = with_job_environment(&job.env, app_runner::start);
+/// ```
+pub fn with_job_environment<T>(variables: &HashMap<String, String>, f: impl FnOnce() -> T) -> T {
+    struct Restore;
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            *JOB_ENVIRONMENT
+                .write()
+                .unwrap_or_else(PoisonError::into_inner) = None;
+        }
+    }
+
+    *JOB_ENVIRONMENT
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(variables.clone());
+    let _restore = Restore;
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_variables_are_those_of_running_job() {
+        temp_env::with_vars(
+            [
+                ("IEXEC_PRE_COMPUTE_OUT", Some("/host")),
+                ("IEXEC_MAX_REDIRECTS", Some("3")),
+            ],
+            || {
+                let job = HashMap::from([("IEXEC_TASK_ID".to_string(), "0x1".to_string())]);
+                let variables = with_job_environment(&job, session_variables);
+                let value = |name: &str| {
+                    variables
+                        .iter()
+                        .find(|(variable, _)| variable == name)
+                        .map(|(_, value)| value.as_str())
+                };
+                assert_eq!(value("IEXEC_TASK_ID"), Some("0x1"));
+                assert_eq!(value("IEXEC_MAX_REDIRECTS"), Some("3"));
+                assert_eq!(value("IEXEC_PRE_COMPUTE_OUT"), None);
+            },
+        );
+    }
+
+    #[test]
+    fn registry_is_sorted_by_name_without_duplicates() {
+        let names: Vec<String> = registry().into_iter().map(|spec| spec.name).collect();
+        let mut sorted = names.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(names, sorted);
+        assert!(names.contains(&"IEXEC_INPUT_FILE_URL_<N>".to_string()));
+    }
+
+    #[test]
+    fn registry_lists_every_variable() {
+        // Registered names are distinct, so the registry lists every variable if it has as
+        // many entries as the enum has variants.
+        let (_, declaration) = include_str!("env_utils.rs")
+            .split_once("pub enum TeeSessionEnvironmentVariable {")
+            .unwrap();
+        let (variants, _) = declaration.split_once('}').unwrap();
+        let variants = variants
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('#'))
+            .count();
+        assert_eq!(registry().len(), variants);
+    }
+
+    #[test]
+    fn spec_names_indexed_variables_with_placeholder() {
+        let spec = TeeSessionEnvironmentVariable::IexecDatasetKeyShareFile(12).spec();
+        assert_eq!(spec.name, "IEXEC_DATASET_KEY_SHARE_FILE_<N>");
+        assert_eq!(spec.value_type, EnvVarType::Path);
+        assert_eq!(
+            TeeSessionEnvironmentVariable::IexecTaskId.spec().name,
+            "IEXEC_TASK_ID"
+        );
+    }
+
+    #[test]
+    fn env_spec_json_describes_variables() {
+        let specs: Vec<serde_json::Value> = serde_json::from_str(&env_spec_json()).unwrap();
+        let spec = specs
+            .iter()
+            .find(|spec| spec["name"] == "IEXEC_INPUT_FILES_CONCURRENCY")
+            .unwrap();
+        assert_eq!(
+            spec,
+            &serde_json::json!({
+                "name": "IEXEC_INPUT_FILES_CONCURRENCY",
+                "type": "integer",
+                "requirement": "optional",
+                "default": "1",
+                "description": "Maximum number of input files downloaded at the same time.",
+            })
+        );
+        let spec = specs
+            .iter()
+            .find(|spec| spec["name"] == "IEXEC_TASK_ID")
+            .unwrap();
+        assert_eq!(spec["requirement"], "required");
+        assert!(spec.get("default").is_none());
+    }
+}
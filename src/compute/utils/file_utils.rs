@@ -1,12 +1,688 @@
+use crate::compute::cancellation::CancellationToken;
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::events::{self, Event};
+use crate::compute::utils::data_uri_utils;
+use crate::compute::utils::dns_utils::{DnsCache, dns_cache, with_dns_cache};
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::utils::fs_utils::{Filesystem, StdFilesystem};
+use crate::compute::utils::ftp_utils;
+use crate::compute::utils::retry_utils::RetryBudget;
+use crate::compute::utils::s3_utils;
+use crate::compute::utils::tls_utils;
+use crate::compute::verifier::ContentHasher;
+use bytes::{Bytes, BytesMut};
 use log::{error, info};
-use reqwest::blocking::get;
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use reqwest::header::{
+    CONTENT_LENGTH, CONTENT_TYPE, ETAG, HeaderName, HeaderValue, LAST_MODIFIED, RANGE, SERVER,
+};
+use reqwest::redirect::Policy;
+use reqwest::tls::TlsInfo;
+use reqwest::{Method, StatusCode, Url};
+use std::collections::{HashMap, HashSet};
+#[cfg(not(target_os = "linux"))]
 use std::fs;
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+#[cfg(target_os = "linux")]
+use std::os::fd::AsRawFd;
+#[cfg(unix)]
+use std::os::unix::fs::{PermissionsExt, fchown};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_WRITE_BUFFER_SIZE: usize = 1024 * 1024;
+const DEFAULT_DOWNLOAD_MEMORY_THRESHOLD: usize = 64 * 1024 * 1024;
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+/// Upper bound of the buffer allocated upfront from an advertised `Content-Length`.
+const MAX_PREALLOCATED_SIZE: usize = 1024 * 1024 * 1024;
+/// Number of times a stalled transfer is restarted before the download fails.
+const MAX_STALL_RETRIES: usize = 2;
+/// Number of chunks read ahead by the thread reading a response body.
+const STALL_CHANNEL_CAPACITY: usize = 16;
+/// Number of bytes requested by [`probe_url`].
+const PROBE_SIZE: u64 = 1024;
+/// Time allowed to establish a connection, for every request of the shared HTTP clients.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time allowed for the whole HEAD request of [`check_url`].
+const CHECK_URL_TIMEOUT: Duration = Duration::from_secs(30);
+/// Interval at which a transfer waiting for its next chunk checks for cancellation.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+static SAME_ORIGIN_HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+static S3_HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+static HOST_THROTTLE: OnceLock<HostThrottle> = OnceLock::new();
+static STALL_WATCHDOG: OnceLock<StallWatchdog> = OnceLock::new();
+
+/// Returns the HTTP client shared by downloads, pre-flight checks and worker API calls.
+///
+/// Building a client loads the TLS root certificates, which is slow inside an enclave, so the
+/// client is built once, either on first use or ahead of time by [`warm_http_client`].
+pub fn http_client() -> &'static Client {
+    HTTP_CLIENT.get_or_init(|| {
+        client_builder(is_compression_enabled())
+            .build()
+            .unwrap_or_else(|e| {
+                error!("Failed to build HTTP client, using defaults: {e}");
+                Client::new()
+            })
+    })
+}
+
+/// Returns the HTTP client of requests carrying extra headers, which only follows redirects
+/// within the origin of the requested URL, see [`same_origin_redirects`].
+fn same_origin_http_client() -> &'static Client {
+    SAME_ORIGIN_HTTP_CLIENT.get_or_init(|| {
+        client_builder(is_compression_enabled())
+            .redirect(same_origin_redirects())
+            .build()
+            .unwrap_or_else(|e| {
+                error!("Failed to build HTTP client, using defaults: {e}");
+                Client::new()
+            })
+    })
+}
+
+/// Returns the HTTP client of `s3://` requests, which never follows redirects.
+///
+/// Signed requests carry the `x-amz-security-token` and `x-amz-*` headers, which the HTTP
+/// client would keep on a redirect to another origin, and a signature that is only valid for
+/// the requested URL anyway.
+pub fn s3_http_client() -> &'static Client {
+    S3_HTTP_CLIENT.get_or_init(|| {
+        client_builder(is_compression_enabled())
+            .redirect(Policy::custom(|attempt| {
+                let error = format!("refused redirect of S3 request: {}", attempt.url());
+                attempt.error(error)
+            }))
+            .build()
+            .unwrap_or_else(|e| {
+                error!("Failed to build HTTP client, using defaults: {e}");
+                Client::builder()
+                    .redirect(Policy::none())
+                    .build()
+                    .unwrap_or_default()
+            })
+    })
+}
+
+/// Returns the redirect policy following up to [`max_redirects`] redirects within the origin
+/// of the requested URL, and failing the request on a redirect to another origin.
+///
+/// The HTTP client only drops well-known credentials headers such as `Authorization` on
+/// cross-host redirects, so custom headers such as `X-Api-Key` would otherwise be sent to
+/// whatever host the server redirects to.
+fn same_origin_redirects() -> Policy {
+    let max_redirects = max_redirects();
+    Policy::custom(move |attempt| {
+        let is_cross_origin = attempt
+            .previous()
+            .first()
+            .is_some_and(|first| first.origin() != attempt.url().origin());
+        if is_cross_origin {
+            let error = format!("refused redirect to another origin: {}", attempt.url());
+            attempt.error(error)
+        } else if attempt.previous().len() > max_redirects {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+/// Returns whether compressed transfers are negotiated, as configured by
+/// `IEXEC_DOWNLOAD_COMPRESSION` (defaults to "false").
+fn is_compression_enabled() -> bool {
+    get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecDownloadCompression,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+    )
+    .is_ok_and(|value| value.to_lowercase().parse::<bool>().unwrap_or(false))
+}
+
+/// Returns the maximum number of redirects followed by a request, as configured by
+/// `IEXEC_MAX_REDIRECTS` (defaults to 10).
+fn max_redirects() -> usize {
+    get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecMaxRedirects,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+    )
+    .ok()
+    .and_then(|value| value.trim().parse::<usize>().ok())
+    .unwrap_or(DEFAULT_MAX_REDIRECTS)
+}
+
+/// Creates the builder of the shared HTTP client.
+///
+/// Connections time out after [`CONNECT_TIMEOUT`], redirects are followed up to
+/// [`max_redirects`] times and the certificate of HTTPS servers is kept on responses, so that
+/// it can be checked against SPKI pins. Hosts are resolved through the DNS cache when
+/// `IEXEC_DNS_CACHE_TTL_SECS` is set. When `compression` is enabled, gzip, deflate and brotli
+/// encodings are advertised and responses are decoded transparently, so that checksums always
+/// apply to the decoded content. Otherwise content is received exactly as served.
+fn client_builder(compression: bool) -> ClientBuilder {
+    let builder = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .redirect(Policy::limited(max_redirects()))
+        .tls_info(true);
+    with_compression(with_dns_cache(builder), compression)
+}
+
+#[cfg(feature = "compression")]
+fn with_compression(builder: ClientBuilder, compression: bool) -> ClientBuilder {
+    builder
+        .gzip(compression)
+        .deflate(compression)
+        .brotli(compression)
+}
+
+#[cfg(not(feature = "compression"))]
+fn with_compression(builder: ClientBuilder, compression: bool) -> ClientBuilder {
+    if compression {
+        log::warn!("Compressed transfers requested but not supported by this build, ignoring");
+    }
+    builder
+}
+
+/// Builds the shared HTTP client ahead of time, and resolves the hosts of `urls` into the
+/// [`dns_cache`] when it is enabled.
+///
+/// Addresses are never pinned in the client itself: the shared client lives as long as the
+/// process, so pinned addresses would go stale in the `--serve` and `--watch` modes. Cached
+/// addresses expire with the cache time to live instead.
+///
+/// # Returns
+///
+/// The number of hosts resolved, `0` when the DNS cache is disabled.
+///
+/// # Example
+///
+/// ```
+/// let resolved = warm_http_client(&["https://ipfs-gateway.v8-bellecour.iex.ec"]);
+/// ```
+pub fn warm_http_client(urls: &[&str]) -> usize {
+    http_client();
+    pre_resolve_hosts(dns_cache().map(Arc::as_ref), urls)
+}
+
+/// Resolves the hosts of `urls` into `cache`, see [`warm_http_client`], and returns the number
+/// of hosts resolved.
+fn pre_resolve_hosts(cache: Option<&DnsCache>, urls: &[&str]) -> usize {
+    let Some(cache) = cache else {
+        return 0;
+    };
+    let hosts: Vec<String> = urls
+        .iter()
+        .filter_map(|url| Url::parse(url).ok()?.host_str().map(str::to_string))
+        .collect();
+    let hosts: Vec<&str> = hosts.iter().map(String::as_str).collect();
+    let failures = cache.pre_resolve(&hosts);
+    for (host, e) in &failures {
+        error!("Failed to resolve host [host:{host}]: {e}");
+    }
+    hosts.iter().collect::<HashSet<_>>().len() - failures.len()
+}
+
+/// Enforces a minimum interval between two requests to the same host.
+///
+/// Fanning out many requests to one server can trigger its anti-abuse protection, which
+/// then answers `403 Forbidden` in the middle of a task. The interval is read from
+/// `IEXEC_HOST_REQUEST_INTERVAL_MS` and throttling is disabled when it is unset or zero.
+pub struct HostThrottle {
+    interval: Duration,
+    next_slots: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostThrottle {
+    pub fn new(interval: Duration) -> Self {
+        HostThrottle {
+            interval,
+            next_slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let interval = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecHostRequestIntervalMs,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .and_then(|interval| interval.trim().parse::<u64>().ok())
+        .unwrap_or_default();
+        HostThrottle::new(Duration::from_millis(interval))
+    }
+
+    /// Blocks until a request to the host of `url` is allowed, and books the next slot.
+    pub fn wait(&self, url: &str) {
+        if self.interval.is_zero() {
+            return;
+        }
+        let Some(host) = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+        else {
+            return;
+        };
+        let now = Instant::now();
+        let slot = {
+            let mut next_slots = self.next_slots.lock().unwrap_or_else(|e| e.into_inner());
+            let slot = next_slots.get(&host).map_or(now, |next| (*next).max(now));
+            next_slots.insert(host.clone(), slot + self.interval);
+            slot
+        };
+        if slot > now {
+            info!(
+                "Waiting before next request to host [host:{host}, delayMs:{}]",
+                (slot - now).as_millis()
+            );
+            thread::sleep(slot - now);
+        }
+    }
+}
+
+fn throttle(url: &str) {
+    HOST_THROTTLE.get_or_init(HostThrottle::from_env).wait(url);
+}
+
+/// Aborts transfers which make no progress for too long.
+///
+/// A transfer can stall without its connection being closed, leaving the task hanging until
+/// the worker kills it. The response body is read by a dedicated thread and, when the
+/// watchdog is enabled, the downloading thread waits for each chunk at most the configured
+/// timeout, read from `IEXEC_DOWNLOAD_STALL_TIMEOUT_MS`. A stalled transfer is abandoned and
+/// restarted, up to [`MAX_STALL_RETRIES`] times and as long as the [`RetryBudget`] of the
+/// download allows it. The watchdog is disabled when the variable is unset or zero.
+pub struct StallWatchdog {
+    timeout: Duration,
+}
+
+impl StallWatchdog {
+    pub fn new(timeout: Duration) -> Self {
+        StallWatchdog { timeout }
+    }
+
+    pub fn from_env() -> Self {
+        let timeout = get_env_var_or_error(
+            TeeSessionEnvironmentVariable::IexecDownloadStallTimeoutMs,
+            ReplicateStatusCause::PreComputeFailedUnknownIssue,
+        )
+        .ok()
+        .and_then(|timeout| timeout.trim().parse::<u64>().ok())
+        .unwrap_or_default();
+        StallWatchdog::new(Duration::from_millis(timeout))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.timeout.is_zero()
+    }
+
+    /// Reads `body` until its end, passing every chunk to `on_chunk` in order.
+    ///
+    /// The body is read by a dedicated thread, so that the downloading thread can give up on
+    /// a stalled or cancelled transfer instead of staying blocked in the HTTP client. The
+    /// reading thread is then abandoned and stops on its next read.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` once the whole body has been read.
+    /// * `Err(DownloadError::Stalled)` if the watchdog is enabled and no byte was received for
+    ///   longer than its timeout.
+    /// * `Err(DownloadError::Cancelled)` if `cancellation` is cancelled before the end of the
+    ///   body.
+    /// * `Err(DownloadError)` if reading fails or if `on_chunk` returns an error.
+    pub fn read(
+        &self,
+        url: &str,
+        mut body: impl Read + Send + 'static,
+        cancellation: &CancellationToken,
+        on_chunk: &mut dyn FnMut(&[u8]) -> Result<(), DownloadError>,
+    ) -> Result<(), DownloadError> {
+        let (sender, receiver) = mpsc::sync_channel(STALL_CHANNEL_CAPACITY);
+        thread::spawn(move || {
+            let mut chunk = vec![0u8; DOWNLOAD_CHUNK_SIZE];
+            loop {
+                let result = match body.read(&mut chunk) {
+                    Ok(0) => return,
+                    Ok(read) => Ok(chunk[..read].to_vec()),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let is_err = result.is_err();
+                if sender.send(result).is_err() || is_err {
+                    return;
+                }
+            }
+        });
+        let mut last_chunk_at = Instant::now();
+        loop {
+            check_cancellation(url, cancellation)?;
+            match receiver.recv_timeout(CANCELLATION_POLL_INTERVAL) {
+                Ok(Ok(chunk)) => {
+                    last_chunk_at = Instant::now();
+                    on_chunk(&chunk)?
+                }
+                Ok(Err(e)) => return Err(read_error(url, e)),
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                Err(RecvTimeoutError::Timeout)
+                    if self.is_enabled() && last_chunk_at.elapsed() >= self.timeout =>
+                {
+                    error!(
+                        "Download stalled [url:{url}, timeoutMs:{}]",
+                        self.timeout.as_millis()
+                    );
+                    return Err(DownloadError::Stalled);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+        }
+    }
+}
+
+fn stall_watchdog() -> &'static StallWatchdog {
+    STALL_WATCHDOG.get_or_init(StallWatchdog::from_env)
+}
+
+/// Aborts the transfer of `url` if `cancellation` has been cancelled.
+fn check_cancellation(url: &str, cancellation: &CancellationToken) -> Result<(), DownloadError> {
+    if cancellation.is_cancelled() {
+        info!("Download cancelled [url:{url}]");
+        return Err(DownloadError::Cancelled);
+    }
+    Ok(())
+}
+
+/// Logs a failed read of the body of `url` and converts it into a [`DownloadError`].
+fn read_error(url: &str, e: io::Error) -> DownloadError {
+    error!("Failed to download from {url}: {e}");
+    DownloadError::Unreachable(e.to_string())
+}
+
+/// Returns the chunk size used when writing files to disk.
+///
+/// The value is read from the `IEXEC_WRITE_BUFFER_SIZE` environment variable (in bytes)
+/// and defaults to 1 MiB when unset or invalid. Large chunks keep the number of write
+/// syscalls low, which matters on Gramine's file system shim.
+pub fn write_buffer_size() -> usize {
+    get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecWriteBufferSize,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+    )
+    .ok()
+    .and_then(|size| size.parse::<usize>().ok())
+    .filter(|size| *size > 0)
+    .unwrap_or(DEFAULT_WRITE_BUFFER_SIZE)
+}
+
+/// Feeds the content of the file at `path` to `hasher`, in chunks of [`write_buffer_size`]
+/// bytes, so that large files never have to fit in memory.
+pub fn hash_file(
+    filesystem: &dyn Filesystem,
+    path: &Path,
+    hasher: &mut dyn ContentHasher,
+) -> io::Result<()> {
+    let size = filesystem.stat(path)?.size;
+    let mut chunk = vec![0; write_buffer_size().min(size as usize)];
+    let mut offset = 0;
+    while offset < size {
+        let len = chunk.len().min((size - offset) as usize);
+        filesystem.read_at(path, offset, &mut chunk[..len])?;
+        hasher.update(&chunk[..len]);
+        offset += len as u64;
+    }
+    Ok(())
+}
+
+/// Returns the size above which a downloaded input file is spilled to disk instead of being
+/// kept in memory until it completes.
+///
+/// The value is read from the `IEXEC_DOWNLOAD_MEMORY_THRESHOLD` environment variable (in
+/// bytes) and defaults to 64 MiB when unset or invalid. `0` spills every download.
+pub fn download_memory_threshold() -> usize {
+    get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecDownloadMemoryThreshold,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+    )
+    .ok()
+    .and_then(|threshold| threshold.parse::<usize>().ok())
+    .unwrap_or(DEFAULT_DOWNLOAD_MEMORY_THRESHOLD)
+}
+
+/// Creates (or truncates) a file and wraps it in a [`BufWriter`] sized with [`write_buffer_size`].
+///
+/// This is the writer to use for streaming writes, where content arrives in many small pieces.
+///
+/// A symbolic link at `file_path` is refused instead of being followed, see
+/// [`open_no_follow`].
+///
+/// # Example
+///
+/// ```
+/// let mut writer = create_buffered_file(Path::new("/tmp/test.txt"))?;
+/// writer.write_all(b"Hello, world!")?;
+/// writer.flush()?;
+/// ```
+pub fn create_buffered_file(file_path: &Path) -> io::Result<BufWriter<File>> {
+    open_no_follow(
+        file_path,
+        OpenOptions::new().write(true).create(true).truncate(true),
+    )
+    .map(|file| BufWriter::with_capacity(write_buffer_size(), file))
+}
+
+/// Reserves `len` bytes of disk space for `file` before it is written.
+///
+/// Running out of space is then reported before any byte is written, instead of leaving a
+/// half-written file behind. File systems which do not support pre-allocation are written
+/// without it.
+///
+/// # Returns
+///
+/// * `Ok(())` if the space is reserved or pre-allocation is not supported.
+/// * `Err(io::Error)` of kind [`io::ErrorKind::StorageFull`] if the disk is full.
+#[cfg(target_os = "linux")]
+fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    if len == 0 {
+        return Ok(());
+    }
+    let Ok(len) = libc::off_t::try_from(len) else {
+        return Ok(());
+    };
+    // SAFETY: the file descriptor is owned by `file`, which outlives the call.
+    match unsafe { libc::posix_fallocate(file.as_raw_fd(), 0, len) } {
+        0 => Ok(()),
+        libc::ENOSPC => {
+            error!("Not enough disk space to pre-allocate file [size:{len}]");
+            Err(io::Error::from_raw_os_error(libc::ENOSPC))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Pre-allocation is only implemented on Linux, files are written without it elsewhere.
+#[cfg(not(target_os = "linux"))]
+fn preallocate(_file: &File, _len: u64) -> io::Result<()> {
+    Ok(())
+}
+
+/// Reserves `len` bytes of disk space for `file` like [`preallocate`], without changing its
+/// size, so that content can then be appended to it.
+#[cfg(target_os = "linux")]
+pub fn preallocate_for_append(file: &File, len: u64) -> io::Result<()> {
+    if len == 0 {
+        return Ok(());
+    }
+    let Ok(len) = libc::off_t::try_from(len) else {
+        return Ok(());
+    };
+    // SAFETY: the file descriptor is owned by `file`, which outlives the call.
+    if unsafe { libc::fallocate(file.as_raw_fd(), libc::FALLOC_FL_KEEP_SIZE, 0, len) } == 0 {
+        return Ok(());
+    }
+    match io::Error::last_os_error().raw_os_error() {
+        Some(libc::ENOSPC) => {
+            error!("Not enough disk space to pre-allocate file [size:{len}]");
+            Err(io::Error::from_raw_os_error(libc::ENOSPC))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Pre-allocation is only implemented on Linux, files are written without it elsewhere.
+#[cfg(not(target_os = "linux"))]
+pub fn preallocate_for_append(_file: &File, _len: u64) -> io::Result<()> {
+    Ok(())
+}
+
+/// Opens `file_path` with `options`, refusing to follow a symbolic link at `file_path`.
+///
+/// The output directory is controlled by the host, so a symbolic link at `file_path` could
+/// redirect the write anywhere. Such destinations are refused with an
+/// [`io::ErrorKind::InvalidInput`] error. The link is refused by the open call itself
+/// (`O_NOFOLLOW`), so that it cannot be swapped in between a check and the open.
+#[cfg(target_os = "linux")]
+pub fn open_no_follow(file_path: &Path, options: &mut OpenOptions) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    options
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(file_path)
+        .map_err(|e| match e.raw_os_error() {
+            Some(libc::ELOOP) => symlink_refused(file_path),
+            _ => e,
+        })
+}
+
+/// `O_NOFOLLOW` is only used on Linux, symbolic links are checked before opening elsewhere.
+#[cfg(not(target_os = "linux"))]
+pub fn open_no_follow(file_path: &Path, options: &mut OpenOptions) -> io::Result<File> {
+    if fs::symlink_metadata(file_path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+        return Err(symlink_refused(file_path));
+    }
+    options.open(file_path)
+}
+
+fn symlink_refused(file_path: &Path) -> io::Error {
+    error!(
+        "Refusing to write through symbolic link [path:{}]",
+        file_path.display()
+    );
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "destination is a symbolic link",
+    )
+}
+
+/// Ownership and mode applied to every file written by [`write_file`].
+///
+/// Produced files are read by the application container, which may run as a different
+/// user. Each setting is optional and left untouched by default.
+#[derive(Debug, Default, PartialEq)]
+pub struct OutputFilePermissions {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub mode: Option<u32>,
+}
+
+impl OutputFilePermissions {
+    /// Reads `IEXEC_OUTPUT_FILE_UID`, `IEXEC_OUTPUT_FILE_GID` and `IEXEC_OUTPUT_FILE_MODE`
+    /// (octal, e.g. `0640`). Invalid values are logged and ignored.
+    pub fn from_env() -> Self {
+        let read = |variable: TeeSessionEnvironmentVariable, radix: u32| {
+            let name = variable.name();
+            let value =
+                get_env_var_or_error(variable, ReplicateStatusCause::PreComputeFailedUnknownIssue)
+                    .ok()?;
+            let digits = value.trim().trim_start_matches("0o");
+            u32::from_str_radix(digits, radix)
+                .inspect_err(|_| error!("Ignoring invalid {name} [value:{value}]"))
+                .ok()
+        };
+        OutputFilePermissions {
+            uid: read(TeeSessionEnvironmentVariable::IexecOutputFileUid, 10),
+            gid: read(TeeSessionEnvironmentVariable::IexecOutputFileGid, 10),
+            mode: read(TeeSessionEnvironmentVariable::IexecOutputFileMode, 8),
+        }
+    }
+
+    /// Applies the configured mode, then ownership, to the open `file`.
+    ///
+    /// Settings go through the file descriptor rather than the path, so that the path being
+    /// replaced after the file was opened cannot redirect them to another file.
+    #[cfg(unix)]
+    pub fn apply(&self, file: &File) -> io::Result<()> {
+        if let Some(mode) = self.mode {
+            file.set_permissions(Permissions::from_mode(mode))?;
+        }
+        if self.uid.is_some() || self.gid.is_some() {
+            fchown(file, self.uid, self.gid)?;
+        }
+        Ok(())
+    }
+
+    /// Applies the configured ownership, and the configured mode with the execute bit added
+    /// wherever the read bit is set, to the directory `dir_path`, so that the application can
+    /// list the directory it can read files from.
+    ///
+    /// A symbolic link at `dir_path` is refused like for files, see [`open_no_follow`].
+    #[cfg(unix)]
+    pub fn apply_to_dir(&self, dir_path: &Path) -> io::Result<()> {
+        if *self == OutputFilePermissions::default() {
+            return Ok(());
+        }
+        OutputFilePermissions {
+            mode: self.mode.map(|mode| mode | ((mode & 0o444) >> 2)),
+            ..*self
+        }
+        .apply(&open_no_follow(dir_path, OpenOptions::new().read(true))?)
+    }
+
+    /// Unix modes and ownership do not exist on this platform, so settings are ignored.
+    #[cfg(not(unix))]
+    pub fn apply_to_dir(&self, _dir_path: &Path) -> io::Result<()> {
+        self.warn_unsupported();
+        Ok(())
+    }
+
+    /// Unix modes and ownership do not exist on this platform, so settings are ignored.
+    #[cfg(not(unix))]
+    pub fn apply(&self, _file: &File) -> io::Result<()> {
+        self.warn_unsupported();
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn warn_unsupported(&self) {
+        if *self != OutputFilePermissions::default() {
+            log::warn!("Ignoring output file permissions, not supported on this platform");
+        }
+    }
+}
+
+/// Writes `content` to `file_path` in chunks of [`write_buffer_size`] bytes, then applies the
+/// [`OutputFilePermissions`] configured in the environment.
+pub fn write_in_chunks(content: &[u8], file_path: &Path) -> io::Result<()> {
+    let mut writer = create_buffered_file(file_path)?;
+    preallocate(writer.get_ref(), content.len() as u64)?;
+    for chunk in content.chunks(writer.capacity()) {
+        writer.write_all(chunk)?;
+    }
+    writer.flush()?;
+    OutputFilePermissions::from_env().apply(writer.get_ref())
+}
 
 /// Writes content to a file at the specified path, with proper error handling and logging.
 ///
 /// This function handles the common pattern of writing data to a file with logging
-/// and error handling.
+/// and error handling. Content is written in chunks of [`write_buffer_size`] bytes, then
+/// the [`OutputFilePermissions`] configured in the environment are applied to the file.
 ///
 /// # Arguments
 ///
@@ -17,7 +693,8 @@ use std::path::{Path, PathBuf};
 /// # Returns
 ///
 /// * `Ok(())` if the file is successfully written
-/// * `Err(())` if the write operation fails
+/// * `Err(io::Error)` if the write operation fails, of kind [`io::ErrorKind::StorageFull`]
+///   when the disk is full
 ///
 /// # Example
 ///
@@ -28,8 +705,18 @@ use std::path::{Path, PathBuf};
 ///     println!("File written successfully");
 /// }
 /// ```
-pub fn write_file(content: &[u8], file_path: &Path, context: &str) -> Result<(), ()> {
-    match fs::write(file_path, content) {
+pub fn write_file(content: &[u8], file_path: &Path, context: &str) -> io::Result<()> {
+    write_file_in(&StdFilesystem, content, file_path, context)
+}
+
+/// Same as [`write_file`], writing through `filesystem`.
+pub fn write_file_in(
+    filesystem: &dyn Filesystem,
+    content: &[u8],
+    file_path: &Path,
+    context: &str,
+) -> io::Result<()> {
+    match filesystem.write(file_path, content) {
         Ok(_) => {
             info!(
                 "File written successfully [{context}, path:{}]",
@@ -37,12 +724,12 @@ pub fn write_file(content: &[u8], file_path: &Path, context: &str) -> Result<(),
             );
             Ok(())
         }
-        Err(_) => {
+        Err(e) => {
             error!(
-                "Failed to write file [{context}, path:{}]",
+                "Failed to write file [{context}, path:{}]: {e}",
                 file_path.display()
             );
-            Err(())
+            Err(e)
         }
     }
 }
@@ -50,24 +737,27 @@ pub fn write_file(content: &[u8], file_path: &Path, context: &str) -> Result<(),
 /// Downloads a file from a given URL and writes it to a specified folder with a specified filename.
 ///
 /// If the download or any file operation fails, the function logs an appropriate error
-/// and returns `None`. It also ensures the parent directory exists, creating it if necessary.
+/// and returns the reason of the failure. It also ensures the parent directory exists, creating it if necessary.
 /// If the directory is newly created but the file write fails, it is cleaned up (deleted).
 ///
 /// # Arguments
 ///
+/// - `filesystem`: The file system the file is written to.
 /// - `url`: The URL to download the file from. Must not be empty.
 /// - `parent_dir`: The directory path where the file will be stored. Must not be empty.
 /// - `filename`: The name to use for the downloaded file. Must not be empty.
+/// - `options`: The cancellation token and retry budget of the download.
 ///
 /// # Returns
 ///
-/// - `Some(PathBuf)` with the full path to the downloaded file if successful.
-/// - `None` if any validation, download, directory creation, or file writing fails.
+/// - `Ok(PathBuf)` with the full path to the downloaded file if successful.
+/// - `Err(DownloadError)` if any validation, download, directory creation, or file writing fails.
 ///
 /// # Example
 ///
 /// ```
-/// if let Some(path) = download_file("https://iex.ec/file.txt", "/tmp", "iexec.txt") {
+/// let options = DownloadOptions::default();
+/// if let Ok(path) = download_file(&StdFilesystem, "https://iex.ec/file.txt", "/tmp", "iexec.txt", &options) {
 ///     println!("File downloaded to: {}", path.display());
 /// } else {
 ///     println!("Failed to download file.");
@@ -77,126 +767,966 @@ pub fn write_file(content: &[u8], file_path: &Path, context: &str) -> Result<(),
 /// # Notes
 ///
 /// - This function uses **blocking** I/O (`reqwest::blocking`) and is not suitable for async contexts.
-/// - The downloaded content is fully loaded into memory before being written to disk.
-pub fn download_file(url: &str, parent_dir: &str, filename: &str) -> Option<PathBuf> {
+/// - The downloaded content is kept in memory until it exceeds [`download_memory_threshold`],
+///   and is then streamed to a `.part` file next to the destination.
+pub fn download_file(
+    filesystem: &dyn Filesystem,
+    url: &str,
+    parent_dir: &str,
+    filename: &str,
+    options: &DownloadOptions,
+) -> Result<PathBuf, DownloadError> {
+    download_file_with(
+        filesystem,
+        url,
+        parent_dir,
+        filename,
+        download_memory_threshold(),
+        &mut |_| {},
+        options,
+    )
+}
+
+/// Downloads a file like [`download_file`], feeding its content to `hasher` as it is received.
+///
+/// The content is written to a `.part` file next to the destination chunk by chunk, whatever
+/// its size, so that memory use stays constant even for multi-GB files, and the file is then
+/// renamed into place.
+///
+/// # Returns
+///
+/// - `Ok(PathBuf)` with the full path to the downloaded file if successful, `hasher` then
+///   holds the whole content.
+/// - `Err(DownloadError)` if any validation, download, directory creation, or file writing fails.
+///
+/// # Example
+///
+/// ```
+/// let mut hasher = verifier.hasher();
+/// let path = download_file_and_hash(
+///     &StdFilesystem,
+///     "https://host/input.bin",
+///     "/iexec_in",
+///     "input.bin",
+///     hasher.as_mut(),
+///     &DownloadOptions::default(),
+/// )?;
+/// let checksum = hasher.finalize();
+/// ```
+pub fn download_file_and_hash(
+    filesystem: &dyn Filesystem,
+    url: &str,
+    parent_dir: &str,
+    filename: &str,
+    hasher: &mut dyn ContentHasher,
+    options: &DownloadOptions,
+) -> Result<PathBuf, DownloadError> {
+    download_file_with(
+        filesystem,
+        url,
+        parent_dir,
+        filename,
+        0,
+        &mut |chunk| hasher.update(chunk),
+        options,
+    )
+}
+
+/// Downloads a file, keeping up to `threshold` bytes in memory and passing every chunk to
+/// `on_chunk`, see [`download_file`].
+fn download_file_with(
+    filesystem: &dyn Filesystem,
+    url: &str,
+    parent_dir: &str,
+    filename: &str,
+    threshold: usize,
+    on_chunk: &mut dyn FnMut(&[u8]),
+    options: &DownloadOptions,
+) -> Result<PathBuf, DownloadError> {
     if url.is_empty() {
         error!("Invalid file url [url:{url}]");
-        return None;
+        return Err(DownloadError::InvalidUrl);
     }
     if parent_dir.is_empty() {
         error!("Invalid parent folder path [url:{url}, parent_dir:{parent_dir}]");
-        return None;
+        return Err(DownloadError::WriteFailed);
     }
     if filename.is_empty() {
         error!("Invalid output filename [url:{url}, parent_dir:{parent_dir}, filename:{filename}]");
-        return None;
+        return Err(DownloadError::WriteFailed);
     }
 
-    let bytes = match download_from_url(url) {
-        Some(b) => b,
-        None => {
-            error!("Failed to download file [url:{url}]");
-            return None;
-        }
-    };
-
     let parent_path = Path::new(parent_dir);
-    let parent_existed = parent_path.exists();
+    let parent_existed = filesystem.exists(parent_path);
 
-    if !parent_existed && fs::create_dir_all(parent_path).is_err() {
+    if !parent_existed && filesystem.create_dir(parent_path).is_err() {
         error!("Failed to create parent folder [url:{url}, parent_dir:{parent_dir}]");
-        return None;
+        return Err(DownloadError::WriteFailed);
     }
 
     let file_path = parent_path.join(filename);
 
-    if write_file(&bytes, &file_path, &format!("url:{url}")).is_ok() {
-        Some(file_path)
+    match download_to(filesystem, url, &file_path, threshold, on_chunk, options) {
+        Ok(()) => Ok(file_path),
+        Err(e) => {
+            if !parent_existed {
+                match filesystem.remove_dir_all(parent_path) {
+                    Ok(_) => {
+                        info!("Folder deleted [path:{}]", parent_path.display());
+                    }
+                    Err(_) => {
+                        error!(
+                            "Folder does not exist, nothing to delete [path:{}]",
+                            parent_path.display()
+                        );
+                    }
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Downloads `url` to `file_path`, spilling the content to `<file_path>.part` once it exceeds
+/// `threshold` bytes, and passes every chunk to `on_chunk`.
+///
+/// Content served over FTP or SFTP is spilled the same way, while the content of a `data:`
+/// URI is decoded and written.
+fn download_to(
+    filesystem: &dyn Filesystem,
+    url: &str,
+    file_path: &Path,
+    threshold: usize,
+    on_chunk: &mut dyn FnMut(&[u8]),
+    options: &DownloadOptions,
+) -> Result<(), DownloadError> {
+    let context = format!("url:{url}");
+    check_cancellation(url, &options.cancellation)?;
+    if data_uri_utils::is_data_uri(url) {
+        let content = download_from_url(url, options).inspect_err(|_| {
+            error!("Failed to download file [url:{url}]");
+        })?;
+        on_chunk(&content);
+        return write_file_in(filesystem, &content, file_path, &context).map_err(write_error);
+    }
+
+    let spill_path = partial_path(file_path, options.staging_dir.as_deref());
+    let mut spool = Spool::spilling_to(filesystem, &spill_path, threshold);
+    let received = if ftp_utils::is_ftp_url(url) {
+        ftp_utils::download_into(url, options, &mut |chunk| {
+            on_chunk(chunk);
+            spool.push(chunk).map_err(|e| {
+                error!("Failed to spill downloaded content [url:{url}]: {e}");
+                write_error(e)
+            })
+        })
+        .map(|_| url.to_string())
     } else {
-        if !parent_existed {
-            match fs::remove_dir_all(parent_path) {
-                Ok(_) => {
-                    info!("Folder deleted [path:{}]", parent_path.display());
+        info!("Attempting to download from {url}");
+        receive_into(url, options, stall_watchdog(), on_chunk, &mut spool)
+    };
+    let mut final_url = None;
+    let result = received
+        .inspect_err(|_| error!("Failed to download file [url:{url}]"))
+        .and_then(|served_by| {
+            final_url = Some(served_by);
+            spool.finish().map_err(write_error)
+        })
+        .and_then(|spooled| match spooled {
+            Spooled::Memory(content) => {
+                write_file_in(filesystem, &content, file_path, &context).map_err(write_error)
+            }
+            Spooled::File(path) => match filesystem.rename(path, file_path) {
+                Ok(()) => {
+                    info!(
+                        "File written successfully [{context}, path:{}]",
+                        file_path.display()
+                    );
+                    Ok(())
                 }
-                Err(_) => {
+                Err(e) => {
                     error!(
-                        "Folder does not exist, nothing to delete [path:{}]",
-                        parent_path.display()
+                        "Failed to move spilled file [{context}, path:{}]: {e}",
+                        file_path.display()
                     );
+                    Err(write_error(e))
+                }
+            },
+        });
+    if result.is_err() && filesystem.exists(&spill_path) {
+        let _ = filesystem.remove_file(&spill_path);
+    }
+    if result.is_ok() {
+        *options
+            .final_url
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = final_url;
+    }
+    result
+}
+
+/// Returns the path the content of `file_path` is written to before being moved into place:
+/// a `.part` file in `staging_dir` when set, next to `file_path` otherwise.
+///
+/// # Example
+///
+/// ```
+/// let path = partial_path(Path::new("/iexec_in/dataset.txt"), None);
+/// assert_eq!(path, Path::new("/iexec_in/dataset.txt.part"));
+/// ```
+pub fn partial_path(file_path: &Path, staging_dir: Option<&Path>) -> PathBuf {
+    let mut partial_name = file_path.file_name().unwrap_or_default().to_os_string();
+    partial_name.push(".part");
+    match staging_dir {
+        Some(dir) => dir.join(partial_name),
+        None => file_path.with_file_name(partial_name),
+    }
+}
+
+/// Converts a failed write of downloaded content into a [`DownloadError`].
+fn write_error(e: io::Error) -> DownloadError {
+    match e.kind() {
+        io::ErrorKind::StorageFull => DownloadError::NotEnoughDiskSpace,
+        _ => DownloadError::WriteFailed,
+    }
+}
+
+/// Content received by a download, kept in memory until it grows past a threshold and then
+/// spilled to a file.
+///
+/// Small downloads never touch the disk before being written, while large ones never have to
+/// fit in memory. Once spilled, content is appended to the file in chunks of
+/// [`write_buffer_size`] bytes.
+struct Spool<'a> {
+    buffer: BytesMut,
+    spill: Option<(&'a dyn Filesystem, &'a Path)>,
+    threshold: usize,
+    chunk_size: usize,
+    /// Number of bytes already written to the spill file.
+    spilled: usize,
+    /// Whether disk space was reserved for the spill file, which then already exists.
+    allocated: bool,
+}
+
+/// Where the content of a [`Spool`] ended up.
+#[derive(Debug, PartialEq)]
+enum Spooled<'a> {
+    Memory(Bytes),
+    File(&'a Path),
+}
+
+impl<'a> Spool<'a> {
+    /// Creates a spool keeping all its content in memory.
+    fn in_memory() -> Self {
+        Spool {
+            buffer: BytesMut::new(),
+            spill: None,
+            threshold: usize::MAX,
+            chunk_size: 0,
+            spilled: 0,
+            allocated: false,
+        }
+    }
+
+    /// Creates a spool writing its content to `path` once it exceeds `threshold` bytes.
+    fn spilling_to(filesystem: &'a dyn Filesystem, path: &'a Path, threshold: usize) -> Self {
+        Spool {
+            spill: Some((filesystem, path)),
+            threshold,
+            chunk_size: write_buffer_size(),
+            ..Self::in_memory()
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.spilled + self.buffer.len()
+    }
+
+    /// Reserves room for `size` bytes of content: memory up to the threshold, and disk space
+    /// for the spill file when the content is going to exceed the threshold.
+    fn reserve(&mut self, size: usize) -> io::Result<()> {
+        if self.spilled > 0 {
+            return Ok(());
+        }
+        self.buffer
+            .reserve(size.min(self.threshold).min(MAX_PREALLOCATED_SIZE));
+        match self.spill {
+            Some((filesystem, path)) if size > self.threshold => {
+                filesystem.allocate(path, size as u64)?;
+                self.allocated = true;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns whether `chunk` is the content already received at `offset`.
+    fn matches(&self, offset: usize, chunk: &[u8]) -> io::Result<bool> {
+        let in_file = self.spilled.saturating_sub(offset).min(chunk.len());
+        if let Some((filesystem, path)) = self.spill.filter(|_| in_file > 0) {
+            let mut stored = vec![0; in_file];
+            filesystem.read_at(path, offset as u64, &mut stored)?;
+            if stored != chunk[..in_file] {
+                return Ok(false);
+            }
+        }
+        let start = offset + in_file - self.spilled;
+        Ok(chunk[in_file..] == self.buffer[start..start + chunk.len() - in_file])
+    }
+
+    /// Appends `chunk`, spilling the buffered content once the threshold is exceeded.
+    fn push(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.buffer.extend_from_slice(chunk);
+        let full = match self.spilled {
+            0 => self.buffer.len() > self.threshold,
+            _ => self.buffer.len() >= self.chunk_size,
+        };
+        if full { self.flush() } else { Ok(()) }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let Some((filesystem, path)) = self.spill else {
+            return Ok(());
+        };
+        if self.spilled == 0 {
+            info!(
+                "Spilling download to disk [path:{}, threshold:{}]",
+                path.display(),
+                self.threshold
+            );
+        }
+        if self.spilled == 0 && !self.allocated {
+            filesystem.write(path, &self.buffer)?;
+        } else {
+            filesystem.append(path, &self.buffer)?;
+        }
+        self.spilled += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+
+    /// Writes the remaining buffered content if the spool was spilled.
+    fn finish(mut self) -> io::Result<Spooled<'a>> {
+        match self.spill {
+            Some((_, path)) if self.spilled > 0 => {
+                if !self.buffer.is_empty() {
+                    self.flush()?;
                 }
+                Ok(Spooled::File(path))
             }
+            Some((filesystem, path)) if self.allocated => {
+                // The content turned out smaller than announced and never left memory.
+                filesystem.remove_file(path)?;
+                Ok(Spooled::Memory(self.buffer.freeze()))
+            }
+            _ => Ok(Spooled::Memory(self.buffer.freeze())),
+        }
+    }
+}
+
+/// Reason why a download (or the pre-flight check of a URL) failed.
+#[derive(Debug, PartialEq)]
+pub enum DownloadError {
+    /// The URL is empty or malformed.
+    InvalidUrl,
+    /// The request could not be sent or the response body could not be read
+    /// (DNS, connection, TLS or URL format issue).
+    Unreachable(String),
+    /// The server answered with a non-success status code.
+    Status(u16),
+    /// The server answered with `206 Partial Content` although the whole content was
+    /// requested.
+    UnexpectedPartialContent,
+    /// The downloaded content does not have the size other servers agree on.
+    SizeMismatch { expected: u64, actual: u64 },
+    /// The downloaded content does not match its expected checksum.
+    ChecksumMismatch,
+    /// The downloaded content could not be written to disk.
+    WriteFailed,
+    /// The downloaded content could not be written to disk because it is full.
+    NotEnoughDiskSpace,
+    /// The downloaded content, once decoded, is larger than the allowed size.
+    TooLarge { limit: u64 },
+    /// The server did not present a certificate, or SFTP host key, matching the pinned
+    /// public keys.
+    UntrustedCertificate,
+    /// No byte was received for longer than the stall timeout, even after restarting the
+    /// transfer.
+    Stalled,
+    /// The transfer was aborted because the task is being cancelled.
+    Cancelled,
+}
+
+impl DownloadError {
+    /// Returns the HTTP status code returned by the server, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            DownloadError::Status(status) => Some(*status),
+            DownloadError::UnexpectedPartialContent => Some(StatusCode::PARTIAL_CONTENT.as_u16()),
+            _ => None,
         }
-        None
     }
 }
 
-/// Downloads the content from the given URL and returns it as a byte vector.
+/// Downloads the content from the given URL and returns it as a [`Bytes`] buffer.
 ///
-/// This function supports any HTTP/HTTPS URL, including IPFS gateway URLs.
+/// This function supports any HTTP/HTTPS URL, including IPFS gateway URLs, and `s3://` URLs
+/// of S3-compatible storages, see [`s3_utils::S3Config`]. `ftp://` and `sftp://` URLs are
+/// downloaded with [`ftp_utils::download`].
 /// It performs a blocking GET request and returns the full response body as bytes.
 ///
 /// # Arguments
 ///
 /// * `url` - The URL to download from. Must not be empty.
+/// * `options` - The constraints, cancellation token and retry budget of the download.
 ///
 /// # Returns
 ///
-/// * `Some(Vec<u8>)` if the download succeeds and the response body is read successfully.
-/// * `None` if the URL is empty, the request fails, or the response status is not successful.
+/// * `Ok(Bytes)` if the download succeeds and the response body is read successfully.
+/// * `Err(DownloadError::Cancelled)` if the cancellation token of `options` is cancelled
+///   before the download completes.
+/// * `Err(DownloadError)` if the URL is empty, the request fails, or the response status is
+///   not successful.
 ///
 /// # Example
 ///
 /// ```
-/// if let Some(bytes) = download_from_url("https://httpbin.org/json/test.json") {
-///     println!("Downloaded {} bytes", bytes.len());
-/// } else {
-///     println!("Download failed");
+/// match download_from_url("https://httpbin.org/json/test.json", &DownloadOptions::default()) {
+///     Ok(bytes) => println!("Downloaded {} bytes", bytes.len()),
+///     Err(e) => println!("Download failed [status:{:?}]", e.status()),
 /// }
 /// ```
 ///
 /// # Notes
 ///
 /// - This function uses blocking I/O and is not suitable for async contexts.
-/// - The entire response body is loaded into memory.
-pub fn download_from_url(url: &str) -> Option<Vec<u8>> {
+/// - The entire response body is loaded into memory, in a buffer sized from the advertised
+///   `Content-Length`, so it can be handed down the pipeline without further copies.
+///   Content which may not fit in memory should be streamed to disk with
+///   [`download_file_and_hash`] instead.
+pub fn download_from_url(url: &str, options: &DownloadOptions) -> Result<Bytes, DownloadError> {
     if url.is_empty() {
         error!("Invalid URL: empty string");
-        return None;
+        return Err(DownloadError::InvalidUrl);
     }
+    check_cancellation(url, &options.cancellation)?;
 
-    info!("Attempting to download from {url}");
-
-    match get(url)
-        .and_then(|response| response.error_for_status())
-        .and_then(|response| response.bytes())
-    {
-        Ok(bytes) => {
-            info!("Successfully downloaded {} bytes from {url}", bytes.len());
-            Some(bytes.to_vec())
-        }
-        Err(e) => {
-            error!("Failed to download from {url}: {e}");
-            None
-        }
+    if data_uri_utils::is_data_uri(url) {
+        return decode_data_uri(url, options);
+    }
+    if ftp_utils::is_ftp_url(url) {
+        return ftp_utils::download(url, options);
     }
-}
+
+    info!("Attempting to download from {url}");
+    receive(url, options, stall_watchdog(), &mut |_| {}).map(|download| download.content)
+}
+
+/// Constraints applied to a download.
+#[derive(Debug, Default, Clone)]
+pub struct DownloadOptions {
+    /// The maximum size of the content once decoded, if any. The transfer is aborted as soon
+    /// as it is exceeded, so that a compressed response cannot expand past it.
+    pub max_size: Option<u64>,
+    /// SPKI pins of the servers allowed to serve the content over HTTPS, any server when
+    /// empty. See [`tls_utils::spki_pin`].
+    pub spki_pins: Vec<String>,
+    /// Token aborting the transfer between two chunks once cancelled.
+    pub cancellation: CancellationToken,
+    /// Budget of the extra attempts made by the download, shared with the other downloads
+    /// of the run.
+    pub retry_budget: RetryBudget,
+    /// Directory partial content is written to before being moved into place, see
+    /// [`partial_path`].
+    pub staging_dir: Option<PathBuf>,
+    /// Task whose audit trail receives the headers of every HTTP(S) response, see
+    /// [`Event::DownloadResponse`]. Nothing is recorded when unset.
+    pub chain_task_id: Option<String>,
+    /// Headers sent with every HTTP(S) request of the download, such as the credentials of a
+    /// private endpoint. They are ignored by the other transports, and redirects to another
+    /// origin fail the download instead of being followed.
+    pub headers: Vec<(String, String)>,
+    /// Number of HTTP(S) transfers started by the download, restarts of stalled transfers
+    /// included, shared by the clones of the options.
+    pub attempts: Arc<AtomicU32>,
+    /// URL which served the content written by [`download_file`] or
+    /// [`download_file_and_hash`], after redirects, shared by the clones of the options.
+    pub final_url: Arc<Mutex<Option<String>>>,
+}
+
+impl DownloadOptions {
+    /// Returns the number of attempts made by the download so far, at least one since
+    /// transports other than HTTP(S) make a single attempt.
+    pub fn attempts(&self) -> u32 {
+        self.attempts.load(Ordering::Relaxed).max(1)
+    }
+
+    /// Returns the URL which served the last file downloaded with these options, absent
+    /// until a download succeeded and for inline `data:` URIs.
+    pub fn final_url(&self) -> Option<String> {
+        self.final_url
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Content downloaded by [`download_and_hash`].
+#[derive(Debug, PartialEq)]
+pub struct Download {
+    pub content: Bytes,
+    /// The URL which served the content, after following redirects.
+    pub final_url: String,
+}
+
+/// Downloads the content from the given URL, feeding it to `hasher` as it is received.
+///
+/// This behaves like [`download_from_url`], except that the checksum is computed while the
+/// response body streams in, so that verification adds almost no time once the download
+/// completes.
+///
+/// # Arguments
+///
+/// * `url` - The URL to download from. Must not be empty.
+/// * `hasher` - The hasher receiving every chunk of the content, in order.
+/// * `options` - The size limit and SPKI pins applied to the download. Pins only apply to
+///   HTTP(S) URLs.
+///
+/// # Returns
+///
+/// * `Ok(Download)` with the content and the URL it was finally served from if the download
+///   succeeds, `hasher` then holds the whole content.
+/// * `Err(DownloadError::TooLarge)` if the content exceeds the maximum size.
+/// * `Err(DownloadError::UntrustedCertificate)` if pins are set and the final server did not
+///   present a matching certificate, before any content is read.
+/// * `Err(DownloadError)` if the URL is empty, the request fails, or the response status is
+///   not successful.
+///
+/// # Example
+///
+/// ```
+/// let mut hasher = verifier.hasher();
+/// let download = download_and_hash(
+///     "https://host/dataset.bin",
+///     hasher.as_mut(),
+///     &DownloadOptions::default(),
+/// )?;
+/// let checksum = hasher.finalize();
+/// ```
+pub fn download_and_hash(
+    url: &str,
+    hasher: &mut dyn ContentHasher,
+    options: &DownloadOptions,
+) -> Result<Download, DownloadError> {
+    if url.is_empty() {
+        error!("Invalid URL: empty string");
+        return Err(DownloadError::InvalidUrl);
+    }
+    check_cancellation(url, &options.cancellation)?;
+
+    if ftp_utils::is_ftp_url(url) {
+        let content = ftp_utils::download(url, options)?;
+        hasher.update(&content);
+        return Ok(Download {
+            content,
+            final_url: url.to_string(),
+        });
+    }
+
+    info!("Attempting to download from {url}");
+    receive(url, options, stall_watchdog(), &mut |chunk| {
+        hasher.update(chunk)
+    })
+}
+
+/// Downloads the content of `url` over HTTP(S) in memory, passing every new chunk to
+/// `on_chunk`.
+fn receive(
+    url: &str,
+    options: &DownloadOptions,
+    watchdog: &StallWatchdog,
+    on_chunk: &mut dyn FnMut(&[u8]),
+) -> Result<Download, DownloadError> {
+    let mut spool = Spool::in_memory();
+    let final_url = receive_into(url, options, watchdog, on_chunk, &mut spool)?;
+    Ok(Download {
+        content: spool.buffer.freeze(),
+        final_url,
+    })
+}
+
+/// Downloads the content of `url` over HTTP(S) into `content`, passing every new chunk to
+/// `on_chunk`, and returns the URL it was finally served from.
+///
+/// Transfers stalled according to `watchdog` are restarted from the beginning.
+/// The bytes already received are then checked against the new transfer and skipped, so
+/// that `on_chunk` receives the content exactly once.
+fn receive_into(
+    url: &str,
+    options: &DownloadOptions,
+    watchdog: &StallWatchdog,
+    on_chunk: &mut dyn FnMut(&[u8]),
+    content: &mut Spool,
+) -> Result<String, DownloadError> {
+    let mut restarts = 0;
+    let mut retry_permit = None;
+    loop {
+        check_cancellation(url, &options.cancellation)?;
+        throttle(url);
+        options.attempts.fetch_add(1, Ordering::Relaxed);
+        let response = get(url, &options.headers)?;
+        let final_url = response.url().to_string();
+        audit_response(url, &response, options.chain_task_id.as_deref());
+        check_pins(&response, &options.spki_pins)?;
+        if content.len() == 0 {
+            // Room is only reserved for content the size limit lets through.
+            let advertised = response
+                .content_length()
+                .filter(|len| options.max_size.is_none_or(|max_size| *len <= max_size));
+            content
+                .reserve(advertised.unwrap_or_default() as usize)
+                .map_err(|e| {
+                    error!("Failed to pre-allocate downloaded content [url:{url}]: {e}");
+                    write_error(e)
+                })?;
+        }
+
+        let received = content.len();
+        let mut offset = 0;
+        let result = watchdog.read(url, response, &options.cancellation, &mut |chunk| {
+            let skipped = received.saturating_sub(offset).min(chunk.len());
+            if !content.matches(offset, &chunk[..skipped]).map_err(|e| {
+                error!("Failed to read spilled content [url:{url}]: {e}");
+                write_error(e)
+            })? {
+                error!("Content changed while restarting stalled download [url:{url}]");
+                return Err(DownloadError::Unreachable(
+                    "content changed while restarting stalled download".to_string(),
+                ));
+            }
+            offset += chunk.len();
+            let chunk = &chunk[skipped..];
+            if !chunk.is_empty() {
+                check_max_size(url, content.len() + chunk.len(), options.max_size)?;
+                on_chunk(chunk);
+                content.push(chunk).map_err(|e| {
+                    error!("Failed to spill downloaded content [url:{url}]: {e}");
+                    write_error(e)
+                })?;
+            }
+            Ok(())
+        });
+        match result {
+            Ok(()) if offset < received => {
+                error!("Content shrank while restarting stalled download [url:{url}]");
+                return Err(DownloadError::SizeMismatch {
+                    expected: received as u64,
+                    actual: offset as u64,
+                });
+            }
+            Ok(()) => {
+                info!("Successfully downloaded {} bytes from {url}", content.len());
+                return Ok(final_url);
+            }
+            Err(DownloadError::Stalled) if restarts < MAX_STALL_RETRIES => {
+                // The previous restart ends before the next one is charged to the budget.
+                drop(retry_permit.take());
+                retry_permit = Some(
+                    options
+                        .retry_budget
+                        .acquire(url)
+                        .ok_or(DownloadError::Stalled)?,
+                );
+                restarts += 1;
+                info!(
+                    "Restarting stalled download [url:{url}, receivedBytes:{}, restart:{restarts}]",
+                    content.len()
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Records the headers identifying the content served for `url` in the audit trail of
+/// `chain_task_id`, if any.
+///
+/// Every response is recorded, including the ones of restarted transfers, so that content
+/// changing between two mirrors or two attempts can be traced back to its origin.
+fn audit_response(url: &str, response: &Response, chain_task_id: Option<&str>) {
+    let Some(chain_task_id) = chain_task_id else {
+        return;
+    };
+    let header = |name: HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    events::emit(
+        chain_task_id,
+        &Event::DownloadResponse {
+            url: url.to_string(),
+            final_url: response.url().to_string(),
+            status: response.status().as_u16(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            content_type: header(CONTENT_TYPE),
+            server: header(SERVER),
+        },
+    );
+}
+
+/// Checks that the certificate presented for `response` matches one of `pins`.
+///
+/// Responses received over plain HTTP never match.
+fn check_pins(response: &Response, pins: &[String]) -> Result<(), DownloadError> {
+    if pins.is_empty() {
+        return Ok(());
+    }
+    let pin = response
+        .extensions()
+        .get::<TlsInfo>()
+        .and_then(TlsInfo::peer_certificate)
+        .and_then(tls_utils::spki_pin);
+    match pin {
+        Some(pin) if pins.contains(&pin) => Ok(()),
+        _ => {
+            error!(
+                "Server certificate does not match pinned keys [url:{}, pin:{}]",
+                response.url(),
+                pin.as_deref().unwrap_or("none")
+            );
+            Err(DownloadError::UntrustedCertificate)
+        }
+    }
+}
+
+/// Decodes the content carried inline by the `data:` URI `url`, checking it against the
+/// maximum size of `options`.
+fn decode_data_uri(url: &str, options: &DownloadOptions) -> Result<Bytes, DownloadError> {
+    info!("Decoding inline content [url:{url}]");
+    let content = data_uri_utils::decode_data_uri(url)?;
+    check_max_size(url, content.len(), options.max_size)?;
+    Ok(Bytes::from(content))
+}
+
+fn check_max_size(url: &str, size: usize, max_size: Option<u64>) -> Result<(), DownloadError> {
+    match max_size {
+        Some(limit) if size as u64 > limit => {
+            error!("Downloaded content exceeds maximum size [url:{url}, maxSize:{limit}]");
+            Err(DownloadError::TooLarge { limit })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Builds a `method` request for `url` with the shared client, `s3://` URLs being requested
+/// from their storage with [`s3_http_client`], see [`s3_utils::S3Config`].
+fn request(method: Method, url: &str) -> Result<RequestBuilder, DownloadError> {
+    if s3_utils::is_s3_url(url) {
+        return s3_utils::request(s3_http_client(), method, url);
+    }
+    Ok(http_client().request(method, url))
+}
+
+/// Builds a `method` request for `url` like [`request`], with the extra `headers`, their
+/// values being marked sensitive so that they are never written to debug output. Invalid
+/// headers, rejected when the arguments are read, are skipped.
+///
+/// Requests carrying extra headers are sent with [`same_origin_http_client`], or
+/// [`s3_http_client`] for `s3://` URLs, so that the headers are never sent to another origin
+/// the server redirects to.
+fn request_with_headers(
+    method: Method,
+    url: &str,
+    headers: &[(String, String)],
+) -> Result<RequestBuilder, DownloadError> {
+    if headers.is_empty() {
+        return request(method, url);
+    }
+    let builder = if s3_utils::is_s3_url(url) {
+        s3_utils::request(s3_http_client(), method, url)?
+    } else {
+        same_origin_http_client().request(method, url)
+    };
+    Ok(headers.iter().fold(builder, |builder, (name, value)| {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            (Ok(name), Ok(mut value)) => {
+                value.set_sensitive(true);
+                builder.header(name, value)
+            }
+            _ => builder,
+        }
+    }))
+}
+
+/// Sends a GET request for the whole content of `url`, with the extra `headers`.
+///
+/// No `Range` header is sent, so a `206 Partial Content` answer can only come from a broken
+/// server or mirror and is rejected instead of being mistaken for the full content.
+fn get(url: &str, headers: &[(String, String)]) -> Result<Response, DownloadError> {
+    let response = request_with_headers(Method::GET, url, headers)?
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| download_error(url, e))?;
+    if response.url().as_str() != url && !s3_utils::is_s3_url(url) {
+        info!(
+            "Followed redirects [url:{url}, finalUrl:{}]",
+            response.url()
+        );
+    }
+    if response.status() == StatusCode::PARTIAL_CONTENT {
+        error!("Unexpected partial content, no range was requested [url:{url}]");
+        return Err(DownloadError::UnexpectedPartialContent);
+    }
+    Ok(response)
+}
+
+/// Logs a failed request to `url` and converts it into a [`DownloadError`].
+fn download_error(url: &str, e: reqwest::Error) -> DownloadError {
+    error!("Failed to download from {url}: {e}");
+    match e.status() {
+        Some(status) => DownloadError::Status(status.as_u16()),
+        None => DownloadError::Unreachable(e.to_string()),
+    }
+}
+
+/// Checks that a URL is reachable and downloadable by issuing a HEAD request.
+///
+/// Servers which do not implement HEAD (`405 Method Not Allowed` or `501 Not Implemented`)
+/// are considered valid, as the actual download may still succeed. `data:` URIs are decoded
+/// instead, without any request, and `ftp://` and `sftp://` URLs are checked with
+/// [`ftp_utils::size`].
+///
+/// # Arguments
+///
+/// * `url` - The URL to check.
+///
+/// # Returns
+///
+/// * `Ok(Some(u64))` with the advertised `Content-Length` if the URL is valid.
+/// * `Ok(None)` if the URL is valid but its size is unknown.
+/// * `Err(DownloadError)` if the request fails or the status is not successful
+///   (e.g. `404 Not Found`, `401 Unauthorized`, `403 Forbidden`).
+///
+/// # Example
+///
+/// ```
+/// match check_url("https://iex.ec/file.txt") {
+///     Ok(size) => println!("URL is valid [size:{size:?}]"),
+///     Err(e) => println!("URL is invalid: {e:?}"),
+/// }
+/// ```
+pub fn check_url(url: &str) -> Result<Option<u64>, DownloadError> {
+    check_url_with_headers(url, &[])
+}
+
+/// Checks that a URL is reachable like [`check_url`], sending the extra `headers` with the
+/// HEAD request, such as the credentials of a private endpoint.
+pub fn check_url_with_headers(
+    url: &str,
+    headers: &[(String, String)],
+) -> Result<Option<u64>, DownloadError> {
+    check_url_within(url, headers, CHECK_URL_TIMEOUT)
+}
+
+/// Checks that a URL is reachable like [`check_url_with_headers`], failing the HEAD request
+/// if it is not answered within `timeout`.
+fn check_url_within(
+    url: &str,
+    headers: &[(String, String)],
+    timeout: Duration,
+) -> Result<Option<u64>, DownloadError> {
+    if data_uri_utils::is_data_uri(url) {
+        return data_uri_utils::decode_data_uri(url).map(|content| Some(content.len() as u64));
+    }
+    if ftp_utils::is_ftp_url(url) {
+        return ftp_utils::size(url);
+    }
+    throttle(url);
+    let response = request_with_headers(Method::HEAD, url, headers)?
+        .timeout(timeout)
+        .send()
+        .map_err(|e| DownloadError::Unreachable(e.to_string()))?;
+    let status = response.status();
+    if status == StatusCode::METHOD_NOT_ALLOWED || status == StatusCode::NOT_IMPLEMENTED {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(DownloadError::Status(status.as_u16()));
+    }
+    // `Response::content_length` reflects the (empty) body of a HEAD response, so the
+    // advertised size has to be read from the header itself.
+    Ok(response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok())
+        .and_then(|length| length.parse::<u64>().ok()))
+}
+
+/// Measures how fast `url` starts serving its content by requesting its first bytes.
+///
+/// A ranged GET request for the first [`PROBE_SIZE`] bytes is sent and timed until these
+/// bytes are received. Servers ignoring the `Range` header are read no further, so that
+/// probing never downloads the whole content.
+///
+/// # Returns
+///
+/// * `Ok(Duration)` with the time taken to receive the first bytes.
+/// * `Err(DownloadError)` if the request fails or the status is not successful.
+///
+/// # Example
+///
+/// ```
+/// let latency = probe_url("https://gateway.ipfs.io/ipfs/Qm...")?;
+/// ```
+pub fn probe_url(url: &str) -> Result<Duration, DownloadError> {
+    throttle(url);
+    let started_at = Instant::now();
+    let response = http_client()
+        .get(url)
+        .header(RANGE, format!("bytes=0-{}", PROBE_SIZE - 1))
+        .send()
+        .map_err(|e| DownloadError::Unreachable(e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(DownloadError::Status(status.as_u16()));
+    }
+    let mut probe = Vec::with_capacity(PROBE_SIZE as usize);
+    response
+        .take(PROBE_SIZE)
+        .read_to_end(&mut probe)
+        .map_err(|e| DownloadError::Unreachable(e.to_string()))?;
+    Ok(started_at.elapsed())
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::Read;
+    use crate::compute::app_runner::ExitMode;
+    use crate::compute::utils::fs_utils::MemoryFilesystem;
+    use crate::compute::verifier::{Blake3Verifier, ChecksumVerifier};
+    use std::fs;
     use tempfile::TempDir;
     use testcontainers::core::WaitFor;
     use testcontainers::runners::SyncRunner;
     use testcontainers::{Container, GenericImage};
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{header, header_exists, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     const EXPECTED_DATA_PATH: &str = "src/tests_resources/httpbin.json";
     const URL: &str = "https://httpbin.org/json";
-    const PARENT_DIR: &str = "/tmp";
     const FILE_NAME: &str = "test.json";
 
+    fn parent_dir() -> String {
+        std::env::temp_dir().to_string_lossy().into_owned()
+    }
+
     fn assert_json_eq_from_file(actual: &[u8], file_path: &str) {
         let expected_bytes =
             fs::read(Path::new(file_path)).expect("Failed to read expected JSON file");
@@ -229,31 +1759,70 @@ mod tests {
     // region download_file
     #[test]
     fn test_empty_url() {
-        assert!(download_file("", PARENT_DIR, FILE_NAME).is_none());
+        assert_eq!(
+            download_file(
+                &StdFilesystem,
+                "",
+                &parent_dir(),
+                FILE_NAME,
+                &DownloadOptions::default(),
+            ),
+            Err(DownloadError::InvalidUrl)
+        );
     }
 
     #[test]
     fn test_empty_parent_dir() {
-        assert!(download_file(URL, "", FILE_NAME).is_none());
+        assert_eq!(
+            download_file(
+                &StdFilesystem,
+                URL,
+                "",
+                FILE_NAME,
+                &DownloadOptions::default(),
+            ),
+            Err(DownloadError::WriteFailed)
+        );
     }
 
     #[test]
     fn test_empty_filename() {
-        assert!(download_file(URL, PARENT_DIR, "").is_none());
+        assert_eq!(
+            download_file(
+                &StdFilesystem,
+                URL,
+                &parent_dir(),
+                "",
+                &DownloadOptions::default(),
+            ),
+            Err(DownloadError::WriteFailed)
+        );
     }
 
     #[test]
     fn test_invalid_url() {
-        let result = download_file("not-a-url", PARENT_DIR, FILE_NAME);
-        assert!(result.is_none());
+        let result = download_file(
+            &StdFilesystem,
+            "not-a-url",
+            &parent_dir(),
+            FILE_NAME,
+            &DownloadOptions::default(),
+        );
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_successful_download() {
         let (_container, container_url) = start_container();
 
-        let result = download_file(&container_url, PARENT_DIR, FILE_NAME);
-        assert!(result.is_some());
+        let result = download_file(
+            &StdFilesystem,
+            &container_url,
+            &parent_dir(),
+            FILE_NAME,
+            &DownloadOptions::default(),
+        );
+        assert!(result.is_ok());
 
         let path = result.unwrap();
         assert!(path.is_file());
@@ -271,8 +1840,14 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let nested_path = temp_dir.path().join("nested").join("deep");
 
-        let result = download_file(&container_url, nested_path.to_str().unwrap(), "test.json");
-        assert!(result.is_some());
+        let result = download_file(
+            &StdFilesystem,
+            &container_url,
+            nested_path.to_str().unwrap(),
+            "test.json",
+            &DownloadOptions::default(),
+        );
+        assert!(result.is_ok());
 
         let path = result.unwrap();
         assert!(path.exists());
@@ -280,6 +1855,291 @@ mod tests {
 
         let _ = fs::remove_file(&path);
     }
+
+    fn start_file_server(content: Vec<u8>) -> (tokio::runtime::Runtime, MockServer) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/input.bin"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content))
+                .mount(&server)
+                .await;
+            server
+        });
+        (rt, mock_server)
+    }
+
+    #[test]
+    fn test_download_file_spills_content_above_memory_threshold() {
+        let content: Vec<u8> = (0..=255).cycle().take(3 * DOWNLOAD_CHUNK_SIZE).collect();
+        let (_rt, mock_server) = start_file_server(content.clone());
+        let filesystem = MemoryFilesystem::default();
+
+        let result = temp_env::with_var("IEXEC_DOWNLOAD_MEMORY_THRESHOLD", Some("1024"), || {
+            download_file(
+                &filesystem,
+                &format!("{}/input.bin", mock_server.uri()),
+                "/input",
+                FILE_NAME,
+                &DownloadOptions::default(),
+            )
+        });
+
+        assert_eq!(result, Ok(PathBuf::from("/input").join(FILE_NAME)));
+        assert_eq!(filesystem.file("/input/test.json"), Some(content));
+        assert!(!filesystem.exists(Path::new("/input/test.json.part")));
+    }
+
+    #[test]
+    fn test_download_file_removes_spilled_content_when_disk_is_full() {
+        let (_rt, mock_server) = start_file_server(vec![1u8; 4096]);
+        let filesystem = MemoryFilesystem::with_capacity(2048);
+
+        let result = temp_env::with_var("IEXEC_DOWNLOAD_MEMORY_THRESHOLD", Some("1024"), || {
+            download_file(
+                &filesystem,
+                &format!("{}/input.bin", mock_server.uri()),
+                "/input",
+                FILE_NAME,
+                &DownloadOptions::default(),
+            )
+        });
+
+        assert_eq!(result, Err(DownloadError::NotEnoughDiskSpace));
+        assert!(!filesystem.exists(Path::new("/input")));
+    }
+
+    #[test]
+    fn test_download_file_and_hash_streams_content_to_file() {
+        let content: Vec<u8> = (0..=255).cycle().take(3 * DOWNLOAD_CHUNK_SIZE).collect();
+        let (_rt, mock_server) = start_file_server(content.clone());
+        let filesystem = MemoryFilesystem::default();
+        let mut hasher = Blake3Verifier.hasher();
+
+        let result = temp_env::with_var("IEXEC_WRITE_BUFFER_SIZE", Some("1024"), || {
+            download_file_and_hash(
+                &filesystem,
+                &format!("{}/input.bin", mock_server.uri()),
+                "/input",
+                FILE_NAME,
+                hasher.as_mut(),
+                &DownloadOptions::default(),
+            )
+        });
+
+        assert_eq!(result, Ok(PathBuf::from("/input").join(FILE_NAME)));
+        assert_eq!(hasher.finalize(), Blake3Verifier.checksum(&content));
+        assert_eq!(filesystem.file("/input/test.json"), Some(content));
+        assert!(!filesystem.exists(Path::new("/input/test.json.part")));
+    }
+
+    #[test]
+    fn test_download_file_and_hash_decodes_data_uri() {
+        let filesystem = MemoryFilesystem::default();
+        let mut hasher = Blake3Verifier.hasher();
+
+        let result = download_file_and_hash(
+            &filesystem,
+            "data:application/json;base64,eyJrZXkiOiJ2YWx1ZSJ9",
+            "/input",
+            FILE_NAME,
+            hasher.as_mut(),
+            &DownloadOptions::default(),
+        );
+
+        let content = br#"{"key":"value"}"#;
+        assert_eq!(result, Ok(PathBuf::from("/input").join(FILE_NAME)));
+        assert_eq!(hasher.finalize(), Blake3Verifier.checksum(content));
+        assert_eq!(filesystem.file("/input/test.json"), Some(content.to_vec()));
+    }
+
+    #[test]
+    fn test_download_file_rejects_data_uri_above_max_size() {
+        let filesystem = MemoryFilesystem::default();
+        let options = DownloadOptions {
+            max_size: Some(4),
+            ..Default::default()
+        };
+
+        let result = download_file(&filesystem, "data:,iExec", "/input", FILE_NAME, &options);
+
+        assert_eq!(result, Err(DownloadError::TooLarge { limit: 4 }));
+        assert!(!filesystem.exists(Path::new("/input/test.json")));
+    }
+
+    #[test]
+    fn test_download_file_and_hash_stages_partial_content_in_staging_dir() {
+        let content: Vec<u8> = (0..=255).cycle().take(3 * DOWNLOAD_CHUNK_SIZE).collect();
+        let (_rt, mock_server) = start_file_server(content.clone());
+        let filesystem = MemoryFilesystem::default();
+        filesystem.create_dir(Path::new("/staging")).unwrap();
+        let mut hasher = Blake3Verifier.hasher();
+        let options = DownloadOptions {
+            staging_dir: Some(PathBuf::from("/staging")),
+            ..Default::default()
+        };
+
+        let result = download_file_and_hash(
+            &filesystem,
+            &format!("{}/input.bin", mock_server.uri()),
+            "/input",
+            FILE_NAME,
+            hasher.as_mut(),
+            &options,
+        );
+
+        assert_eq!(result, Ok(PathBuf::from("/input").join(FILE_NAME)));
+        assert_eq!(filesystem.file("/input/test.json"), Some(content));
+        assert!(!filesystem.exists(Path::new("/staging/test.json.part")));
+        assert!(!filesystem.exists(Path::new("/input/test.json.part")));
+    }
+
+    #[test]
+    fn test_partial_path() {
+        let file_path = Path::new("/input/test.json");
+
+        assert_eq!(
+            partial_path(file_path, None),
+            Path::new("/input/test.json.part")
+        );
+        assert_eq!(
+            partial_path(file_path, Some(Path::new("/staging"))),
+            Path::new("/staging/test.json.part")
+        );
+    }
+
+    #[test]
+    fn test_hash_file_reads_file_in_chunks() {
+        let filesystem = MemoryFilesystem::default();
+        let content: Vec<u8> = (0..=255).cycle().take(2500).collect();
+        filesystem.write(Path::new("/input.bin"), &content).unwrap();
+        filesystem.write(Path::new("/empty.bin"), b"").unwrap();
+
+        temp_env::with_var("IEXEC_WRITE_BUFFER_SIZE", Some("1024"), || {
+            let mut hasher = Blake3Verifier.hasher();
+            hash_file(&filesystem, Path::new("/input.bin"), hasher.as_mut()).unwrap();
+            assert_eq!(hasher.finalize(), Blake3Verifier.checksum(&content));
+
+            let mut hasher = Blake3Verifier.hasher();
+            hash_file(&filesystem, Path::new("/empty.bin"), hasher.as_mut()).unwrap();
+            assert_eq!(hasher.finalize(), Blake3Verifier.checksum(b""));
+
+            let mut hasher = Blake3Verifier.hasher();
+            assert!(hash_file(&filesystem, Path::new("/missing.bin"), hasher.as_mut()).is_err());
+        });
+    }
+
+    #[test]
+    fn test_download_memory_threshold_defaults_when_invalid() {
+        temp_env::with_var("IEXEC_DOWNLOAD_MEMORY_THRESHOLD", Some("64k"), || {
+            assert_eq!(
+                download_memory_threshold(),
+                DEFAULT_DOWNLOAD_MEMORY_THRESHOLD
+            );
+        });
+        temp_env::with_var("IEXEC_DOWNLOAD_MEMORY_THRESHOLD", Some("0"), || {
+            assert_eq!(download_memory_threshold(), 0);
+        });
+    }
+    // endregion
+
+    // region Spool
+    #[test]
+    fn test_spool_keeps_content_below_threshold_in_memory() {
+        let filesystem = MemoryFilesystem::default();
+        let path = Path::new("/input.part");
+        let mut spool = Spool::spilling_to(&filesystem, path, 8);
+
+        spool.push(b"0123").unwrap();
+        spool.push(b"4567").unwrap();
+
+        assert!(spool.matches(2, b"2345").unwrap());
+        assert_eq!(
+            spool.finish().unwrap(),
+            Spooled::Memory(Bytes::from_static(b"01234567"))
+        );
+        assert!(!filesystem.exists(path));
+    }
+
+    #[test]
+    fn test_spool_spills_content_above_threshold() {
+        let filesystem = MemoryFilesystem::default();
+        let path = Path::new("/input.part");
+        let mut spool = Spool::spilling_to(&filesystem, path, 4);
+        spool.chunk_size = 3;
+
+        spool.push(b"0123").unwrap();
+        assert_eq!(filesystem.file(path), None);
+        spool.push(b"45").unwrap();
+        assert_eq!(filesystem.file(path), Some(b"012345".to_vec()));
+        spool.push(b"67").unwrap();
+        assert_eq!(filesystem.file(path), Some(b"012345".to_vec()));
+
+        assert_eq!(spool.len(), 8);
+        assert!(spool.matches(4, b"4567").unwrap());
+        assert!(!spool.matches(4, b"4x67").unwrap());
+        assert!(!spool.matches(4, b"456x").unwrap());
+        assert_eq!(spool.finish().unwrap(), Spooled::File(path));
+        assert_eq!(filesystem.file(path), Some(b"01234567".to_vec()));
+    }
+
+    #[test]
+    fn test_spool_reserves_disk_space_for_content_above_threshold() {
+        let filesystem = MemoryFilesystem::with_capacity(8);
+        let path = Path::new("/input.part");
+        let mut spool = Spool::spilling_to(&filesystem, path, 4);
+
+        assert_eq!(
+            spool.reserve(16).map_err(|e| e.kind()),
+            Err(io::ErrorKind::StorageFull)
+        );
+        spool.reserve(8).unwrap();
+        assert_eq!(filesystem.file(path), Some(Vec::new()));
+
+        spool.push(b"012345").unwrap();
+        spool.push(b"67").unwrap();
+        assert_eq!(spool.finish().unwrap(), Spooled::File(path));
+        assert_eq!(filesystem.file(path), Some(b"01234567".to_vec()));
+    }
+
+    #[test]
+    fn test_spool_removes_reserved_file_when_content_stays_below_threshold() {
+        let filesystem = MemoryFilesystem::default();
+        let path = Path::new("/input.part");
+        let mut spool = Spool::spilling_to(&filesystem, path, 4);
+
+        spool.reserve(8).unwrap();
+        spool.push(b"0123").unwrap();
+
+        assert_eq!(
+            spool.finish().unwrap(),
+            Spooled::Memory(Bytes::from_static(b"0123"))
+        );
+        assert!(!filesystem.exists(path));
+    }
+
+    #[test]
+    fn test_receive_into_restarts_stalled_transfer_of_spilled_content() {
+        let content: &[u8] = b"0123456789abcdef";
+        let url = serve_stalling(content, 6, 1);
+        let watchdog = StallWatchdog::new(Duration::from_millis(200));
+        let filesystem = MemoryFilesystem::default();
+        let path = Path::new("/input.part");
+        let mut spool = Spool::spilling_to(&filesystem, path, 4);
+
+        receive_into(
+            &url,
+            &DownloadOptions::default(),
+            &watchdog,
+            &mut |_| {},
+            &mut spool,
+        )
+        .unwrap();
+
+        assert_eq!(spool.finish().unwrap(), Spooled::File(path));
+        assert_eq!(filesystem.file(path), Some(content.to_vec()));
+    }
     // endregion
 
     // region download_from_url
@@ -287,22 +2147,44 @@ mod tests {
     fn test_download_from_url_success() {
         let (_container, container_url) = start_container();
 
-        let result = download_from_url(&container_url);
+        let result = download_from_url(&container_url, &DownloadOptions::default());
 
-        assert!(result.is_some());
+        assert!(result.is_ok());
         assert_json_eq_from_file(&result.unwrap(), EXPECTED_DATA_PATH);
     }
 
     #[test]
     fn test_download_from_url_with_empty_url() {
-        let result = download_from_url("");
-        assert!(result.is_none());
+        let result = download_from_url("", &DownloadOptions::default());
+        assert_eq!(result, Err(DownloadError::InvalidUrl));
     }
 
     #[test]
     fn test_download_from_url_with_invalid_url() {
-        let result = download_from_url("not-a-valid-url");
-        assert!(result.is_none());
+        let result = download_from_url("not-a-valid-url", &DownloadOptions::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_download_from_url_reports_status() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/missing"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        let result = download_from_url(
+            &format!("{}/missing", mock_server.uri()),
+            &DownloadOptions::default(),
+        );
+        assert_eq!(result, Err(DownloadError::Status(404)));
+        assert_eq!(result.unwrap_err().status(), Some(404));
     }
 
     #[test]
@@ -320,13 +2202,835 @@ mod tests {
         });
 
         let server_uri = mock_server.uri();
-        let result = download_from_url(&format!("{server_uri}/error"));
+        let result = download_from_url(&format!("{server_uri}/error"), &DownloadOptions::default());
 
-        assert!(result.is_none());
+        assert_eq!(result, Err(DownloadError::Status(500)));
     }
-    // endregion
 
-    // region write_file
+    #[test]
+    fn test_download_from_url_rejects_unrequested_partial_content() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(206).set_body_string("partial"))
+                .mount(&server)
+                .await;
+            server
+        });
+        let url = format!("{}/dataset.bin", mock_server.uri());
+
+        let result = download_from_url(&url, &DownloadOptions::default());
+        assert_eq!(result, Err(DownloadError::UnexpectedPartialContent));
+        assert_eq!(result.unwrap_err().status(), Some(206));
+
+        let mut hasher = Blake3Verifier.hasher();
+        assert_eq!(
+            download_and_hash(&url, hasher.as_mut(), &DownloadOptions::default()),
+            Err(DownloadError::UnexpectedPartialContent)
+        );
+    }
+    // endregion
+
+    // region download_and_hash
+    #[test]
+    fn test_download_and_hash_hashes_streamed_content() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let content = vec![7u8; 3 * DOWNLOAD_CHUNK_SIZE + 5];
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/dataset.bin"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(content.clone()))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        let verifier = Blake3Verifier;
+        let mut hasher = verifier.hasher();
+        let url = format!("{}/dataset.bin", mock_server.uri());
+        let result = download_and_hash(&url, hasher.as_mut(), &DownloadOptions::default());
+
+        assert_eq!(
+            result,
+            Ok(Download {
+                content: Bytes::from(content.clone()),
+                final_url: url,
+            })
+        );
+        assert_eq!(hasher.finalize(), verifier.checksum(&content));
+    }
+
+    #[test]
+    fn test_download_and_hash_aborts_when_content_exceeds_max_size() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/dataset.bin"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_bytes(vec![0u8; 2 * DOWNLOAD_CHUNK_SIZE]),
+                )
+                .mount(&server)
+                .await;
+            server
+        });
+        let url = format!("{}/dataset.bin", mock_server.uri());
+
+        let options = |max_size| DownloadOptions {
+            max_size: Some(max_size as u64),
+            ..Default::default()
+        };
+
+        let mut hasher = Blake3Verifier.hasher();
+        assert_eq!(
+            download_and_hash(&url, hasher.as_mut(), &options(DOWNLOAD_CHUNK_SIZE)),
+            Err(DownloadError::TooLarge {
+                limit: DOWNLOAD_CHUNK_SIZE as u64
+            })
+        );
+        let mut hasher = Blake3Verifier.hasher();
+        assert!(
+            download_and_hash(&url, hasher.as_mut(), &options(2 * DOWNLOAD_CHUNK_SIZE)).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_download_and_hash_rejects_plain_http_when_pinned() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&server)
+                .await;
+            server
+        });
+        let options = DownloadOptions {
+            spki_pins: vec!["7sJ/oG4X1OXwestb3XqGJlD4WE45Zlt++MKjbEToc/I=".to_string()],
+            ..Default::default()
+        };
+
+        let mut hasher = Blake3Verifier.hasher();
+        assert_eq!(
+            download_and_hash(
+                &format!("{}/dataset.bin", mock_server.uri()),
+                hasher.as_mut(),
+                &options
+            ),
+            Err(DownloadError::UntrustedCertificate)
+        );
+    }
+
+    #[test]
+    fn test_download_and_hash_records_final_url_after_redirects() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/alias"))
+                .respond_with(ResponseTemplate::new(302).insert_header("Location", "/dataset.bin"))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/dataset.bin"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        let mut hasher = Blake3Verifier.hasher();
+        let download = download_and_hash(
+            &format!("{}/alias", mock_server.uri()),
+            hasher.as_mut(),
+            &DownloadOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(download.content, Bytes::from_static(b"content"));
+        assert_eq!(
+            download.final_url,
+            format!("{}/dataset.bin", mock_server.uri())
+        );
+    }
+
+    #[test]
+    fn test_download_from_url_requests_s3_object_from_endpoint() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/datasets/encrypted/dataset.bin"))
+                .and(header_exists("authorization"))
+                .and(header_exists("x-amz-date"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        temp_env::with_vars(
+            [
+                (
+                    TeeSessionEnvironmentVariable::IexecS3Endpoint.name(),
+                    Some(mock_server.uri()),
+                ),
+                (
+                    TeeSessionEnvironmentVariable::IexecS3AccessKeyId.name(),
+                    Some("access-key".to_string()),
+                ),
+                (
+                    TeeSessionEnvironmentVariable::IexecS3SecretAccessKey.name(),
+                    Some("secret-key".to_string()),
+                ),
+            ],
+            || {
+                let content = download_from_url(
+                    "s3://datasets/encrypted/dataset.bin",
+                    &DownloadOptions::default(),
+                )
+                .unwrap();
+                assert_eq!(content, Bytes::from_static(b"content"));
+            },
+        );
+    }
+
+    #[test]
+    fn test_download_from_url_refuses_redirect_of_s3_request() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (mock_server, other_server) = rt.block_on(async {
+            let other_server = MockServer::start().await;
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/datasets/dataset.bin"))
+                .respond_with(ResponseTemplate::new(307).insert_header(
+                    "Location",
+                    format!("{}/dataset.bin", other_server.uri()).as_str(),
+                ))
+                .mount(&server)
+                .await;
+            (server, other_server)
+        });
+
+        temp_env::with_vars(
+            [
+                (
+                    TeeSessionEnvironmentVariable::IexecS3Endpoint.name(),
+                    Some(mock_server.uri()),
+                ),
+                (
+                    TeeSessionEnvironmentVariable::IexecS3AccessKeyId.name(),
+                    Some("access-key".to_string()),
+                ),
+                (
+                    TeeSessionEnvironmentVariable::IexecS3SecretAccessKey.name(),
+                    Some("secret-key".to_string()),
+                ),
+                (
+                    TeeSessionEnvironmentVariable::IexecS3SessionToken.name(),
+                    Some("session-token".to_string()),
+                ),
+            ],
+            || {
+                let result =
+                    download_from_url("s3://datasets/dataset.bin", &DownloadOptions::default());
+                assert!(matches!(result, Err(DownloadError::Unreachable(_))));
+            },
+        );
+        let forwarded = rt.block_on(other_server.received_requests()).unwrap();
+        assert!(forwarded.is_empty());
+    }
+
+    #[test]
+    fn test_download_and_hash_records_response_headers() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/dataset.bin"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("ETag", "\"v1\"")
+                        .insert_header("Last-Modified", "Wed, 21 Oct 2026 07:28:00 GMT")
+                        .insert_header("Server", "mirror-1")
+                        .set_body_string("content"),
+                )
+                .mount(&server)
+                .await;
+            server
+        });
+        let url = format!("{}/dataset.bin", mock_server.uri());
+        let options = DownloadOptions {
+            chain_task_id: Some("0xaudited-download".to_string()),
+            ..Default::default()
+        };
+
+        let mut hasher = Blake3Verifier.hasher();
+        download_and_hash(&url, hasher.as_mut(), &options).unwrap();
+
+        let line = events::recorded_lines()
+            .into_iter()
+            .find(|line| line.contains("0xaudited-download"))
+            .unwrap();
+        let line: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(line["event"], "download_response");
+        assert_eq!(line["url"], url);
+        assert_eq!(line["status"], 200);
+        assert_eq!(line["etag"], "\"v1\"");
+        assert_eq!(line["lastModified"], "Wed, 21 Oct 2026 07:28:00 GMT");
+        assert_eq!(line["server"], "mirror-1");
+    }
+
+    #[test]
+    fn test_client_builder_limits_redirects() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/alias"))
+                .respond_with(ResponseTemplate::new(302).insert_header("Location", "/dataset.bin"))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        temp_env::with_var(
+            TeeSessionEnvironmentVariable::IexecMaxRedirects.name(),
+            Some("0"),
+            || {
+                let result = client_builder(false)
+                    .build()
+                    .unwrap()
+                    .get(format!("{}/alias", mock_server.uri()))
+                    .send();
+                assert!(result.unwrap_err().is_redirect());
+            },
+        );
+    }
+
+    #[test]
+    fn test_download_and_hash_reports_status() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        let mut hasher = Blake3Verifier.hasher();
+        let result = download_and_hash(
+            &format!("{}/missing", mock_server.uri()),
+            hasher.as_mut(),
+            &DownloadOptions::default(),
+        );
+        assert_eq!(result, Err(DownloadError::Status(404)));
+    }
+    // endregion
+
+    // region check_url
+    fn start_head_mock_server(status: u16) -> (tokio::runtime::Runtime, MockServer) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("HEAD"))
+                .and(path("/file"))
+                .respond_with(ResponseTemplate::new(status).set_body_bytes(vec![0u8; 42]))
+                .mount(&server)
+                .await;
+            server
+        });
+        (rt, mock_server)
+    }
+
+    #[test]
+    fn test_check_url_success() {
+        let (_rt, mock_server) = start_head_mock_server(200);
+        let result = check_url(&format!("{}/file", mock_server.uri()));
+        assert_eq!(result, Ok(Some(42)));
+    }
+
+    #[test]
+    fn test_check_url_accepts_head_not_allowed() {
+        let (_rt, mock_server) = start_head_mock_server(405);
+        let result = check_url(&format!("{}/file", mock_server.uri()));
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn test_check_url_with_error_status() {
+        let (_rt, mock_server) = start_head_mock_server(403);
+        let result = check_url(&format!("{}/file", mock_server.uri()));
+        assert_eq!(result, Err(DownloadError::Status(403)));
+    }
+
+    #[test]
+    fn test_check_url_times_out_on_unanswered_request() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("HEAD"))
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+                .mount(&server)
+                .await;
+            server
+        });
+        let started_at = Instant::now();
+
+        let result = check_url_within(
+            &format!("{}/file", mock_server.uri()),
+            &[],
+            Duration::from_millis(200),
+        );
+
+        assert!(matches!(result, Err(DownloadError::Unreachable(_))));
+        assert!(started_at.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_check_url_decodes_data_uri() {
+        assert_eq!(check_url("data:,iExec"), Ok(Some(5)));
+        assert_eq!(check_url("data:;base64,%"), Err(DownloadError::InvalidUrl));
+    }
+
+    fn start_private_mock_server() -> (tokio::runtime::Runtime, MockServer) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(path("/private"))
+                .and(header("authorization", "Bearer token"))
+                .and(header("x-api-key", "key"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("private"))
+                .mount(&server)
+                .await;
+            server
+        });
+        (rt, mock_server)
+    }
+
+    #[test]
+    fn test_requests_carry_headers_of_options() {
+        let (_rt, mock_server) = start_private_mock_server();
+        let url = format!("{}/private", mock_server.uri());
+        let headers = vec![
+            ("Authorization".to_string(), "Bearer token".to_string()),
+            ("X-Api-Key".to_string(), "key".to_string()),
+        ];
+        let options = DownloadOptions {
+            headers: headers.clone(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            download_from_url(&url, &DownloadOptions::default()),
+            Err(DownloadError::Status(404))
+        );
+        assert_eq!(
+            download_from_url(&url, &options),
+            Ok(Bytes::from_static(b"private"))
+        );
+        assert_eq!(check_url_with_headers(&url, &headers), Ok(Some(7)));
+    }
+
+    #[test]
+    fn test_headers_of_options_are_not_sent_to_another_origin() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (server, other_server) = rt.block_on(async {
+            let other_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("private"))
+                .mount(&other_server)
+                .await;
+            let server = MockServer::start().await;
+            Mock::given(path("/moved"))
+                .respond_with(
+                    ResponseTemplate::new(302)
+                        .insert_header("Location", format!("{}/private", other_server.uri())),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(path("/renamed"))
+                .respond_with(ResponseTemplate::new(302).insert_header("Location", "/private"))
+                .mount(&server)
+                .await;
+            Mock::given(path("/private"))
+                .and(header("X-Api-Key", "key"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("private"))
+                .mount(&server)
+                .await;
+            (server, other_server)
+        });
+        let options = DownloadOptions {
+            headers: vec![("X-Api-Key".to_string(), "key".to_string())],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            download_from_url(&format!("{}/renamed", server.uri()), &options),
+            Ok(Bytes::from_static(b"private"))
+        );
+        assert!(matches!(
+            download_from_url(&format!("{}/moved", server.uri()), &options),
+            Err(DownloadError::Unreachable(_))
+        ));
+        let requests = rt.block_on(other_server.received_requests()).unwrap();
+        assert!(
+            requests
+                .iter()
+                .all(|request| !request.headers.contains_key("x-api-key"))
+        );
+    }
+
+    #[test]
+    fn test_check_url_with_invalid_url() {
+        let result = check_url("not-a-valid-url");
+        assert!(matches!(result, Err(DownloadError::Unreachable(_))));
+    }
+    // endregion
+
+    // region probe_url
+    #[test]
+    fn test_probe_url_requests_first_bytes_only() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(header("Range", "bytes=0-1023"))
+                .respond_with(ResponseTemplate::new(206).set_body_bytes(vec![0u8; 1024]))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/ignored-range"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0u8; 4096]))
+                .with_priority(1)
+                .mount(&server)
+                .await;
+            server
+        });
+
+        assert!(probe_url(&format!("{}/file", mock_server.uri())).is_ok());
+        assert!(probe_url(&format!("{}/ignored-range", mock_server.uri())).is_ok());
+    }
+
+    #[test]
+    fn test_probe_url_with_error_status() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let mock_server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            server
+        });
+
+        assert_eq!(
+            probe_url(&format!("{}/file", mock_server.uri())),
+            Err(DownloadError::Status(404))
+        );
+    }
+    // endregion
+
+    // region HostThrottle
+    #[test]
+    fn test_host_throttle_spaces_requests_to_same_host() {
+        let throttle = HostThrottle::new(Duration::from_millis(100));
+        let started_at = Instant::now();
+
+        throttle.wait("https://host-a/file-1");
+        throttle.wait("https://host-b/file-1");
+        assert!(started_at.elapsed() < Duration::from_millis(100));
+
+        throttle.wait("https://host-a/file-2");
+        throttle.wait("https://host-a/file-3");
+        assert!(started_at.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_host_throttle_disabled_by_default() {
+        temp_env::with_var_unset(
+            TeeSessionEnvironmentVariable::IexecHostRequestIntervalMs.name(),
+            || {
+                let throttle = HostThrottle::from_env();
+                assert!(throttle.interval.is_zero());
+            },
+        );
+        temp_env::with_var(
+            TeeSessionEnvironmentVariable::IexecHostRequestIntervalMs.name(),
+            Some("250"),
+            || {
+                let throttle = HostThrottle::from_env();
+                assert_eq!(throttle.interval, Duration::from_millis(250));
+            },
+        );
+    }
+    // endregion
+
+    // region StallWatchdog
+    /// Body yielding `content` then blocking for `stall` before ending.
+    struct StallingBody {
+        content: io::Cursor<Vec<u8>>,
+        stall: Duration,
+    }
+
+    impl Read for StallingBody {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.content.read(buf)? {
+                0 => {
+                    thread::sleep(self.stall);
+                    Ok(0)
+                }
+                read => Ok(read),
+            }
+        }
+    }
+
+    /// Serves `content` over HTTP, stalling after `stalled_bytes` bytes on the first
+    /// `stalled_connections` connections.
+    fn serve_stalling(
+        content: &'static [u8],
+        stalled_bytes: usize,
+        stalled_connections: usize,
+    ) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/dataset", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for (index, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request);
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    content.len()
+                );
+                stream.write_all(header.as_bytes()).unwrap();
+                if index < stalled_connections {
+                    stream.write_all(&content[..stalled_bytes]).unwrap();
+                    stream.flush().unwrap();
+                    thread::spawn(move || {
+                        thread::sleep(Duration::from_secs(5));
+                        drop(stream);
+                    });
+                } else {
+                    stream.write_all(content).unwrap();
+                }
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_stall_watchdog_aborts_stalled_body() {
+        let watchdog = StallWatchdog::new(Duration::from_millis(100));
+        let body = StallingBody {
+            content: io::Cursor::new(b"partial".to_vec()),
+            stall: Duration::from_secs(5),
+        };
+        let mut received = Vec::new();
+
+        let started_at = Instant::now();
+        let result = watchdog.read(URL, body, &CancellationToken::new(), &mut |chunk| {
+            received.extend_from_slice(chunk);
+            Ok(())
+        });
+
+        assert_eq!(result, Err(DownloadError::Stalled));
+        assert_eq!(received, b"partial");
+        assert!(started_at.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_stall_watchdog_disabled_reads_whole_body() {
+        let watchdog = StallWatchdog::new(Duration::ZERO);
+        let body = StallingBody {
+            content: io::Cursor::new(b"content".to_vec()),
+            stall: Duration::from_millis(200),
+        };
+        let mut received = Vec::new();
+
+        let result = watchdog.read(URL, body, &CancellationToken::new(), &mut |chunk| {
+            received.extend_from_slice(chunk);
+            Ok(())
+        });
+
+        assert!(!watchdog.is_enabled());
+        assert_eq!(result, Ok(()));
+        assert_eq!(received, b"content");
+    }
+
+    #[test]
+    fn test_receive_restarts_stalled_transfer_without_repeating_chunks() {
+        let content: &[u8] = b"0123456789abcdef";
+        let url = serve_stalling(content, 6, 1);
+        let watchdog = StallWatchdog::new(Duration::from_millis(200));
+        let mut chunks = Vec::new();
+
+        let download = receive(&url, &DownloadOptions::default(), &watchdog, &mut |chunk| {
+            chunks.extend_from_slice(chunk)
+        })
+        .unwrap();
+
+        assert_eq!(download.content, content);
+        assert_eq!(chunks, content);
+    }
+
+    #[test]
+    fn test_receive_fails_when_transfer_keeps_stalling() {
+        let url = serve_stalling(b"0123456789abcdef", 6, MAX_STALL_RETRIES + 1);
+        let watchdog = StallWatchdog::new(Duration::from_millis(100));
+
+        let result = receive(&url, &DownloadOptions::default(), &watchdog, &mut |_| {});
+
+        assert_eq!(result, Err(DownloadError::Stalled));
+    }
+
+    #[test]
+    fn test_receive_does_not_restart_when_retry_budget_exhausted() {
+        let url = serve_stalling(b"0123456789abcdef", 6, 1);
+        let watchdog = StallWatchdog::new(Duration::from_millis(100));
+        let options = DownloadOptions {
+            retry_budget: RetryBudget::new(Some(0), None),
+            ..Default::default()
+        };
+
+        let result = receive(&url, &options, &watchdog, &mut |_| {});
+
+        assert_eq!(result, Err(DownloadError::Stalled));
+        assert_eq!(options.retry_budget.usage().denied, 1);
+        assert_eq!(options.attempts(), 1);
+    }
+
+    #[test]
+    fn test_read_aborts_blocked_body_when_cancelled() {
+        let watchdog = StallWatchdog::new(Duration::ZERO);
+        let body = StallingBody {
+            content: io::Cursor::new(b"partial".to_vec()),
+            stall: Duration::from_secs(5),
+        };
+        let cancellation = CancellationToken::new();
+        let canceller = cancellation.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            canceller.cancel(ExitMode::Aborted);
+        });
+        let mut received = Vec::new();
+
+        let started_at = Instant::now();
+        let result = watchdog.read(URL, body, &cancellation, &mut |chunk| {
+            received.extend_from_slice(chunk);
+            Ok(())
+        });
+
+        assert_eq!(result, Err(DownloadError::Cancelled));
+        assert_eq!(received, b"partial");
+        assert!(started_at.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_download_from_url_fails_when_already_cancelled() {
+        let cancellation = CancellationToken::new();
+        cancellation.cancel(ExitMode::Timeout);
+
+        let options = DownloadOptions {
+            cancellation,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            download_from_url("http://127.0.0.1:9/file", &options),
+            Err(DownloadError::Cancelled)
+        );
+    }
+    // endregion
+
+    // region client_builder
+    #[cfg(feature = "compression")]
+    const GZIPPED_CONTENT: &[u8] = &[
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 11, 206, 207, 77, 85, 40, 75, 45, 170, 84, 40, 45, 78, 77,
+        43, 205, 81, 72, 73, 44, 73, 212, 3, 0, 17, 17, 176, 229, 22, 0, 0, 0,
+    ];
+
+    #[cfg(feature = "compression")]
+    fn download_with(compression: bool, server_uri: &str) -> Bytes {
+        client_builder(compression)
+            .build()
+            .unwrap()
+            .get(format!("{server_uri}/data.txt"))
+            .send()
+            .unwrap()
+            .bytes()
+            .unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_client_builder_decodes_compressed_content_when_enabled() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let server = rt.block_on(async {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/data.txt"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("Content-Encoding", "gzip")
+                        .set_body_bytes(GZIPPED_CONTENT),
+                )
+                .mount(&server)
+                .await;
+            server
+        });
+        let server_uri = server.uri();
+
+        assert_eq!(
+            download_with(true, &server_uri),
+            Bytes::from_static(b"Some very useful data.")
+        );
+        assert_eq!(
+            download_with(false, &server_uri),
+            Bytes::from_static(GZIPPED_CONTENT)
+        );
+    }
+
+    #[test]
+    fn test_compression_disabled_by_default() {
+        temp_env::with_var_unset(
+            TeeSessionEnvironmentVariable::IexecDownloadCompression.name(),
+            || assert!(!is_compression_enabled()),
+        );
+        temp_env::with_var(
+            TeeSessionEnvironmentVariable::IexecDownloadCompression.name(),
+            Some("true"),
+            || assert!(is_compression_enabled()),
+        );
+    }
+    // endregion
+
+    // region warm_http_client
+    #[test]
+    fn test_pre_resolve_hosts_fills_dns_cache() {
+        let cache = DnsCache::new(Duration::from_secs(60));
+        let urls = [
+            "http://localhost:8080",
+            "https://localhost/path",
+            "not-a-valid-url",
+            "http://unresolvable.invalid",
+        ];
+
+        assert_eq!(pre_resolve_hosts(Some(&cache), &urls), 1);
+        assert!(cache.resolve("localhost").is_ok());
+    }
+
+    #[test]
+    fn test_pre_resolve_hosts_without_dns_cache() {
+        assert_eq!(pre_resolve_hosts(None, &["http://localhost:8080"]), 0);
+    }
+    // endregion
+
+    // region write_file
     #[test]
     fn test_write_file_success() {
         let temp_dir = TempDir::new().unwrap();
@@ -341,15 +3045,70 @@ mod tests {
         assert_eq!(buf, content);
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_preallocate_reserves_file_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = File::create(temp_dir.path().join("preallocated.bin")).unwrap();
+
+        assert!(preallocate(&file, 4096).is_ok());
+        assert_eq!(file.metadata().unwrap().len(), 4096);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_preallocate_for_append_keeps_file_size() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = File::create(temp_dir.path().join("preallocated.bin")).unwrap();
+
+        assert!(preallocate_for_append(&file, 1024 * 1024).is_ok());
+        let metadata = file.metadata().unwrap();
+        assert_eq!(metadata.len(), 0);
+        assert!(metadata.blocks() * 512 >= 1024 * 1024);
+    }
+
     #[test]
     fn test_write_file_failure_invalid_path() {
-        let file_path = Path::new("/invalid_dir_123456789/test.txt");
+        let file_path = &std::env::temp_dir()
+            .join("invalid_dir_123456789")
+            .join("test.txt");
         let content = b"should fail";
         let context = "test_write_file_failure_invalid_path";
         let result = write_file(content, file_path, context);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_write_file_with_small_buffer_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("chunked.txt");
+        let content: Vec<u8> = (0..1000u32).map(|i| (i % 256) as u8).collect();
+        temp_env::with_var("IEXEC_WRITE_BUFFER_SIZE", Some("7"), || {
+            assert_eq!(write_buffer_size(), 7);
+            assert!(
+                write_file(
+                    &content,
+                    &file_path,
+                    "test_write_file_with_small_buffer_size"
+                )
+                .is_ok()
+            );
+        });
+        assert_eq!(fs::read(&file_path).unwrap(), content);
+    }
+
+    #[test]
+    fn test_write_buffer_size_defaults_when_invalid() {
+        temp_env::with_var("IEXEC_WRITE_BUFFER_SIZE", Some("not-a-number"), || {
+            assert_eq!(write_buffer_size(), DEFAULT_WRITE_BUFFER_SIZE);
+        });
+        temp_env::with_var_unset("IEXEC_WRITE_BUFFER_SIZE", || {
+            assert_eq!(write_buffer_size(), DEFAULT_WRITE_BUFFER_SIZE);
+        });
+    }
+
     #[test]
     fn test_write_file_overwrite() {
         let temp_dir = TempDir::new().unwrap();
@@ -363,4 +3122,137 @@ mod tests {
         assert_eq!(data, content2);
     }
     // endregion
+
+    // region open_no_follow
+    #[test]
+    #[cfg(unix)]
+    fn test_write_file_refuses_symlink_destination() {
+        let temp_dir = TempDir::new().unwrap();
+        let target_path = temp_dir.path().join("outside.txt");
+        fs::write(&target_path, b"original").unwrap();
+        let link_path = temp_dir.path().join("dataset.txt");
+        std::os::unix::fs::symlink(&target_path, &link_path).unwrap();
+
+        assert!(write_file(b"content", &link_path, "symlink").is_err());
+        assert_eq!(fs::read(&target_path).unwrap(), b"original");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_write_file_refuses_dangling_symlink_destination() {
+        let temp_dir = TempDir::new().unwrap();
+        let target_path = temp_dir.path().join("missing.txt");
+        let link_path = temp_dir.path().join("dataset.txt");
+        std::os::unix::fs::symlink(&target_path, &link_path).unwrap();
+
+        assert!(write_file(b"content", &link_path, "symlink").is_err());
+        assert!(!target_path.exists());
+    }
+    // endregion
+
+    // region OutputFilePermissions
+    #[test]
+    fn test_output_file_permissions_from_env() {
+        temp_env::with_vars(
+            vec![
+                ("IEXEC_OUTPUT_FILE_UID", Some("1000")),
+                ("IEXEC_OUTPUT_FILE_GID", Some("not-a-number")),
+                ("IEXEC_OUTPUT_FILE_MODE", Some("0640")),
+            ],
+            || {
+                assert_eq!(
+                    OutputFilePermissions::from_env(),
+                    OutputFilePermissions {
+                        uid: Some(1000),
+                        gid: None,
+                        mode: Some(0o640),
+                    }
+                );
+            },
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_write_file_applies_output_file_permissions() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("permissions.txt");
+        let current_uid = fs::metadata(temp_dir.path()).unwrap().uid().to_string();
+        temp_env::with_vars(
+            vec![
+                ("IEXEC_OUTPUT_FILE_UID", Some(current_uid.as_str())),
+                ("IEXEC_OUTPUT_FILE_MODE", Some("0o604")),
+            ],
+            || {
+                assert!(write_file(b"content", &file_path, "permissions").is_ok());
+            },
+        );
+        let metadata = fs::metadata(&file_path).unwrap();
+        assert_eq!(metadata.mode() & 0o777, 0o604);
+        assert_eq!(metadata.uid().to_string(), current_uid);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_apply_keeps_to_opened_file_when_path_is_replaced_by_symlink() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("output.txt");
+        let target_path = temp_dir.path().join("target.txt");
+        fs::write(&target_path, b"target").unwrap();
+        fs::set_permissions(&target_path, Permissions::from_mode(0o600)).unwrap();
+        let file = File::create(&file_path).unwrap();
+        fs::remove_file(&file_path).unwrap();
+        std::os::unix::fs::symlink(&target_path, &file_path).unwrap();
+
+        let permissions = OutputFilePermissions {
+            mode: Some(0o604),
+            ..Default::default()
+        };
+        permissions.apply(&file).unwrap();
+
+        assert_eq!(file.metadata().unwrap().mode() & 0o777, 0o604);
+        assert_eq!(fs::metadata(&target_path).unwrap().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_apply_to_dir_adds_execute_bits_to_readable_classes() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let permissions = OutputFilePermissions {
+            mode: Some(0o640),
+            ..Default::default()
+        };
+
+        permissions.apply_to_dir(temp_dir.path()).unwrap();
+
+        let metadata = fs::metadata(temp_dir.path()).unwrap();
+        assert_eq!(metadata.mode() & 0o777, 0o750);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_apply_to_dir_refuses_symlink() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let target_dir = temp_dir.path().join("target");
+        let link_dir = temp_dir.path().join("link");
+        fs::create_dir(&target_dir).unwrap();
+        fs::set_permissions(&target_dir, Permissions::from_mode(0o700)).unwrap();
+        std::os::unix::fs::symlink(&target_dir, &link_dir).unwrap();
+        let permissions = OutputFilePermissions {
+            mode: Some(0o644),
+            ..Default::default()
+        };
+
+        assert!(permissions.apply_to_dir(&link_dir).is_err());
+        assert_eq!(fs::metadata(&target_dir).unwrap().mode() & 0o777, 0o700);
+    }
+    // endregion
 }
@@ -0,0 +1,487 @@
+use crate::compute::utils::file_utils::{
+    OutputFilePermissions, open_no_follow, preallocate_for_append, write_in_chunks,
+};
+#[cfg(unix)]
+use std::fs::Permissions;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+
+/// Metadata of a file or directory returned by [`Filesystem::stat`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FileStat {
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+/// File system operations performed by the pre-compute stage.
+///
+/// [`PreComputeApp`](crate::compute::pre_compute_app::PreComputeApp) goes through this trait
+/// instead of [`std::fs`], so that its logic can be tested against [`MemoryFilesystem`] and so
+/// that other backends (e.g. Gramine protected files) can be swapped in.
+///
+/// Input files can be downloaded concurrently, so implementations must be thread-safe.
+pub trait Filesystem: Send + Sync {
+    /// Creates or truncates the file at `path` and writes `content` to it.
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()>;
+    /// Creates or truncates the file at `path`, restricted to its owner, and writes `content`
+    /// to it. Unlike [`Filesystem::write`], no configured permissions are applied.
+    fn write_private(&self, path: &Path, content: &[u8]) -> io::Result<()>;
+    /// Creates or truncates the file at `path` and reserves `len` bytes of disk space for
+    /// content appended to it later, without changing its size.
+    fn allocate(&self, path: &Path, len: u64) -> io::Result<()>;
+    /// Appends `content` to the existing file at `path`.
+    fn append(&self, path: &Path, content: &[u8]) -> io::Result<()>;
+    /// Reads the whole content of the file at `path`.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    /// Fills `buf` with the content of the file at `path` starting at `offset`.
+    fn read_at(&self, path: &Path, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+    /// Creates the directory at `path` and all its missing parents. An existing entry at `path`
+    /// that is not a directory, such as a symbolic link to one, is refused.
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+    /// Returns whether a file or directory exists at `path`.
+    fn exists(&self, path: &Path) -> bool;
+    /// Moves the file at `from` to `to`, replacing any existing file.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    /// Returns the metadata of the file or directory at `path`.
+    fn stat(&self, path: &Path) -> io::Result<FileStat>;
+    /// Removes the file at `path`.
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    /// Removes the directory at `path` with all its content.
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    /// Returns the number of bytes available for new content on the volume holding `path`.
+    fn available_space(&self, path: &Path) -> io::Result<u64>;
+}
+
+/// [`Filesystem`] backed by [`std::fs`].
+///
+/// Writes refuse symbolic links and apply the configured [`OutputFilePermissions`], which are
+/// also applied to created directories.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdFilesystem;
+
+impl Filesystem for StdFilesystem {
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        write_in_chunks(content, path)
+    }
+
+    fn write_private(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = open_no_follow(path, &mut options)?;
+        // The mode above only applies when the file is created.
+        #[cfg(unix)]
+        file.set_permissions(Permissions::from_mode(0o600))?;
+        file.write_all(content)
+    }
+
+    fn allocate(&self, path: &Path, len: u64) -> io::Result<()> {
+        let file = open_no_follow(
+            path,
+            OpenOptions::new().write(true).create(true).truncate(true),
+        )?;
+        OutputFilePermissions::from_env().apply(&file)?;
+        preallocate_for_append(&file, len)
+    }
+
+    fn append(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        open_no_follow(path, OpenOptions::new().append(true))?.write_all(content)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn read_at(&self, path: &Path, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)?;
+        // create_dir_all accepts a symbolic link to an existing directory, which would redirect
+        // everything written below `path`.
+        if !fs::symlink_metadata(path)?.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", path.display()),
+            ));
+        }
+        OutputFilePermissions::from_env().apply_to_dir(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<FileStat> {
+        fs::metadata(path).map(|metadata| FileStat {
+            size: metadata.len(),
+            is_dir: metadata.is_dir(),
+        })
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+        // SAFETY: `path` is a valid C string and `stat` is only read once initialized.
+        if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `statvfs` succeeded, so it initialized `stat`.
+        let stat = unsafe { stat.assume_init() };
+        // The width of these fields depends on the target.
+        #[allow(clippy::unnecessary_cast)]
+        Ok((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+    }
+
+    /// Available space is not checked outside Linux.
+    #[cfg(not(target_os = "linux"))]
+    fn available_space(&self, _path: &Path) -> io::Result<u64> {
+        Ok(u64::MAX)
+    }
+}
+
+#[cfg(test)]
+pub use memory::MemoryFilesystem;
+
+#[cfg(test)]
+mod memory {
+    use super::{FileStat, Filesystem};
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    /// In-memory [`Filesystem`] for tests.
+    ///
+    /// The root directory `/` always exists. Writing a file requires its parent directory to
+    /// exist, as with [`std::fs`]. A capacity can be set to simulate a full disk.
+    #[derive(Debug, Default)]
+    pub struct MemoryFilesystem {
+        files: Mutex<BTreeMap<PathBuf, Vec<u8>>>,
+        dirs: Mutex<BTreeSet<PathBuf>>,
+        capacity: Option<usize>,
+    }
+
+    impl MemoryFilesystem {
+        /// Creates a file system holding at most `capacity` bytes of file content.
+        pub fn with_capacity(capacity: usize) -> Self {
+            MemoryFilesystem {
+                capacity: Some(capacity),
+                ..Default::default()
+            }
+        }
+
+        /// Returns the content of the file at `path`, if any.
+        pub fn file(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+            self.files.lock().unwrap().get(path.as_ref()).cloned()
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            path == Path::new("/") || self.dirs.lock().unwrap().contains(path)
+        }
+
+        /// Fails if replacing the file at `path` with `size` bytes exceeds the capacity.
+        fn check_capacity(&self, path: &Path, size: usize) -> io::Result<()> {
+            let Some(capacity) = self.capacity else {
+                return Ok(());
+            };
+            let used: usize = self
+                .files
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(file_path, _)| file_path.as_path() != path)
+                .map(|(_, file_content)| file_content.len())
+                .sum();
+            if used + size > capacity {
+                return Err(io::Error::from(io::ErrorKind::StorageFull));
+            }
+            Ok(())
+        }
+
+        fn not_found(path: &Path) -> io::Error {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} not found", path.display()),
+            )
+        }
+    }
+
+    impl Filesystem for MemoryFilesystem {
+        fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+            match path.parent() {
+                Some(parent) if self.is_dir(parent) => {}
+                _ => return Err(Self::not_found(path)),
+            }
+            if self.is_dir(path) {
+                return Err(io::Error::new(
+                    io::ErrorKind::IsADirectory,
+                    "is a directory",
+                ));
+            }
+            self.check_capacity(path, content.len())?;
+            self.files
+                .lock()
+                .unwrap()
+                .insert(path.to_path_buf(), content.to_vec());
+            Ok(())
+        }
+
+        fn write_private(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+            self.write(path, content)
+        }
+
+        fn allocate(&self, path: &Path, len: u64) -> io::Result<()> {
+            self.check_capacity(path, len as usize)?;
+            self.write(path, &[])
+        }
+
+        fn append(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+            let size = self.stat(path)?.size as usize;
+            self.check_capacity(path, size + content.len())?;
+            self.files
+                .lock()
+                .unwrap()
+                .get_mut(path)
+                .ok_or_else(|| Self::not_found(path))?
+                .extend_from_slice(content);
+            Ok(())
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.file(path).ok_or_else(|| Self::not_found(path))
+        }
+
+        fn read_at(&self, path: &Path, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+            let files = self.files.lock().unwrap();
+            let content = files.get(path).ok_or_else(|| Self::not_found(path))?;
+            let range = usize::try_from(offset)
+                .ok()
+                .and_then(|start| content.get(start..start.checked_add(buf.len())?))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+            buf.copy_from_slice(range);
+            Ok(())
+        }
+
+        fn create_dir(&self, path: &Path) -> io::Result<()> {
+            let mut dirs = self.dirs.lock().unwrap();
+            for ancestor in path.ancestors().filter(|a| !a.as_os_str().is_empty()) {
+                dirs.insert(ancestor.to_path_buf());
+            }
+            Ok(())
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.is_dir(path) || self.files.lock().unwrap().contains_key(path)
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            let content = self
+                .files
+                .lock()
+                .unwrap()
+                .remove(from)
+                .ok_or_else(|| Self::not_found(from))?;
+            self.write(to, &content)
+        }
+
+        fn stat(&self, path: &Path) -> io::Result<FileStat> {
+            if self.is_dir(path) {
+                return Ok(FileStat {
+                    size: 0,
+                    is_dir: true,
+                });
+            }
+            self.files
+                .lock()
+                .unwrap()
+                .get(path)
+                .map(|content| FileStat {
+                    size: content.len() as u64,
+                    is_dir: false,
+                })
+                .ok_or_else(|| Self::not_found(path))
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            self.files
+                .lock()
+                .unwrap()
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| Self::not_found(path))
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+            if !self.is_dir(path) {
+                return Err(Self::not_found(path));
+            }
+            self.dirs
+                .lock()
+                .unwrap()
+                .retain(|dir| !dir.starts_with(path));
+            self.files
+                .lock()
+                .unwrap()
+                .retain(|file, _| !file.starts_with(path));
+            Ok(())
+        }
+
+        fn available_space(&self, _path: &Path) -> io::Result<u64> {
+            let Some(capacity) = self.capacity else {
+                return Ok(u64::MAX);
+            };
+            let used: usize = self.files.lock().unwrap().values().map(Vec::len).sum();
+            Ok(capacity.saturating_sub(used) as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn exercise(filesystem: &dyn Filesystem, root: &Path) {
+        let dir = root.join("a/b");
+        let file = dir.join("file.txt");
+
+        assert!(filesystem.write(&file, b"content").is_err());
+        filesystem.create_dir(&dir).unwrap();
+        assert!(filesystem.stat(&dir).unwrap().is_dir);
+
+        filesystem.write(&file, b"content").unwrap();
+        assert!(filesystem.exists(&file));
+        assert_eq!(
+            filesystem.stat(&file).unwrap(),
+            FileStat {
+                size: 7,
+                is_dir: false
+            }
+        );
+        assert_eq!(filesystem.read(&file).unwrap(), b"content");
+
+        filesystem.append(&file, b" appended").unwrap();
+        let mut buf = [0u8; 8];
+        filesystem.read_at(&file, 5, &mut buf).unwrap();
+        assert_eq!(&buf, b"nt appen");
+        assert!(filesystem.read_at(&file, 10, &mut buf).is_err());
+        assert!(
+            filesystem
+                .append(&dir.join("missing.txt"), b"content")
+                .is_err()
+        );
+        filesystem.write(&file, b"content").unwrap();
+
+        let renamed = dir.join("renamed.txt");
+        filesystem.rename(&file, &renamed).unwrap();
+        assert!(!filesystem.exists(&file));
+        assert_eq!(filesystem.read(&renamed).unwrap(), b"content");
+
+        filesystem.remove_file(&renamed).unwrap();
+        assert!(filesystem.read(&renamed).is_err());
+
+        assert!(filesystem.available_space(&dir).unwrap() > 0);
+        filesystem.write(&file, b"content").unwrap();
+        filesystem.remove_dir_all(&root.join("a")).unwrap();
+        assert!(!filesystem.exists(&dir));
+        assert!(!filesystem.exists(&file));
+    }
+
+    #[test]
+    fn std_filesystem_performs_operations() {
+        let temp_dir = TempDir::new().unwrap();
+        exercise(&StdFilesystem, temp_dir.path());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn std_filesystem_refuses_to_append_through_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let target_path = temp_dir.path().join("outside.txt");
+        fs::write(&target_path, b"original").unwrap();
+        let link_path = temp_dir.path().join("dataset.txt.part");
+        std::os::unix::fs::symlink(&target_path, &link_path).unwrap();
+
+        let result = StdFilesystem.append(&link_path, b"content");
+
+        assert_eq!(
+            result.map_err(|e| e.kind()),
+            Err(io::ErrorKind::InvalidInput)
+        );
+        assert_eq!(fs::read(&target_path).unwrap(), b"original");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn std_filesystem_refuses_to_create_dir_over_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let target_dir = temp_dir.path().join("outside");
+        fs::create_dir(&target_dir).unwrap();
+        let link_dir = temp_dir.path().join("0xtask");
+        std::os::unix::fs::symlink(&target_dir, &link_dir).unwrap();
+
+        let result = StdFilesystem.create_dir(&link_dir);
+
+        assert_eq!(
+            result.map_err(|e| e.kind()),
+            Err(io::ErrorKind::InvalidInput)
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn std_filesystem_writes_private_file_for_owner_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("dataset.key");
+        fs::write(&file_path, b"previous").unwrap();
+        fs::set_permissions(&file_path, Permissions::from_mode(0o644)).unwrap();
+
+        temp_env::with_var("IEXEC_OUTPUT_FILE_MODE", Some("0644"), || {
+            StdFilesystem.write_private(&file_path, b"key").unwrap();
+        });
+
+        let metadata = fs::metadata(&file_path).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read(&file_path).unwrap(), b"key");
+    }
+
+    #[test]
+    fn memory_filesystem_behaves_like_std_filesystem() {
+        exercise(&MemoryFilesystem::default(), Path::new("/root-dir"));
+    }
+
+    #[test]
+    fn memory_filesystem_reports_space_left_by_capacity() {
+        let filesystem = MemoryFilesystem::with_capacity(10);
+        filesystem
+            .write(Path::new("/file.txt"), b"content")
+            .unwrap();
+
+        assert_eq!(filesystem.available_space(Path::new("/")).unwrap(), 3);
+    }
+}
@@ -1,10 +1,15 @@
+use cid::Cid;
+use log::debug;
 use sha3::{Digest, Keccak256};
 use sha256::digest;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 pub fn concatenate_and_hash(hexa_strings: &[&str]) -> String {
     let mut hasher = Keccak256::default();
     for hexa_string in hexa_strings {
-        println!("value {hexa_string}");
+        debug!("value {hexa_string}");
         hasher.update(hex_string_to_byte_array(hexa_string));
     }
     format!("0x{:x}", hasher.finalize())
@@ -18,7 +23,7 @@ pub fn hex_string_to_byte_array(input: &str) -> Vec<u8> {
     }
 
     let mut data: Vec<u8> = vec![];
-    let start_idx = if len % 2 != 0 {
+    let start_idx = if !len.is_multiple_of(2) {
         let byte = u8::from_str_radix(&clean_input[0..1], 16).expect("");
         data.push(byte);
         1
@@ -41,10 +46,102 @@ pub fn sha256(input: String) -> String {
     format!("0x{}", digest(input))
 }
 
+#[cfg(any(test, feature = "bench"))]
 pub fn sha256_from_bytes(bytes: &[u8]) -> String {
     format!("0x{}", digest(bytes))
 }
 
+/// A normalized checksum: either a 32-byte hex digest or an IPFS CID.
+///
+/// Hex digests are accepted with or without the `0x` prefix and in any case, and are stored
+/// with the `0x` prefix in lowercase, so that `"ABC…"` and `"0xabc…"` compare equal. CIDs are
+/// stored in their canonical string form. [`Display`](fmt::Display) renders the stored form.
+///
+/// # Example
+///
+/// ```
+/// let expected: Checksum = "0xB33845DB05FB0822F1F1E3677CC6787B8A1A7A21F3C12F9E97C70CB596222218".parse()?;
+/// assert_eq!(Checksum::sha256_of(b"utf8String"), expected);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Checksum(String);
+
+impl Checksum {
+    /// Computes the SHA-256 checksum of `bytes`.
+    #[cfg(test)]
+    pub fn sha256_of(bytes: &[u8]) -> Self {
+        Checksum(format!("0x{}", digest(bytes)))
+    }
+
+    /// Wraps a raw 32-byte digest computed by any hash function.
+    pub fn from_digest(digest: &[u8; 32]) -> Self {
+        let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+        Checksum(format!("0x{hex}"))
+    }
+
+    /// Wraps an IPFS CID.
+    pub fn from_cid(cid: &Cid) -> Self {
+        Checksum(cid.to_string())
+    }
+}
+
+impl FromStr for Checksum {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let hex = clean_hex_prefix(value);
+        let hex = hex.strip_prefix("0X").unwrap_or(hex);
+        if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(Checksum(format!("0x{}", hex.to_lowercase())));
+        }
+        Cid::try_from(value)
+            .map(|cid| Checksum::from_cid(&cid))
+            .map_err(|_| ())
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Parses the content of a `SHA256SUMS`-style file, as produced by `sha256sum`.
+///
+/// Each non-empty line holds a [`Checksum`] followed by whitespace and a
+/// file name, optionally prefixed with `*` (binary mode). Lines starting with `#` are
+/// ignored.
+///
+/// # Returns
+///
+/// * `Ok(HashMap<String, Checksum>)` mapping each file name to its checksum.
+/// * `Err(usize)` with the 1-based number of the first malformed line.
+///
+/// # Example
+///
+/// ```
+/// let checksums = parse_sha256sums("e3b0c442...b855  empty.txt\n")?;
+/// ```
+pub fn parse_sha256sums(content: &str) -> Result<HashMap<String, Checksum>, usize> {
+    let mut checksums = HashMap::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (checksum, name) = line.split_once(char::is_whitespace).ok_or(index + 1)?;
+        let name = name.trim_start();
+        let name = name.strip_prefix('*').unwrap_or(name);
+        let checksum = checksum.parse::<Checksum>().map_err(|_| index + 1)?;
+        if name.is_empty() {
+            return Err(index + 1);
+        }
+        checksums.insert(name.to_string(), checksum);
+    }
+    Ok(checksums)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,4 +199,58 @@ mod tests {
             sha256(String::from("utf8String"))
         )
     }
+
+    #[test]
+    fn parse_sha256sums_reads_text_and_binary_entries() {
+        let digest = "B33845DB05FB0822F1F1E3677CC6787B8A1A7A21F3C12F9E97C70CB596222218";
+        let content = format!("# checksums\n{digest}  input-1.txt\n\n{digest} *input 2.bin\n");
+
+        let checksums = parse_sha256sums(&content).unwrap();
+
+        assert_eq!(checksums.len(), 2);
+        assert_eq!(checksums["input-1.txt"], digest.parse().unwrap());
+        assert_eq!(checksums["input 2.bin"], digest.parse().unwrap());
+    }
+
+    #[test]
+    fn parse_sha256sums_reports_malformed_line() {
+        let digest = "b33845db05fb0822f1f1e3677cc6787b8a1a7a21f3c12f9e97c70cb596222218";
+        assert_eq!(
+            parse_sha256sums(&format!("{digest}  ok.txt\nnot-a-digest  ko.txt")),
+            Err(2)
+        );
+        assert_eq!(parse_sha256sums(digest), Err(1));
+    }
+
+    #[test]
+    fn checksum_normalizes_prefix_and_case() {
+        let lowercase = "b33845db05fb0822f1f1e3677cc6787b8a1a7a21f3c12f9e97c70cb596222218";
+        let expected = Checksum::sha256_of(b"utf8String");
+        for value in [
+            lowercase.to_string(),
+            format!("0x{lowercase}"),
+            lowercase.to_uppercase(),
+            format!("0x{}", lowercase.to_uppercase()),
+            format!(" {lowercase} "),
+        ] {
+            assert_eq!(value.parse::<Checksum>(), Ok(expected.clone()), "{value}");
+        }
+        assert_eq!(expected.to_string(), format!("0x{lowercase}"));
+    }
+
+    #[test]
+    fn checksum_accepts_cids() {
+        let cid = "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+        let checksum = cid.parse::<Checksum>().unwrap();
+        assert_eq!(checksum.to_string(), cid);
+        let cid_v0 = "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH";
+        assert_eq!(cid_v0.parse::<Checksum>().unwrap().to_string(), cid_v0);
+    }
+
+    #[test]
+    fn checksum_rejects_invalid_values() {
+        for value in ["", "0x", "0x123checksum", &"g".repeat(64), &"a".repeat(63)] {
+            assert_eq!(value.parse::<Checksum>(), Err(()), "{value}");
+        }
+    }
 }
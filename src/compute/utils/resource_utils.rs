@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+/// Resources consumed by the process since it started.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ResourceUsage {
+    /// User and system CPU time.
+    pub cpu_time: Duration,
+    /// Peak resident set size, in kilobytes.
+    pub peak_rss_kb: u64,
+}
+
+impl ResourceUsage {
+    /// Returns the CPU time spent since `earlier` was sampled.
+    pub fn cpu_time_since(&self, earlier: &ResourceUsage) -> Duration {
+        self.cpu_time.saturating_sub(earlier.cpu_time)
+    }
+}
+
+/// Samples the resources consumed by the current process with `getrusage`.
+///
+/// # Returns
+///
+/// * `Some(ResourceUsage)` with the CPU time and peak RSS of the process.
+/// * `None` if the platform does not support `getrusage`, which happens in some enclaves.
+#[cfg(target_os = "linux")]
+pub fn sample() -> Option<ResourceUsage> {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: `usage` is a valid pointer to a `rusage` structure, filled on success.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    // SAFETY: `getrusage` succeeded, so the structure is initialized.
+    let usage = unsafe { usage.assume_init() };
+    Some(ResourceUsage {
+        cpu_time: timeval_to_duration(usage.ru_utime) + timeval_to_duration(usage.ru_stime),
+        // `ru_maxrss` is expressed in kilobytes on Linux.
+        peak_rss_kb: u64::try_from(usage.ru_maxrss).unwrap_or_default(),
+    })
+}
+
+/// Resource sampling is only implemented on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn sample() -> Option<ResourceUsage> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn timeval_to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(u64::try_from(time.tv_sec).unwrap_or_default())
+        + Duration::from_micros(u64::try_from(time.tv_usec).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn sample_reports_peak_rss_and_monotonic_cpu_time() {
+        let first = sample().unwrap();
+        let mut value = 0u64;
+        for i in 0..5_000_000u64 {
+            value = std::hint::black_box(value.wrapping_add(i * i));
+        }
+        let second = sample().unwrap();
+
+        assert!(first.peak_rss_kb > 0);
+        assert!(second.peak_rss_kb >= first.peak_rss_kb);
+        assert!(second.cpu_time >= first.cpu_time);
+    }
+
+    #[test]
+    fn cpu_time_since_never_underflows() {
+        let earlier = ResourceUsage {
+            cpu_time: Duration::from_millis(30),
+            peak_rss_kb: 1024,
+        };
+        let later = ResourceUsage {
+            cpu_time: Duration::from_millis(80),
+            peak_rss_kb: 2048,
+        };
+
+        assert_eq!(later.cpu_time_since(&earlier), Duration::from_millis(50));
+        assert_eq!(earlier.cpu_time_since(&later), Duration::ZERO);
+    }
+}
@@ -0,0 +1,146 @@
+use log::warn;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Extra attempts consumed from a [`RetryBudget`], as written in the run report.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryUsage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_secs: Option<u64>,
+    /// Extra attempts made.
+    pub attempts: u32,
+    /// Time spent in extra attempts.
+    pub duration_ms: u64,
+    /// Extra attempts refused because the budget was exhausted.
+    pub denied: u32,
+}
+
+/// Bounds the extra attempts made by every download of a run.
+///
+/// Restarting a stalled transfer or falling back to another gateway is an extra attempt.
+/// The budget is shared by the dataset and all input files, so that a task with many flaky
+/// URLs cannot multiply retries unboundedly: once `max_attempts` extra attempts were made,
+/// or `max_duration` was spent in extra attempts, failures are no longer retried. Clones
+/// share the same budget, which is unlimited by default.
+#[derive(Debug, Clone, Default)]
+pub struct RetryBudget {
+    max_attempts: Option<u32>,
+    max_duration: Option<Duration>,
+    usage: Arc<Mutex<RetryUsage>>,
+}
+
+/// Extra attempt granted by a [`RetryBudget`], whose duration is charged to the budget when
+/// the permit is dropped.
+pub struct RetryPermit {
+    started_at: Instant,
+    usage: Arc<Mutex<RetryUsage>>,
+}
+
+impl RetryBudget {
+    pub fn new(max_attempts: Option<u32>, max_duration: Option<Duration>) -> Self {
+        RetryBudget {
+            max_attempts,
+            max_duration,
+            usage: Arc::new(Mutex::new(RetryUsage {
+                max_attempts,
+                max_secs: max_duration.map(|duration| duration.as_secs()),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Takes one extra attempt from the budget, before retrying `url`.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(RetryPermit)` if the attempt can be made, its duration being charged to the
+    ///   budget once the permit is dropped.
+    /// * `None` if the budget is exhausted.
+    pub fn acquire(&self, url: &str) -> Option<RetryPermit> {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let is_exhausted = self
+            .max_attempts
+            .is_some_and(|max_attempts| usage.attempts >= max_attempts)
+            || self.max_duration.is_some_and(|max_duration| {
+                Duration::from_millis(usage.duration_ms) >= max_duration
+            });
+        if is_exhausted {
+            usage.denied += 1;
+            warn!(
+                "Retry budget exhausted [url:{url}, attempts:{}, durationMs:{}]",
+                usage.attempts, usage.duration_ms
+            );
+            return None;
+        }
+        usage.attempts += 1;
+        Some(RetryPermit {
+            started_at: Instant::now(),
+            usage: self.usage.clone(),
+        })
+    }
+
+    /// Returns the extra attempts consumed so far.
+    pub fn usage(&self) -> RetryUsage {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl Drop for RetryPermit {
+    fn drop(&mut self) {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        usage.duration_ms += self.started_at.elapsed().as_millis() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const URL: &str = "https://host/file";
+
+    #[test]
+    fn acquire_is_unlimited_by_default() {
+        let budget = RetryBudget::default();
+
+        for _ in 0..100 {
+            assert!(budget.acquire(URL).is_some());
+        }
+        assert_eq!(budget.usage().attempts, 100);
+    }
+
+    #[test]
+    fn acquire_is_shared_by_clones_and_bounded_by_attempts() {
+        let budget = RetryBudget::new(Some(2), None);
+        let clone = budget.clone();
+
+        assert!(budget.acquire(URL).is_some());
+        assert!(clone.acquire(URL).is_some());
+        assert!(budget.acquire(URL).is_none());
+        assert_eq!(
+            clone.usage(),
+            RetryUsage {
+                max_attempts: Some(2),
+                attempts: 2,
+                denied: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn acquire_is_bounded_by_time_spent_in_retries() {
+        let budget = RetryBudget::new(None, Some(Duration::from_millis(50)));
+
+        let permit = budget.acquire(URL).unwrap();
+        thread::sleep(Duration::from_millis(60));
+        drop(permit);
+
+        assert!(budget.acquire(URL).is_none());
+        assert!(budget.usage().duration_ms >= 60);
+    }
+}
@@ -0,0 +1,136 @@
+/// Low byte of the AES reducing polynomial x^8 + x^4 + x^3 + x + 1.
+const GF256_REDUCING_POLYNOMIAL: u8 = 0x1b;
+
+/// Recombines a secret split with Shamir's secret sharing over GF(2^8).
+///
+/// Each share holds one byte per byte of the secret followed by its x coordinate. The secret is
+/// the value at `x = 0` of the polynomials interpolated through the shares.
+///
+/// Combining fewer shares than the threshold used to split the secret gives a wrong secret
+/// without any error, so callers must check the number of shares beforehand.
+///
+/// # Returns
+///
+/// * `Some(Vec<u8>)` containing the secret.
+/// * `None` if there are no shares, if they have different or zero lengths, or if two
+///   shares have the same x coordinate or one has a zero x coordinate.
+///
+/// # Example
+///
+/// ```
+/// let key = combine_shares(&[first_share, second_share])?;
+/// ```
+pub fn combine_shares(shares: &[Vec<u8>]) -> Option<Vec<u8>> {
+    let share_length = shares.first()?.len();
+    if share_length < 2 || shares.iter().any(|share| share.len() != share_length) {
+        return None;
+    }
+    let xs: Vec<u8> = shares.iter().map(|share| share[share_length - 1]).collect();
+    for (index, x) in xs.iter().enumerate() {
+        if *x == 0 || xs[..index].contains(x) {
+            return None;
+        }
+    }
+
+    let mut secret = vec![0u8; share_length - 1];
+    for (i, share) in shares.iter().enumerate() {
+        let basis = xs
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .fold(1, |basis, (_, &x_j)| {
+                gf256_mul(basis, gf256_mul(x_j, gf256_inverse(x_j ^ xs[i])))
+            });
+        for (secret_byte, &y) in secret.iter_mut().zip(&share[..share_length - 1]) {
+            *secret_byte ^= gf256_mul(y, basis);
+        }
+    }
+    Some(secret)
+}
+
+/// Multiplies two elements of GF(2^8) without branching on their values.
+fn gf256_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (GF256_REDUCING_POLYNOMIAL & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Returns the multiplicative inverse of a non-zero element of GF(2^8), `a^254`.
+fn gf256_inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    let mut exponent = 254u8;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = gf256_mul(result, power);
+        }
+        power = gf256_mul(power, power);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits `secret` into `count` shares, any `coefficients.len() + 1` of which recombine it.
+    fn split(secret: &[u8], coefficients: &[u8], count: u8) -> Vec<Vec<u8>> {
+        (1..=count)
+            .map(|x| {
+                let mut share: Vec<u8> = secret
+                    .iter()
+                    .map(|&byte| {
+                        coefficients
+                            .iter()
+                            .rev()
+                            .fold(0, |y, &coefficient| gf256_mul(y ^ coefficient, x))
+                            ^ byte
+                    })
+                    .collect();
+                share.push(x);
+                share
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gf256_inverse_is_multiplicative_inverse() {
+        assert_eq!(gf256_mul(0x53, 0xca), 0x01);
+        for a in 1..=255u8 {
+            assert_eq!(gf256_mul(a, gf256_inverse(a)), 1);
+        }
+    }
+
+    #[test]
+    fn combine_shares_recovers_secret_from_any_threshold_subset() {
+        let secret = b"0123456789abcdef0123456789abcdef";
+        let shares = split(secret, &[0x17, 0xc4], 5);
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let subset: Vec<Vec<u8>> = subset.iter().map(|&i| shares[i].clone()).collect();
+            assert_eq!(combine_shares(&subset), Some(secret.to_vec()));
+        }
+        assert_eq!(combine_shares(&shares), Some(secret.to_vec()));
+        assert_ne!(combine_shares(&shares[..2]), Some(secret.to_vec()));
+    }
+
+    #[test]
+    fn combine_shares_rejects_inconsistent_shares() {
+        let shares = split(b"secret", &[0x42], 3);
+
+        assert_eq!(combine_shares(&[]), None);
+        assert_eq!(
+            combine_shares(&[shares[0].clone(), shares[0].clone()]),
+            None
+        );
+        assert_eq!(combine_shares(&[shares[0].clone(), vec![1, 2, 3]]), None);
+        assert_eq!(combine_shares(&[vec![1, 2, 0], vec![1, 2, 3]]), None);
+        assert_eq!(combine_shares(&[vec![1]]), None);
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current wall-clock time.
+///
+/// Code reading the time takes a `Clock` instead of calling [`SystemTime::now`] directly, so
+/// that tests can pin timestamps with [`FixedClock`].
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+
+    /// Returns the number of milliseconds elapsed since the Unix epoch.
+    fn unix_millis(&self) -> u128 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default()
+    }
+}
+
+/// [`Clock`] reading the operating system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// [`Clock`] always returning the same time.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub SystemTime);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn fixed_clock_returns_unix_millis() {
+        let clock = FixedClock(UNIX_EPOCH + Duration::from_millis(1_718_000_000_123));
+        assert_eq!(clock.unix_millis(), 1_718_000_000_123);
+    }
+
+    #[test]
+    fn system_clock_is_after_epoch() {
+        assert!(SystemClock.unix_millis() > 0);
+    }
+}
@@ -0,0 +1,120 @@
+use base64::{Engine as _, engine::general_purpose};
+use sha2::{Digest, Sha256};
+
+const PIN_PREFIX: &str = "sha256/";
+const DER_SEQUENCE: u8 = 0x30;
+const DER_CONTEXT_SPECIFIC_0: u8 = 0xa0;
+
+/// Parses a comma-separated list of SPKI pins.
+///
+/// A pin is the base64-encoded SHA-256 of a DER-encoded SubjectPublicKeyInfo, optionally
+/// prefixed by `sha256/` as in HTTP Public Key Pinning. Blank entries are dropped.
+///
+/// # Example
+///
+/// ```
+/// let pins = parse_pins("sha256/7sJ/oG4X1OXwestb3XqGJlD4WE45Zlt++MKjbEToc/I=");
+/// ```
+pub fn parse_pins(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|pin| {
+            let pin = pin.trim();
+            pin.strip_prefix(PIN_PREFIX).unwrap_or(pin).to_string()
+        })
+        .filter(|pin| !pin.is_empty())
+        .collect()
+}
+
+/// Returns the SPKI pin of a DER-encoded X.509 certificate, or `None` if the certificate
+/// cannot be parsed.
+pub fn spki_pin(certificate: &[u8]) -> Option<String> {
+    let spki = subject_public_key_info(certificate)?;
+    Some(general_purpose::STANDARD.encode(Sha256::digest(spki)))
+}
+
+/// Returns the DER encoding of the SubjectPublicKeyInfo of a certificate.
+///
+/// The TBSCertificate fields are an optional `[0]` version, followed by the serial number,
+/// signature algorithm, issuer, validity and subject, then the SubjectPublicKeyInfo.
+fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    let (tag, certificate, _) = read_tlv(certificate)?;
+    if tag != DER_SEQUENCE {
+        return None;
+    }
+    let (tag, mut fields, _) = read_tlv(certificate)?;
+    if tag != DER_SEQUENCE {
+        return None;
+    }
+    let mut skipped = 0;
+    loop {
+        let (tag, _, rest) = read_tlv(fields)?;
+        let element = &fields[..fields.len() - rest.len()];
+        if tag == DER_CONTEXT_SPECIFIC_0 && skipped == 0 {
+            fields = rest;
+            continue;
+        }
+        if skipped == 5 {
+            return (tag == DER_SEQUENCE).then_some(element);
+        }
+        skipped += 1;
+        fields = rest;
+    }
+}
+
+/// Reads one DER element.
+///
+/// # Returns
+///
+/// The tag, the content and the bytes following the element.
+fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, input) = input.split_first()?;
+    let (length, input) = if first < 0x80 {
+        (first as usize, input)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > size_of::<u32>() || input.len() < count {
+            return None;
+        }
+        let length = input[..count]
+            .iter()
+            .fold(0usize, |length, &byte| (length << 8) | byte as usize);
+        (length, &input[count..])
+    };
+    if input.len() < length {
+        return None;
+    }
+    Some((tag, &input[..length], &input[length..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTIFICATE: &[u8] = include_bytes!("../../tests_resources/dataset-origin-cert.der");
+    const CERTIFICATE_PIN: &str = "7sJ/oG4X1OXwestb3XqGJlD4WE45Zlt++MKjbEToc/I=";
+
+    #[test]
+    fn spki_pin_hashes_subject_public_key_info() {
+        assert_eq!(spki_pin(CERTIFICATE), Some(CERTIFICATE_PIN.to_string()));
+    }
+
+    #[test]
+    fn spki_pin_returns_none_for_malformed_certificate() {
+        assert_eq!(spki_pin(b""), None);
+        assert_eq!(spki_pin(&CERTIFICATE[..100]), None);
+        assert_eq!(
+            spki_pin(&[DER_SEQUENCE, 0x84, 0xff, 0xff, 0xff, 0xff]),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_pins_strips_prefix_and_blank_entries() {
+        assert_eq!(
+            parse_pins(" sha256/abc= , ,def= "),
+            vec!["abc=".to_string(), "def=".to_string()]
+        );
+    }
+}
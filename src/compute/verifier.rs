@@ -0,0 +1,278 @@
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::utils::hash_utils::Checksum;
+use cid::Cid;
+use cid::multihash::Multihash;
+use log::error;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use std::str::FromStr;
+
+/// Multicodec code of raw binary content.
+const RAW_CODEC: u64 = 0x55;
+/// Multihash code of SHA2-256.
+const SHA2_256_CODE: u64 = 0x12;
+
+/// Computes and verifies checksums of downloaded content.
+///
+/// Implementations differ by the hash function used, so that the integrity policy can vary
+/// per deployment. The algorithm is selected with `IEXEC_CHECKSUM_ALGORITHM`.
+pub trait ChecksumVerifier: Send + Sync {
+    /// Creates a hasher computing the checksum of content received in chunks.
+    fn hasher(&self) -> Box<dyn ContentHasher>;
+
+    /// Computes the checksum of `content`.
+    fn checksum(&self, content: &[u8]) -> Checksum {
+        let mut hasher = self.hasher();
+        hasher.update(content);
+        hasher.finalize()
+    }
+
+    /// Verifies `content` against the `expected` checksum.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Checksum)` with the computed checksum if it matches `expected`.
+    /// * `Err(Checksum)` with the computed checksum otherwise.
+    fn verify(&self, content: &[u8], expected: &Checksum) -> Result<Checksum, Checksum> {
+        verify_checksum(self.checksum(content), expected)
+    }
+}
+
+/// Incremental checksum computation, fed with content as it is received.
+///
+/// Hashing chunks while they are downloaded overlaps verification with the transfer,
+/// instead of hashing the whole content once it is available.
+pub trait ContentHasher {
+    /// Adds `chunk` to the hashed content.
+    fn update(&mut self, chunk: &[u8]);
+
+    /// Returns the checksum of all the content added so far.
+    fn finalize(self: Box<Self>) -> Checksum;
+}
+
+impl ContentHasher for Sha256 {
+    fn update(&mut self, chunk: &[u8]) {
+        Digest::update(self, chunk);
+    }
+
+    fn finalize(self: Box<Self>) -> Checksum {
+        Checksum::from_digest(&Digest::finalize(*self).into())
+    }
+}
+
+impl ContentHasher for Keccak256 {
+    fn update(&mut self, chunk: &[u8]) {
+        Digest::update(self, chunk);
+    }
+
+    fn finalize(self: Box<Self>) -> Checksum {
+        Checksum::from_digest(&Digest::finalize(*self).into())
+    }
+}
+
+impl ContentHasher for blake3::Hasher {
+    fn update(&mut self, chunk: &[u8]) {
+        blake3::Hasher::update(self, chunk);
+    }
+
+    fn finalize(self: Box<Self>) -> Checksum {
+        Checksum::from_digest(blake3::Hasher::finalize(&self).as_bytes())
+    }
+}
+
+/// [`ContentHasher`] computing raw-codec CIDv1s from a SHA2-256 digest.
+struct CidHasher(Sha256);
+
+impl ContentHasher for CidHasher {
+    fn update(&mut self, chunk: &[u8]) {
+        Digest::update(&mut self.0, chunk);
+    }
+
+    fn finalize(self: Box<Self>) -> Checksum {
+        let multihash = Multihash::<64>::wrap(SHA2_256_CODE, &Digest::finalize(self.0))
+            .expect("a SHA2-256 digest always fits in a multihash");
+        Checksum::from_cid(&Cid::new_v1(RAW_CODEC, multihash))
+    }
+}
+
+/// Compares a computed checksum with the `expected` one.
+///
+/// # Returns
+///
+/// * `Ok(Checksum)` with the `actual` checksum if it matches `expected`.
+/// * `Err(Checksum)` with the `actual` checksum otherwise.
+pub fn verify_checksum(actual: Checksum, expected: &Checksum) -> Result<Checksum, Checksum> {
+    if actual == *expected {
+        Ok(actual)
+    } else {
+        Err(actual)
+    }
+}
+
+/// Hash functions supported to verify the dataset and input files.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ChecksumAlgorithm {
+    /// SHA-256 digest, as produced by `sha256sum`.
+    #[default]
+    Sha256,
+    /// Keccak-256 digest, as used on-chain.
+    Keccak256,
+    /// BLAKE3 digest with a 32-byte output.
+    Blake3,
+    /// CIDv1 of the content stored as a single raw block hashed with SHA2-256.
+    Cid,
+}
+
+impl FromStr for ChecksumAlgorithm {
+    type Err = ReplicateStatusCause;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "sha256" => Ok(ChecksumAlgorithm::Sha256),
+            "keccak256" | "keccak" => Ok(ChecksumAlgorithm::Keccak256),
+            "blake3" => Ok(ChecksumAlgorithm::Blake3),
+            "cid" => Ok(ChecksumAlgorithm::Cid),
+            _ => {
+                error!("Unsupported checksum algorithm [algorithm:{value}]");
+                Err(ReplicateStatusCause::PreComputeInvalidChecksumAlgorithm)
+            }
+        }
+    }
+}
+
+/// [`ChecksumVerifier`] computing SHA-256 digests.
+pub struct Sha256Verifier;
+
+impl ChecksumVerifier for Sha256Verifier {
+    fn hasher(&self) -> Box<dyn ContentHasher> {
+        Box::new(Sha256::new())
+    }
+}
+
+/// [`ChecksumVerifier`] computing Keccak-256 digests.
+pub struct Keccak256Verifier;
+
+impl ChecksumVerifier for Keccak256Verifier {
+    fn hasher(&self) -> Box<dyn ContentHasher> {
+        Box::new(Keccak256::new())
+    }
+}
+
+/// [`ChecksumVerifier`] computing BLAKE3 digests.
+pub struct Blake3Verifier;
+
+impl ChecksumVerifier for Blake3Verifier {
+    fn hasher(&self) -> Box<dyn ContentHasher> {
+        Box::new(blake3::Hasher::new())
+    }
+}
+
+/// [`ChecksumVerifier`] computing raw-codec CIDv1s.
+///
+/// Only content added to IPFS as a single raw block (`ipfs add --raw-leaves --cid-version 1`
+/// for files smaller than the chunk size) has a matching CID. Chunked files are addressed by
+/// the CID of their DAG root and cannot be verified this way.
+pub struct CidVerifier;
+
+impl ChecksumVerifier for CidVerifier {
+    fn hasher(&self) -> Box<dyn ContentHasher> {
+        Box::new(CidHasher(Sha256::new()))
+    }
+}
+
+/// Builds the [`ChecksumVerifier`] of `algorithm`.
+///
+/// # Example
+///
+/// ```
+/// let verifier = checksum_verifier(ChecksumAlgorithm::Blake3);
+/// verifier.verify(b"content", &expected_checksum)?;
+/// ```
+pub fn checksum_verifier(algorithm: ChecksumAlgorithm) -> Box<dyn ChecksumVerifier> {
+    match algorithm {
+        ChecksumAlgorithm::Sha256 => Box::new(Sha256Verifier),
+        ChecksumAlgorithm::Keccak256 => Box::new(Keccak256Verifier),
+        ChecksumAlgorithm::Blake3 => Box::new(Blake3Verifier),
+        ChecksumAlgorithm::Cid => Box::new(CidVerifier),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifiers_compute_known_digests_of_empty_content() {
+        let cases: [(ChecksumAlgorithm, &str); 4] = [
+            (
+                ChecksumAlgorithm::Sha256,
+                "0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                ChecksumAlgorithm::Keccak256,
+                "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            ),
+            (
+                ChecksumAlgorithm::Blake3,
+                "0xaf1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            ),
+            (
+                ChecksumAlgorithm::Cid,
+                "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku",
+            ),
+        ];
+        for (algorithm, expected) in cases {
+            assert_eq!(
+                checksum_verifier(algorithm).checksum(b"").to_string(),
+                expected,
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn hashers_match_one_shot_checksums_when_fed_in_chunks() {
+        let content = b"Some very useful data split into several chunks.";
+        for algorithm in [
+            ChecksumAlgorithm::Sha256,
+            ChecksumAlgorithm::Keccak256,
+            ChecksumAlgorithm::Blake3,
+            ChecksumAlgorithm::Cid,
+        ] {
+            let verifier = checksum_verifier(algorithm);
+            let mut hasher = verifier.hasher();
+            for chunk in content.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(
+                hasher.finalize(),
+                verifier.checksum(content),
+                "{algorithm:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn verify_returns_actual_checksum_on_mismatch() {
+        let verifier = Keccak256Verifier;
+        let expected = verifier.checksum(b"content");
+
+        assert_eq!(verifier.verify(b"content", &expected), Ok(expected.clone()));
+        assert_eq!(
+            verifier.verify(b"other", &expected),
+            Err(verifier.checksum(b"other"))
+        );
+    }
+
+    #[test]
+    fn checksum_algorithm_parses_supported_values() {
+        assert_eq!("SHA256".parse(), Ok(ChecksumAlgorithm::Sha256));
+        assert_eq!("keccak".parse(), Ok(ChecksumAlgorithm::Keccak256));
+        assert_eq!("blake3".parse(), Ok(ChecksumAlgorithm::Blake3));
+        assert_eq!("cid".parse(), Ok(ChecksumAlgorithm::Cid));
+        assert_eq!(
+            "md5".parse::<ChecksumAlgorithm>(),
+            Err(ReplicateStatusCause::PreComputeInvalidChecksumAlgorithm)
+        );
+    }
+}
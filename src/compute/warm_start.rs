@@ -0,0 +1,60 @@
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::pre_compute_app::IPFS_GATEWAYS;
+use crate::compute::pre_compute_args::parse_gateways;
+use crate::compute::signer::preload_challenge_signer;
+use crate::compute::utils::env_utils::{TeeSessionEnvironmentVariable, get_env_var_or_error};
+use crate::compute::utils::file_utils::warm_http_client;
+use log::{info, warn};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+static WARM_UP_DURATION: OnceLock<Duration> = OnceLock::new();
+
+/// Initializes everything a task needs which does not depend on the task itself.
+///
+/// This builds the shared HTTP client, resolves the dataset gateways into the DNS cache and
+/// parses the challenge signing key, so that this latency is paid once at enclave boot rather
+/// than when a task arrives. Failures are logged and left for the task to report.
+///
+/// # Returns
+///
+/// The time spent warming up, also available later through [`warm_up_duration`].
+///
+/// # Example
+///
+/// ```
+/// let duration = warm_up();
+/// ```
+pub fn warm_up() -> Duration {
+    let started_at = Instant::now();
+
+    let gateways = get_env_var_or_error(
+        TeeSessionEnvironmentVariable::IexecDatasetGateways,
+        ReplicateStatusCause::PreComputeFailedUnknownIssue,
+    )
+    .map(|value| parse_gateways(&value))
+    .unwrap_or_default();
+    let gateways: Vec<&str> = if gateways.is_empty() {
+        IPFS_GATEWAYS.to_vec()
+    } else {
+        gateways.iter().map(String::as_str).collect()
+    };
+    let resolved_hosts = warm_http_client(&gateways);
+
+    if let Err(e) = preload_challenge_signer() {
+        warn!("Challenge signer not preloaded: {e:?}");
+    }
+
+    let duration = started_at.elapsed();
+    let _ = WARM_UP_DURATION.set(duration);
+    info!(
+        "Warm start done [durationMs:{}, resolvedHosts:{resolved_hosts}]",
+        duration.as_millis()
+    );
+    duration
+}
+
+/// Returns the time spent in [`warm_up`], if it ran.
+pub fn warm_up_duration() -> Option<Duration> {
+    WARM_UP_DURATION.get().copied()
+}
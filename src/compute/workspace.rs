@@ -0,0 +1,249 @@
+use crate::compute::errors::ReplicateStatusCause;
+use crate::compute::utils::crypto_utils::SecureRng;
+use crate::compute::utils::fs_utils::Filesystem;
+use log::{error, info, warn};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Prefix of the name of workspace directories.
+pub const WORKSPACE_PREFIX: &str = ".pre-compute-";
+
+/// Workspaces of the running tasks, removed by [`remove_active`] when the process exits
+/// without running destructors.
+static ACTIVE_WORKSPACES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Private directory holding the temporary artifacts of a run, such as partial downloads
+/// and partially written dataset files.
+///
+/// The workspace is created in the output folder, so that its files are moved into place
+/// without crossing file systems, under a random name so that concurrent runs never share
+/// it. It is removed with its content when dropped. The process may also end without
+/// running destructors, after a panic or when a cancelled task overruns its grace period,
+/// so every exit path calls [`remove_active`].
+pub struct Workspace {
+    filesystem: Rc<dyn Filesystem>,
+    path: PathBuf,
+}
+
+impl Workspace {
+    /// Creates the workspace of a run in `parent_dir`.
+    ///
+    /// # Arguments
+    ///
+    /// * `filesystem` - The file system the workspace is created in
+    /// * `parent_dir` - The directory the workspace is created in, the output folder
+    /// * `rng` - The generator of the random name of the workspace
+    /// * `required_space` - The number of bytes the run is known to write, if any
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Workspace)` once the workspace directory is created.
+    /// * `Err(ReplicateStatusCause::PreComputeNotEnoughDiskSpace)` if the volume has less
+    ///   than `required_space` bytes available, or is full.
+    /// * `Err(ReplicateStatusCause::PreComputeWorkspaceCreationFailed)` if the directory
+    ///   could not be created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let workspace = Workspace::create(filesystem, Path::new("/iexec_in"), &mut OsRng, None)?;
+    /// let partial_path = workspace.path().join("dataset.part");
+    /// ```
+    pub fn create(
+        filesystem: Rc<dyn Filesystem>,
+        parent_dir: &Path,
+        rng: &mut dyn SecureRng,
+        required_space: Option<u64>,
+    ) -> Result<Self, ReplicateStatusCause> {
+        if let Some(required_space) = required_space {
+            match filesystem.available_space(parent_dir) {
+                Ok(available) if available < required_space => {
+                    error!(
+                        "Not enough disk space for the task [path:{}, required:{required_space}, available:{available}]",
+                        parent_dir.display()
+                    );
+                    return Err(ReplicateStatusCause::PreComputeNotEnoughDiskSpace);
+                }
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "Failed to check available disk space [path:{}]: {e}",
+                    parent_dir.display()
+                ),
+            }
+        }
+
+        let path = parent_dir.join(format!("{WORKSPACE_PREFIX}{:016x}", rng.next_u64()));
+        filesystem.create_dir(&path).map_err(|e| {
+            error!(
+                "Failed to create temporary workspace [path:{}]: {e}",
+                path.display()
+            );
+            match e.kind() {
+                io::ErrorKind::StorageFull => ReplicateStatusCause::PreComputeNotEnoughDiskSpace,
+                _ => ReplicateStatusCause::PreComputeWorkspaceCreationFailed,
+            }
+        })?;
+        register(&path);
+        info!("Temporary workspace created [path:{}]", path.display());
+        Ok(Workspace { filesystem, path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        if self.filesystem.exists(&self.path)
+            && let Err(e) = self.filesystem.remove_dir_all(&self.path)
+        {
+            error!(
+                "Failed to remove temporary workspace [path:{}]: {e}",
+                self.path.display()
+            );
+        }
+        unregister(&self.path);
+    }
+}
+
+fn active_workspaces() -> MutexGuard<'static, Vec<PathBuf>> {
+    ACTIVE_WORKSPACES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+fn register(path: &Path) {
+    active_workspaces().push(path.to_path_buf());
+}
+
+fn unregister(path: &Path) {
+    let mut workspaces = active_workspaces();
+    if let Some(index) = workspaces.iter().position(|workspace| workspace == path) {
+        workspaces.remove(index);
+    }
+}
+
+/// Removes the workspaces of the running tasks from the disk, before the process exits
+/// without running destructors.
+pub fn remove_active() {
+    for path in active_workspaces().iter() {
+        if let Err(e) = fs::remove_dir_all(path)
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            error!(
+                "Failed to remove temporary workspace [path:{}]: {e}",
+                path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute::utils::fs_utils::{MemoryFilesystem, StdFilesystem};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use tempfile::TempDir;
+
+    fn create_in_memory(
+        filesystem: &Rc<MemoryFilesystem>,
+        seed: u64,
+        required_space: Option<u64>,
+    ) -> Result<Workspace, ReplicateStatusCause> {
+        filesystem.create_dir(Path::new("/output")).unwrap();
+        Workspace::create(
+            filesystem.clone(),
+            Path::new("/output"),
+            &mut StdRng::seed_from_u64(seed),
+            required_space,
+        )
+    }
+
+    #[test]
+    fn create_makes_private_directory_removed_on_drop() {
+        let filesystem = Rc::new(MemoryFilesystem::default());
+        let workspace = create_in_memory(&filesystem, 1, None).unwrap();
+        let path = workspace.path().to_path_buf();
+        filesystem
+            .write(&path.join("file.part"), b"partial")
+            .unwrap();
+
+        assert_eq!(path.parent(), Some(Path::new("/output")));
+        assert!(
+            path.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with(WORKSPACE_PREFIX)
+        );
+        assert!(active_workspaces().contains(&path));
+
+        drop(workspace);
+
+        assert!(!filesystem.exists(&path));
+        assert!(!filesystem.exists(&path.join("file.part")));
+        assert!(!active_workspaces().contains(&path));
+    }
+
+    #[test]
+    fn create_fails_when_space_is_insufficient() {
+        let filesystem = Rc::new(MemoryFilesystem::with_capacity(10));
+
+        let result = create_in_memory(&filesystem, 2, Some(11));
+
+        assert_eq!(
+            result.err(),
+            Some(ReplicateStatusCause::PreComputeNotEnoughDiskSpace)
+        );
+    }
+
+    #[test]
+    fn create_succeeds_when_space_is_sufficient() {
+        let filesystem = Rc::new(MemoryFilesystem::with_capacity(10));
+
+        assert!(create_in_memory(&filesystem, 3, Some(10)).is_ok());
+    }
+
+    #[test]
+    fn create_fails_when_parent_is_not_writable() {
+        let temp_dir = TempDir::new().unwrap();
+        let parent = temp_dir.path().join("file");
+        fs::write(&parent, b"not a directory").unwrap();
+
+        let result = Workspace::create(
+            Rc::new(StdFilesystem),
+            &parent,
+            &mut StdRng::seed_from_u64(42),
+            None,
+        );
+
+        assert_eq!(
+            result.err(),
+            Some(ReplicateStatusCause::PreComputeWorkspaceCreationFailed)
+        );
+    }
+
+    #[test]
+    fn remove_active_removes_workspaces_from_disk() {
+        let temp_dir = TempDir::new().unwrap();
+        let workspace = Workspace::create(
+            Rc::new(StdFilesystem),
+            temp_dir.path(),
+            &mut StdRng::seed_from_u64(7),
+            None,
+        )
+        .unwrap();
+        let path = workspace.path().to_path_buf();
+        fs::write(path.join("file.part"), b"partial").unwrap();
+
+        remove_active();
+
+        assert!(!path.exists());
+        drop(workspace);
+        assert!(!active_workspaces().contains(&path));
+    }
+}
@@ -1,12 +1,72 @@
+use compute::utils::log_utils;
 use env_logger::{Builder, Env, Target};
-use std::process;
+use std::path::Path;
+use std::{env, io, process};
 
 mod api;
 mod compute;
 
 fn main() {
-    Builder::from_env(Env::default().default_filter_or("info"))
-        .target(Target::Stdout)
-        .init();
-    process::exit(compute::app_runner::start() as i32);
+    let args: Vec<String> = env::args().collect();
+    // In service and conformance modes, stdout only carries the job results or the
+    // conformance report.
+    let is_stdout_reserved = matches!(
+        args.get(1).map(String::as_str),
+        Some("--serve" | "--conformance")
+    );
+    let logger = Builder::from_env(Env::default().default_filter_or("info"))
+        .target(if is_stdout_reserved {
+            Target::Stderr
+        } else {
+            Target::Stdout
+        })
+        .build();
+    let max_level = logger.filter();
+    log_utils::init(logger, max_level).expect("Logger should only be initialized once");
+    compute::supervisor::install_signal_handlers();
+    compute::app_runner::install_panic_hook(if is_stdout_reserved {
+        || Box::new(io::stderr())
+    } else {
+        || Box::new(io::stdout())
+    });
+    let exit_mode = match args.get(1).map(String::as_str) {
+        #[cfg(feature = "bench")]
+        Some("--bench") => compute::benchmark::run(args.get(2).map(String::as_str)),
+        #[cfg(feature = "conformance")]
+        Some("--conformance") => compute::conformance::run(args.get(2).map(String::as_str)),
+        Some("--serve") => {
+            compute::warm_start::warm_up();
+            compute::service::serve(io::stdin().lock(), io::stdout(), || {
+                compute::app_runner::start_with_summary_output(&mut io::stderr())
+            })
+        }
+        Some("--watch") => match args.get(2) {
+            Some(dir) => {
+                compute::warm_start::warm_up();
+                compute::service::watch(Path::new(dir), compute::app_runner::start)
+            }
+            None => {
+                log::error!("Missing jobs directory, usage: --watch <dir>");
+                compute::app_runner::ExitMode::InitializationFailure
+            }
+        },
+        Some("--print-env-spec") => {
+            println!("{}", compute::utils::env_utils::env_spec_json());
+            compute::app_runner::ExitMode::Success
+        }
+        Some("--verify-report") => match (args.get(2), args.get(3)) {
+            (Some(dir), Some(signer)) => {
+                compute::report::verify(args.get(4).unwrap_or(dir), dir, signer)
+            }
+            _ => {
+                log::error!(
+                    "Missing arguments, usage: --verify-report <dir> <signer> [<report-dir>]"
+                );
+                compute::app_runner::ExitMode::InitializationFailure
+            }
+        },
+        _ => compute::app_runner::start(),
+    };
+    log::logger().flush();
+    process::exit(exit_mode as i32);
 }